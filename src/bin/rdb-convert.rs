@@ -0,0 +1,66 @@
+//! Converts snapshot files between the format of this server and the RDB format of upstream
+//! Redis, e.g. to move a dataset from one to the other during a migration.
+//!
+//! Usage: `rdb-convert <input> <output> [--to native|redis] [--databases N]`
+//!
+//! The input format is detected from the file, and the output defaults to the other format.
+
+use redis_rust::config::SnapshotFormat;
+use redis_rust::data_store::Databases;
+use redis_rust::persistence::{rdb, snapshot};
+use std::path::Path;
+
+const USAGE: &str = "usage: rdb-convert <input> <output> [--to native|redis] [--databases N]";
+
+fn main() {
+    env_logger::init();
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    let mut paths = Vec::new();
+    let mut format = None;
+    let mut databases = 16;
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--to" => {
+                format = match args.next().map(|v| v.as_str()) {
+                    Some("native") => Some(SnapshotFormat::Native),
+                    Some("redis") => Some(SnapshotFormat::Redis),
+                    _ => exit_with_error(USAGE.to_string()),
+                }
+            }
+            "--databases" => {
+                databases = match args.next().and_then(|v| v.parse().ok()) {
+                    Some(n) if n >= 1 => n,
+                    _ => exit_with_error(USAGE.to_string()),
+                }
+            }
+            _ => paths.push(arg),
+        }
+    }
+    let [input, output] = paths[..] else {
+        exit_with_error(USAGE.to_string());
+    };
+
+    let data = match std::fs::read(input) {
+        Ok(v) => v,
+        Err(e) => exit_with_error(format!("failed to read `{}`: {}", input, e)),
+    };
+    let format = format.unwrap_or(match rdb::is_rdb(&data) {
+        true => SnapshotFormat::Native,
+        false => SnapshotFormat::Redis,
+    });
+    let mut dbs = Databases::new(databases);
+    if let Err(e) = snapshot::read(&data, &mut dbs) {
+        exit_with_error(format!("failed to load `{}`: {}", input, e));
+    }
+    if let Err(e) = snapshot::save(Path::new(output), &dbs, format) {
+        exit_with_error(format!("failed to write `{}`: {}", output, e));
+    }
+    let keys: usize = (0..dbs.count()).map(|i| dbs.db(i).len()).sum();
+    println!("Converted {} keys to {:?} format", keys, format);
+}
+
+fn exit_with_error(message: String) -> ! {
+    eprintln!("Error: {}", message);
+    std::process::exit(1);
+}
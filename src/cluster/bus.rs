@@ -0,0 +1,103 @@
+//! The connections of the cluster bus. Every node opens a connection to each node it knows, which
+//! carries its pings and the replies to them, and serves the connections other nodes open to it.
+
+use super::BusLink;
+use crate::scripting::ScriptMonitor;
+use crate::server::Server;
+use crate::utils::{lock_server, RequestDecoder};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::{TcpListener, TcpStream};
+
+const CONNECT_TIMEOUT: Duration = Duration::from_secs(1);
+
+type BusResult = Result<(), Box<dyn std::error::Error + Send + Sync>>;
+
+/// Handles the messages waiting in `decoder`, and returns the replies to send back.
+fn process(
+    server: &mut Server,
+    decoder: &mut RequestDecoder,
+    link: Option<u64>,
+) -> Result<Vec<u8>, Box<dyn std::error::Error + Send + Sync>> {
+    let mut replies = Vec::new();
+    while let Some(tokens) = decoder.decode()? {
+        for reply in server
+            .cluster
+            .process(&tokens, link, &mut server.replication)
+        {
+            replies.extend(reply);
+        }
+    }
+    Ok(replies)
+}
+
+/// Serves the connections other nodes open to this one.
+pub async fn accept(
+    listener: TcpListener,
+    server: Arc<Mutex<Server>>,
+    monitor: Arc<ScriptMonitor>,
+) {
+    while let Ok((stream, address)) = listener.accept().await {
+        let (server, monitor) = (server.clone(), monitor.clone());
+        tokio::spawn(async move {
+            if let Err(e) = serve(stream, &server, &monitor).await {
+                log::debug!("Cluster bus connection from {} closed: {}", address, e);
+            }
+        });
+    }
+}
+
+async fn serve(stream: TcpStream, server: &Mutex<Server>, monitor: &ScriptMonitor) -> BusResult {
+    let (mut rx, mut tx) = stream.into_split();
+    let mut decoder = RequestDecoder::new();
+    loop {
+        if rx.read_buf(decoder.buffer_mut()).await? == 0 {
+            return Ok(());
+        }
+        let replies = process(&mut *lock_server(server, monitor).await, &mut decoder, None)?;
+        tx.write_all(&replies).await?;
+    }
+}
+
+/// Runs a connection to another node until it fails or the node is forgotten.
+pub async fn connect(server: Arc<Mutex<Server>>, monitor: Arc<ScriptMonitor>, mut link: BusLink) {
+    if let Err(e) = run(&server, &monitor, &mut link).await {
+        log::debug!(
+            "Cluster bus link to {}:{} closed: {}",
+            link.host,
+            link.cport,
+            e
+        );
+    }
+    lock_server(&server, &monitor)
+        .await
+        .cluster
+        .link_closed(link.id);
+}
+
+async fn run(server: &Mutex<Server>, monitor: &ScriptMonitor, link: &mut BusLink) -> BusResult {
+    let connecting = TcpStream::connect((link.host.as_str(), link.cport));
+    let stream = tokio::time::timeout(CONNECT_TIMEOUT, connecting).await??;
+    let (mut rx, mut tx) = stream.into_split();
+    let mut decoder = RequestDecoder::new();
+    loop {
+        tokio::select! {
+            message = link.outbox.recv() => match message {
+                Some(message) => tx.write_all(&message).await?,
+                None => return Ok(()),
+            },
+            read = rx.read_buf(decoder.buffer_mut()) => {
+                if read? == 0 {
+                    return Err("connection closed by peer".into());
+                }
+                let replies = process(
+                    &mut *lock_server(server, monitor).await,
+                    &mut decoder,
+                    Some(link.id),
+                )?;
+                tx.write_all(&replies).await?;
+            }
+        }
+    }
+}
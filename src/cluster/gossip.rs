@@ -0,0 +1,738 @@
+//! The cluster bus protocol. Nodes ping each other every second, with gossip about the other
+//! nodes they know, and tell in the header of every message which slots they serve with which
+//! config epoch, the highest epoch winning when two masters claim the same slot.
+//!
+//! A node that does not answer a ping within the node timeout is flagged `PFAIL` by the node
+//! that pinged it. Once a majority of masters reported it, it is flagged `FAIL` everywhere, and if
+//! it was a master, its replicas ask the other masters for their votes to replace it. A master
+//! votes once per epoch, and the replica that gets a majority takes the slots over with a new,
+//! higher config epoch.
+
+use super::message::{Gossip, Header, Message, MessageType};
+use super::{BusLink, Cluster, Election};
+use crate::data_store::now_ms;
+use crate::replication::Replication;
+use rand::Rng;
+use std::collections::HashSet;
+use tokio::sync::mpsc::unbounded_channel;
+
+/// How often nodes ping each other, in milliseconds.
+const PING_INTERVAL: u64 = 1000;
+/// How long to wait before connecting again to a node, in milliseconds.
+const RECONNECT_INTERVAL: u64 = 1000;
+
+impl Cluster {
+    /// The header of a message from this node. A replica tells the slots of its master.
+    fn header(&self, kind: MessageType, offset: u64) -> Header {
+        let myself = self.myself();
+        let owner = match &myself.master_id {
+            Some(id) => self.nodes.get(id).unwrap_or(myself),
+            None => myself,
+        };
+        Header {
+            kind,
+            sender: myself.id.clone(),
+            current_epoch: self.current_epoch,
+            config_epoch: owner.config_epoch,
+            master_id: myself.master_id.clone(),
+            host: myself.host.clone(),
+            port: myself.port,
+            cport: myself.cport,
+            offset,
+            slots: self.slots_of(&owner.id),
+        }
+    }
+
+    /// A ping, pong or meet for node `to`, with gossip about the other nodes.
+    fn ping(&self, kind: MessageType, offset: u64, to: &str) -> Message {
+        let mut message = Message::new(self.header(kind, offset));
+        message.gossip = self
+            .nodes
+            .values()
+            .filter(|node| node.id != self.myself && node.id != to && !node.handshake)
+            .map(|node| Gossip {
+                id: node.id.clone(),
+                host: node.host.clone(),
+                port: node.port,
+                cport: node.cport,
+                pfail: node.pfail,
+                fail: node.fail,
+            })
+            .collect();
+        message
+    }
+
+    /// Sends a message to node `to`, if it is connected.
+    fn send(&self, to: &str, message: &Message) {
+        let outbox = self
+            .nodes
+            .get(to)
+            .and_then(|node| node.link)
+            .and_then(|link| self.links.get(&link));
+        if let Some(outbox) = outbox {
+            let _ = outbox.send(message.encode());
+        }
+    }
+
+    /// Sends a message to every other node, or only to masters.
+    fn broadcast(&self, message: &Message, masters_only: bool) {
+        for node in self.nodes.values() {
+            if node.id != self.myself && !node.handshake && (node.is_master() || !masters_only) {
+                self.send(&node.id, message);
+            }
+        }
+    }
+
+    /// Returns the connections to open to the nodes that are not connected, with the first
+    /// message to send on each.
+    pub fn take_pending_links(&mut self, offset: u64) -> Vec<BusLink> {
+        if !self.enabled {
+            return Vec::new();
+        }
+        let now = now_ms();
+        let pending: Vec<String> = self
+            .nodes
+            .values()
+            .filter(|node| node.id != self.myself && node.link.is_none())
+            .filter(|node| now.saturating_sub(node.link_attempt) >= RECONNECT_INTERVAL)
+            .map(|node| node.id.clone())
+            .collect();
+        let mut links = Vec::new();
+        for id in pending {
+            let kind = match self.nodes[&id].handshake {
+                true => MessageType::Meet,
+                false => MessageType::Ping,
+            };
+            let message = self.ping(kind, offset, &id);
+            let (outbox, rx) = unbounded_channel();
+            let _ = outbox.send(message.encode());
+            let link = self.next_link_id;
+            self.next_link_id += 1;
+            self.links.insert(link, outbox);
+            let node = self.nodes.get_mut(&id).unwrap();
+            node.link = Some(link);
+            node.link_attempt = now;
+            if node.ping_sent == 0 {
+                node.ping_sent = now;
+            }
+            links.push(BusLink {
+                id: link,
+                host: node.host.clone(),
+                cport: node.cport,
+                outbox: rx,
+            });
+        }
+        links
+    }
+
+    /// Records that a connection to another node was closed.
+    pub fn link_closed(&mut self, link: u64) {
+        self.links.remove(&link);
+        for node in self.nodes.values_mut() {
+            if node.link == Some(link) {
+                node.link = None;
+            }
+        }
+    }
+
+    fn drop_link(&mut self, id: &str) {
+        if let Some(link) = self.nodes.get_mut(id).and_then(|node| node.link.take()) {
+            // The connection closes once its outbox is gone.
+            self.links.remove(&link);
+        }
+    }
+
+    /// Runs the periodic work of the cluster bus: pinging the other nodes, detecting the ones
+    /// that fail and replacing the master of this node if it failed.
+    pub fn cron(&mut self, replication: &mut Replication) {
+        if !self.enabled {
+            return;
+        }
+        let now = now_ms();
+        let handshake_timeout = self.node_timeout.max(1000);
+        let expired: Vec<String> = self
+            .nodes
+            .values()
+            .filter(|node| node.handshake && now - node.created > handshake_timeout)
+            .map(|node| node.id.clone())
+            .collect();
+        for id in expired {
+            log::info!("Handshake with {} timed out", self.nodes[&id].addr());
+            self.drop_link(&id);
+            self.nodes.remove(&id);
+        }
+
+        let others: Vec<String> = self
+            .nodes
+            .values()
+            .filter(|node| node.id != self.myself && !node.handshake)
+            .map(|node| node.id.clone())
+            .collect();
+        for id in others {
+            let node = &self.nodes[&id];
+            let waiting = match node.ping_sent {
+                0 => 0,
+                sent => now.saturating_sub(sent),
+            };
+            if node.link.is_some()
+                && node.ping_sent == 0
+                && now.saturating_sub(node.pong_received) >= PING_INTERVAL
+            {
+                let message = self.ping(MessageType::Ping, replication.offset, &id);
+                self.send(&id, &message);
+                self.nodes.get_mut(&id).unwrap().ping_sent = now;
+                continue;
+            }
+            // A connection that stays silent for half the timeout may be broken, so it is opened
+            // again before the node gets flagged.
+            if waiting > self.node_timeout / 2 && node.link_attempt < node.ping_sent {
+                self.drop_link(&id);
+            }
+            let node = self.nodes.get_mut(&id).unwrap();
+            if waiting > self.node_timeout && !node.pfail && !node.fail {
+                log::info!("Node {} possibly failing", id);
+                node.pfail = true;
+            }
+            self.mark_failed_if_needed(&id);
+        }
+
+        self.failover_cron(replication);
+        if self.config_dirty {
+            self.config_dirty = false;
+            let _ = self.save();
+        }
+    }
+
+    /// Flags node `id` as failed if this node and a majority of the masters think it is down,
+    /// and tells every node about it.
+    fn mark_failed_if_needed(&mut self, id: &str) {
+        let now = now_ms();
+        let validity = self.node_timeout * 2;
+        let myself_is_master = self.myself().is_master();
+        let Some(node) = self.nodes.get_mut(id) else {
+            return;
+        };
+        node.fail_reports
+            .retain(|_, reported| now.saturating_sub(*reported) <= validity);
+        if !node.pfail || node.fail {
+            return;
+        }
+        let reports = node.fail_reports.len() + myself_is_master as usize;
+        if reports < self.quorum() {
+            return;
+        }
+        let node = self.nodes.get_mut(id).unwrap();
+        log::info!("Marking node {} as failing (quorum reached)", id);
+        node.fail = true;
+        node.fail_time = now;
+        self.config_dirty = true;
+        let mut message = Message::new(self.header(MessageType::Fail, 0));
+        message.failed = Some(id.to_string());
+        self.broadcast(&message, false);
+    }
+
+    /// Handles a message from another node, received on connection `link` if this node opened
+    /// it. Returns the replies to send back.
+    pub fn process(
+        &mut self,
+        tokens: &[Vec<u8>],
+        link: Option<u64>,
+        replication: &mut Replication,
+    ) -> Vec<Vec<u8>> {
+        let Some(message) = Message::decode(tokens) else {
+            log::warn!("Invalid cluster bus message");
+            return Vec::new();
+        };
+        let header = &message.header;
+        if !self.enabled || header.sender == self.myself {
+            return Vec::new();
+        }
+        let now = now_ms();
+        if header.current_epoch > self.current_epoch {
+            self.current_epoch = header.current_epoch;
+            self.config_dirty = true;
+        }
+        if let Some(link) = link {
+            if header.kind == MessageType::Pong && !self.complete_handshake(link, header) {
+                return Vec::new();
+            }
+        }
+        let known = self.nodes.contains_key(&header.sender);
+        if !known && header.kind == MessageType::Meet {
+            log::info!(
+                "Met node {} at {}:{}",
+                header.sender,
+                header.host,
+                header.port
+            );
+            let mut node = super::Node::new(
+                header.sender.clone(),
+                header.host.clone(),
+                header.port,
+                header.cport,
+            );
+            node.master_id = header.master_id.clone();
+            self.nodes.insert(node.id.clone(), node);
+            self.config_dirty = true;
+        }
+        let known = self.nodes.contains_key(&header.sender);
+
+        let mut replies = Vec::new();
+        if matches!(header.kind, MessageType::Ping | MessageType::Meet) {
+            let pong = self.ping(MessageType::Pong, replication.offset, &header.sender);
+            replies.push(pong.encode());
+        }
+        if !known {
+            return replies;
+        }
+        if header.kind == MessageType::Pong {
+            self.pong_received(&header.sender, now);
+        }
+        self.update_sender(header, replication);
+        for gossip in &message.gossip {
+            self.process_gossip(&header.sender, gossip, now);
+        }
+
+        match header.kind {
+            MessageType::Fail => {
+                let failed = message.failed.as_deref().unwrap_or_default();
+                if failed != self.myself {
+                    if let Some(node) = self.nodes.get_mut(failed).filter(|n| !n.fail) {
+                        log::info!("Node {} reported as failing by {}", failed, header.sender);
+                        node.fail = true;
+                        node.pfail = false;
+                        node.fail_time = now;
+                        self.config_dirty = true;
+                    }
+                }
+            }
+            MessageType::AuthRequest if self.vote(header, now) => {
+                let ack = Message::new(self.header(MessageType::AuthAck, replication.offset));
+                replies.push(ack.encode());
+            }
+            MessageType::AuthAck => self.count_vote(header, replication),
+            _ => (),
+        }
+        replies
+    }
+
+    /// Handles a pong on connection `link`, which gives the real ID of a node met with a
+    /// handshake. Returns false if the node turned out to be known already.
+    fn complete_handshake(&mut self, link: u64, header: &Header) -> bool {
+        let Some(id) = self
+            .nodes
+            .values()
+            .find(|node| node.link == Some(link) && node.handshake)
+            .map(|node| node.id.clone())
+        else {
+            return true;
+        };
+        let mut node = self.nodes.remove(&id).unwrap();
+        if self.nodes.contains_key(&header.sender) {
+            self.links.remove(&link);
+            return false;
+        }
+        log::info!(
+            "Handshake with {} completed, node ID {}",
+            node.addr(),
+            header.sender
+        );
+        node.id = header.sender.clone();
+        node.handshake = false;
+        node.master_id = header.master_id.clone();
+        self.nodes.insert(node.id.clone(), node);
+        self.config_dirty = true;
+        true
+    }
+
+    /// Records that node `id` answered a ping, which clears its failure flags.
+    fn pong_received(&mut self, id: &str, now: u64) {
+        let timeout = self.node_timeout;
+        let has_slots = self.slots.iter().flatten().any(|owner| owner == id);
+        let node = self.nodes.get_mut(id).unwrap();
+        node.pong_received = now;
+        node.ping_sent = 0;
+        if node.pfail {
+            log::info!("Node {} is reachable again", id);
+            node.pfail = false;
+        }
+        // A master serving slots may have been replaced meanwhile, so it only loses its flag
+        // when no replica took over after some time.
+        if node.fail && (!node.is_master() || !has_slots || now - node.fail_time > timeout * 2) {
+            log::info!("Clearing FAIL state of node {}", id);
+            node.fail = false;
+            self.config_dirty = true;
+        }
+    }
+
+    /// Updates what this node knows about the sender of a message: its role, its slots and its
+    /// config epoch.
+    fn update_sender(&mut self, header: &Header, replication: &mut Replication) {
+        let node = self.nodes.get_mut(&header.sender).unwrap();
+        node.offset = header.offset;
+        if node.master_id != header.master_id {
+            log::info!("Node {} changed its master", header.sender);
+            node.master_id = header.master_id.clone();
+            self.config_dirty = true;
+            if header.master_id.is_some() {
+                for owner in self.slots.iter_mut() {
+                    if owner.as_deref() == Some(header.sender.as_str()) {
+                        *owner = None;
+                    }
+                }
+            }
+        }
+        if header.master_id.is_some() {
+            return;
+        }
+        let node = self.nodes.get_mut(&header.sender).unwrap();
+        if node.config_epoch != header.config_epoch {
+            node.config_epoch = header.config_epoch;
+            self.config_dirty = true;
+        }
+
+        let myself = self.myself();
+        let served_by_us = myself.master_id.clone().unwrap_or(myself.id.clone());
+        let mut lost = false;
+        for &(start, end) in &header.slots {
+            for slot in start..=end {
+                let owner = match &self.slots[slot as usize] {
+                    Some(owner) if *owner == header.sender => continue,
+                    Some(owner) => Some(owner.clone()),
+                    None => None,
+                };
+                // The slots being imported are only assigned with CLUSTER SETSLOT.
+                if self.importing.contains_key(&slot) {
+                    continue;
+                }
+                let owner_epoch = owner
+                    .as_ref()
+                    .and_then(|id| self.nodes.get(id))
+                    .map_or(0, |node| node.config_epoch);
+                if owner.is_some() && owner_epoch >= header.config_epoch {
+                    continue;
+                }
+                lost |= owner.as_ref() == Some(&served_by_us);
+                self.slots[slot as usize] = Some(header.sender.clone());
+                self.config_dirty = true;
+            }
+        }
+        // A master that lost all its slots follows the node that took them, and so do its
+        // replicas.
+        if lost
+            && !self
+                .slots
+                .iter()
+                .flatten()
+                .any(|owner| *owner == served_by_us)
+        {
+            self.follow(&header.sender, replication);
+        }
+
+        let myself = self.myself();
+        if myself.is_master()
+            && header.config_epoch == myself.config_epoch
+            && header.sender > self.myself
+        {
+            self.current_epoch += 1;
+            let epoch = self.current_epoch;
+            self.myself_mut().config_epoch = epoch;
+            self.config_dirty = true;
+            log::info!(
+                "Config epoch collision with {}, moved to {}",
+                header.sender,
+                epoch
+            );
+        }
+    }
+
+    /// Turns this node into a replica of master `id`.
+    fn follow(&mut self, id: &str, replication: &mut Replication) {
+        log::info!("Configuring myself as a replica of {}", id);
+        self.myself_mut().master_id = Some(id.to_string());
+        self.migrating.clear();
+        self.importing.clear();
+        self.election = None;
+        let master = &self.nodes[id];
+        replication.set_master(&master.host.clone(), master.port);
+        self.config_dirty = true;
+    }
+
+    fn process_gossip(&mut self, sender: &str, gossip: &Gossip, now: u64) {
+        if gossip.id == self.myself {
+            return;
+        }
+        let sender_is_master = self.nodes[sender].is_master();
+        match self.nodes.get_mut(&gossip.id) {
+            Some(node) if sender_is_master => {
+                if gossip.pfail || gossip.fail {
+                    node.fail_reports.insert(sender.to_string(), now);
+                    self.mark_failed_if_needed(&gossip.id);
+                } else {
+                    node.fail_reports.remove(sender);
+                }
+            }
+            Some(_) => (),
+            None => {
+                let known_address = self.nodes.values().any(|node| {
+                    node.host == gossip.host && node.port == gossip.port && !node.handshake
+                });
+                if !known_address && !gossip.fail {
+                    self.meet(&gossip.host, gossip.port, gossip.cport);
+                }
+            }
+        }
+    }
+
+    /// Decides whether to vote for the replica that sent `request` to replace its master.
+    fn vote(&mut self, request: &Header, now: u64) -> bool {
+        let myself = self.myself();
+        if !myself.is_master() || self.slots_of(&myself.id).is_empty() {
+            return false;
+        }
+        let refuse = |reason: &str| {
+            log::info!("Refusing to vote for {}: {}", request.sender, reason);
+            false
+        };
+        if request.current_epoch < self.current_epoch {
+            return refuse("its epoch is too old");
+        }
+        if self.last_vote_epoch == self.current_epoch {
+            return refuse("already voted in this epoch");
+        }
+        let master = request.master_id.as_ref().and_then(|id| self.nodes.get(id));
+        let Some(master) = master else {
+            return refuse("its master is unknown");
+        };
+        if !master.fail {
+            return refuse("its master is not failing");
+        }
+        if now.saturating_sub(master.voted_time) < self.node_timeout * 2 {
+            return refuse("already voted for a replica of the same master");
+        }
+        for &(start, end) in &request.slots {
+            for slot in start..=end {
+                let owner = self.slots[slot as usize].as_ref();
+                if owner
+                    .and_then(|id| self.nodes.get(id))
+                    .is_some_and(|node| node.config_epoch > request.config_epoch)
+                {
+                    return refuse("a slot has a newer owner");
+                }
+            }
+        }
+        let master_id = master.id.clone();
+        self.nodes.get_mut(&master_id).unwrap().voted_time = now;
+        self.last_vote_epoch = self.current_epoch;
+        // The vote must survive a restart, or the node could vote twice in the same epoch.
+        let _ = self.save();
+        log::info!(
+            "Voting for {} to replace {} in epoch {}",
+            request.sender,
+            master_id,
+            self.current_epoch
+        );
+        true
+    }
+
+    fn count_vote(&mut self, ack: &Header, replication: &mut Replication) {
+        let quorum = self.quorum();
+        let Some(election) = self.election.as_mut() else {
+            return;
+        };
+        if election.epoch == 0 || ack.current_epoch < election.epoch {
+            return;
+        }
+        election.votes.insert(ack.sender.clone());
+        if election.votes.len() >= quorum {
+            self.promote(replication);
+        }
+    }
+
+    /// Runs the election of a replica whose master failed.
+    fn failover_cron(&mut self, replication: &mut Replication) {
+        let now = now_ms();
+        let myself = self.myself();
+        let master = myself.master_id.as_ref().and_then(|id| self.nodes.get(id));
+        let Some(master) = master.filter(|m| m.fail && !self.slots_of(&m.id).is_empty()) else {
+            self.election = None;
+            return;
+        };
+        let auth_timeout = (self.node_timeout * 2).max(2000);
+        let Some(election) = &self.election else {
+            // Replicas with more data start earlier, so that they are more likely to win.
+            let rank = self
+                .nodes
+                .values()
+                .filter(|node| node.master_id == Some(master.id.clone()))
+                .filter(|node| node.id != self.myself && node.offset > replication.offset)
+                .count() as u64;
+            let delay = 500 + rand::thread_rng().gen_range(0..500) + rank * 1000;
+            log::info!("Start of election delayed for {} milliseconds", delay);
+            self.election = Some(Election {
+                start: now + delay,
+                epoch: 0,
+                votes: HashSet::new(),
+            });
+            return;
+        };
+        if now < election.start {
+            return;
+        }
+        if election.epoch != 0 {
+            if now - election.start > auth_timeout * 2 {
+                // No majority was reached, so another election is scheduled.
+                self.election = None;
+            }
+            return;
+        }
+        self.current_epoch += 1;
+        let epoch = self.current_epoch;
+        self.election.as_mut().unwrap().epoch = epoch;
+        let _ = self.save();
+        log::info!("Starting a failover election for epoch {}", epoch);
+        let request = Message::new(self.header(MessageType::AuthRequest, replication.offset));
+        self.broadcast(&request, true);
+    }
+
+    /// Replaces the failed master of this node after winning the election.
+    fn promote(&mut self, replication: &mut Replication) {
+        let election = self.election.take().unwrap();
+        let Some(master) = self.myself_mut().master_id.take() else {
+            return;
+        };
+        log::info!("Failover election won, replacing {}", master);
+        for owner in self.slots.iter_mut() {
+            if owner.as_ref() == Some(&master) {
+                *owner = Some(self.myself.clone());
+            }
+        }
+        self.myself_mut().config_epoch = election.epoch;
+        replication.promote();
+        let _ = self.save();
+        for id in self.nodes.keys() {
+            if *id != self.myself {
+                let pong = self.ping(MessageType::Pong, replication.offset, id);
+                self.send(id, &pong);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::super::message::{Header, Message, MessageType};
+    use super::super::{Cluster, Node};
+    use crate::config::ServerConfig;
+    use crate::replication::Replication;
+
+    fn header(kind: MessageType, sender: &str, config_epoch: u64) -> Header {
+        Header {
+            kind,
+            sender: sender.to_string(),
+            current_epoch: config_epoch,
+            config_epoch,
+            master_id: None,
+            host: "127.0.0.1".to_string(),
+            port: 7001,
+            cport: 17001,
+            offset: 0,
+            slots: Vec::new(),
+        }
+    }
+
+    fn tokens(message: &Message) -> Vec<Vec<u8>> {
+        let mut decoder = crate::utils::RequestDecoder::new();
+        decoder.buffer_mut().extend(message.encode());
+        decoder.decode().unwrap().unwrap()
+    }
+
+    /// A cluster of masters a, b and c, serving a third of the slots each, seen by a.
+    fn cluster(name: &str) -> (Cluster, Replication) {
+        let config = ServerConfig {
+            cluster_enabled: true,
+            port: 7000,
+            dir: std::env::temp_dir().to_str().unwrap().to_string(),
+            cluster_config_file: format!("redis-rust-gossip-{}-{}.conf", name, std::process::id()),
+            ..Default::default()
+        };
+        let mut cluster = Cluster::new(&config);
+        cluster.nodes.clear();
+        for (i, id) in ["a", "b", "c"].iter().enumerate() {
+            let mut node = Node::new(id.repeat(40), "127.0.0.1".to_string(), 7000 + i as u16, 0);
+            node.config_epoch = i as u64 + 1;
+            cluster.nodes.insert(node.id.clone(), node);
+        }
+        cluster.myself = "a".repeat(40);
+        for slot in 0..16384 {
+            cluster.slots[slot] = Some(["a", "b", "c"][slot * 3 / 16384].repeat(40));
+        }
+        (cluster, Replication::new(&config))
+    }
+
+    #[test]
+    fn should_mark_failed_masters_and_vote_once() {
+        let (mut cluster, mut replication) = cluster("vote");
+        let (b, c) = ("b".repeat(40), "c".repeat(40));
+        cluster.nodes.get_mut(&b).unwrap().pfail = true;
+        cluster.mark_failed_if_needed(&b);
+        assert!(!cluster.nodes[&b].fail);
+
+        // A report from c makes a majority with this node.
+        let mut ping = Message::new(header(MessageType::Ping, &c, 3));
+        ping.gossip.push(super::Gossip {
+            id: b.clone(),
+            host: "127.0.0.1".to_string(),
+            port: 7001,
+            cport: 0,
+            pfail: true,
+            fail: false,
+        });
+        let replies = cluster.process(&tokens(&ping), None, &mut replication);
+        assert_eq!(replies.len(), 1);
+        assert!(cluster.nodes[&b].fail);
+        assert!(cluster.info().starts_with("cluster_state:fail\r\n"));
+
+        // A replica of b asks for a vote, which is given once per epoch.
+        let d = "d".repeat(40);
+        cluster.nodes.insert(
+            d.clone(),
+            Node::new(d.clone(), "127.0.0.1".to_string(), 7003, 0),
+        );
+        let mut request = header(MessageType::AuthRequest, &d, 2);
+        request.current_epoch = 4;
+        request.master_id = Some(b.clone());
+        request.slots = vec![(5462, 10922)];
+        let request = Message::new(request);
+        let replies = cluster.process(&tokens(&request), None, &mut replication);
+        assert_eq!(replies.len(), 1);
+        assert_eq!(cluster.last_vote_epoch, 4);
+        let replies = cluster.process(&tokens(&request), None, &mut replication);
+        assert!(replies.is_empty());
+        std::fs::remove_file(&cluster.path).unwrap();
+    }
+
+    #[test]
+    fn should_follow_the_node_that_took_all_slots() {
+        let (mut cluster, mut replication) = cluster("follow");
+        let (a, d) = ("a".repeat(40), "d".repeat(40));
+        cluster.nodes.insert(
+            d.clone(),
+            Node::new(d.clone(), "127.0.0.1".to_string(), 7003, 0),
+        );
+
+        // An older claim is ignored.
+        let mut pong = header(MessageType::Pong, &d, 1);
+        pong.slots = vec![(0, 5461)];
+        cluster.process(&tokens(&Message::new(pong.clone())), None, &mut replication);
+        assert_eq!(cluster.slots[0], Some(a.clone()));
+
+        pong.config_epoch = 5;
+        pong.current_epoch = 5;
+        cluster.process(&tokens(&Message::new(pong)), None, &mut replication);
+        assert_eq!(cluster.slots[0], Some(d.clone()));
+        assert_eq!(cluster.nodes[&a].master_id, Some(d));
+        assert!(replication.is_replica());
+        assert_eq!(cluster.current_epoch, 5);
+    }
+}
@@ -0,0 +1,244 @@
+//! The messages nodes exchange over the cluster bus. Each one is sent as a RESP array of bulk
+//! strings: a header in which the sender describes itself, followed by fields that depend on the
+//! type of the message.
+
+use crate::persistence::aof::encode_command;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum MessageType {
+    Ping,
+    Pong,
+    /// A ping that also asks the receiver to add the sender to its nodes.
+    Meet,
+    /// Tells that the cluster agreed that a node failed.
+    Fail,
+    /// Asks masters to vote for the sender to replace its failed master.
+    AuthRequest,
+    /// A vote for the replica that sent an `AuthRequest`.
+    AuthAck,
+}
+
+impl MessageType {
+    fn name(&self) -> &'static str {
+        match self {
+            MessageType::Ping => "PING",
+            MessageType::Pong => "PONG",
+            MessageType::Meet => "MEET",
+            MessageType::Fail => "FAIL",
+            MessageType::AuthRequest => "AUTHREQ",
+            MessageType::AuthAck => "AUTHACK",
+        }
+    }
+
+    fn from_name(name: &[u8]) -> Option<Self> {
+        match name {
+            b"PING" => Some(MessageType::Ping),
+            b"PONG" => Some(MessageType::Pong),
+            b"MEET" => Some(MessageType::Meet),
+            b"FAIL" => Some(MessageType::Fail),
+            b"AUTHREQ" => Some(MessageType::AuthRequest),
+            b"AUTHACK" => Some(MessageType::AuthAck),
+            _ => None,
+        }
+    }
+}
+
+/// What the sender of a message tells about itself.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Header {
+    pub kind: MessageType,
+    pub sender: String,
+    pub current_epoch: u64,
+    /// The config epoch of the sender, or of its master if it is a replica.
+    pub config_epoch: u64,
+    pub master_id: Option<String>,
+    pub host: String,
+    pub port: u16,
+    pub cport: u16,
+    /// The replication offset of the sender.
+    pub offset: u64,
+    /// The slots of the sender, or of its master if it is a replica, as ranges.
+    pub slots: Vec<(u16, u16)>,
+}
+
+/// What the sender of a message knows about another node.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Gossip {
+    pub id: String,
+    pub host: String,
+    pub port: u16,
+    pub cport: u16,
+    pub pfail: bool,
+    pub fail: bool,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Message {
+    pub header: Header,
+    /// Other nodes the sender knows, in pings.
+    pub gossip: Vec<Gossip>,
+    /// The node that failed, in `Fail`.
+    pub failed: Option<String>,
+}
+
+fn format_slots(slots: &[(u16, u16)]) -> String {
+    slots
+        .iter()
+        .map(|(start, end)| format!("{}-{}", start, end))
+        .collect::<Vec<_>>()
+        .join(",")
+}
+
+fn parse_slots(value: &str) -> Option<Vec<(u16, u16)>> {
+    if value.is_empty() {
+        return Some(Vec::new());
+    }
+    value
+        .split(',')
+        .map(|range| {
+            let (start, end) = range.split_once('-')?;
+            let (start, end) = (start.parse().ok()?, end.parse().ok()?);
+            match start <= end && (end as usize) < super::slot::SLOTS {
+                true => Some((start, end)),
+                false => None,
+            }
+        })
+        .collect()
+}
+
+impl Message {
+    pub fn new(header: Header) -> Self {
+        Message {
+            header,
+            gossip: Vec::new(),
+            failed: None,
+        }
+    }
+
+    pub fn encode(&self) -> Vec<u8> {
+        let header = &self.header;
+        let mut fields = vec![
+            header.kind.name().to_string(),
+            header.sender.clone(),
+            header.current_epoch.to_string(),
+            header.config_epoch.to_string(),
+            header.master_id.clone().unwrap_or_else(|| "-".to_string()),
+            header.host.clone(),
+            header.port.to_string(),
+            header.cport.to_string(),
+            header.offset.to_string(),
+            format_slots(&header.slots),
+        ];
+        if let Some(failed) = &self.failed {
+            fields.push(failed.clone());
+        }
+        for node in &self.gossip {
+            let flags = match (node.fail, node.pfail) {
+                (true, _) => "fail",
+                (false, true) => "pfail",
+                (false, false) => "-",
+            };
+            fields.extend([
+                node.id.clone(),
+                node.host.clone(),
+                node.port.to_string(),
+                node.cport.to_string(),
+                flags.to_string(),
+            ]);
+        }
+        let tokens: Vec<Vec<u8>> = fields.into_iter().map(String::into_bytes).collect();
+        encode_command(&tokens)
+    }
+
+    /// Decodes a message from the tokens of a RESP array. Returns `None` if it is malformed.
+    pub fn decode(tokens: &[Vec<u8>]) -> Option<Self> {
+        let fields = tokens
+            .iter()
+            .map(|t| String::from_utf8(t.clone()).ok())
+            .collect::<Option<Vec<String>>>()?;
+        if fields.len() < 10 {
+            return None;
+        }
+        let kind = MessageType::from_name(fields[0].as_bytes())?;
+        let header = Header {
+            kind,
+            sender: fields[1].clone(),
+            current_epoch: fields[2].parse().ok()?,
+            config_epoch: fields[3].parse().ok()?,
+            master_id: match fields[4].as_str() {
+                "-" => None,
+                v => Some(v.to_string()),
+            },
+            host: fields[5].clone(),
+            port: fields[6].parse().ok()?,
+            cport: fields[7].parse().ok()?,
+            offset: fields[8].parse().ok()?,
+            slots: parse_slots(&fields[9])?,
+        };
+        let mut message = Message::new(header);
+        let mut rest = &fields[10..];
+        if kind == MessageType::Fail {
+            let (failed, tail) = rest.split_first()?;
+            message.failed = Some(failed.clone());
+            rest = tail;
+        }
+        if rest.len() % 5 != 0 {
+            return None;
+        }
+        for node in rest.chunks(5) {
+            message.gossip.push(Gossip {
+                id: node[0].clone(),
+                host: node[1].clone(),
+                port: node[2].parse().ok()?,
+                cport: node[3].parse().ok()?,
+                pfail: node[4] == "pfail",
+                fail: node[4] == "fail",
+            });
+        }
+        Some(message)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{Gossip, Header, Message, MessageType};
+    use crate::utils::RequestDecoder;
+
+    #[test]
+    fn should_encode_and_decode_messages() {
+        let mut message = Message::new(Header {
+            kind: MessageType::Ping,
+            sender: "a".repeat(40),
+            current_epoch: 3,
+            config_epoch: 2,
+            master_id: None,
+            host: "127.0.0.1".to_string(),
+            port: 7000,
+            cport: 17000,
+            offset: 120,
+            slots: vec![(0, 100), (200, 200)],
+        });
+        message.gossip.push(Gossip {
+            id: "b".repeat(40),
+            host: "127.0.0.1".to_string(),
+            port: 7001,
+            cport: 17001,
+            pfail: true,
+            fail: false,
+        });
+        let mut decoder = RequestDecoder::new();
+        decoder.buffer_mut().extend(message.encode());
+        let tokens = decoder.decode().unwrap().unwrap();
+        assert_eq!(Message::decode(&tokens), Some(message.clone()));
+
+        message.header.kind = MessageType::Fail;
+        message.header.slots = Vec::new();
+        message.failed = Some("c".repeat(40));
+        message.gossip.clear();
+        decoder.buffer_mut().extend(message.encode());
+        let tokens = decoder.decode().unwrap().unwrap();
+        assert_eq!(Message::decode(&tokens), Some(message));
+
+        assert_eq!(Message::decode(&[b"PING".to_vec()]), None);
+    }
+}
@@ -0,0 +1,669 @@
+//! Cluster mode. The keys are spread over 16384 hash slots, each served by one master node, and
+//! a node only serves the keys of its own slots. Clients asking for other keys are redirected to
+//! the node serving them with `MOVED`, or with `ASK` while a slot is being moved between nodes.
+//!
+//! Nodes learn about each other and about who serves which slot by gossiping over the cluster
+//! bus, where they also detect failed masters and elect replicas to replace them.
+
+pub mod bus;
+mod gossip;
+mod message;
+mod nodes;
+pub mod slot;
+
+use crate::config::ServerConfig;
+use crate::data_store::now_ms;
+use crate::error::ClusterError;
+use crate::replication::Replication;
+use crate::utils::random_id;
+use slot::{key_slot, SLOTS};
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::path::PathBuf;
+use tokio::sync::mpsc::{UnboundedReceiver, UnboundedSender};
+
+/// A node of the cluster, as this node knows it.
+#[derive(Debug, Clone)]
+pub struct Node {
+    pub id: String,
+    pub host: String,
+    pub port: u16,
+    /// The port of the cluster bus, which nodes talk to each other on.
+    pub cport: u16,
+    /// The master this node replicates from, if it is a replica.
+    pub master_id: Option<String>,
+    pub config_epoch: u64,
+    /// Whether the node was met but did not answer yet, so that its ID is still a made up one.
+    pub handshake: bool,
+    /// Whether this node thinks the node is down, as it did not answer a ping in time.
+    pub pfail: bool,
+    /// Whether a majority of masters agreed that the node is down.
+    pub fail: bool,
+    /// When the node was marked as failed, in Unix milliseconds.
+    fail_time: u64,
+    /// When the unanswered ping to the node was sent, in Unix milliseconds, or 0 if none.
+    pub ping_sent: u64,
+    /// When the node last answered a ping, in Unix milliseconds.
+    pub pong_received: u64,
+    created: u64,
+    /// The replication offset the node last reported.
+    pub offset: u64,
+    /// The masters that reported the node as down, with the time of their last report.
+    fail_reports: HashMap<String, u64>,
+    /// When this node last voted for a replica of the node to replace it.
+    voted_time: u64,
+    /// The connection to the cluster bus of the node, if any.
+    link: Option<u64>,
+    link_attempt: u64,
+}
+
+impl Node {
+    fn new(id: String, host: String, port: u16, cport: u16) -> Self {
+        Node {
+            id,
+            host,
+            port,
+            cport,
+            master_id: None,
+            config_epoch: 0,
+            handshake: false,
+            pfail: false,
+            fail: false,
+            fail_time: 0,
+            ping_sent: 0,
+            pong_received: 0,
+            created: now_ms(),
+            offset: 0,
+            fail_reports: HashMap::new(),
+            voted_time: 0,
+            link: None,
+            link_attempt: 0,
+        }
+    }
+
+    pub fn addr(&self) -> String {
+        format!("{}:{}", self.host, self.port)
+    }
+
+    pub fn is_master(&self) -> bool {
+        self.master_id.is_none()
+    }
+}
+
+/// A range of consecutive slots served by the same master, and the nodes serving it, the master
+/// first.
+pub struct SlotRange {
+    pub start: u16,
+    pub end: u16,
+    pub nodes: Vec<Node>,
+}
+
+/// A master with its replicas, and the ranges of slots they serve.
+pub struct Shard {
+    pub slots: Vec<(u16, u16)>,
+    pub nodes: Vec<Node>,
+}
+
+/// The change `CLUSTER SETSLOT` makes to the state of a slot.
+#[derive(Debug)]
+pub enum SlotState {
+    /// The slot is being moved from this node to the given node.
+    Migrating(String),
+    /// The slot is being moved from the given node to this node.
+    Importing(String),
+    /// The slot is no longer being moved.
+    Stable,
+    /// The slot is now served by the given node, which ends its migration.
+    Node(String),
+}
+
+/// A connection to the cluster bus of another node, to be opened by a background task.
+pub struct BusLink {
+    pub id: u64,
+    pub host: String,
+    pub cport: u16,
+    /// The messages to send to the node.
+    pub outbox: UnboundedReceiver<Vec<u8>>,
+}
+
+/// The election a replica runs to replace its failed master.
+struct Election {
+    /// When to ask the masters for their votes, in Unix milliseconds. Replicas with more data
+    /// start earlier.
+    start: u64,
+    /// The epoch the replica asked for votes for, or 0 until it does.
+    epoch: u64,
+    votes: HashSet<String>,
+}
+
+pub struct Cluster {
+    pub enabled: bool,
+    /// The path of the cluster config file.
+    path: PathBuf,
+    /// The ID of this node.
+    pub myself: String,
+    nodes: BTreeMap<String, Node>,
+    /// The ID of the master serving each slot.
+    slots: Vec<Option<String>>,
+    /// Slots of this node being moved to another node, with the ID of that node.
+    migrating: BTreeMap<u16, String>,
+    /// Slots being moved to this node from another node, with the ID of that node.
+    importing: BTreeMap<u16, String>,
+    current_epoch: u64,
+    /// The last epoch this node voted in, so that it votes at most once per epoch.
+    last_vote_epoch: u64,
+    node_timeout: u64,
+    /// The messages to send on each connection to another node.
+    links: HashMap<u64, UnboundedSender<Vec<u8>>>,
+    next_link_id: u64,
+    election: Option<Election>,
+    /// Whether the config changed since it was last saved.
+    config_dirty: bool,
+}
+
+impl Cluster {
+    pub fn new(config: &ServerConfig) -> Self {
+        let host = match &config.cluster_announce_ip {
+            Some(v) => v.clone(),
+            None => match config.bind.first().map(|v| v.trim_start_matches('-')) {
+                Some("*" | "::*" | "0.0.0.0" | "::") | None => "127.0.0.1".to_string(),
+                Some(v) => v.to_string(),
+            },
+        };
+        let cport = match config.cluster_port {
+            0 => config.port.saturating_add(10000),
+            v => v,
+        };
+        let myself = Node::new(random_id(), host, config.port, cport);
+        Cluster {
+            enabled: config.cluster_enabled,
+            path: PathBuf::from(&config.dir).join(&config.cluster_config_file),
+            myself: myself.id.clone(),
+            nodes: BTreeMap::from([(myself.id.clone(), myself)]),
+            slots: vec![None; SLOTS],
+            migrating: BTreeMap::new(),
+            importing: BTreeMap::new(),
+            current_epoch: 0,
+            last_vote_epoch: 0,
+            node_timeout: config.cluster_node_timeout,
+            links: HashMap::new(),
+            next_link_id: 1,
+            election: None,
+            config_dirty: false,
+        }
+    }
+
+    fn myself(&self) -> &Node {
+        &self.nodes[&self.myself]
+    }
+
+    fn myself_mut(&mut self) -> &mut Node {
+        self.nodes.get_mut(&self.myself).unwrap()
+    }
+
+    /// The port this node listens on for the cluster bus.
+    pub fn bus_port(&self) -> u16 {
+        self.myself().cport
+    }
+
+    /// The address of the master of this node, if it is a replica.
+    pub fn master_addr(&self) -> Option<(String, u16)> {
+        let master = self.nodes.get(self.myself().master_id.as_ref()?)?;
+        Some((master.host.clone(), master.port))
+    }
+
+    /// Starts a handshake with the node at `host:port`, which becomes known once it answers.
+    pub fn meet(&mut self, host: &str, port: u16, cport: u16) {
+        let already_met = self.nodes.values().any(|node| {
+            node.handshake && node.host == host && node.port == port && node.cport == cport
+        });
+        if already_met {
+            return;
+        }
+        let mut node = Node::new(random_id(), host.to_string(), port, cport);
+        node.handshake = true;
+        self.nodes.insert(node.id.clone(), node);
+    }
+
+    /// Turns this node into a replica of the master `id`. A master can only do so once it has
+    /// no slots left.
+    pub fn replicate(
+        &mut self,
+        id: &str,
+        replication: &mut Replication,
+    ) -> Result<(), ClusterError> {
+        let Some(master) = self.nodes.get(id).filter(|node| !node.handshake) else {
+            return Err(ClusterError::UnknownNode(id.to_string()));
+        };
+        if id == self.myself {
+            return Err(ClusterError::ReplicateMyself);
+        }
+        if !master.is_master() {
+            return Err(ClusterError::ReplicateReplica);
+        }
+        if self.myself().is_master() && self.slots.iter().flatten().any(|o| *o == self.myself) {
+            return Err(ClusterError::NotEmpty);
+        }
+        let (host, port) = (master.host.clone(), master.port);
+        self.myself_mut().master_id = Some(id.to_string());
+        self.migrating.clear();
+        self.importing.clear();
+        replication.set_master(&host, port);
+        self.save()
+    }
+
+    /// The number of masters serving slots, a majority of which must agree for a master to be
+    /// marked as failed or for a replica to replace it.
+    fn size(&self) -> usize {
+        let mut masters: Vec<&str> = Vec::new();
+        for (_, _, owner) in self.slot_runs() {
+            if !masters.contains(&owner) {
+                masters.push(owner);
+            }
+        }
+        masters.len()
+    }
+
+    fn quorum(&self) -> usize {
+        self.size() / 2 + 1
+    }
+
+    fn address_of(&self, id: &str) -> String {
+        self.nodes.get(id).map(Node::addr).unwrap_or_default()
+    }
+
+    /// Checks that this node can serve a command on `keys`, which must all be in the same slot.
+    /// Keys that are not in the database, as told by `exists`, may have been moved already if
+    /// their slot is being migrated. A client sends `ASKING` first to be served by the node a
+    /// slot is being imported to.
+    pub fn check_keys(
+        &self,
+        keys: &[&[u8]],
+        asking: bool,
+        mut exists: impl FnMut(&[u8]) -> bool,
+    ) -> Result<(), ClusterError> {
+        let Some(first) = keys.first() else {
+            return Ok(());
+        };
+        let slot = key_slot(first);
+        if keys[1..].iter().any(|key| key_slot(key) != slot) {
+            return Err(ClusterError::CrossSlot);
+        }
+        let missing = keys.iter().filter(|key| !exists(key)).count();
+        if self.slots[slot as usize].as_ref() == Some(&self.myself) {
+            return match self.migrating.get(&slot) {
+                Some(target) if missing == keys.len() => {
+                    Err(ClusterError::Ask(slot, self.address_of(target)))
+                }
+                Some(_) if missing > 0 => Err(ClusterError::TryAgain),
+                _ => Ok(()),
+            };
+        }
+        if asking && self.importing.contains_key(&slot) {
+            return match missing > 0 && keys.len() > 1 {
+                true => Err(ClusterError::TryAgain),
+                false => Ok(()),
+            };
+        }
+        match &self.slots[slot as usize] {
+            Some(owner) => Err(ClusterError::Moved(slot, self.address_of(owner))),
+            None => Err(ClusterError::SlotNotServed),
+        }
+    }
+
+    /// Assigns unassigned slots to this node.
+    pub fn add_slots(&mut self, slots: &[u16]) -> Result<(), ClusterError> {
+        let mut seen = HashSet::new();
+        for &slot in slots {
+            if self.slots[slot as usize].is_some() {
+                return Err(ClusterError::SlotBusy(slot));
+            }
+            if !seen.insert(slot) {
+                return Err(ClusterError::DuplicateSlot(slot));
+            }
+        }
+        for &slot in slots {
+            self.slots[slot as usize] = Some(self.myself.clone());
+            self.importing.remove(&slot);
+        }
+        self.save()
+    }
+
+    /// Changes the state of `slot` during its migration between two nodes. `keys` is the number
+    /// of keys this node holds in the slot, which must be 0 to give the slot to another node.
+    pub fn set_slot(
+        &mut self,
+        slot: u16,
+        state: &SlotState,
+        keys: usize,
+    ) -> Result<(), ClusterError> {
+        if !self.myself().is_master() {
+            return Err(ClusterError::SetSlotOnReplica);
+        }
+        let owned = self.slots[slot as usize].as_ref() == Some(&self.myself);
+        let target = match state {
+            SlotState::Migrating(id) | SlotState::Importing(id) | SlotState::Node(id) => {
+                match self.nodes.get(id).filter(|node| !node.handshake) {
+                    Some(node) if !node.is_master() => return Err(ClusterError::TargetNotMaster),
+                    Some(_) => Some(id.clone()),
+                    None => return Err(ClusterError::UnknownNode(id.clone())),
+                }
+            }
+            SlotState::Stable => None,
+        };
+        match state {
+            SlotState::Migrating(id) => {
+                if !owned {
+                    return Err(ClusterError::NotSlotOwner(slot));
+                }
+                self.migrating.insert(slot, id.clone());
+            }
+            SlotState::Importing(id) => {
+                if owned {
+                    return Err(ClusterError::AlreadySlotOwner(slot));
+                }
+                self.importing.insert(slot, id.clone());
+            }
+            SlotState::Stable => {
+                self.migrating.remove(&slot);
+                self.importing.remove(&slot);
+            }
+            SlotState::Node(id) => {
+                if owned && *id != self.myself && keys > 0 {
+                    return Err(ClusterError::SlotNotEmpty(slot));
+                }
+                if keys == 0 {
+                    self.migrating.remove(&slot);
+                }
+                // Taking over an imported slot needs a config epoch greater than that of the
+                // previous owner, for the other nodes to accept the change.
+                if *id == self.myself && self.importing.remove(&slot).is_some() {
+                    self.current_epoch += 1;
+                    let epoch = self.current_epoch;
+                    self.myself_mut().config_epoch = epoch;
+                }
+                self.slots[slot as usize] = target;
+            }
+        }
+        self.save()
+    }
+
+    /// Returns the runs of consecutive slots served by the same master, with its ID.
+    fn slot_runs(&self) -> Vec<(u16, u16, &str)> {
+        let mut runs: Vec<(u16, u16, &str)> = Vec::new();
+        for (slot, owner) in self.slots.iter().enumerate() {
+            let Some(owner) = owner else {
+                continue;
+            };
+            match runs.last_mut() {
+                Some((_, end, id)) if *id == owner && *end as usize + 1 == slot => {
+                    *end = slot as u16
+                }
+                _ => runs.push((slot as u16, slot as u16, owner)),
+            }
+        }
+        runs
+    }
+
+    /// Returns `master` followed by its replicas.
+    fn with_replicas(&self, master: &str) -> Vec<Node> {
+        self.nodes
+            .get(master)
+            .into_iter()
+            .chain(
+                self.nodes
+                    .values()
+                    .filter(|node| node.master_id.as_deref() == Some(master)),
+            )
+            .cloned()
+            .collect()
+    }
+
+    /// Returns the ranges of slots served by `id`.
+    fn slots_of(&self, id: &str) -> Vec<(u16, u16)> {
+        self.slot_runs()
+            .into_iter()
+            .filter(|(_, _, owner)| *owner == id)
+            .map(|(start, end, _)| (start, end))
+            .collect()
+    }
+
+    /// The reply to `CLUSTER SLOTS`.
+    pub fn slot_ranges(&self) -> Vec<SlotRange> {
+        self.slot_runs()
+            .into_iter()
+            .map(|(start, end, owner)| SlotRange {
+                start,
+                end,
+                nodes: self.with_replicas(owner),
+            })
+            .collect()
+    }
+
+    /// The reply to `CLUSTER SHARDS`.
+    pub fn shards(&self) -> Vec<Shard> {
+        let runs = self.slot_runs();
+        self.nodes
+            .values()
+            .filter(|node| node.is_master() && !node.handshake)
+            .map(|master| Shard {
+                slots: runs
+                    .iter()
+                    .filter(|(_, _, owner)| *owner == master.id)
+                    .map(|&(start, end, _)| (start, end))
+                    .collect(),
+                nodes: self.with_replicas(&master.id),
+            })
+            .collect()
+    }
+
+    /// The reply to `CLUSTER INFO`. The cluster is only up when every slot is served by a master
+    /// that did not fail.
+    pub fn info(&self) -> String {
+        let owners: Vec<&Node> = self
+            .slots
+            .iter()
+            .flatten()
+            .map(|id| &self.nodes[id])
+            .collect();
+        let assigned = owners.len();
+        let pfail = owners.iter().filter(|node| node.pfail).count();
+        let fail = owners.iter().filter(|node| node.fail).count();
+        let myself = self.myself();
+        let my_epoch = match &myself.master_id {
+            Some(master) => self.nodes.get(master).map_or(0, |node| node.config_epoch),
+            None => myself.config_epoch,
+        };
+        format!(
+            "cluster_state:{}\r\ncluster_slots_assigned:{}\r\ncluster_slots_ok:{}\r\n\
+             cluster_slots_pfail:{}\r\ncluster_slots_fail:{}\r\ncluster_known_nodes:{}\r\n\
+             cluster_size:{}\r\ncluster_current_epoch:{}\r\ncluster_my_epoch:{}\r\n",
+            match assigned == SLOTS && fail == 0 {
+                true => "ok",
+                false => "fail",
+            },
+            assigned,
+            assigned - pfail - fail,
+            pfail,
+            fail,
+            self.nodes.len(),
+            self.size(),
+            self.current_epoch,
+            my_epoch
+        )
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{Cluster, SlotState};
+    use crate::cluster::slot::key_slot;
+    use crate::config::ServerConfig;
+
+    fn cluster() -> Cluster {
+        Cluster::new(&ServerConfig {
+            cluster_enabled: true,
+            dir: std::env::temp_dir().to_str().unwrap().to_string(),
+            cluster_config_file: format!("redis-rust-cluster-{}.conf", std::process::id()),
+            ..Default::default()
+        })
+    }
+
+    #[test]
+    fn should_redirect_keys_of_other_nodes() {
+        let mut cluster = cluster();
+        cluster
+            .parse(&format!(
+                "{} 127.0.0.1:6379@16379 myself,master - 0 0 1 connected 0-8191\n\
+                 {} 127.0.0.1:6380@16380 master - 0 0 2 connected 8192-16383\n\
+                 vars currentEpoch 2 lastVoteEpoch 0\n",
+                "a".repeat(40),
+                "b".repeat(40)
+            ))
+            .unwrap();
+        let foo = key_slot(b"foo");
+        let bar = key_slot(b"bar");
+        assert!(cluster.check_keys(&[b"bar"], false, |_| true).is_ok());
+        assert!(cluster.check_keys(&[], false, |_| true).is_ok());
+        let err = cluster
+            .check_keys(&[b"foo"], false, |_| true)
+            .err()
+            .unwrap();
+        assert_eq!(err.to_string(), format!("MOVED {} 127.0.0.1:6380", foo));
+        let err = cluster
+            .check_keys(&[b"foo", b"bar"], false, |_| true)
+            .err()
+            .unwrap();
+        assert_eq!(
+            err.to_string(),
+            "CROSSSLOT Keys in request don't hash to the same slot".to_string()
+        );
+        assert!(cluster
+            .check_keys(&[b"{bar}1", b"{bar}2"], false, |_| true)
+            .is_ok());
+
+        // Missing keys of a slot being migrated are looked up on the target.
+        cluster.migrating.insert(bar, "b".repeat(40));
+        let err = cluster
+            .check_keys(&[b"bar"], false, |_| false)
+            .err()
+            .unwrap();
+        assert_eq!(err.to_string(), format!("ASK {} 127.0.0.1:6380", bar));
+        let err = cluster
+            .check_keys(&[b"{bar}1", b"{bar}2"], false, |k| k == b"{bar}1")
+            .err()
+            .unwrap();
+        assert_eq!(
+            err.to_string(),
+            "TRYAGAIN Multiple keys request during rehashing of slot".to_string()
+        );
+
+        // Only clients that sent `ASKING` are served for a slot being imported.
+        cluster.importing.insert(foo, "b".repeat(40));
+        assert!(cluster.check_keys(&[b"foo"], true, |_| false).is_ok());
+        assert!(cluster.check_keys(&[b"foo"], false, |_| false).is_err());
+    }
+
+    #[test]
+    fn should_assign_slots_to_myself() {
+        let mut cluster = cluster();
+        let err = cluster
+            .check_keys(&[b"foo"], false, |_| true)
+            .err()
+            .unwrap();
+        assert_eq!(
+            err.to_string(),
+            "CLUSTERDOWN Hash slot not served".to_string()
+        );
+        cluster.add_slots(&[0, 1, 2, 5]).unwrap();
+        let err = cluster.add_slots(&[3, 2]).err().unwrap();
+        assert_eq!(err.to_string(), "ERR Slot 2 is already busy".to_string());
+        let err = cluster.add_slots(&[3, 3]).err().unwrap();
+        assert_eq!(
+            err.to_string(),
+            "ERR Slot 3 specified multiple times".to_string()
+        );
+        let ranges = cluster.slot_ranges();
+        assert_eq!(
+            ranges
+                .iter()
+                .map(|r| (r.start, r.end, r.nodes[0].id.clone()))
+                .collect::<Vec<_>>(),
+            vec![
+                (0, 2, cluster.myself.clone()),
+                (5, 5, cluster.myself.clone())
+            ]
+        );
+        assert!(cluster
+            .info()
+            .starts_with("cluster_state:fail\r\ncluster_slots_assigned:4\r\n"));
+        std::fs::remove_file(&cluster.path).unwrap();
+    }
+
+    #[test]
+    fn should_move_slots_between_nodes() {
+        let mut cluster = cluster();
+        cluster.path.set_extension("setslot");
+        let (a, b) = ("a".repeat(40), "b".repeat(40));
+        cluster
+            .parse(&format!(
+                "{} 127.0.0.1:6379@16379 myself,master - 0 0 1 connected 0-8191\n\
+                 {} 127.0.0.1:6380@16380 master - 0 0 2 connected 8192-16383\n\
+                 vars currentEpoch 2 lastVoteEpoch 0\n",
+                a, b
+            ))
+            .unwrap();
+        cluster
+            .set_slot(0, &SlotState::Migrating(b.clone()), 3)
+            .unwrap();
+        assert_eq!(cluster.migrating.get(&0), Some(&b));
+        let err = cluster
+            .set_slot(9000, &SlotState::Migrating(b.clone()), 0)
+            .err()
+            .unwrap();
+        assert_eq!(
+            err.to_string(),
+            "ERR I'm not the owner of hash slot 9000".to_string()
+        );
+        let err = cluster
+            .set_slot(0, &SlotState::Node(b.clone()), 3)
+            .err()
+            .unwrap();
+        assert_eq!(
+            err.to_string(),
+            "ERR Can't assign hashslot 0 to a different node while I still hold keys for this hash slot."
+                .to_string()
+        );
+        cluster.set_slot(0, &SlotState::Node(b.clone()), 0).unwrap();
+        assert_eq!(cluster.slots[0], Some(b.clone()));
+        assert!(cluster.migrating.is_empty());
+
+        // Taking over an imported slot bumps the config epoch of this node.
+        cluster
+            .set_slot(9000, &SlotState::Importing(b.clone()), 0)
+            .unwrap();
+        let err = cluster
+            .set_slot(1, &SlotState::Importing(b.clone()), 0)
+            .err()
+            .unwrap();
+        assert_eq!(
+            err.to_string(),
+            "ERR I'm already the owner of hash slot 1".to_string()
+        );
+        cluster
+            .set_slot(9000, &SlotState::Node(a.clone()), 0)
+            .unwrap();
+        assert_eq!(cluster.slots[9000], Some(a.clone()));
+        assert!(cluster.importing.is_empty());
+        assert_eq!(cluster.myself().config_epoch, 3);
+
+        let err = cluster
+            .set_slot(1, &SlotState::Node("c".repeat(40)), 0)
+            .err()
+            .unwrap();
+        assert_eq!(
+            err.to_string(),
+            format!("ERR Unknown node {}", "c".repeat(40))
+        );
+        std::fs::remove_file(&cluster.path).unwrap();
+    }
+}
@@ -0,0 +1,316 @@
+//! The cluster config file, in the `nodes.conf` format of Redis: a line per node, the same as in
+//! the reply to `CLUSTER NODES`, followed by a line with the epochs.
+//!
+//! ```text
+//! <id> <ip:port@cport> <flags> <master> <ping-sent> <pong-recv> <config-epoch> <link> <slot>...
+//! vars currentEpoch <epoch> lastVoteEpoch <epoch>
+//! ```
+//!
+//! A slot is either a number, a `start-end` range, `[slot->-id]` for a slot being migrated to
+//! another node or `[slot-<-id]` for a slot being imported from another node.
+
+use super::slot::SLOTS;
+use super::{Cluster, Node};
+use crate::error::ClusterError;
+use std::collections::BTreeMap;
+use std::fmt::Write;
+
+/// Parses `ip:port@cport`, which may be followed by `,hostname` and other fields.
+fn parse_address(value: &str) -> Option<(String, u16, u16)> {
+    let value = value.split(',').next()?;
+    let (addr, cport) = value.split_once('@')?;
+    let (host, port) = addr.rsplit_once(':')?;
+    Some((host.to_string(), port.parse().ok()?, cport.parse().ok()?))
+}
+
+fn parse_slot(value: &str) -> Option<u16> {
+    value
+        .parse()
+        .ok()
+        .filter(|&slot: &u16| (slot as usize) < SLOTS)
+}
+
+impl Cluster {
+    /// Loads the cluster config file, or creates it if there is none yet. The address of this
+    /// node is taken from the server config rather than from the file.
+    pub fn load(&mut self) -> Result<(), ClusterError> {
+        if !self.enabled {
+            return Ok(());
+        }
+        match std::fs::read_to_string(&self.path) {
+            Ok(text) => {
+                let address = self.nodes[&self.myself].clone();
+                self.parse(&text)?;
+                let myself = self.nodes.get_mut(&self.myself).unwrap();
+                myself.host = address.host;
+                myself.port = address.port;
+                myself.cport = address.cport;
+                log::info!("Cluster config loaded, node ID {}", self.myself);
+            }
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
+                log::info!("No cluster config found, new node ID {}", self.myself);
+            }
+            Err(e) => {
+                return Err(ClusterError::ReadFailed(
+                    self.path.display().to_string(),
+                    e.to_string(),
+                ))
+            }
+        }
+        self.save()
+    }
+
+    /// Writes the cluster config file. A temporary file is renamed over it, so that a crash never
+    /// leaves a partial file behind.
+    pub fn save(&self) -> Result<(), ClusterError> {
+        let text = format!(
+            "{}vars currentEpoch {} lastVoteEpoch {}\n",
+            self.describe(false),
+            self.current_epoch,
+            self.last_vote_epoch
+        );
+        let mut temp = self.path.clone().into_os_string();
+        temp.push(format!(".tmp-{}", std::process::id()));
+        std::fs::write(&temp, text)
+            .and_then(|_| std::fs::rename(&temp, &self.path))
+            .map_err(|e| {
+                log::error!("Failed to save cluster config: {}", e);
+                ClusterError::SaveFailed(e.to_string())
+            })
+    }
+
+    /// The reply to `CLUSTER NODES`.
+    pub fn nodes_text(&self) -> String {
+        self.describe(true)
+    }
+
+    /// Describes the nodes, one per line. Nodes still in a handshake are left out of the config
+    /// file, as their IDs are made up.
+    fn describe(&self, handshake: bool) -> String {
+        let runs = self.slot_runs();
+        let mut text = String::new();
+        for node in self.nodes.values().filter(|n| handshake || !n.handshake) {
+            let mut flags = Vec::new();
+            if node.id == self.myself {
+                flags.push("myself");
+            }
+            flags.push(match node.is_master() {
+                true => "master",
+                false => "slave",
+            });
+            if node.fail {
+                flags.push("fail");
+            } else if node.pfail {
+                flags.push("fail?");
+            }
+            if node.handshake {
+                flags.push("handshake");
+            }
+            let connected = node.id == self.myself || node.link.is_some();
+            let _ = write!(
+                text,
+                "{} {}:{}@{} {} {} {} {} {} {}",
+                node.id,
+                node.host,
+                node.port,
+                node.cport,
+                flags.join(","),
+                node.master_id.as_deref().unwrap_or("-"),
+                node.ping_sent,
+                node.pong_received,
+                node.config_epoch,
+                match connected {
+                    true => "connected",
+                    false => "disconnected",
+                }
+            );
+            for &(start, end, _) in runs.iter().filter(|(_, _, owner)| *owner == node.id) {
+                let _ = match start == end {
+                    true => write!(text, " {}", start),
+                    false => write!(text, " {}-{}", start, end),
+                };
+            }
+            if node.id == self.myself {
+                for (slot, target) in &self.migrating {
+                    let _ = write!(text, " [{}->-{}]", slot, target);
+                }
+                for (slot, source) in &self.importing {
+                    let _ = write!(text, " [{}-<-{}]", slot, source);
+                }
+            }
+            text.push('\n');
+        }
+        text
+    }
+
+    /// Replaces the view of the cluster with the one of a cluster config file.
+    pub(super) fn parse(&mut self, text: &str) -> Result<(), ClusterError> {
+        let mut myself = None;
+        let mut nodes = BTreeMap::new();
+        let mut slots = vec![None; SLOTS];
+        let mut migrating = BTreeMap::new();
+        let mut importing = BTreeMap::new();
+        let (mut current_epoch, mut last_vote_epoch) = (0, 0);
+        for (i, line) in text.lines().enumerate() {
+            let invalid = |details: String| ClusterError::InvalidConfig(i + 1, details);
+            let fields: Vec<&str> = line.split_whitespace().collect();
+            match fields.as_slice() {
+                [] => continue,
+                ["vars", vars @ ..] => {
+                    for pair in vars.chunks(2) {
+                        let value = match pair {
+                            [_, value] => value
+                                .parse()
+                                .map_err(|_| invalid(format!("invalid epoch `{}`", value)))?,
+                            _ => return Err(invalid("missing value".to_string())),
+                        };
+                        match pair[0] {
+                            "currentEpoch" => current_epoch = value,
+                            "lastVoteEpoch" => last_vote_epoch = value,
+                            _ => (),
+                        }
+                    }
+                    continue;
+                }
+                fields if fields.len() < 8 => {
+                    return Err(invalid("missing fields".to_string()));
+                }
+                _ => (),
+            }
+            let id = fields[0].to_string();
+            let (host, port, cport) = parse_address(fields[1])
+                .ok_or_else(|| invalid(format!("invalid address `{}`", fields[1])))?;
+            if fields[2].split(',').any(|flag| flag == "myself") {
+                myself = Some(id.clone());
+            }
+            let master_id = match fields[3] {
+                "-" => None,
+                v => Some(v.to_string()),
+            };
+            let config_epoch = fields[6]
+                .parse()
+                .map_err(|_| invalid(format!("invalid config epoch `{}`", fields[6])))?;
+            for spec in &fields[8..] {
+                let invalid_slot = || invalid(format!("invalid slot `{}`", spec));
+                if let Some(spec) = spec.strip_prefix('[').and_then(|s| s.strip_suffix(']')) {
+                    if let Some((slot, target)) = spec.split_once("->-") {
+                        migrating.insert(parse_slot(slot).ok_or_else(invalid_slot)?, target);
+                    } else if let Some((slot, source)) = spec.split_once("-<-") {
+                        importing.insert(parse_slot(slot).ok_or_else(invalid_slot)?, source);
+                    } else {
+                        return Err(invalid_slot());
+                    }
+                    continue;
+                }
+                let (start, end) = match spec.split_once('-') {
+                    Some((start, end)) => (parse_slot(start), parse_slot(end)),
+                    None => (parse_slot(spec), parse_slot(spec)),
+                };
+                match (start, end) {
+                    (Some(start), Some(end)) if start <= end => {
+                        for slot in start..=end {
+                            slots[slot as usize] = Some(id.clone());
+                        }
+                    }
+                    _ => return Err(invalid_slot()),
+                }
+            }
+            let mut node = Node::new(id.clone(), host, port, cport);
+            node.master_id = master_id;
+            node.config_epoch = config_epoch;
+            nodes.insert(id, node);
+        }
+        let myself = myself.ok_or(ClusterError::MissingMyself)?;
+        // Slots moving from or to a node that is not in the file cannot be redirected to.
+        let unknown = migrating
+            .values()
+            .chain(importing.values())
+            .find(|id| !nodes.contains_key(**id));
+        if let Some(id) = unknown {
+            return Err(ClusterError::InvalidConfig(
+                text.lines().count(),
+                format!("unknown node `{}`", id),
+            ));
+        }
+        self.myself = myself;
+        self.nodes = nodes;
+        self.slots = slots;
+        self.migrating = migrating
+            .into_iter()
+            .map(|(slot, id)| (slot, id.to_string()))
+            .collect();
+        self.importing = importing
+            .into_iter()
+            .map(|(slot, id)| (slot, id.to_string()))
+            .collect();
+        self.current_epoch = current_epoch;
+        self.last_vote_epoch = last_vote_epoch;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::cluster::Cluster;
+    use crate::config::ServerConfig;
+
+    #[test]
+    fn should_save_and_load_config_file() {
+        let config = ServerConfig {
+            cluster_enabled: true,
+            port: 7000,
+            dir: std::env::temp_dir().to_str().unwrap().to_string(),
+            cluster_config_file: format!("redis-rust-nodes-{}.conf", std::process::id()),
+            ..Default::default()
+        };
+        let path = std::path::Path::new(&config.dir).join(&config.cluster_config_file);
+        let a = "a".repeat(40);
+        let b = "b".repeat(40);
+        let text = format!(
+            "{a} 10.0.0.1:6379@16379,host-a myself,master - 0 0 1 connected 0-100 200 [300->-{b}]\n\
+             {b} 10.0.0.2:6379@16379 master - 0 0 2 connected 101-199\n\
+             {c} 10.0.0.3:6379@16379 slave {b} 0 0 2 connected\n\
+             vars currentEpoch 5 lastVoteEpoch 3\n",
+            c = "c".repeat(40)
+        );
+        std::fs::write(&path, &text).unwrap();
+        let mut cluster = Cluster::new(&config);
+        cluster.load().unwrap();
+        assert_eq!(cluster.myself, a);
+        assert_eq!(cluster.slot_ranges().len(), 3);
+        assert_eq!(cluster.shards()[1].nodes.len(), 2);
+        assert_eq!(cluster.migrating.get(&300), Some(&b));
+
+        // The address of this node comes from the server config, and the other nodes are not
+        // connected yet.
+        let saved = std::fs::read_to_string(&path).unwrap();
+        assert_eq!(
+            saved,
+            text.replace("10.0.0.1:6379@16379,host-a", "127.0.0.1:7000@17000")
+                .replace("2 connected", "2 disconnected")
+        );
+        let mut cluster = Cluster::new(&config);
+        cluster.load().unwrap();
+        assert_eq!(cluster.myself, a);
+        assert_eq!(cluster.current_epoch, 5);
+        std::fs::remove_file(&path).unwrap();
+
+        for (text, message) in [
+            (
+                format!("{a} 10.0.0.1:6379 myself,master - 0 0 1 connected\n"),
+                "invalid cluster config at line 1: invalid address `10.0.0.1:6379`",
+            ),
+            (
+                format!("{a} 10.0.0.1:6379@16379 myself,master - 0 0 1 connected 16384\n"),
+                "invalid cluster config at line 1: invalid slot `16384`",
+            ),
+            (
+                format!("{a} 10.0.0.1:6379@16379 master - 0 0 1 connected\n"),
+                "invalid cluster config: no node is flagged as myself",
+            ),
+        ] {
+            let err = Cluster::new(&config).parse(&text).err().unwrap();
+            assert_eq!(err.to_string(), message.to_string());
+        }
+    }
+}
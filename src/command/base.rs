@@ -1,5 +1,7 @@
 use crate::data_store::DataStore;
 use crate::execution_result::ExecutionResult;
+use crate::server::Server;
+use crate::session::Session;
 
 pub trait Command {
     fn execute(
@@ -7,3 +9,21 @@ pub trait Command {
         data_store: &mut DataStore,
     ) -> Result<Box<dyn ExecutionResult>, Box<dyn std::error::Error>>;
 }
+
+/// A command that acts on the connection it was received on rather than on the data store.
+pub trait ConnectionCommand {
+    fn execute(
+        &self,
+        session: &mut Session,
+    ) -> Result<Box<dyn ExecutionResult>, Box<dyn std::error::Error>>;
+}
+
+/// A command that acts on the server as a whole rather than only on the selected database, e.g. to
+/// move keys between databases.
+pub trait ServerCommand {
+    fn execute(
+        &self,
+        server: &mut Server,
+        session: &mut Session,
+    ) -> Result<Box<dyn ExecutionResult>, Box<dyn std::error::Error>>;
+}
@@ -0,0 +1,126 @@
+use crate::command::ConnectionCommand;
+use crate::error::RequestError;
+use crate::execution_result::connection::HelloResult;
+use crate::execution_result::{ExecutionResult, RespVersion};
+use crate::session::Session;
+
+#[derive(Debug)]
+pub struct HelloCommand {
+    protocol: Option<RespVersion>,
+    name: Option<Vec<u8>>,
+}
+
+impl HelloCommand {
+    pub fn new(tokens: Vec<Vec<u8>>) -> Result<Box<Self>, RequestError> {
+        let mut iter = tokens.into_iter();
+        let protocol = match iter.next() {
+            Some(v) => match String::from_utf8_lossy(&v).parse::<i64>() {
+                Ok(2) => Some(RespVersion::Resp2),
+                Ok(3) => Some(RespVersion::Resp3),
+                Ok(_) => return Err(RequestError::UnsupportedProtocolVersion),
+                Err(_) => return Err(RequestError::InvalidProtocolVersion),
+            },
+            None => None,
+        };
+        let mut name = None;
+        while let Some(option) = iter.next() {
+            match option.to_ascii_lowercase().as_slice() {
+                // No password can be configured yet, so every client is allowed in as the
+                // default user whatever the credentials.
+                b"auth" => {
+                    if iter.next().is_none() || iter.next().is_none() {
+                        return Err(RequestError::SyntaxError);
+                    }
+                }
+                b"setname" => match iter.next() {
+                    Some(v) if v.iter().all(|c| c.is_ascii_graphic()) => name = Some(v),
+                    Some(_) => return Err(RequestError::InvalidClientName),
+                    None => return Err(RequestError::SyntaxError),
+                },
+                _ => return Err(RequestError::SyntaxError),
+            }
+        }
+        Ok(Box::new(HelloCommand { protocol, name }))
+    }
+}
+
+impl ConnectionCommand for HelloCommand {
+    fn execute(
+        &self,
+        session: &mut Session,
+    ) -> Result<Box<dyn ExecutionResult>, Box<dyn std::error::Error>> {
+        if let Some(protocol) = self.protocol {
+            session.protocol = protocol;
+        }
+        if let Some(name) = &self.name {
+            session.name = Some(name.clone());
+        }
+        Ok(Box::new(HelloResult {
+            id: session.id,
+            protocol: session.protocol,
+        }))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::HelloCommand;
+    use crate::command::ConnectionCommand;
+    use crate::execution_result::RespVersion;
+    use crate::session::Session;
+
+    #[test]
+    fn should_reject_invalid_protocol_version() {
+        let err = HelloCommand::new(vec![b"two".to_vec()]).err().unwrap();
+        assert_eq!(
+            err.to_string(),
+            "ERR Protocol version is not an integer or out of range".to_string()
+        );
+        let err = HelloCommand::new(vec![b"4".to_vec()]).err().unwrap();
+        assert_eq!(
+            err.to_string(),
+            "NOPROTO unsupported protocol version".to_string()
+        );
+    }
+
+    #[test]
+    fn should_parse_options() {
+        let v = HelloCommand::new(vec![
+            b"3".to_vec(),
+            b"AUTH".to_vec(),
+            b"default".to_vec(),
+            b"pass".to_vec(),
+            b"setname".to_vec(),
+            b"conn".to_vec(),
+        ])
+        .unwrap();
+        assert_eq!(v.protocol, Some(RespVersion::Resp3));
+        assert_eq!(v.name, Some(b"conn".to_vec()));
+        let err = HelloCommand::new(vec![b"3".to_vec(), b"AUTH".to_vec(), b"default".to_vec()])
+            .err()
+            .unwrap();
+        assert_eq!(err.to_string(), "ERR syntax error".to_string());
+        let err = HelloCommand::new(vec![b"3".to_vec(), b"SETNAME".to_vec(), b"a b".to_vec()])
+            .err()
+            .unwrap();
+        assert_eq!(
+            err.to_string(),
+            "ERR Client names cannot contain spaces, newlines or special characters.".to_string()
+        );
+    }
+
+    #[test]
+    fn should_switch_protocol() {
+        let mut session = Session::new();
+        assert_eq!(session.protocol, RespVersion::Resp2);
+        let cmd = HelloCommand::new(vec![b"3".to_vec()]).unwrap();
+        cmd.execute(&mut session).unwrap();
+        assert_eq!(session.protocol, RespVersion::Resp3);
+
+        // Without a protocol version the current one is kept.
+        let cmd = HelloCommand::new(vec![]).unwrap();
+        let result = cmd.execute(&mut session).unwrap();
+        assert_eq!(session.protocol, RespVersion::Resp3);
+        assert!(result.to_string().contains("proto,3"));
+    }
+}
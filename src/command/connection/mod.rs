@@ -0,0 +1,4 @@
+mod hello;
+pub use hello::HelloCommand;
+mod quit;
+pub use quit::QuitCommand;
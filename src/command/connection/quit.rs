@@ -0,0 +1,25 @@
+use crate::command::ConnectionCommand;
+use crate::error::RequestError;
+use crate::execution_result::server::OkResult;
+use crate::execution_result::ExecutionResult;
+use crate::session::Session;
+
+/// `QUIT`, which asks the server to close the connection once it replied.
+#[derive(Debug)]
+pub struct QuitCommand;
+
+impl QuitCommand {
+    pub fn new(_: Vec<Vec<u8>>) -> Result<Box<Self>, RequestError> {
+        Ok(Box::new(QuitCommand))
+    }
+}
+
+impl ConnectionCommand for QuitCommand {
+    fn execute(
+        &self,
+        session: &mut Session,
+    ) -> Result<Box<dyn ExecutionResult>, Box<dyn std::error::Error>> {
+        session.closing = true;
+        Ok(Box::new(OkResult))
+    }
+}
@@ -5,12 +5,12 @@ use crate::execution_result::{hash::HGetResult, ExecutionResult};
 
 #[derive(Debug)]
 pub struct HGetCommand {
-    key: String,
-    field: String,
+    key: Vec<u8>,
+    field: Vec<u8>,
 }
 
 impl HGetCommand {
-    pub fn new(tokens: Vec<String>) -> Result<Box<Self>, RequestError> {
+    pub fn new(tokens: Vec<Vec<u8>>) -> Result<Box<Self>, RequestError> {
         if tokens.len() != 2 {
             return Err(RequestError::IncorrectArgCount);
         }
@@ -44,10 +44,10 @@ mod test {
 
     #[test]
     fn should_accept_exactly_two_tokens() {
-        let v = HGetCommand::new(vec!["foo".to_string(), "bar".to_string()]).unwrap();
-        assert_eq!(v.key, "foo".to_string());
-        assert_eq!(v.field, "bar".to_string());
-        let err = HGetCommand::new(vec!["foo".to_string()]).err().unwrap();
+        let v = HGetCommand::new(vec![b"foo".to_vec(), b"bar".to_vec()]).unwrap();
+        assert_eq!(v.key, b"foo".to_vec());
+        assert_eq!(v.field, b"bar".to_vec());
+        let err = HGetCommand::new(vec![b"foo".to_vec()]).err().unwrap();
         assert_eq!(
             err.to_string(),
             "ERR wrong number of arguments for command".to_string()
@@ -57,9 +57,9 @@ mod test {
     #[test]
     fn should_get_value_if_key_and_field_exist() {
         let mut ds = DataStore::new();
-        let key = "foo".to_string();
-        let field = "k1".to_string();
-        HSetCommand::new(vec![key.clone(), field.clone(), "v1".to_string()])
+        let key = b"foo".to_vec();
+        let field = b"k1".to_vec();
+        HSetCommand::new(vec![key.clone(), field.clone(), b"v1".to_vec()])
             .unwrap()
             .execute(&mut ds)
             .unwrap();
@@ -71,20 +71,20 @@ mod test {
     #[test]
     fn should_return_null_if_field_does_not_exist() {
         let mut ds = DataStore::new();
-        let key = "foo".to_string();
-        let field = "k1".to_string();
-        HSetCommand::new(vec![key.clone(), field.clone(), "v1".to_string()])
+        let key = b"foo".to_vec();
+        let field = b"k1".to_vec();
+        HSetCommand::new(vec![key.clone(), field.clone(), b"v1".to_vec()])
             .unwrap()
             .execute(&mut ds)
             .unwrap();
-        let cmd = HGetCommand::new(vec![key.clone(), "k2".to_string()]).unwrap();
+        let cmd = HGetCommand::new(vec![key.clone(), b"k2".to_vec()]).unwrap();
         let result = cmd.execute(&mut ds);
         assert_eq!(result.unwrap().to_string(), "".to_string());
     }
 
     #[test]
     fn should_return_null_if_key_does_not_exist() {
-        let cmd = HGetCommand::new(vec!["foo".to_string(), "bar".to_string()]).unwrap();
+        let cmd = HGetCommand::new(vec![b"foo".to_vec(), b"bar".to_vec()]).unwrap();
         let mut ds = DataStore::new();
         let result = cmd.execute(&mut ds);
         assert_eq!(result.unwrap().to_string(), "".to_string());
@@ -5,11 +5,11 @@ use crate::execution_result::{hash::HGetAllResult, ExecutionResult};
 
 #[derive(Debug)]
 pub struct HGetAllCommand {
-    key: String,
+    key: Vec<u8>,
 }
 
 impl HGetAllCommand {
-    pub fn new(tokens: Vec<String>) -> Result<Box<Self>, RequestError> {
+    pub fn new(tokens: Vec<Vec<u8>>) -> Result<Box<Self>, RequestError> {
         if tokens.len() != 1 {
             return Err(RequestError::IncorrectArgCount);
         }
@@ -45,9 +45,9 @@ mod test {
 
     #[test]
     fn should_accept_exactly_two_tokens() {
-        let v = HGetAllCommand::new(vec!["foo".to_string()]).unwrap();
-        assert_eq!(v.key, "foo".to_string());
-        let err = HGetAllCommand::new(vec!["foo".to_string(), "bar".to_string()])
+        let v = HGetAllCommand::new(vec![b"foo".to_vec()]).unwrap();
+        assert_eq!(v.key, b"foo".to_vec());
+        let err = HGetAllCommand::new(vec![b"foo".to_vec(), b"bar".to_vec()])
             .err()
             .unwrap();
         assert_eq!(
@@ -59,13 +59,13 @@ mod test {
     #[test]
     fn should_return_key_value() {
         let mut ds = DataStore::new();
-        let key = "foo".to_string();
+        let key = b"foo".to_vec();
         HSetCommand::new(vec![
             key.clone(),
-            "k1".to_string(),
-            "v1".to_string(),
-            "k2".to_string(),
-            "v2".to_string(),
+            b"k1".to_vec(),
+            b"v1".to_vec(),
+            b"k2".to_vec(),
+            b"v2".to_vec(),
         ])
         .unwrap()
         .execute(&mut ds)
@@ -99,7 +99,7 @@ mod test {
 
     #[test]
     fn should_return_empty_vec_if_key_does_not_exist() {
-        let cmd = HGetAllCommand::new(vec!["foo".to_string()]).unwrap();
+        let cmd = HGetAllCommand::new(vec![b"foo".to_vec()]).unwrap();
         let mut ds = DataStore::new();
         let result = cmd.execute(&mut ds);
         assert_eq!(result.unwrap().to_string(), "".to_string());
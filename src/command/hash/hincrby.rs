@@ -6,17 +6,17 @@ use crate::execution_result::ExecutionResult;
 
 #[derive(Debug)]
 pub struct HIncrByCommand {
-    key: String,
-    field: String,
+    key: Vec<u8>,
+    field: Vec<u8>,
     amount: i64,
 }
 
 impl HIncrByCommand {
-    pub fn new(tokens: Vec<String>) -> Result<Box<Self>, RequestError> {
+    pub fn new(tokens: Vec<Vec<u8>>) -> Result<Box<Self>, RequestError> {
         if tokens.len() != 3 {
             return Err(RequestError::IncorrectArgCount);
         }
-        match tokens[2].parse::<i64>() {
+        match String::from_utf8_lossy(&tokens[2]).parse::<i64>() {
             Ok(amount) => Ok(Box::new(HIncrByCommand {
                 key: tokens[0].clone(),
                 field: tokens[1].clone(),
@@ -32,7 +32,6 @@ impl Command for HIncrByCommand {
         &self,
         data_store: &mut DataStore,
     ) -> Result<Box<dyn ExecutionResult>, Box<dyn std::error::Error>> {
-        // TODO: atomicity
         let hash = match data_store.get_hash_mut(&self.key)? {
             Some(hash) => hash,
             None => {
@@ -41,10 +40,10 @@ impl Command for HIncrByCommand {
             }
         };
         let result = match hash.get(&self.field) {
-            Some(v) => match v.parse::<i64>() {
+            Some(v) => match String::from_utf8_lossy(v).parse::<i64>() {
                 Ok(current_value) => match current_value.checked_add(self.amount) {
                     Some(result) => {
-                        hash.insert(self.field.clone(), result.to_string());
+                        hash.insert(self.field.clone(), result.to_string().into_bytes());
                         result
                     }
                     None => return Err(Box::new(IncrCommandError::ResultOverflow)),
@@ -52,10 +51,11 @@ impl Command for HIncrByCommand {
                 Err(_) => return Err(Box::new(HIncrByCommandError::InvalidHashValue)),
             },
             None => {
-                hash.insert(self.field.clone(), self.amount.to_string());
+                hash.insert(self.field.clone(), self.amount.to_string().into_bytes());
                 self.amount
             }
         };
+        data_store.touch(&self.key);
         Ok(Box::new(HIncrByResult { value: result }))
     }
 }
@@ -70,47 +70,42 @@ mod test {
 
         #[test]
         fn should_accept_3_tokens() {
-            let err = HIncrByCommand::new(vec!["foo".to_string(), "bar".to_string()])
+            let err = HIncrByCommand::new(vec![b"foo".to_vec(), b"bar".to_vec()])
                 .err()
                 .unwrap();
             assert_eq!(
                 err.to_string(),
                 "ERR wrong number of arguments for command".to_string()
             );
-            let err = HIncrByCommand::new(vec![
-                "foo".to_string(),
-                "bar".to_string(),
-                "baz".to_string(),
-            ])
-            .err()
-            .unwrap();
+            let err = HIncrByCommand::new(vec![b"foo".to_vec(), b"bar".to_vec(), b"baz".to_vec()])
+                .err()
+                .unwrap();
             assert_eq!(err.to_string(), RequestError::InvalidIntValue.to_string());
             let v =
-                HIncrByCommand::new(vec!["foo".to_string(), "bar".to_string(), "1".to_string()])
-                    .unwrap();
-            assert_eq!(v.key, "foo".to_string());
-            assert_eq!(v.field, "bar".to_string());
+                HIncrByCommand::new(vec![b"foo".to_vec(), b"bar".to_vec(), b"1".to_vec()]).unwrap();
+            assert_eq!(v.key, b"foo".to_vec());
+            assert_eq!(v.field, b"bar".to_vec());
             assert_eq!(v.amount, 1);
         }
 
         #[test]
         fn should_set_value_when_key_does_not_exist() {
             let mut ds = DataStore::new();
-            let key = "foo".to_string();
+            let key = b"foo".to_vec();
             let cmd =
-                HIncrByCommand::new(vec![key.clone(), "k1".to_string(), "2".to_string()]).unwrap();
+                HIncrByCommand::new(vec![key.clone(), b"k1".to_vec(), b"2".to_vec()]).unwrap();
             let result = cmd.execute(&mut ds);
             assert_eq!(result.unwrap().to_string(), "2".to_string());
             let hash = ds.get_hash_mut(&key).unwrap().unwrap();
             assert_eq!(hash.len(), 1);
-            assert_eq!(hash.get(&"k1".to_string()).unwrap(), &"2".to_string());
+            assert_eq!(hash.get(b"k1").unwrap(), &b"2".to_vec());
         }
 
         #[test]
         fn should_set_value_when_field_does_not_exist() {
             let mut ds = DataStore::new();
-            let key = "foo".to_string();
-            HSetCommand::new(vec![key.clone(), "k0".to_string(), "v0".to_string()])
+            let key = b"foo".to_vec();
+            HSetCommand::new(vec![key.clone(), b"k0".to_vec(), b"v0".to_vec()])
                 .unwrap()
                 .execute(&mut ds)
                 .unwrap();
@@ -118,37 +113,37 @@ mod test {
             assert_eq!(hash.len(), 1);
 
             let cmd =
-                HIncrByCommand::new(vec![key.clone(), "k1".to_string(), "2".to_string()]).unwrap();
+                HIncrByCommand::new(vec![key.clone(), b"k1".to_vec(), b"2".to_vec()]).unwrap();
             let result = cmd.execute(&mut ds);
             assert_eq!(result.unwrap().to_string(), "2".to_string());
             let hash = ds.get_hash_mut(&key).unwrap().unwrap();
             assert_eq!(hash.len(), 2);
-            assert_eq!(hash.get(&"k1".to_string()).unwrap(), &"2".to_string());
+            assert_eq!(hash.get(b"k1").unwrap(), &b"2".to_vec());
         }
 
         #[test]
         fn should_increase_value_when_a_valid_field_exists() {
             let mut ds = DataStore::new();
-            let key = "foo".to_string();
+            let key = b"foo".to_vec();
             let cmd =
-                HIncrByCommand::new(vec![key.clone(), "k1".to_string(), "2".to_string()]).unwrap();
+                HIncrByCommand::new(vec![key.clone(), b"k1".to_vec(), b"2".to_vec()]).unwrap();
             cmd.execute(&mut ds).unwrap();
             let result = cmd.execute(&mut ds);
             assert_eq!(result.unwrap().to_string(), "4".to_string());
             let hash = ds.get_hash_mut(&key).unwrap().unwrap();
-            assert_eq!(hash.get(&"k1".to_string()).unwrap(), &"4".to_string());
+            assert_eq!(hash.get(b"k1").unwrap(), &b"4".to_vec());
         }
 
         #[test]
         fn should_throw_error_for_nonnumerical_value() {
             let mut ds = DataStore::new();
-            let key = "foo".to_string();
-            HSetCommand::new(vec![key.clone(), "k1".to_string(), "v1".to_string()])
+            let key = b"foo".to_vec();
+            HSetCommand::new(vec![key.clone(), b"k1".to_vec(), b"v1".to_vec()])
                 .unwrap()
                 .execute(&mut ds)
                 .unwrap();
             let cmd =
-                HIncrByCommand::new(vec![key.clone(), "k1".to_string(), "2".to_string()]).unwrap();
+                HIncrByCommand::new(vec![key.clone(), b"k1".to_vec(), b"2".to_vec()]).unwrap();
             let result = cmd.execute(&mut ds);
             assert_eq!(
                 result.err().unwrap().to_string(),
@@ -159,13 +154,17 @@ mod test {
         #[test]
         fn should_throw_error_when_overflow() {
             let mut ds = DataStore::new();
-            let key = "foo".to_string();
-            HSetCommand::new(vec![key.clone(), "k1".to_string(), i64::MAX.to_string()])
-                .unwrap()
-                .execute(&mut ds)
-                .unwrap();
+            let key = b"foo".to_vec();
+            HSetCommand::new(vec![
+                key.clone(),
+                b"k1".to_vec(),
+                i64::MAX.to_string().into_bytes(),
+            ])
+            .unwrap()
+            .execute(&mut ds)
+            .unwrap();
 
-            let result = HIncrByCommand::new(vec![key.clone(), "k1".to_string(), "1".to_string()])
+            let result = HIncrByCommand::new(vec![key.clone(), b"k1".to_vec(), b"1".to_vec()])
                 .unwrap()
                 .execute(&mut ds);
             assert_eq!(
@@ -173,11 +172,15 @@ mod test {
                 IncrCommandError::ResultOverflow.to_string()
             );
 
-            HSetCommand::new(vec![key.clone(), "k1".to_string(), i64::MIN.to_string()])
-                .unwrap()
-                .execute(&mut ds)
-                .unwrap();
-            let result = HIncrByCommand::new(vec![key.clone(), "k1".to_string(), "-1".to_string()])
+            HSetCommand::new(vec![
+                key.clone(),
+                b"k1".to_vec(),
+                i64::MIN.to_string().into_bytes(),
+            ])
+            .unwrap()
+            .execute(&mut ds)
+            .unwrap();
+            let result = HIncrByCommand::new(vec![key.clone(), b"k1".to_vec(), b"-1".to_vec()])
                 .unwrap()
                 .execute(&mut ds);
             assert_eq!(
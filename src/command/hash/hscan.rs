@@ -0,0 +1,110 @@
+use crate::command::keyspace::ScanOptions;
+use crate::command::Command;
+use crate::data_store::DataStore;
+use crate::error::RequestError;
+use crate::execution_result::keyspace::ScanResult;
+use crate::execution_result::ExecutionResult;
+
+#[derive(Debug)]
+pub struct HScanCommand {
+    key: Vec<u8>,
+    options: ScanOptions,
+    /// Only return the fields, as requested by `NOVALUES`.
+    no_values: bool,
+}
+
+impl HScanCommand {
+    pub fn new(tokens: Vec<Vec<u8>>) -> Result<Box<Self>, RequestError> {
+        if tokens.len() < 2 {
+            return Err(RequestError::IncorrectArgCount);
+        }
+        let mut no_values = false;
+        let options = ScanOptions::parse(&tokens[1..], |option, _| match option {
+            b"novalues" => {
+                no_values = true;
+                true
+            }
+            _ => false,
+        })?;
+        Ok(Box::new(HScanCommand {
+            key: tokens[0].clone(),
+            options,
+            no_values,
+        }))
+    }
+}
+
+impl Command for HScanCommand {
+    fn execute(
+        &self,
+        data_store: &mut DataStore,
+    ) -> Result<Box<dyn ExecutionResult>, Box<dyn std::error::Error>> {
+        let (values, cursor) = match data_store.get_hash_mut(&self.key)? {
+            Some(hash) => {
+                let (pairs, cursor) = hash.scan(self.options.cursor, self.options.count);
+                let mut values = Vec::new();
+                for (field, value) in pairs {
+                    if !self.options.matches(field) {
+                        continue;
+                    }
+                    values.push(field.to_vec());
+                    if !self.no_values {
+                        values.push(value.to_vec());
+                    }
+                }
+                (values, cursor)
+            }
+            None => (Vec::new(), 0),
+        };
+        Ok(Box::new(ScanResult { cursor, values }))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::HScanCommand;
+    use crate::command::hash::HSetCommand;
+    use crate::command::Command;
+    use crate::data_store::DataStore;
+    use crate::execution_result::RespVersion;
+    use crate::utils::tokens;
+
+    #[test]
+    fn should_parse_options() {
+        let v = HScanCommand::new(tokens(&["foo", "3", "NoValues", "COUNT", "5"])).unwrap();
+        assert_eq!(v.options.cursor, 3);
+        assert_eq!(v.options.count, 5);
+        assert!(v.no_values);
+        let err = HScanCommand::new(tokens(&["foo"])).err().unwrap();
+        assert_eq!(
+            err.to_string(),
+            "ERR wrong number of arguments for command".to_string()
+        );
+        let err = HScanCommand::new(tokens(&["foo", "0", "COUNT"]))
+            .err()
+            .unwrap();
+        assert_eq!(err.to_string(), "ERR syntax error".to_string());
+    }
+
+    #[test]
+    fn should_return_fields_with_or_without_values() {
+        let mut ds = DataStore::new();
+        HSetCommand::new(tokens(&["foo", "k1", "v1", "x1", "v2"]))
+            .unwrap()
+            .execute(&mut ds)
+            .unwrap();
+        let cmd = HScanCommand::new(tokens(&["foo", "0", "MATCH", "k*"])).unwrap();
+        let result = cmd.execute(&mut ds).unwrap();
+        assert_eq!(result.to_string(), "0,k1,v1".to_string());
+        assert_eq!(
+            result.serialise(RespVersion::Resp2),
+            b"*2\r\n$1\r\n0\r\n*2\r\n$2\r\nk1\r\n$2\r\nv1\r\n".to_vec()
+        );
+
+        let cmd = HScanCommand::new(tokens(&["foo", "0", "MATCH", "k*", "NOVALUES"])).unwrap();
+        assert_eq!(
+            cmd.execute(&mut ds).unwrap().to_string(),
+            "0,k1".to_string()
+        );
+    }
+}
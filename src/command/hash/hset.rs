@@ -6,12 +6,12 @@ use crate::execution_result::ExecutionResult;
 
 #[derive(Debug)]
 pub struct HSetCommand {
-    key: String,
-    values: Vec<(String, String)>,
+    key: Vec<u8>,
+    values: Vec<(Vec<u8>, Vec<u8>)>,
 }
 
 impl HSetCommand {
-    pub fn new(tokens: Vec<String>) -> Result<Box<Self>, RequestError> {
+    pub fn new(tokens: Vec<Vec<u8>>) -> Result<Box<Self>, RequestError> {
         if tokens.len() % 2 != 1 || tokens.len() < 3 {
             return Err(RequestError::IncorrectArgCount);
         }
@@ -31,7 +31,6 @@ impl Command for HSetCommand {
         &self,
         data_store: &mut DataStore,
     ) -> Result<Box<dyn ExecutionResult>, Box<dyn std::error::Error>> {
-        // TODO: atomicity
         let hash = match data_store.get_hash_mut(&self.key)? {
             Some(hash) => hash,
             None => {
@@ -46,6 +45,7 @@ impl Command for HSetCommand {
                 None => 1,
             }
         }
+        data_store.touch(&self.key);
         Ok(Box::new(HSetResult { value: count }))
     }
 }
@@ -59,54 +59,49 @@ mod test {
 
         #[test]
         fn should_accept_correct_amount_of_tokens() {
-            let err = HSetCommand::new(vec!["foo".to_string()]).err().unwrap();
+            let err = HSetCommand::new(vec![b"foo".to_vec()]).err().unwrap();
             assert_eq!(
                 err.to_string(),
                 "ERR wrong number of arguments for command".to_string()
             );
-            let err = HSetCommand::new(vec!["foo".to_string(), "bar".to_string()])
+            let err = HSetCommand::new(vec![b"foo".to_vec(), b"bar".to_vec()])
                 .err()
                 .unwrap();
             assert_eq!(
                 err.to_string(),
                 "ERR wrong number of arguments for command".to_string()
             );
-            let v = HSetCommand::new(vec![
-                "foo".to_string(),
-                "bar".to_string(),
-                "baz".to_string(),
-            ])
-            .unwrap();
-            assert_eq!(v.key, "foo".to_string());
-            assert_eq!(v.values, vec![("bar".to_string(), "baz".to_string())]);
+            let v =
+                HSetCommand::new(vec![b"foo".to_vec(), b"bar".to_vec(), b"baz".to_vec()]).unwrap();
+            assert_eq!(v.key, b"foo".to_vec());
+            assert_eq!(v.values, vec![(b"bar".to_vec(), b"baz".to_vec())]);
         }
 
         #[test]
         fn should_insert_value() {
             let mut ds = DataStore::new();
-            let key = "foo".to_string();
+            let key = b"foo".to_vec();
             let cmd = HSetCommand::new(vec![
                 key.clone(),
-                "k1".to_string(),
-                "v1".to_string(),
-                "k2".to_string(),
-                "v2".to_string(),
+                b"k1".to_vec(),
+                b"v1".to_vec(),
+                b"k2".to_vec(),
+                b"v2".to_vec(),
             ])
             .unwrap();
             let result = cmd.execute(&mut ds);
             assert_eq!(result.unwrap().to_string(), "2".to_string());
             let hash = ds.get_hash_mut(&key).unwrap().unwrap();
             assert_eq!(hash.len(), 2);
-            assert_eq!(hash.get(&"k1".to_string()).unwrap(), &"v1".to_string());
-            assert_eq!(hash.get(&"k2".to_string()).unwrap(), &"v2".to_string());
+            assert_eq!(hash.get(b"k1").unwrap(), &b"v1".to_vec());
+            assert_eq!(hash.get(b"k2").unwrap(), &b"v2".to_vec());
 
             // Should return 0 because key "bar" already exists
-            let cmd =
-                HSetCommand::new(vec![key.clone(), "k1".to_string(), "v3".to_string()]).unwrap();
+            let cmd = HSetCommand::new(vec![key.clone(), b"k1".to_vec(), b"v3".to_vec()]).unwrap();
             let result = cmd.execute(&mut ds);
             assert_eq!(result.unwrap().to_string(), "0".to_string());
             let hash = ds.get_hash_mut(&key).unwrap().unwrap();
-            assert_eq!(hash.get(&"k1".to_string()).unwrap(), &"v3".to_string());
+            assert_eq!(hash.get(b"k1").unwrap(), &b"v3".to_vec());
         }
     }
 }
@@ -6,3 +6,5 @@ mod hgetall;
 pub use hgetall::HGetAllCommand;
 mod hincrby;
 pub use hincrby::HIncrByCommand;
+mod hscan;
+pub use hscan::HScanCommand;
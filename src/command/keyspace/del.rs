@@ -0,0 +1,68 @@
+use crate::command::Command;
+use crate::data_store::DataStore;
+use crate::error::RequestError;
+use crate::execution_result::keyspace::DelResult;
+use crate::execution_result::ExecutionResult;
+
+#[derive(Debug)]
+pub struct DelCommand {
+    keys: Vec<Vec<u8>>,
+}
+
+impl DelCommand {
+    pub fn new(tokens: Vec<Vec<u8>>) -> Result<Box<Self>, RequestError> {
+        if tokens.is_empty() {
+            return Err(RequestError::IncorrectArgCount);
+        }
+        Ok(Box::new(DelCommand { keys: tokens }))
+    }
+}
+
+impl Command for DelCommand {
+    fn execute(
+        &self,
+        data_store: &mut DataStore,
+    ) -> Result<Box<dyn ExecutionResult>, Box<dyn std::error::Error>> {
+        let mut count = 0;
+        for key in &self.keys {
+            // An expired key is gone already and does not count as deleted.
+            if data_store.contains_key(key) && data_store.drop_key(key) {
+                count += 1;
+            }
+        }
+        Ok(Box::new(DelResult { value: count }))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::DelCommand;
+    use crate::command::Command;
+    use crate::data_store::DataStore;
+
+    #[test]
+    fn should_accept_at_least_one_token() {
+        let err = DelCommand::new(vec![]).err().unwrap();
+        assert_eq!(
+            err.to_string(),
+            "ERR wrong number of arguments for command".to_string()
+        );
+    }
+
+    #[test]
+    fn should_delete_existing_keys() {
+        let mut ds = DataStore::new();
+        ds.set_string_overwrite(b"k1", b"v1");
+        let _ = ds.insert_list(b"k2");
+        let cmd = DelCommand::new(vec![
+            b"k1".to_vec(),
+            b"k2".to_vec(),
+            b"k3".to_vec(),
+            b"k1".to_vec(),
+        ])
+        .unwrap();
+        assert_eq!(cmd.execute(&mut ds).unwrap().to_string(), "2".to_string());
+        assert!(!ds.contains_key(b"k1"));
+        assert!(!ds.contains_key(b"k2"));
+    }
+}
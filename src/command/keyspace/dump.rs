@@ -0,0 +1,164 @@
+use crate::command::Command;
+use crate::data_store::{now_ms, DataStore};
+use crate::error::{ExecutionError, RequestError};
+use crate::execution_result::keyspace::DumpResult;
+use crate::execution_result::server::OkResult;
+use crate::execution_result::ExecutionResult;
+use crate::persistence::rdb;
+
+/// `DUMP key`, which serialises the value of a key so that `RESTORE` can recreate it, possibly on
+/// another server.
+#[derive(Debug)]
+pub struct DumpCommand {
+    key: Vec<u8>,
+}
+
+impl DumpCommand {
+    pub fn new(tokens: Vec<Vec<u8>>) -> Result<Box<Self>, RequestError> {
+        if tokens.len() != 1 {
+            return Err(RequestError::IncorrectArgCount);
+        }
+        Ok(Box::new(DumpCommand {
+            key: tokens[0].clone(),
+        }))
+    }
+}
+
+impl Command for DumpCommand {
+    fn execute(
+        &self,
+        data_store: &mut DataStore,
+    ) -> Result<Box<dyn ExecutionResult>, Box<dyn std::error::Error>> {
+        let value = match data_store.dump(&self.key) {
+            Some((entry, _)) => Some(rdb::dump(&entry)?),
+            None => None,
+        };
+        Ok(Box::new(DumpResult { value }))
+    }
+}
+
+/// `RESTORE key ttl payload [REPLACE] [ABSTTL]`, which creates a key from the output of `DUMP`.
+/// A TTL of 0 means no expiry, and with `ABSTTL` the TTL is a Unix time in milliseconds.
+#[derive(Debug)]
+pub struct RestoreCommand {
+    key: Vec<u8>,
+    ttl: u64,
+    payload: Vec<u8>,
+    replace: bool,
+    absolute: bool,
+}
+
+impl RestoreCommand {
+    pub fn new(tokens: Vec<Vec<u8>>) -> Result<Box<Self>, RequestError> {
+        if tokens.len() < 3 {
+            return Err(RequestError::IncorrectArgCount);
+        }
+        let ttl = match String::from_utf8_lossy(&tokens[1]).parse::<i64>() {
+            Ok(v) if v >= 0 => v as u64,
+            Ok(_) => return Err(RequestError::InvalidTtl),
+            Err(_) => return Err(RequestError::InvalidIntValue),
+        };
+        let (mut replace, mut absolute) = (false, false);
+        for option in &tokens[3..] {
+            match option.to_ascii_lowercase().as_slice() {
+                b"replace" => replace = true,
+                b"absttl" => absolute = true,
+                _ => return Err(RequestError::SyntaxError),
+            }
+        }
+        Ok(Box::new(RestoreCommand {
+            key: tokens[0].clone(),
+            ttl,
+            payload: tokens[2].clone(),
+            replace,
+            absolute,
+        }))
+    }
+}
+
+impl Command for RestoreCommand {
+    fn execute(
+        &self,
+        data_store: &mut DataStore,
+    ) -> Result<Box<dyn ExecutionResult>, Box<dyn std::error::Error>> {
+        if !self.replace && data_store.contains_key(&self.key) {
+            return Err(Box::new(ExecutionError::BusyKey));
+        }
+        let entry = match rdb::load_dump(&self.payload) {
+            Ok(v) => v,
+            Err(e) => {
+                log::debug!("Invalid DUMP payload: {}", e);
+                return Err(Box::new(ExecutionError::InvalidDumpPayload));
+            }
+        };
+        let deadline = match (self.ttl, self.absolute) {
+            (0, _) => None,
+            (ttl, true) => Some(ttl),
+            (ttl, false) => Some(now_ms() + ttl),
+        };
+        // A deadline in the past leaves no key behind, as if it expired right away.
+        if deadline.is_some_and(|d| d <= now_ms()) {
+            data_store.drop_key(&self.key);
+            return Ok(Box::new(OkResult));
+        }
+        data_store.restore(&self.key, entry, deadline);
+        Ok(Box::new(OkResult))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{DumpCommand, RestoreCommand};
+    use crate::command::Command;
+    use crate::data_store::DataStore;
+    use crate::execution_result::RespVersion;
+    use crate::persistence::rdb;
+
+    fn tokens(values: &[&[u8]]) -> Vec<Vec<u8>> {
+        values.iter().map(|v| v.to_vec()).collect()
+    }
+
+    #[test]
+    fn should_dump_and_restore_keys() {
+        let mut ds = DataStore::new();
+        let cmd = DumpCommand::new(tokens(&[b"foo"])).unwrap();
+        let result = cmd.execute(&mut ds).unwrap();
+        assert_eq!(result.serialise(RespVersion::Resp2), b"$-1\r\n".to_vec());
+
+        ds.set_string_overwrite(b"foo", b"bar");
+        let payload = rdb::dump(&ds.dump(b"foo").unwrap().0).unwrap();
+        let result = cmd.execute(&mut ds).unwrap();
+        assert!(result
+            .serialise(RespVersion::Resp2)
+            .ends_with(&[payload.as_slice(), b"\r\n"].concat()));
+        let cmd = RestoreCommand::new(tokens(&[b"copy", b"5000", &payload])).unwrap();
+        cmd.execute(&mut ds).unwrap();
+        assert!(ds.dump(b"copy").unwrap().0.string == Some(b"bar".to_vec()));
+        let deadline = ds.get_expire_at(b"copy").unwrap();
+        assert!(deadline > crate::data_store::now_ms() + 4000);
+
+        let err = cmd.execute(&mut ds).err().unwrap();
+        assert_eq!(
+            err.to_string(),
+            "BUSYKEY Target key name already exists.".to_string()
+        );
+        let cmd = RestoreCommand::new(tokens(&[b"copy", b"0", b"junk", b"REPLACE"])).unwrap();
+        let err = cmd.execute(&mut ds).err().unwrap();
+        assert_eq!(
+            err.to_string(),
+            "ERR DUMP payload version or checksum are wrong".to_string()
+        );
+        let cmd =
+            RestoreCommand::new(tokens(&[b"copy", b"1", &payload, b"REPLACE", b"ABSTTL"])).unwrap();
+        cmd.execute(&mut ds).unwrap();
+        assert!(!ds.contains_key(b"copy"));
+
+        let err = RestoreCommand::new(tokens(&[b"copy", b"-1", &payload]))
+            .err()
+            .unwrap();
+        assert_eq!(
+            err.to_string(),
+            "ERR Invalid TTL value, must be >= 0".to_string()
+        );
+    }
+}
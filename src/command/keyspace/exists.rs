@@ -0,0 +1,57 @@
+use crate::command::Command;
+use crate::data_store::DataStore;
+use crate::error::RequestError;
+use crate::execution_result::keyspace::ExistsResult;
+use crate::execution_result::ExecutionResult;
+
+#[derive(Debug)]
+pub struct ExistsCommand {
+    keys: Vec<Vec<u8>>,
+}
+
+impl ExistsCommand {
+    pub fn new(tokens: Vec<Vec<u8>>) -> Result<Box<Self>, RequestError> {
+        if tokens.is_empty() {
+            return Err(RequestError::IncorrectArgCount);
+        }
+        Ok(Box::new(ExistsCommand { keys: tokens }))
+    }
+}
+
+impl Command for ExistsCommand {
+    fn execute(
+        &self,
+        data_store: &mut DataStore,
+    ) -> Result<Box<dyn ExecutionResult>, Box<dyn std::error::Error>> {
+        // A key given several times is counted as many times.
+        let count = self
+            .keys
+            .iter()
+            .filter(|key| data_store.contains_key(key))
+            .count();
+        Ok(Box::new(ExistsResult { value: count }))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::ExistsCommand;
+    use crate::command::Command;
+    use crate::data_store::{now_ms, DataStore};
+
+    #[test]
+    fn should_count_existing_keys() {
+        let mut ds = DataStore::new();
+        ds.set_string_overwrite(b"k1", b"v1");
+        ds.set_string_overwrite(b"k2", b"v2");
+        ds.set_expire_at(b"k2", now_ms() - 1);
+        let cmd = ExistsCommand::new(vec![
+            b"k1".to_vec(),
+            b"k2".to_vec(),
+            b"k3".to_vec(),
+            b"k1".to_vec(),
+        ])
+        .unwrap();
+        assert_eq!(cmd.execute(&mut ds).unwrap().to_string(), "2".to_string());
+    }
+}
@@ -0,0 +1,243 @@
+use crate::command::keyspace::TimeUnit;
+use crate::command::Command;
+use crate::data_store::{now_ms, DataStore};
+use crate::error::RequestError;
+use crate::execution_result::keyspace::ExpireResult;
+use crate::execution_result::ExecutionResult;
+
+#[derive(Debug, PartialEq)]
+enum ExpireCondition {
+    /// Only set the TTL if the key has none.
+    Nx,
+    /// Only set the TTL if the key already has one.
+    Xx,
+    /// Only set the TTL if it is later than the current one.
+    Gt,
+    /// Only set the TTL if it is earlier than the current one.
+    Lt,
+}
+
+#[derive(Debug)]
+pub struct ExpireCommand {
+    key: Vec<u8>,
+    value: i64,
+    unit: TimeUnit,
+    /// Whether `value` is a Unix timestamp rather than a duration from now.
+    absolute: bool,
+    condition: Option<ExpireCondition>,
+}
+
+impl ExpireCommand {
+    pub fn new(
+        tokens: Vec<Vec<u8>>,
+        unit: TimeUnit,
+        absolute: bool,
+    ) -> Result<Box<Self>, RequestError> {
+        if tokens.len() < 2 {
+            return Err(RequestError::IncorrectArgCount);
+        }
+        let value = match String::from_utf8_lossy(&tokens[1]).parse::<i64>() {
+            Ok(v) => v,
+            Err(_) => return Err(RequestError::InvalidIntValue),
+        };
+        let mut condition = None;
+        for option in &tokens[2..] {
+            let new = match option.to_ascii_lowercase().as_slice() {
+                b"nx" => ExpireCondition::Nx,
+                b"xx" => ExpireCondition::Xx,
+                b"gt" => ExpireCondition::Gt,
+                b"lt" => ExpireCondition::Lt,
+                _ => return Err(RequestError::SyntaxError),
+            };
+            condition = match (condition, new) {
+                (None, new) => Some(new),
+                (Some(old), new) if old == new => Some(new),
+                (Some(ExpireCondition::Nx), _) | (_, ExpireCondition::Nx) => {
+                    return Err(RequestError::IncompatibleNxOption)
+                }
+                // XX can be combined with either GT or LT, and the ordering condition is the one
+                // that matters since it implies the key has a TTL.
+                (Some(ExpireCondition::Xx), new) => Some(new),
+                (Some(old), ExpireCondition::Xx) => Some(old),
+                (Some(_), _) => return Err(RequestError::IncompatibleGtLtOptions),
+            };
+        }
+        Ok(Box::new(ExpireCommand {
+            key: tokens[0].clone(),
+            value,
+            unit,
+            absolute,
+            condition,
+        }))
+    }
+
+    fn name(&self) -> &'static str {
+        match (self.unit, self.absolute) {
+            (TimeUnit::Seconds, false) => "expire",
+            (TimeUnit::Milliseconds, false) => "pexpire",
+            (TimeUnit::Seconds, true) => "expireat",
+            (TimeUnit::Milliseconds, true) => "pexpireat",
+        }
+    }
+}
+
+impl Command for ExpireCommand {
+    fn execute(
+        &self,
+        data_store: &mut DataStore,
+    ) -> Result<Box<dyn ExecutionResult>, Box<dyn std::error::Error>> {
+        let now = now_ms() as i64;
+        let deadline = match self.absolute {
+            true => self.value.checked_mul(self.unit.as_ms()),
+            false => self
+                .value
+                .checked_mul(self.unit.as_ms())
+                .and_then(|v| v.checked_add(now)),
+        };
+        let Some(deadline) = deadline else {
+            return Err(Box::new(RequestError::InvalidExpireTime(
+                self.name().to_string(),
+            )));
+        };
+        if !data_store.contains_key(&self.key) {
+            return Ok(Box::new(ExpireResult { value: false }));
+        }
+        let current = data_store.get_expire_at(&self.key).map(|v| v as i64);
+        let allowed = match self.condition {
+            None => true,
+            Some(ExpireCondition::Nx) => current.is_none(),
+            Some(ExpireCondition::Xx) => current.is_some(),
+            // A key without a TTL never expires, which is later than any deadline.
+            Some(ExpireCondition::Gt) => current.is_some_and(|v| deadline > v),
+            Some(ExpireCondition::Lt) => current.is_none_or(|v| deadline < v),
+        };
+        if !allowed {
+            return Ok(Box::new(ExpireResult { value: false }));
+        }
+        if deadline <= now {
+            data_store.drop_key(&self.key);
+        } else {
+            data_store.set_expire_at(&self.key, deadline as u64);
+        }
+        Ok(Box::new(ExpireResult { value: true }))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{ExpireCommand, ExpireCondition};
+    use crate::command::keyspace::TimeUnit;
+    use crate::command::Command;
+    use crate::data_store::{now_ms, DataStore};
+
+    fn expire(tokens: &[&str], unit: TimeUnit, absolute: bool) -> Box<ExpireCommand> {
+        let tokens = tokens.iter().map(|t| t.as_bytes().to_vec()).collect();
+        ExpireCommand::new(tokens, unit, absolute).unwrap()
+    }
+
+    #[test]
+    fn should_parse_conditions() {
+        let v = expire(&["foo", "10", "XX", "gt"], TimeUnit::Seconds, false);
+        assert_eq!(v.value, 10);
+        assert_eq!(v.condition, Some(ExpireCondition::Gt));
+        let err = ExpireCommand::new(
+            vec![
+                b"foo".to_vec(),
+                b"10".to_vec(),
+                b"nx".to_vec(),
+                b"gt".to_vec(),
+            ],
+            TimeUnit::Seconds,
+            false,
+        )
+        .err()
+        .unwrap();
+        assert_eq!(
+            err.to_string(),
+            "ERR NX and XX, GT or LT options at the same time are not compatible".to_string()
+        );
+        let err = ExpireCommand::new(
+            vec![
+                b"foo".to_vec(),
+                b"10".to_vec(),
+                b"lt".to_vec(),
+                b"gt".to_vec(),
+            ],
+            TimeUnit::Seconds,
+            false,
+        )
+        .err()
+        .unwrap();
+        assert_eq!(
+            err.to_string(),
+            "ERR GT and LT options at the same time are not compatible".to_string()
+        );
+        let err = ExpireCommand::new(
+            vec![b"foo".to_vec(), b"ten".to_vec()],
+            TimeUnit::Seconds,
+            false,
+        )
+        .err()
+        .unwrap();
+        assert_eq!(
+            err.to_string(),
+            "ERR value is not an integer or out of range".to_string()
+        );
+    }
+
+    #[test]
+    fn should_set_expiry_of_existing_key() {
+        let mut ds = DataStore::new();
+        let cmd = expire(&["foo", "10"], TimeUnit::Seconds, false);
+        assert_eq!(cmd.execute(&mut ds).unwrap().to_string(), "0");
+        ds.set_string_overwrite(b"foo", b"bar");
+        assert_eq!(cmd.execute(&mut ds).unwrap().to_string(), "1");
+        let deadline = ds.get_expire_at(b"foo").unwrap();
+        assert!(deadline > now_ms() + 9000 && deadline <= now_ms() + 10000);
+
+        let cmd = expire(&["foo", "4000000000000"], TimeUnit::Milliseconds, true);
+        assert_eq!(cmd.execute(&mut ds).unwrap().to_string(), "1");
+        assert_eq!(ds.get_expire_at(b"foo"), Some(4000000000000));
+    }
+
+    #[test]
+    fn should_delete_key_when_deadline_has_passed() {
+        let mut ds = DataStore::new();
+        ds.set_string_overwrite(b"foo", b"bar");
+        let cmd = expire(&["foo", "-1"], TimeUnit::Seconds, false);
+        assert_eq!(cmd.execute(&mut ds).unwrap().to_string(), "1");
+        assert!(!ds.contains_key(b"foo"));
+    }
+
+    #[test]
+    fn should_apply_conditions() {
+        let mut ds = DataStore::new();
+        ds.set_string_overwrite(b"foo", b"bar");
+        let later = (now_ms() + 100000).to_string();
+        let earlier = (now_ms() + 50000).to_string();
+        let cmd = expire(&["foo", &later, "xx"], TimeUnit::Milliseconds, true);
+        assert_eq!(cmd.execute(&mut ds).unwrap().to_string(), "0");
+        let cmd = expire(&["foo", &later, "gt"], TimeUnit::Milliseconds, true);
+        assert_eq!(cmd.execute(&mut ds).unwrap().to_string(), "0");
+        let cmd = expire(&["foo", &later, "nx"], TimeUnit::Milliseconds, true);
+        assert_eq!(cmd.execute(&mut ds).unwrap().to_string(), "1");
+        let cmd = expire(&["foo", &earlier, "nx"], TimeUnit::Milliseconds, true);
+        assert_eq!(cmd.execute(&mut ds).unwrap().to_string(), "0");
+        let cmd = expire(&["foo", &earlier, "gt"], TimeUnit::Milliseconds, true);
+        assert_eq!(cmd.execute(&mut ds).unwrap().to_string(), "0");
+        let cmd = expire(&["foo", &earlier, "lt"], TimeUnit::Milliseconds, true);
+        assert_eq!(cmd.execute(&mut ds).unwrap().to_string(), "1");
+        assert_eq!(ds.get_expire_at(b"foo"), Some(earlier.parse().unwrap()));
+    }
+
+    #[test]
+    fn should_reject_overflowing_expiry() {
+        let mut ds = DataStore::new();
+        ds.set_string_overwrite(b"foo", b"bar");
+        let cmd = expire(&["foo", &i64::MAX.to_string()], TimeUnit::Seconds, false);
+        assert_eq!(
+            cmd.execute(&mut ds).err().unwrap().to_string(),
+            "ERR invalid expire time in 'expire' command".to_string()
+        );
+    }
+}
@@ -0,0 +1,64 @@
+use crate::command::Command;
+use crate::data_store::{DataStore, RedisEntryType};
+use crate::error::RequestError;
+use crate::execution_result::keyspace::TypeResult;
+use crate::execution_result::ExecutionResult;
+
+#[derive(Debug)]
+pub struct TypeCommand {
+    key: Vec<u8>,
+}
+
+impl TypeCommand {
+    pub fn new(tokens: Vec<Vec<u8>>) -> Result<Box<Self>, RequestError> {
+        if tokens.len() != 1 {
+            return Err(RequestError::IncorrectArgCount);
+        }
+        Ok(Box::new(TypeCommand {
+            key: tokens[0].clone(),
+        }))
+    }
+}
+
+impl Command for TypeCommand {
+    fn execute(
+        &self,
+        data_store: &mut DataStore,
+    ) -> Result<Box<dyn ExecutionResult>, Box<dyn std::error::Error>> {
+        let type_ = data_store
+            .get_type(&self.key)
+            .cloned()
+            .unwrap_or(RedisEntryType::Unknown);
+        Ok(Box::new(TypeResult { value: type_ }))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::TypeCommand;
+    use crate::command::Command;
+    use crate::data_store::DataStore;
+
+    #[test]
+    fn should_report_type_of_key() {
+        let mut ds = DataStore::new();
+        ds.set_string_overwrite(b"string", b"v");
+        let _ = ds.insert_list(b"list");
+        let _ = ds.insert_set(b"set");
+        let _ = ds.insert_hash(b"hash");
+        let _ = ds.insert_sorted_set(b"zset");
+        let _ = ds.insert_stream(b"stream");
+        for (key, expected) in [
+            ("string", "string"),
+            ("list", "list"),
+            ("set", "set"),
+            ("hash", "hash"),
+            ("zset", "zset"),
+            ("stream", "stream"),
+            ("missing", "none"),
+        ] {
+            let cmd = TypeCommand::new(vec![key.as_bytes().to_vec()]).unwrap();
+            assert_eq!(cmd.execute(&mut ds).unwrap().to_string(), expected);
+        }
+    }
+}
@@ -0,0 +1,55 @@
+use crate::command::Command;
+use crate::data_store::DataStore;
+use crate::error::RequestError;
+use crate::execution_result::keyspace::KeysResult;
+use crate::execution_result::ExecutionResult;
+
+#[derive(Debug)]
+pub struct KeysCommand {
+    pattern: Vec<u8>,
+}
+
+impl KeysCommand {
+    pub fn new(tokens: Vec<Vec<u8>>) -> Result<Box<Self>, RequestError> {
+        if tokens.len() != 1 {
+            return Err(RequestError::IncorrectArgCount);
+        }
+        Ok(Box::new(KeysCommand {
+            pattern: tokens[0].clone(),
+        }))
+    }
+}
+
+impl Command for KeysCommand {
+    fn execute(
+        &self,
+        data_store: &mut DataStore,
+    ) -> Result<Box<dyn ExecutionResult>, Box<dyn std::error::Error>> {
+        Ok(Box::new(KeysResult {
+            values: data_store.keys(&self.pattern),
+        }))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::KeysCommand;
+    use crate::command::Command;
+    use crate::data_store::{now_ms, DataStore};
+
+    #[test]
+    fn should_list_matching_keys() {
+        let mut ds = DataStore::new();
+        for key in ["user:1", "user:2", "user:10", "order:1"] {
+            ds.set_string_overwrite(key.as_bytes(), b"v");
+        }
+        ds.set_expire_at(b"user:2", now_ms() - 1);
+        let cmd = KeysCommand::new(vec![b"user:?".to_vec()]).unwrap();
+        assert_eq!(cmd.execute(&mut ds).unwrap().to_string(), "user:1");
+        let cmd = KeysCommand::new(vec![b"*:1".to_vec()]).unwrap();
+        let result = cmd.execute(&mut ds).unwrap().to_string();
+        let mut keys: Vec<&str> = result.split(',').collect();
+        keys.sort();
+        assert_eq!(keys, vec!["order:1", "user:1"]);
+    }
+}
@@ -0,0 +1,40 @@
+mod del;
+pub use del::DelCommand;
+mod dump;
+pub use dump::{DumpCommand, RestoreCommand};
+mod exists;
+pub use exists::ExistsCommand;
+mod expire;
+pub use expire::ExpireCommand;
+mod keys;
+pub use keys::KeysCommand;
+mod key_type;
+pub use key_type::TypeCommand;
+mod persist;
+pub use persist::PersistCommand;
+mod rename;
+pub use rename::RenameCommand;
+mod scan;
+pub use scan::{ScanCommand, ScanOptions};
+mod ttl;
+pub use ttl::TtlCommand;
+
+/// Values are freed as soon as they are removed, so `UNLINK` is the same as `DEL`.
+pub type UnlinkCommand = DelCommand;
+/// No access time is tracked, so `TOUCH` only counts the keys that exist, like `EXISTS`.
+pub type TouchCommand = ExistsCommand;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum TimeUnit {
+    Seconds,
+    Milliseconds,
+}
+
+impl TimeUnit {
+    pub fn as_ms(&self) -> i64 {
+        match self {
+            TimeUnit::Seconds => 1000,
+            TimeUnit::Milliseconds => 1,
+        }
+    }
+}
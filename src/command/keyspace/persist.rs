@@ -0,0 +1,52 @@
+use crate::command::Command;
+use crate::data_store::DataStore;
+use crate::error::RequestError;
+use crate::execution_result::keyspace::PersistResult;
+use crate::execution_result::ExecutionResult;
+
+#[derive(Debug)]
+pub struct PersistCommand {
+    key: Vec<u8>,
+}
+
+impl PersistCommand {
+    pub fn new(tokens: Vec<Vec<u8>>) -> Result<Box<Self>, RequestError> {
+        if tokens.len() != 1 {
+            return Err(RequestError::IncorrectArgCount);
+        }
+        Ok(Box::new(PersistCommand {
+            key: tokens[0].clone(),
+        }))
+    }
+}
+
+impl Command for PersistCommand {
+    fn execute(
+        &self,
+        data_store: &mut DataStore,
+    ) -> Result<Box<dyn ExecutionResult>, Box<dyn std::error::Error>> {
+        Ok(Box::new(PersistResult {
+            value: data_store.persist(&self.key),
+        }))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::PersistCommand;
+    use crate::command::Command;
+    use crate::data_store::{now_ms, DataStore};
+
+    #[test]
+    fn should_remove_expiry() {
+        let mut ds = DataStore::new();
+        let cmd = PersistCommand::new(vec![b"foo".to_vec()]).unwrap();
+        assert_eq!(cmd.execute(&mut ds).unwrap().to_string(), "0");
+        ds.set_string_overwrite(b"foo", b"bar");
+        assert_eq!(cmd.execute(&mut ds).unwrap().to_string(), "0");
+        ds.set_expire_at(b"foo", now_ms() + 10000);
+        assert_eq!(cmd.execute(&mut ds).unwrap().to_string(), "1");
+        assert_eq!(ds.get_expire_at(b"foo"), None);
+        assert!(ds.contains_key(b"foo"));
+    }
+}
@@ -0,0 +1,93 @@
+use crate::command::Command;
+use crate::data_store::DataStore;
+use crate::error::{ExecutionError, RequestError};
+use crate::execution_result::keyspace::{RenameNxResult, RenameResult};
+use crate::execution_result::ExecutionResult;
+
+#[derive(Debug)]
+pub struct RenameCommand {
+    key: Vec<u8>,
+    new_key: Vec<u8>,
+    /// Whether to leave an existing `new_key` untouched instead of overwriting it.
+    nx: bool,
+}
+
+impl RenameCommand {
+    pub fn new(tokens: Vec<Vec<u8>>, nx: bool) -> Result<Box<Self>, RequestError> {
+        if tokens.len() != 2 {
+            return Err(RequestError::IncorrectArgCount);
+        }
+        Ok(Box::new(RenameCommand {
+            key: tokens[0].clone(),
+            new_key: tokens[1].clone(),
+            nx,
+        }))
+    }
+}
+
+impl Command for RenameCommand {
+    fn execute(
+        &self,
+        data_store: &mut DataStore,
+    ) -> Result<Box<dyn ExecutionResult>, Box<dyn std::error::Error>> {
+        if !data_store.contains_key(&self.key) {
+            return Err(Box::new(ExecutionError::NoSuchKey));
+        }
+        if !self.nx {
+            data_store.rename(&self.key, &self.new_key);
+            return Ok(Box::new(RenameResult {}));
+        }
+        if data_store.contains_key(&self.new_key) {
+            return Ok(Box::new(RenameNxResult { value: false }));
+        }
+        data_store.rename(&self.key, &self.new_key);
+        Ok(Box::new(RenameNxResult { value: true }))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::RenameCommand;
+    use crate::command::Command;
+    use crate::data_store::DataStore;
+
+    fn rename(key: &str, new_key: &str, nx: bool) -> Box<RenameCommand> {
+        RenameCommand::new(
+            vec![key.as_bytes().to_vec(), new_key.as_bytes().to_vec()],
+            nx,
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn should_rename_key() {
+        let mut ds = DataStore::new();
+        ds.set_string_overwrite(b"foo", b"bar");
+        ds.set_string_overwrite(b"dst", b"old");
+        let result = rename("foo", "dst", false).execute(&mut ds);
+        assert_eq!(result.unwrap().to_string(), "OK".to_string());
+        assert!(!ds.contains_key(b"foo"));
+        assert_eq!(ds.get_string(b"dst").unwrap().unwrap(), &b"bar".to_vec());
+
+        let result = rename("dst", "dst", false).execute(&mut ds);
+        assert_eq!(result.unwrap().to_string(), "OK".to_string());
+        assert_eq!(ds.get_string(b"dst").unwrap().unwrap(), &b"bar".to_vec());
+
+        let err = rename("foo", "dst", false).execute(&mut ds).err().unwrap();
+        assert_eq!(err.to_string(), "ERR no such key".to_string());
+    }
+
+    #[test]
+    fn should_only_rename_to_new_key_with_nx() {
+        let mut ds = DataStore::new();
+        ds.set_string_overwrite(b"foo", b"bar");
+        ds.set_string_overwrite(b"dst", b"old");
+        let result = rename("foo", "dst", true).execute(&mut ds);
+        assert_eq!(result.unwrap().to_string(), "0".to_string());
+        assert_eq!(ds.get_string(b"dst").unwrap().unwrap(), &b"old".to_vec());
+        let result = rename("foo", "new", true).execute(&mut ds);
+        assert_eq!(result.unwrap().to_string(), "1".to_string());
+        assert!(!ds.contains_key(b"foo"));
+        assert_eq!(ds.get_string(b"new").unwrap().unwrap(), &b"bar".to_vec());
+    }
+}
@@ -0,0 +1,197 @@
+use crate::command::Command;
+use crate::data_store::DataStore;
+use crate::error::RequestError;
+use crate::execution_result::keyspace::ScanResult;
+use crate::execution_result::ExecutionResult;
+use crate::utils::glob_match;
+
+const DEFAULT_SCAN_COUNT: usize = 10;
+
+/// The options shared by the `SCAN` family of commands.
+#[derive(Debug, PartialEq)]
+pub struct ScanOptions {
+    pub cursor: u64,
+    pub pattern: Option<Vec<u8>>,
+    /// A hint of how many elements to return per call.
+    pub count: usize,
+}
+
+impl ScanOptions {
+    /// Parses the cursor and the `MATCH` and `COUNT` options. Any other option is handed to
+    /// `parse_extra` along with the remaining tokens, and is a syntax error if it returns false.
+    pub fn parse<'a>(
+        tokens: &'a [Vec<u8>],
+        mut parse_extra: impl FnMut(&[u8], &mut std::slice::Iter<'a, Vec<u8>>) -> bool,
+    ) -> Result<Self, RequestError> {
+        let cursor = match String::from_utf8_lossy(&tokens[0]).parse::<u64>() {
+            Ok(v) => v,
+            Err(_) => return Err(RequestError::InvalidCursor),
+        };
+        let mut pattern = None;
+        let mut count = DEFAULT_SCAN_COUNT;
+        let mut options = tokens[1..].iter();
+        while let Some(option) = options.next() {
+            let option = option.to_ascii_lowercase();
+            match option.as_slice() {
+                b"match" => match options.next() {
+                    Some(v) => pattern = Some(v.clone()),
+                    None => return Err(RequestError::SyntaxError),
+                },
+                b"count" => {
+                    let Some(v) = options.next() else {
+                        return Err(RequestError::SyntaxError);
+                    };
+                    count = match String::from_utf8_lossy(v).parse::<i64>() {
+                        Ok(v) if v >= 1 => v as usize,
+                        Ok(_) => return Err(RequestError::SyntaxError),
+                        Err(_) => return Err(RequestError::InvalidIntValue),
+                    };
+                }
+                v if parse_extra(v, &mut options) => (),
+                _ => return Err(RequestError::SyntaxError),
+            }
+        }
+        Ok(ScanOptions {
+            cursor,
+            pattern,
+            count,
+        })
+    }
+
+    pub fn matches(&self, value: &[u8]) -> bool {
+        match &self.pattern {
+            Some(pattern) => glob_match(pattern, value),
+            None => true,
+        }
+    }
+}
+
+#[derive(Debug)]
+pub struct ScanCommand {
+    options: ScanOptions,
+    /// Only return keys of this type, as named by `TYPE`.
+    type_: Option<String>,
+}
+
+impl ScanCommand {
+    pub fn new(tokens: Vec<Vec<u8>>) -> Result<Box<Self>, RequestError> {
+        if tokens.is_empty() {
+            return Err(RequestError::IncorrectArgCount);
+        }
+        let mut type_ = None;
+        let mut missing_type = false;
+        let options = ScanOptions::parse(&tokens, |option, rest| match option {
+            b"type" => {
+                match rest.next() {
+                    Some(v) => type_ = Some(String::from_utf8_lossy(v).to_lowercase()),
+                    None => missing_type = true,
+                }
+                true
+            }
+            _ => false,
+        })?;
+        if missing_type {
+            return Err(RequestError::SyntaxError);
+        }
+        Ok(Box::new(ScanCommand { options, type_ }))
+    }
+}
+
+impl Command for ScanCommand {
+    fn execute(
+        &self,
+        data_store: &mut DataStore,
+    ) -> Result<Box<dyn ExecutionResult>, Box<dyn std::error::Error>> {
+        // Filters are applied after picking the keys of this iteration, so a call may return
+        // fewer keys than the count, or none at all, before the scan is complete.
+        let (keys, cursor) = data_store.scan(self.options.cursor, self.options.count);
+        let values = keys
+            .into_iter()
+            .filter(|key| self.options.matches(key))
+            .filter(|key| match &self.type_ {
+                Some(type_) => data_store.get_type(key).is_some_and(|t| t.name() == type_),
+                None => true,
+            })
+            .collect();
+        Ok(Box::new(ScanResult { cursor, values }))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{ScanCommand, ScanOptions};
+    use crate::command::Command;
+    use crate::data_store::DataStore;
+    use crate::execution_result::RespVersion;
+    use crate::utils::tokens;
+    use std::collections::HashSet;
+
+    #[test]
+    fn should_parse_options() {
+        let v =
+            ScanCommand::new(tokens(&["5", "MATCH", "a*", "count", "20", "TYPE", "Hash"])).unwrap();
+        assert_eq!(
+            v.options,
+            ScanOptions {
+                cursor: 5,
+                pattern: Some(b"a*".to_vec()),
+                count: 20,
+            }
+        );
+        assert_eq!(v.type_, Some("hash".to_string()));
+
+        let err = ScanCommand::new(tokens(&["-1"])).err().unwrap();
+        assert_eq!(err.to_string(), "ERR invalid cursor".to_string());
+        for options in [
+            &["0", "COUNT", "0"][..],
+            &["0", "MATCH"],
+            &["0", "TYPE"],
+            &["0", "LIMIT", "1"],
+        ] {
+            let err = ScanCommand::new(tokens(options)).err().unwrap();
+            assert_eq!(err.to_string(), "ERR syntax error".to_string());
+        }
+    }
+
+    #[test]
+    fn should_iterate_over_all_keys() {
+        let mut ds = DataStore::new();
+        for i in 0..30 {
+            ds.set_string_overwrite(format!("key{}", i).as_bytes(), b"v");
+        }
+        let mut seen = HashSet::new();
+        let mut cursor = "0".to_string();
+        loop {
+            let cmd = ScanCommand::new(tokens(&[&cursor, "COUNT", "4"])).unwrap();
+            let result = cmd.execute(&mut ds).unwrap().to_string();
+            let mut values = result.split(',');
+            cursor = values.next().unwrap().to_string();
+            for key in values {
+                assert!(seen.insert(key.to_string()));
+            }
+            if cursor == "0" {
+                break;
+            }
+        }
+        assert_eq!(seen.len(), 30);
+    }
+
+    #[test]
+    fn should_filter_by_pattern_and_type() {
+        let mut ds = DataStore::new();
+        ds.set_string_overwrite(b"a1", b"v");
+        ds.set_string_overwrite(b"b1", b"v");
+        let _ = ds.insert_list(b"a2");
+        let cmd = ScanCommand::new(tokens(&["0", "MATCH", "a*", "COUNT", "100"])).unwrap();
+        let result = cmd.execute(&mut ds).unwrap().to_string();
+        let keys: HashSet<&str> = result.split(',').skip(1).collect();
+        assert_eq!(keys, HashSet::from(["a1", "a2"]));
+
+        let cmd = ScanCommand::new(tokens(&["0", "MATCH", "a*", "TYPE", "list"])).unwrap();
+        let result = cmd.execute(&mut ds).unwrap();
+        assert_eq!(
+            result.serialise(RespVersion::Resp2),
+            b"*2\r\n$1\r\n0\r\n*1\r\n$2\r\na2\r\n".to_vec()
+        );
+    }
+}
@@ -0,0 +1,77 @@
+use crate::command::keyspace::TimeUnit;
+use crate::command::Command;
+use crate::data_store::{now_ms, DataStore};
+use crate::error::RequestError;
+use crate::execution_result::keyspace::TtlResult;
+use crate::execution_result::ExecutionResult;
+
+#[derive(Debug)]
+pub struct TtlCommand {
+    key: Vec<u8>,
+    unit: TimeUnit,
+}
+
+impl TtlCommand {
+    pub fn new(tokens: Vec<Vec<u8>>, unit: TimeUnit) -> Result<Box<Self>, RequestError> {
+        if tokens.len() != 1 {
+            return Err(RequestError::IncorrectArgCount);
+        }
+        Ok(Box::new(TtlCommand {
+            key: tokens[0].clone(),
+            unit,
+        }))
+    }
+}
+
+impl Command for TtlCommand {
+    fn execute(
+        &self,
+        data_store: &mut DataStore,
+    ) -> Result<Box<dyn ExecutionResult>, Box<dyn std::error::Error>> {
+        if !data_store.contains_key(&self.key) {
+            return Ok(Box::new(TtlResult { value: -2 }));
+        }
+        let value = match data_store.get_expire_at(&self.key) {
+            Some(deadline) => {
+                let remaining = deadline.saturating_sub(now_ms()) as i64;
+                // Round to the closest second rather than down, the way Redis does.
+                (remaining + self.unit.as_ms() / 2) / self.unit.as_ms()
+            }
+            None => -1,
+        };
+        Ok(Box::new(TtlResult { value }))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::TtlCommand;
+    use crate::command::keyspace::TimeUnit;
+    use crate::command::Command;
+    use crate::data_store::{now_ms, DataStore};
+
+    #[test]
+    fn should_accept_exactly_one_token() {
+        let err = TtlCommand::new(vec![], TimeUnit::Seconds).err().unwrap();
+        assert_eq!(
+            err.to_string(),
+            "ERR wrong number of arguments for command".to_string()
+        );
+    }
+
+    #[test]
+    fn should_return_remaining_time() {
+        let mut ds = DataStore::new();
+        let ttl = TtlCommand::new(vec![b"foo".to_vec()], TimeUnit::Seconds).unwrap();
+        let pttl = TtlCommand::new(vec![b"foo".to_vec()], TimeUnit::Milliseconds).unwrap();
+        assert_eq!(ttl.execute(&mut ds).unwrap().to_string(), "-2");
+        assert_eq!(pttl.execute(&mut ds).unwrap().to_string(), "-2");
+        ds.set_string_overwrite(b"foo", b"bar");
+        assert_eq!(ttl.execute(&mut ds).unwrap().to_string(), "-1");
+        assert_eq!(pttl.execute(&mut ds).unwrap().to_string(), "-1");
+        ds.set_expire_at(b"foo", now_ms() + 10000);
+        assert_eq!(ttl.execute(&mut ds).unwrap().to_string(), "10");
+        let remaining: i64 = pttl.execute(&mut ds).unwrap().to_string().parse().unwrap();
+        assert!(remaining > 9000 && remaining <= 10000);
+    }
+}
@@ -6,11 +6,11 @@ use crate::execution_result::ExecutionResult;
 
 #[derive(Debug)]
 pub struct LLenCommand {
-    key: String,
+    key: Vec<u8>,
 }
 
 impl LLenCommand {
-    pub fn new(tokens: Vec<String>) -> Result<Box<Self>, RequestError> {
+    pub fn new(tokens: Vec<Vec<u8>>) -> Result<Box<Self>, RequestError> {
         if tokens.len() != 1 {
             return Err(RequestError::IncorrectArgCount);
         }
@@ -42,32 +42,32 @@ mod test {
 
     #[test]
     fn should_accept_exactly_one_token() {
-        let err = LLenCommand::new(vec!["foo".to_string(), "bar".to_string()])
+        let err = LLenCommand::new(vec![b"foo".to_vec(), b"bar".to_vec()])
             .err()
             .unwrap();
         assert_eq!(
             err.to_string(),
             "ERR wrong number of arguments for command".to_string()
         );
-        let v = LLenCommand::new(vec!["foo".to_string()]).unwrap();
-        assert_eq!(v.key, "foo".to_string());
+        let v = LLenCommand::new(vec![b"foo".to_vec()]).unwrap();
+        assert_eq!(v.key, b"foo".to_vec());
     }
 
     #[test]
     fn should_return_list_length() {
         let mut ds = DataStore::new();
-        let key = "foo".to_string();
+        let key = b"foo".to_vec();
         let _ = ds.insert_list(&key);
         let list = ds.get_list_mut(&key).unwrap().unwrap();
         for v in 0..10 {
-            list.push_back(v.to_string());
+            list.push_back(v.to_string().into_bytes());
         }
         let cmd = LLenCommand::new(vec![key.clone()]).unwrap();
         let result = cmd.execute(&mut ds);
         assert_eq!(result.unwrap().to_string(), "10".to_string());
 
         // non-existent key
-        let cmd = LLenCommand::new(vec!["bar".to_string()]).unwrap();
+        let cmd = LLenCommand::new(vec![b"bar".to_vec()]).unwrap();
         let result = cmd.execute(&mut ds);
         assert_eq!(result.unwrap().to_string(), "0".to_string());
     }
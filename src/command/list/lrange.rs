@@ -6,20 +6,20 @@ use crate::execution_result::ExecutionResult;
 
 #[derive(Debug)]
 pub struct LRangeCommand {
-    key: String,
+    key: Vec<u8>,
     start: i64,
     stop: i64,
 }
 
 impl LRangeCommand {
-    pub fn new(tokens: Vec<String>) -> Result<Box<Self>, RequestError> {
+    pub fn new(tokens: Vec<Vec<u8>>) -> Result<Box<Self>, RequestError> {
         if tokens.len() != 3 {
             return Err(RequestError::IncorrectArgCount);
         }
-        let Ok(start) = tokens[1].parse::<i64>() else {
+        let Ok(start) = String::from_utf8_lossy(&tokens[1]).parse::<i64>() else {
             return Err(RequestError::InvalidIntValue);
         };
-        let Ok(stop) = tokens[2].parse::<i64>() else {
+        let Ok(stop) = String::from_utf8_lossy(&tokens[2]).parse::<i64>() else {
             return Err(RequestError::InvalidIntValue);
         };
         Ok(Box::new(LRangeCommand {
@@ -68,21 +68,20 @@ mod test {
 
     #[test]
     fn should_accept_exactly_3_tokens() {
-        let err = LRangeCommand::new(vec!["foo".to_string()]).err().unwrap();
+        let err = LRangeCommand::new(vec![b"foo".to_vec()]).err().unwrap();
         assert_eq!(
             err.to_string(),
             "ERR wrong number of arguments for command".to_string()
         );
-        let v =
-            LRangeCommand::new(vec!["foo".to_string(), "1".to_string(), "2".to_string()]).unwrap();
-        assert_eq!(v.key, "foo".to_string());
+        let v = LRangeCommand::new(vec![b"foo".to_vec(), b"1".to_vec(), b"2".to_vec()]).unwrap();
+        assert_eq!(v.key, b"foo".to_vec());
         assert_eq!(v.start, 1);
         assert_eq!(v.stop, 2);
     }
 
     #[test]
     fn should_reject_invalid_start_and_stop() {
-        let err = LRangeCommand::new(vec!["foo".to_string(), "bad".to_string(), "2".to_string()])
+        let err = LRangeCommand::new(vec![b"foo".to_vec(), b"bad".to_vec(), b"2".to_vec()])
             .err()
             .unwrap();
         assert_eq!(
@@ -94,25 +93,24 @@ mod test {
     #[test]
     fn should_list_items() {
         let mut ds = DataStore::new();
-        let key = "foo".to_string();
+        let key = b"foo".to_vec();
         let _ = ds.insert_list(&key);
         let list = ds.get_list_mut(&key).unwrap().unwrap();
         for v in 0..10 {
-            list.push_back(v.to_string());
+            list.push_back(v.to_string().into_bytes());
         }
-        let cmd = LRangeCommand::new(vec![key.clone(), "1".to_string(), "3".to_string()]).unwrap();
+        let cmd = LRangeCommand::new(vec![key.clone(), b"1".to_vec(), b"3".to_vec()]).unwrap();
         let result = cmd.execute(&mut ds);
         assert_eq!(result.unwrap().to_string(), "1,2,3".to_string());
 
         // negative start/stop
-        let cmd =
-            LRangeCommand::new(vec![key.clone(), "-4".to_string(), "-2".to_string()]).unwrap();
+        let cmd = LRangeCommand::new(vec![key.clone(), b"-4".to_vec(), b"-2".to_vec()]).unwrap();
         let result = cmd.execute(&mut ds);
         assert_eq!(result.unwrap().to_string(), "6,7,8".to_string());
 
         // non-existent key
-        let cmd = LRangeCommand::new(vec!["bar".to_string(), "-4".to_string(), "-2".to_string()])
-            .unwrap();
+        let cmd =
+            LRangeCommand::new(vec![b"bar".to_vec(), b"-4".to_vec(), b"-2".to_vec()]).unwrap();
         let result = cmd.execute(&mut ds);
         assert_eq!(result.unwrap().to_string(), "".to_string());
     }
@@ -7,26 +7,27 @@ use crate::execution_result::ExecutionResult;
 
 #[derive(Debug)]
 pub struct PopCommand {
-    key: String,
-    count: usize,
+    key: Vec<u8>,
+    /// The number of elements to pop, or `None` to pop a single element without a count.
+    count: Option<usize>,
     direction: OperationDirection,
 }
 
 impl PopCommand {
     pub fn new(
-        tokens: Vec<String>,
+        tokens: Vec<Vec<u8>>,
         direction: OperationDirection,
     ) -> Result<Box<Self>, RequestError> {
         if tokens.len() != 1 && tokens.len() != 2 {
             return Err(RequestError::IncorrectArgCount);
         }
         let count = if tokens.len() == 2 {
-            match tokens[1].parse::<usize>() {
-                Ok(v) => v,
+            match String::from_utf8_lossy(&tokens[1]).parse::<usize>() {
+                Ok(v) => Some(v),
                 Err(_) => return Err(RequestError::InvalidNegValue),
             }
         } else {
-            1
+            None
         };
         Ok(Box::new(PopCommand {
             key: tokens[0].clone(),
@@ -44,7 +45,7 @@ impl Command for PopCommand {
         let values = match data_store.get_list_mut(&self.key)? {
             Some(list) => {
                 let mut values = Vec::new();
-                for _ in 0..self.count {
+                for _ in 0..self.count.unwrap_or(1) {
                     let pop_result = match &self.direction {
                         OperationDirection::Left => list.pop_front(),
                         OperationDirection::Right => list.pop_back(),
@@ -58,12 +59,17 @@ impl Command for PopCommand {
                 let len = list.len();
                 if len == 0 {
                     data_store.drop_key(&self.key);
+                } else if !values.is_empty() {
+                    data_store.touch(&self.key);
                 }
                 values
             }
             None => Vec::new(),
         };
-        Ok(Box::new(PopResult { values }))
+        Ok(Box::new(PopResult {
+            values,
+            with_count: self.count.is_some(),
+        }))
     }
 }
 
@@ -72,33 +78,34 @@ mod test {
     use crate::command::list::{OperationDirection, PopCommand, PushCommand};
     use crate::command::Command;
     use crate::data_store::DataStore;
+    use crate::execution_result::RespVersion;
 
     #[test]
     fn should_accept_one_or_two_tokens() {
-        let v = PopCommand::new(vec!["foo".to_string()], OperationDirection::Left).unwrap();
-        assert_eq!(v.key, "foo".to_string());
-        assert_eq!(v.count, 1);
+        let v = PopCommand::new(vec![b"foo".to_vec()], OperationDirection::Left).unwrap();
+        assert_eq!(v.key, b"foo".to_vec());
+        assert_eq!(v.count, None);
         let v = PopCommand::new(
-            vec!["foo".to_string(), "3".to_string()],
+            vec![b"foo".to_vec(), b"3".to_vec()],
             OperationDirection::Left,
         )
         .unwrap();
-        assert_eq!(v.key, "foo".to_string());
-        assert_eq!(v.count, 3);
+        assert_eq!(v.key, b"foo".to_vec());
+        assert_eq!(v.count, Some(3));
     }
 
     #[test]
     fn should_reject_invalid_count() {
         let expected_msg = "value is out of range, must be positive".to_string();
         let err = PopCommand::new(
-            vec!["foo".to_string(), "bar".to_string()],
+            vec![b"foo".to_vec(), b"bar".to_vec()],
             OperationDirection::Left,
         )
         .err()
         .unwrap();
         assert_eq!(err.to_string(), expected_msg);
         let err = PopCommand::new(
-            vec!["foo".to_string(), "-6".to_string()],
+            vec![b"foo".to_vec(), b"-6".to_vec()],
             OperationDirection::Left,
         )
         .err()
@@ -108,10 +115,10 @@ mod test {
 
     #[test]
     fn should_pop_item_from_the_front_with_count_1() {
-        let key = "foo".to_string();
+        let key = b"foo".to_vec();
         let mut ds = DataStore::new();
         let _ = PushCommand::new(
-            vec![key.clone(), "bar".to_string(), "baz".to_string()],
+            vec![key.clone(), b"bar".to_vec(), b"baz".to_vec()],
             OperationDirection::Left,
         )
         .unwrap()
@@ -122,81 +129,95 @@ mod test {
         assert_eq!(result.unwrap().to_string(), "baz".to_string());
         let list = ds.get_list_mut(&key).unwrap().unwrap();
         assert_eq!(list.len(), 1);
-        assert_eq!(*list.back().unwrap(), "bar".to_string());
+        assert_eq!(*list.back().unwrap(), b"bar".to_vec());
     }
 
     #[test]
     fn should_pop_item_from_the_front_with_count_greater_than_1_left() {
-        let key = "foo".to_string();
+        let key = b"foo".to_vec();
         let mut ds = DataStore::new();
         let _ = PushCommand::new(
             vec![
                 key.clone(),
-                "v0".to_string(),
-                "v1".to_string(),
-                "v2".to_string(),
-                "v3".to_string(),
+                b"v0".to_vec(),
+                b"v1".to_vec(),
+                b"v2".to_vec(),
+                b"v3".to_vec(),
             ],
             OperationDirection::Left,
         )
         .unwrap()
         .execute(&mut ds);
-        let result = PopCommand::new(vec![key.clone(), "3".to_string()], OperationDirection::Left)
+        let result = PopCommand::new(vec![key.clone(), b"3".to_vec()], OperationDirection::Left)
             .unwrap()
             .execute(&mut ds);
         assert_eq!(result.unwrap().to_string(), "v3,v2,v1");
         let list = ds.get_list_mut(&key).unwrap().unwrap();
         assert_eq!(list.len(), 1);
-        assert_eq!(*list.back().unwrap(), "v0".to_string());
+        assert_eq!(*list.back().unwrap(), b"v0".to_vec());
     }
 
     #[test]
     fn should_pop_item_from_the_front_with_count_greater_than_1_right() {
-        let key = "foo".to_string();
+        let key = b"foo".to_vec();
         let mut ds = DataStore::new();
         let _ = PushCommand::new(
             vec![
                 key.clone(),
-                "v0".to_string(),
-                "v1".to_string(),
-                "v2".to_string(),
-                "v3".to_string(),
+                b"v0".to_vec(),
+                b"v1".to_vec(),
+                b"v2".to_vec(),
+                b"v3".to_vec(),
             ],
             OperationDirection::Left,
         )
         .unwrap()
         .execute(&mut ds);
-        let result = PopCommand::new(
-            vec![key.clone(), "3".to_string()],
-            OperationDirection::Right,
-        )
-        .unwrap()
-        .execute(&mut ds);
+        let result = PopCommand::new(vec![key.clone(), b"3".to_vec()], OperationDirection::Right)
+            .unwrap()
+            .execute(&mut ds);
         assert_eq!(result.unwrap().to_string(), "v0,v1,v2");
         let list = ds.get_list_mut(&key).unwrap().unwrap();
         assert_eq!(list.len(), 1);
-        assert_eq!(*list.back().unwrap(), "v3".to_string());
+        assert_eq!(*list.back().unwrap(), b"v3".to_vec());
     }
 
     #[test]
     fn should_return_nothing_when_key_does_not_exist() {
         let mut ds = DataStore::new();
-        let result = PopCommand::new(vec!["foo".to_string()], OperationDirection::Left)
+        let result = PopCommand::new(vec![b"foo".to_vec()], OperationDirection::Left)
             .unwrap()
             .execute(&mut ds);
         assert_eq!(result.unwrap().to_string(), "".to_string());
     }
 
     #[test]
-    fn should_remove_key_when_list_is_empty() {
-        let key = "foo".to_string();
+    fn should_reply_with_null_per_count_and_protocol() {
         let mut ds = DataStore::new();
-        let _ = PushCommand::new(
-            vec![key.clone(), "bar".to_string()],
+        let result = PopCommand::new(vec![b"foo".to_vec()], OperationDirection::Left)
+            .unwrap()
+            .execute(&mut ds)
+            .unwrap();
+        assert_eq!(result.serialise(RespVersion::Resp2), b"$-1\r\n".to_vec());
+        assert_eq!(result.serialise(RespVersion::Resp3), b"_\r\n".to_vec());
+        let result = PopCommand::new(
+            vec![b"foo".to_vec(), b"2".to_vec()],
             OperationDirection::Left,
         )
         .unwrap()
-        .execute(&mut ds);
+        .execute(&mut ds)
+        .unwrap();
+        assert_eq!(result.serialise(RespVersion::Resp2), b"*-1\r\n".to_vec());
+        assert_eq!(result.serialise(RespVersion::Resp3), b"_\r\n".to_vec());
+    }
+
+    #[test]
+    fn should_remove_key_when_list_is_empty() {
+        let key = b"foo".to_vec();
+        let mut ds = DataStore::new();
+        let _ = PushCommand::new(vec![key.clone(), b"bar".to_vec()], OperationDirection::Left)
+            .unwrap()
+            .execute(&mut ds);
         let result = PopCommand::new(vec![key.clone()], OperationDirection::Left)
             .unwrap()
             .execute(&mut ds);
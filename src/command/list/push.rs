@@ -7,14 +7,14 @@ use crate::execution_result::ExecutionResult;
 
 #[derive(Debug)]
 pub struct PushCommand {
-    key: String,
-    values: Vec<String>,
+    key: Vec<u8>,
+    values: Vec<Vec<u8>>,
     direction: OperationDirection,
 }
 
 impl PushCommand {
     pub fn new(
-        tokens: Vec<String>,
+        tokens: Vec<Vec<u8>>,
         direction: OperationDirection,
     ) -> Result<Box<Self>, RequestError> {
         if tokens.len() < 2 {
@@ -46,7 +46,9 @@ impl Command for PushCommand {
                 OperationDirection::Right => list.push_back(value.clone()),
             };
         }
-        Ok(Box::new(PushResult { value: list.len() }))
+        let len = list.len();
+        data_store.touch(&self.key);
+        Ok(Box::new(PushResult { value: len }))
     }
 }
 
@@ -59,7 +61,7 @@ mod test {
 
     #[test]
     fn should_accept_at_least_two_tokens() {
-        let err = PushCommand::new(vec!["foo".to_string()], OperationDirection::Left)
+        let err = PushCommand::new(vec![b"foo".to_vec()], OperationDirection::Left)
             .err()
             .unwrap();
         assert_eq!(
@@ -67,19 +69,19 @@ mod test {
             "ERR wrong number of arguments for command".to_string()
         );
         let v = PushCommand::new(
-            vec!["foo".to_string(), "bar".to_string(), "baz".to_string()],
+            vec![b"foo".to_vec(), b"bar".to_vec(), b"baz".to_vec()],
             OperationDirection::Left,
         )
         .unwrap();
-        assert_eq!(v.key, "foo".to_string());
-        assert_eq!(v.values, vec!["bar".to_string(), "baz".to_string()]);
+        assert_eq!(v.key, b"foo".to_vec());
+        assert_eq!(v.values, vec![b"bar".to_vec(), b"baz".to_vec()]);
     }
 
     #[test]
     fn should_push_item_if_key_does_not_exist_left() {
-        let key = "foo".to_string();
+        let key = b"foo".to_vec();
         let cmd = PushCommand::new(
-            vec![key.clone(), "bar".to_string(), "baz".to_string()],
+            vec![key.clone(), b"bar".to_vec(), b"baz".to_vec()],
             OperationDirection::Left,
         )
         .unwrap();
@@ -87,15 +89,15 @@ mod test {
         let result = cmd.execute(&mut ds);
         assert_eq!(result.unwrap().to_string(), "2".to_string());
         let list = ds.get_list_mut(&key).unwrap().unwrap();
-        assert_eq!(*list.front().unwrap(), "baz".to_string());
-        assert_eq!(*list.back().unwrap(), "bar".to_string());
+        assert_eq!(*list.front().unwrap(), b"baz".to_vec());
+        assert_eq!(*list.back().unwrap(), b"bar".to_vec());
     }
 
     #[test]
     fn should_push_item_if_key_does_not_exist_right() {
-        let key = "foo".to_string();
+        let key = b"foo".to_vec();
         let cmd = PushCommand::new(
-            vec![key.clone(), "bar".to_string(), "baz".to_string()],
+            vec![key.clone(), b"bar".to_vec(), b"baz".to_vec()],
             OperationDirection::Right,
         )
         .unwrap();
@@ -103,7 +105,7 @@ mod test {
         let result = cmd.execute(&mut ds);
         assert_eq!(result.unwrap().to_string(), "2".to_string());
         let list = ds.get_list_mut(&key).unwrap().unwrap();
-        assert_eq!(*list.front().unwrap(), "bar".to_string());
-        assert_eq!(*list.back().unwrap(), "baz".to_string());
+        assert_eq!(*list.front().unwrap(), b"bar".to_vec());
+        assert_eq!(*list.back().unwrap(), b"baz".to_vec());
     }
 }
@@ -1,42 +1,66 @@
 mod base;
 mod ping;
 use crate::error::RequestError;
-pub use base::Command;
+pub use base::{Command, ConnectionCommand, ServerCommand};
 use ping::PingCommand;
+pub use server::Migration;
 mod types;
 use std::str::FromStr;
+pub use types::{
+    command_keys, is_script_command, is_subscriber_command, is_transaction_command,
+    is_write_command,
+};
 use types::{
-    CommandType, HashCommandType, ListCommandType, SetCommandType, SortedSetCommandType,
-    StreamCommandType, StringCommandType,
+    CommandType, ConnectionCommandType, HashCommandType, KeyspaceCommandType, ListCommandType,
+    ServerCommandType, SetCommandType, SortedSetCommandType, StreamCommandType, StringCommandType,
 };
 
+mod connection;
 mod hash;
+mod keyspace;
 mod list;
+mod server;
 mod set;
 mod sorted_set;
 mod stream;
 mod string;
 
+/// A command built from a request, grouped by the state it needs to be executed against.
+pub enum ParsedCommand {
+    Data(Box<dyn Command>),
+    Connection(Box<dyn ConnectionCommand>),
+    Server(Box<dyn ServerCommand>),
+}
+
 #[derive(Debug)]
 pub struct CommandFactory;
 
 impl CommandFactory {
     #[allow(clippy::new_ret_no_self)]
-    pub fn new(tokens: &[String]) -> Result<Box<dyn Command>, RequestError> {
-        let command = tokens[0].to_lowercase();
+    pub fn new(tokens: &[Vec<u8>]) -> Result<ParsedCommand, RequestError> {
+        let command = String::from_utf8_lossy(&tokens[0]).to_lowercase();
         let body = tokens[1..tokens.len()].into();
         match CommandType::from_str(&command) {
             Ok(c) => match c {
                 CommandType::Ping => match PingCommand::new(body) {
-                    Ok(v) => Ok(v),
+                    Ok(v) => Ok(ParsedCommand::Connection(v)),
                     Err(e) => Err(e),
                 },
-                CommandType::String(v) => handle_string_command(v, body),
-                CommandType::List(v) => handle_list_command(v, body),
-                CommandType::Set(v) => handle_set_command(v, body),
-                CommandType::Hash(v) => handle_hash_command(v, body),
-                CommandType::SortedSet(v) => handle_sorted_set_command(v, body),
-                CommandType::Stream(v) => handle_stream_command(v, body),
+                CommandType::String(v) => handle_string_command(v, body).map(ParsedCommand::Data),
+                CommandType::List(v) => handle_list_command(v, body).map(ParsedCommand::Data),
+                CommandType::Set(v) => handle_set_command(v, body).map(ParsedCommand::Data),
+                CommandType::Hash(v) => handle_hash_command(v, body).map(ParsedCommand::Data),
+                CommandType::SortedSet(v) => {
+                    handle_sorted_set_command(v, body).map(ParsedCommand::Data)
+                }
+                CommandType::Stream(v) => handle_stream_command(v, body).map(ParsedCommand::Data),
+                CommandType::Keyspace(v) => {
+                    handle_keyspace_command(v, body).map(ParsedCommand::Data)
+                }
+                CommandType::Connection(v) => {
+                    handle_connection_command(v, body).map(ParsedCommand::Connection)
+                }
+                CommandType::Server(v) => handle_server_command(v, body).map(ParsedCommand::Server),
             },
             Err(_) => Err(RequestError::UnsupportedCommand(command)),
         }
@@ -45,7 +69,7 @@ impl CommandFactory {
 
 fn handle_string_command(
     v: StringCommandType,
-    body: Vec<String>,
+    body: Vec<Vec<u8>>,
 ) -> Result<Box<dyn Command>, RequestError> {
     match v {
         StringCommandType::Set => match string::SetCommand::new(body) {
@@ -93,7 +117,7 @@ fn handle_string_command(
 
 fn handle_list_command(
     v: ListCommandType,
-    body: Vec<String>,
+    body: Vec<Vec<u8>>,
 ) -> Result<Box<dyn Command>, RequestError> {
     match v {
         ListCommandType::LPush => {
@@ -133,7 +157,7 @@ fn handle_list_command(
 
 fn handle_set_command(
     v: SetCommandType,
-    body: Vec<String>,
+    body: Vec<Vec<u8>>,
 ) -> Result<Box<dyn Command>, RequestError> {
     match v {
         SetCommandType::Add => match set::SAddCommand::new(body) {
@@ -160,12 +184,16 @@ fn handle_set_command(
             Ok(v) => Ok(v),
             Err(e) => Err(e),
         },
+        SetCommandType::Scan => match set::SScanCommand::new(body) {
+            Ok(v) => Ok(v),
+            Err(e) => Err(e),
+        },
     }
 }
 
 fn handle_hash_command(
     v: HashCommandType,
-    body: Vec<String>,
+    body: Vec<Vec<u8>>,
 ) -> Result<Box<dyn Command>, RequestError> {
     match v {
         HashCommandType::Set => match hash::HSetCommand::new(body) {
@@ -184,12 +212,16 @@ fn handle_hash_command(
             Ok(v) => Ok(v),
             Err(e) => Err(e),
         },
+        HashCommandType::Scan => match hash::HScanCommand::new(body) {
+            Ok(v) => Ok(v),
+            Err(e) => Err(e),
+        },
     }
 }
 
 fn handle_sorted_set_command(
     v: SortedSetCommandType,
-    body: Vec<String>,
+    body: Vec<Vec<u8>>,
 ) -> Result<Box<dyn Command>, RequestError> {
     match v {
         SortedSetCommandType::Add => match sorted_set::ZAddCommand::new(body) {
@@ -208,12 +240,16 @@ fn handle_sorted_set_command(
             Ok(v) => Ok(v),
             Err(e) => Err(e),
         },
+        SortedSetCommandType::Scan => match sorted_set::ZScanCommand::new(body) {
+            Ok(v) => Ok(v),
+            Err(e) => Err(e),
+        },
     }
 }
 
 fn handle_stream_command(
     v: StreamCommandType,
-    body: Vec<String>,
+    body: Vec<Vec<u8>>,
 ) -> Result<Box<dyn Command>, RequestError> {
     match v {
         StreamCommandType::Add => match stream::XAddCommand::new(body) {
@@ -222,3 +258,249 @@ fn handle_stream_command(
         },
     }
 }
+
+fn handle_keyspace_command(
+    v: KeyspaceCommandType,
+    body: Vec<Vec<u8>>,
+) -> Result<Box<dyn Command>, RequestError> {
+    match v {
+        KeyspaceCommandType::Del => match keyspace::DelCommand::new(body) {
+            Ok(v) => Ok(v),
+            Err(e) => Err(e),
+        },
+        KeyspaceCommandType::Unlink => match keyspace::UnlinkCommand::new(body) {
+            Ok(v) => Ok(v),
+            Err(e) => Err(e),
+        },
+        KeyspaceCommandType::Exists => match keyspace::ExistsCommand::new(body) {
+            Ok(v) => Ok(v),
+            Err(e) => Err(e),
+        },
+        KeyspaceCommandType::Touch => match keyspace::TouchCommand::new(body) {
+            Ok(v) => Ok(v),
+            Err(e) => Err(e),
+        },
+        KeyspaceCommandType::Type => match keyspace::TypeCommand::new(body) {
+            Ok(v) => Ok(v),
+            Err(e) => Err(e),
+        },
+        KeyspaceCommandType::Rename => match keyspace::RenameCommand::new(body, false) {
+            Ok(v) => Ok(v),
+            Err(e) => Err(e),
+        },
+        KeyspaceCommandType::RenameNx => match keyspace::RenameCommand::new(body, true) {
+            Ok(v) => Ok(v),
+            Err(e) => Err(e),
+        },
+        KeyspaceCommandType::Keys => match keyspace::KeysCommand::new(body) {
+            Ok(v) => Ok(v),
+            Err(e) => Err(e),
+        },
+        KeyspaceCommandType::Scan => match keyspace::ScanCommand::new(body) {
+            Ok(v) => Ok(v),
+            Err(e) => Err(e),
+        },
+        KeyspaceCommandType::Expire => {
+            match keyspace::ExpireCommand::new(body, keyspace::TimeUnit::Seconds, false) {
+                Ok(v) => Ok(v),
+                Err(e) => Err(e),
+            }
+        }
+        KeyspaceCommandType::PExpire => {
+            match keyspace::ExpireCommand::new(body, keyspace::TimeUnit::Milliseconds, false) {
+                Ok(v) => Ok(v),
+                Err(e) => Err(e),
+            }
+        }
+        KeyspaceCommandType::ExpireAt => {
+            match keyspace::ExpireCommand::new(body, keyspace::TimeUnit::Seconds, true) {
+                Ok(v) => Ok(v),
+                Err(e) => Err(e),
+            }
+        }
+        KeyspaceCommandType::PExpireAt => {
+            match keyspace::ExpireCommand::new(body, keyspace::TimeUnit::Milliseconds, true) {
+                Ok(v) => Ok(v),
+                Err(e) => Err(e),
+            }
+        }
+        KeyspaceCommandType::Ttl => {
+            match keyspace::TtlCommand::new(body, keyspace::TimeUnit::Seconds) {
+                Ok(v) => Ok(v),
+                Err(e) => Err(e),
+            }
+        }
+        KeyspaceCommandType::PTtl => {
+            match keyspace::TtlCommand::new(body, keyspace::TimeUnit::Milliseconds) {
+                Ok(v) => Ok(v),
+                Err(e) => Err(e),
+            }
+        }
+        KeyspaceCommandType::Persist => match keyspace::PersistCommand::new(body) {
+            Ok(v) => Ok(v),
+            Err(e) => Err(e),
+        },
+        KeyspaceCommandType::Dump => match keyspace::DumpCommand::new(body) {
+            Ok(v) => Ok(v),
+            Err(e) => Err(e),
+        },
+        KeyspaceCommandType::Restore | KeyspaceCommandType::RestoreAsking => {
+            match keyspace::RestoreCommand::new(body) {
+                Ok(v) => Ok(v),
+                Err(e) => Err(e),
+            }
+        }
+    }
+}
+
+fn handle_connection_command(
+    v: ConnectionCommandType,
+    body: Vec<Vec<u8>>,
+) -> Result<Box<dyn ConnectionCommand>, RequestError> {
+    match v {
+        ConnectionCommandType::Hello => match connection::HelloCommand::new(body) {
+            Ok(v) => Ok(v),
+            Err(e) => Err(e),
+        },
+        ConnectionCommandType::Quit => match connection::QuitCommand::new(body) {
+            Ok(v) => Ok(v),
+            Err(e) => Err(e),
+        },
+    }
+}
+
+fn handle_server_command(
+    v: ServerCommandType,
+    body: Vec<Vec<u8>>,
+) -> Result<Box<dyn ServerCommand>, RequestError> {
+    match v {
+        ServerCommandType::Select => match server::SelectCommand::new(body) {
+            Ok(v) => Ok(v),
+            Err(e) => Err(e),
+        },
+        ServerCommandType::Move => match server::MoveCommand::new(body) {
+            Ok(v) => Ok(v),
+            Err(e) => Err(e),
+        },
+        ServerCommandType::Copy => match server::CopyCommand::new(body) {
+            Ok(v) => Ok(v),
+            Err(e) => Err(e),
+        },
+        ServerCommandType::SwapDb => match server::SwapDbCommand::new(body) {
+            Ok(v) => Ok(v),
+            Err(e) => Err(e),
+        },
+        ServerCommandType::FlushDb => match server::FlushCommand::new(body, false) {
+            Ok(v) => Ok(v),
+            Err(e) => Err(e),
+        },
+        ServerCommandType::FlushAll => match server::FlushCommand::new(body, true) {
+            Ok(v) => Ok(v),
+            Err(e) => Err(e),
+        },
+        ServerCommandType::DbSize => match server::DbSizeCommand::new(body) {
+            Ok(v) => Ok(v),
+            Err(e) => Err(e),
+        },
+        ServerCommandType::Save => match server::SaveCommand::new(body, false) {
+            Ok(v) => Ok(v),
+            Err(e) => Err(e),
+        },
+        ServerCommandType::BgSave => match server::SaveCommand::new(body, true) {
+            Ok(v) => Ok(v),
+            Err(e) => Err(e),
+        },
+        ServerCommandType::LastSave => match server::LastSaveCommand::new(body) {
+            Ok(v) => Ok(v),
+            Err(e) => Err(e),
+        },
+        ServerCommandType::BgRewriteAof => match server::BgRewriteAofCommand::new(body) {
+            Ok(v) => Ok(v),
+            Err(e) => Err(e),
+        },
+        ServerCommandType::ReplicaOf => match server::ReplicaOfCommand::new(body) {
+            Ok(v) => Ok(v),
+            Err(e) => Err(e),
+        },
+        ServerCommandType::ReplConf => match server::ReplConfCommand::new(body) {
+            Ok(v) => Ok(v),
+            Err(e) => Err(e),
+        },
+        ServerCommandType::Psync => match server::PsyncCommand::new(body) {
+            Ok(v) => Ok(v),
+            Err(e) => Err(e),
+        },
+        ServerCommandType::Info => match server::InfoCommand::new(body) {
+            Ok(v) => Ok(v),
+            Err(e) => Err(e),
+        },
+        ServerCommandType::Cluster => match server::ClusterCommand::new(body) {
+            Ok(v) => Ok(v),
+            Err(e) => Err(e),
+        },
+        ServerCommandType::Asking => match server::AskingCommand::new(body) {
+            Ok(v) => Ok(v),
+            Err(e) => Err(e),
+        },
+        ServerCommandType::Migrate => match server::MigrateCommand::new(body) {
+            Ok(v) => Ok(v),
+            Err(e) => Err(e),
+        },
+        ServerCommandType::Multi => match server::MultiCommand::new(body) {
+            Ok(v) => Ok(v),
+            Err(e) => Err(e),
+        },
+        ServerCommandType::Exec => match server::ExecCommand::new(body) {
+            Ok(v) => Ok(v),
+            Err(e) => Err(e),
+        },
+        ServerCommandType::Discard => match server::DiscardCommand::new(body) {
+            Ok(v) => Ok(v),
+            Err(e) => Err(e),
+        },
+        ServerCommandType::Watch => match server::WatchCommand::new(body) {
+            Ok(v) => Ok(v),
+            Err(e) => Err(e),
+        },
+        ServerCommandType::Unwatch => match server::UnwatchCommand::new(body) {
+            Ok(v) => Ok(v),
+            Err(e) => Err(e),
+        },
+        ServerCommandType::Eval => match server::EvalCommand::new(body, false) {
+            Ok(v) => Ok(v),
+            Err(e) => Err(e),
+        },
+        ServerCommandType::EvalSha => match server::EvalCommand::new(body, true) {
+            Ok(v) => Ok(v),
+            Err(e) => Err(e),
+        },
+        ServerCommandType::Script => match server::ScriptCommand::new(body) {
+            Ok(v) => Ok(v),
+            Err(e) => Err(e),
+        },
+        ServerCommandType::Subscribe => match server::SubscribeCommand::new(body, false) {
+            Ok(v) => Ok(v),
+            Err(e) => Err(e),
+        },
+        ServerCommandType::Unsubscribe => match server::UnsubscribeCommand::new(body, false) {
+            Ok(v) => Ok(v),
+            Err(e) => Err(e),
+        },
+        ServerCommandType::PSubscribe => match server::SubscribeCommand::new(body, true) {
+            Ok(v) => Ok(v),
+            Err(e) => Err(e),
+        },
+        ServerCommandType::PUnsubscribe => match server::UnsubscribeCommand::new(body, true) {
+            Ok(v) => Ok(v),
+            Err(e) => Err(e),
+        },
+        ServerCommandType::Publish => match server::PublishCommand::new(body) {
+            Ok(v) => Ok(v),
+            Err(e) => Err(e),
+        },
+        ServerCommandType::PubSub => match server::PubSubCommand::new(body) {
+            Ok(v) => Ok(v),
+            Err(e) => Err(e),
+        },
+    }
+}
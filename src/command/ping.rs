@@ -1,13 +1,13 @@
-use crate::command::Command;
-use crate::data_store::DataStore;
+use crate::command::ConnectionCommand;
 use crate::error::RequestError;
 use crate::execution_result::{ExecutionResult, PingResult};
+use crate::session::Session;
 
 #[derive(Debug)]
 pub struct PingCommand;
 
 impl PingCommand {
-    pub fn new(tokens: Vec<String>) -> Result<Box<Self>, RequestError> {
+    pub fn new(tokens: Vec<Vec<u8>>) -> Result<Box<Self>, RequestError> {
         if !tokens.is_empty() {
             return Err(RequestError::IncorrectArgCount);
         }
@@ -15,11 +15,13 @@ impl PingCommand {
     }
 }
 
-impl Command for PingCommand {
+impl ConnectionCommand for PingCommand {
     fn execute(
         &self,
-        _: &mut DataStore,
+        session: &mut Session,
     ) -> Result<Box<dyn ExecutionResult>, Box<dyn std::error::Error>> {
-        Ok(Box::new(PingResult {}))
+        Ok(Box::new(PingResult {
+            subscribed: session.subscriber.is_some(),
+        }))
     }
 }
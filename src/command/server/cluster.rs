@@ -0,0 +1,284 @@
+use crate::cluster::slot::{key_slot, SLOTS};
+use crate::cluster::SlotState;
+use crate::command::ServerCommand;
+use crate::data_store::{now_ms, DataStore};
+use crate::error::{ClusterError, RequestError};
+use crate::execution_result::server::{
+    ClusterShardsResult, ClusterSlotsResult, ClusterTextResult, CountKeysInSlotResult,
+    GetKeysInSlotResult, KeySlotResult, OkResult,
+};
+use crate::execution_result::ExecutionResult;
+use crate::server::Server;
+use crate::session::Session;
+
+fn parse_slot(token: &[u8]) -> Result<u16, RequestError> {
+    match String::from_utf8_lossy(token).parse::<u16>() {
+        Ok(v) if (v as usize) < SLOTS => Ok(v),
+        _ => Err(RequestError::InvalidSlot),
+    }
+}
+
+#[derive(Debug)]
+enum Subcommand {
+    KeySlot(Vec<u8>),
+    Slots,
+    Shards,
+    Nodes,
+    Info,
+    MyId,
+    AddSlots(Vec<u16>),
+    Meet(String, String, Option<String>),
+    Replicate(String),
+    SetSlot(u16, SlotState),
+    GetKeysInSlot(u16, usize),
+    CountKeysInSlot(u16),
+}
+
+/// Parses the address given to `CLUSTER MEET`. The bus port defaults to the port plus 10000.
+fn parse_address(host: &str, port: &str, cport: Option<&str>) -> Result<(u16, u16), ClusterError> {
+    let invalid = || ClusterError::InvalidNodeAddress(format!("{}:{}", host, port));
+    if host.parse::<std::net::IpAddr>().is_err() {
+        return Err(invalid());
+    }
+    let port: u16 = port.parse().map_err(|_| invalid())?;
+    let cport = match cport {
+        Some(v) => v.parse().map_err(|_| invalid())?,
+        None => port.saturating_add(10000),
+    };
+    Ok((port, cport))
+}
+
+/// `CLUSTER subcommand [argument ...]`, which inspects and changes the cluster this node is part
+/// of.
+#[derive(Debug)]
+pub struct ClusterCommand {
+    subcommand: Subcommand,
+}
+
+impl ClusterCommand {
+    pub fn new(tokens: Vec<Vec<u8>>) -> Result<Box<Self>, RequestError> {
+        let Some((name, args)) = tokens.split_first() else {
+            return Err(RequestError::IncorrectArgCount);
+        };
+        let text = |v: &[u8]| String::from_utf8_lossy(v).to_string();
+        let name = String::from_utf8_lossy(name).to_lowercase();
+        let subcommand = match (name.as_str(), args) {
+            ("keyslot", [key]) => Subcommand::KeySlot(key.clone()),
+            ("slots", []) => Subcommand::Slots,
+            ("shards", []) => Subcommand::Shards,
+            ("nodes", []) => Subcommand::Nodes,
+            ("info", []) => Subcommand::Info,
+            ("myid", []) => Subcommand::MyId,
+            ("addslots", [_, ..]) => Subcommand::AddSlots(
+                args.iter()
+                    .map(|v| parse_slot(v))
+                    .collect::<Result<_, _>>()?,
+            ),
+            ("meet", [host, port] | [host, port, _]) => {
+                Subcommand::Meet(text(host), text(port), args.get(2).map(|v| text(v)))
+            }
+            ("replicate", [id]) => Subcommand::Replicate(text(id)),
+            ("setslot", [slot, state, rest @ ..]) => {
+                let state = match (state.to_ascii_lowercase().as_slice(), rest) {
+                    (b"migrating", [id]) => SlotState::Migrating(text(id)),
+                    (b"importing", [id]) => SlotState::Importing(text(id)),
+                    (b"node", [id]) => SlotState::Node(text(id)),
+                    (b"stable", []) => SlotState::Stable,
+                    _ => return Err(RequestError::SyntaxError),
+                };
+                Subcommand::SetSlot(parse_slot(slot)?, state)
+            }
+            ("getkeysinslot", [slot, count]) => {
+                let count = match String::from_utf8_lossy(count).parse::<usize>() {
+                    Ok(v) => v,
+                    Err(_) => return Err(RequestError::InvalidIntValue),
+                };
+                Subcommand::GetKeysInSlot(parse_slot(slot)?, count)
+            }
+            ("countkeysinslot", [slot]) => Subcommand::CountKeysInSlot(parse_slot(slot)?),
+            (
+                "keyslot" | "slots" | "shards" | "nodes" | "info" | "myid" | "addslots" | "meet"
+                | "replicate" | "setslot" | "getkeysinslot" | "countkeysinslot",
+                _,
+            ) => return Err(RequestError::IncorrectArgCount),
+            _ => {
+                return Err(RequestError::UnsupportedCommand(format!(
+                    "cluster {}",
+                    name
+                )))
+            }
+        };
+        Ok(Box::new(ClusterCommand { subcommand }))
+    }
+}
+
+impl ServerCommand for ClusterCommand {
+    fn execute(
+        &self,
+        server: &mut Server,
+        _: &mut Session,
+    ) -> Result<Box<dyn ExecutionResult>, Box<dyn std::error::Error>> {
+        let cluster = &mut server.cluster;
+        if !cluster.enabled {
+            return Err(Box::new(ClusterError::Disabled));
+        }
+        match &self.subcommand {
+            Subcommand::KeySlot(key) => Ok(Box::new(KeySlotResult {
+                value: key_slot(key) as usize,
+            })),
+            Subcommand::Slots => Ok(Box::new(ClusterSlotsResult {
+                ranges: cluster.slot_ranges(),
+            })),
+            Subcommand::Shards => Ok(Box::new(ClusterShardsResult {
+                shards: cluster.shards(),
+                myself: cluster.myself.clone(),
+                offset: server.replication.offset,
+            })),
+            Subcommand::Nodes => Ok(Box::new(ClusterTextResult {
+                value: cluster.nodes_text(),
+            })),
+            Subcommand::Info => Ok(Box::new(ClusterTextResult {
+                value: cluster.info(),
+            })),
+            Subcommand::MyId => Ok(Box::new(ClusterTextResult {
+                value: cluster.myself.clone(),
+            })),
+            Subcommand::AddSlots(slots) => {
+                cluster.add_slots(slots)?;
+                Ok(Box::new(OkResult))
+            }
+            Subcommand::Meet(host, port, cport) => {
+                let (port, cport) = parse_address(host, port, cport.as_deref())?;
+                cluster.meet(host, port, cport);
+                Ok(Box::new(OkResult))
+            }
+            Subcommand::Replicate(id) => {
+                cluster.replicate(id, &mut server.replication)?;
+                Ok(Box::new(OkResult))
+            }
+            Subcommand::SetSlot(slot, state) => {
+                let keys = keys_in_slot(server.databases.db_mut(0), *slot).count();
+                cluster.set_slot(*slot, state, keys)?;
+                Ok(Box::new(OkResult))
+            }
+            Subcommand::GetKeysInSlot(slot, count) => Ok(Box::new(GetKeysInSlotResult {
+                values: keys_in_slot(server.databases.db_mut(0), *slot)
+                    .take(*count)
+                    .map(|key| key.to_vec())
+                    .collect(),
+            })),
+            Subcommand::CountKeysInSlot(slot) => Ok(Box::new(CountKeysInSlotResult {
+                value: keys_in_slot(server.databases.db_mut(0), *slot).count(),
+            })),
+        }
+    }
+}
+
+/// Returns the keys of `slot` that have not expired. Nodes of a cluster only use database 0.
+fn keys_in_slot(data_store: &DataStore, slot: u16) -> impl Iterator<Item = &[u8]> {
+    let now = now_ms();
+    data_store
+        .entries()
+        .filter(move |(key, _, deadline)| key_slot(key) == slot && deadline.is_none_or(|d| d > now))
+        .map(|(key, _, _)| key)
+}
+
+/// `ASKING`, sent by clients redirected with `ASK` before the command they were redirected for.
+#[derive(Debug)]
+pub struct AskingCommand;
+
+impl AskingCommand {
+    pub fn new(tokens: Vec<Vec<u8>>) -> Result<Box<Self>, RequestError> {
+        if !tokens.is_empty() {
+            return Err(RequestError::IncorrectArgCount);
+        }
+        Ok(Box::new(AskingCommand))
+    }
+}
+
+impl ServerCommand for AskingCommand {
+    fn execute(
+        &self,
+        server: &mut Server,
+        session: &mut Session,
+    ) -> Result<Box<dyn ExecutionResult>, Box<dyn std::error::Error>> {
+        if !server.cluster.enabled {
+            return Err(Box::new(ClusterError::Disabled));
+        }
+        session.asking = true;
+        Ok(Box::new(OkResult))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::ClusterCommand;
+    use crate::command::ServerCommand;
+    use crate::config::ServerConfig;
+    use crate::execution_result::RespVersion;
+    use crate::server::Server;
+    use crate::session::Session;
+    use crate::utils::tokens;
+
+    #[test]
+    fn should_report_and_assign_slots() {
+        let config = ServerConfig {
+            cluster_enabled: true,
+            port: 7000,
+            dir: std::env::temp_dir().to_str().unwrap().to_string(),
+            cluster_config_file: format!("redis-rust-cluster-cmd-{}.conf", std::process::id()),
+            ..Default::default()
+        };
+        let mut server = Server::new(&config);
+        let mut session = Session::new();
+        let cmd = ClusterCommand::new(tokens(&["KEYSLOT", "{user1000}.following"])).unwrap();
+        let result = cmd.execute(&mut server, &mut session).unwrap();
+        assert_eq!(result.to_string(), "3443".to_string());
+
+        let cmd = ClusterCommand::new(tokens(&["addslots", "0", "1", "2"])).unwrap();
+        cmd.execute(&mut server, &mut session).unwrap();
+        let cmd = ClusterCommand::new(tokens(&["SLOTS"])).unwrap();
+        let result = cmd.execute(&mut server, &mut session).unwrap();
+        let id = server.cluster.myself.clone();
+        assert_eq!(
+            result.serialise(RespVersion::Resp2),
+            format!(
+                "*1\r\n*3\r\n:0\r\n:2\r\n*3\r\n$9\r\n127.0.0.1\r\n:7000\r\n$40\r\n{}\r\n",
+                id
+            )
+            .into_bytes()
+        );
+        let cmd = ClusterCommand::new(tokens(&["NODES"])).unwrap();
+        let result = cmd.execute(&mut server, &mut session).unwrap();
+        assert_eq!(
+            result.to_string(),
+            format!(
+                "{} 127.0.0.1:7000@17000 myself,master - 0 0 0 connected 0-2\n",
+                id
+            )
+        );
+
+        let err = ClusterCommand::new(tokens(&["ADDSLOTS", "16384"]))
+            .err()
+            .unwrap();
+        assert_eq!(
+            err.to_string(),
+            "ERR Invalid or out of range slot".to_string()
+        );
+        let err = ClusterCommand::new(tokens(&["ADDSLOTS"])).err().unwrap();
+        assert_eq!(
+            err.to_string(),
+            "ERR wrong number of arguments for command".to_string()
+        );
+        std::fs::remove_file(std::path::Path::new(&config.dir).join(&config.cluster_config_file))
+            .unwrap();
+
+        let mut server = Server::new(&ServerConfig::default());
+        let cmd = ClusterCommand::new(tokens(&["INFO"])).unwrap();
+        let err = cmd.execute(&mut server, &mut session).err().unwrap();
+        assert_eq!(
+            err.to_string(),
+            "ERR This instance has cluster support disabled".to_string()
+        );
+    }
+}
@@ -0,0 +1,204 @@
+use super::{check_db_index, parse_db_index};
+use crate::command::ServerCommand;
+use crate::error::{ExecutionError, RequestError};
+use crate::execution_result::keyspace::CopyResult;
+use crate::execution_result::ExecutionResult;
+use crate::server::Server;
+use crate::session::Session;
+
+#[derive(Debug)]
+pub struct CopyCommand {
+    source: Vec<u8>,
+    destination: Vec<u8>,
+    /// The database to copy to, as given by `DB`. Defaults to the selected one.
+    db: Option<i64>,
+    /// Whether to overwrite an existing destination.
+    replace: bool,
+}
+
+impl CopyCommand {
+    pub fn new(tokens: Vec<Vec<u8>>) -> Result<Box<Self>, RequestError> {
+        if tokens.len() < 2 {
+            return Err(RequestError::IncorrectArgCount);
+        }
+        let mut db = None;
+        let mut replace = false;
+        let mut options = tokens[2..].iter();
+        while let Some(option) = options.next() {
+            match option.to_ascii_lowercase().as_slice() {
+                b"replace" => replace = true,
+                b"db" => match options.next() {
+                    Some(v) => db = Some(parse_db_index(v)?),
+                    None => return Err(RequestError::SyntaxError),
+                },
+                _ => return Err(RequestError::SyntaxError),
+            }
+        }
+        Ok(Box::new(CopyCommand {
+            source: tokens[0].clone(),
+            destination: tokens[1].clone(),
+            db,
+            replace,
+        }))
+    }
+}
+
+impl ServerCommand for CopyCommand {
+    fn execute(
+        &self,
+        server: &mut Server,
+        session: &mut Session,
+    ) -> Result<Box<dyn ExecutionResult>, Box<dyn std::error::Error>> {
+        let db = match self.db {
+            Some(v) => check_db_index(&server.databases, v)?,
+            None => session.db,
+        };
+        if db == session.db {
+            let data_store = server.databases.db_mut(db);
+            if self.source == self.destination {
+                return Err(Box::new(ExecutionError::SameObject));
+            }
+            if !data_store.contains_key(&self.source)
+                || (!self.replace && data_store.contains_key(&self.destination))
+            {
+                return Ok(Box::new(CopyResult { value: false }));
+            }
+            data_store.copy(&self.source, &self.destination);
+            return Ok(Box::new(CopyResult { value: true }));
+        }
+        let (source, destination) = server.databases.pair_mut(session.db, db);
+        if !self.replace && destination.contains_key(&self.destination) {
+            return Ok(Box::new(CopyResult { value: false }));
+        }
+        let Some((entry, deadline)) = source.dump(&self.source) else {
+            return Ok(Box::new(CopyResult { value: false }));
+        };
+        destination.restore(&self.destination, entry, deadline);
+        Ok(Box::new(CopyResult { value: true }))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::CopyCommand;
+    use crate::command::ServerCommand;
+    use crate::config::ServerConfig;
+    use crate::server::Server;
+    use crate::session::Session;
+
+    fn copy(tokens: &[&str]) -> Box<CopyCommand> {
+        CopyCommand::new(tokens.iter().map(|t| t.as_bytes().to_vec()).collect()).unwrap()
+    }
+
+    #[test]
+    fn should_parse_options() {
+        assert!(!copy(&["a", "b"]).replace);
+        assert!(copy(&["a", "b", "REPLACE"]).replace);
+        assert_eq!(copy(&["a", "b", "db", "3"]).db, Some(3));
+        let err = CopyCommand::new(vec![b"a".to_vec(), b"b".to_vec(), b"c".to_vec()])
+            .err()
+            .unwrap();
+        assert_eq!(err.to_string(), "ERR syntax error".to_string());
+        let err = CopyCommand::new(vec![b"a".to_vec(), b"b".to_vec(), b"DB".to_vec()])
+            .err()
+            .unwrap();
+        assert_eq!(err.to_string(), "ERR syntax error".to_string());
+    }
+
+    #[test]
+    fn should_copy_value() {
+        let mut server = Server::new(&ServerConfig {
+            databases: 1,
+            ..Default::default()
+        });
+        let mut session = Session::new();
+        let ds = server.databases.db_mut(0);
+        let _ = ds.insert_list(b"src");
+        ds.get_list_mut(b"src")
+            .unwrap()
+            .unwrap()
+            .push_back(b"v".to_vec());
+        ds.set_string_overwrite(b"dst", b"old");
+
+        let result = copy(&["src", "new"]).execute(&mut server, &mut session);
+        assert_eq!(result.unwrap().to_string(), "1".to_string());
+        // The copy is independent of the source.
+        let ds = server.databases.db_mut(0);
+        ds.get_list_mut(b"new")
+            .unwrap()
+            .unwrap()
+            .push_back(b"w".to_vec());
+        assert_eq!(ds.get_list_mut(b"src").unwrap().unwrap().len(), 1);
+
+        let result = copy(&["src", "dst"]).execute(&mut server, &mut session);
+        assert_eq!(result.unwrap().to_string(), "0".to_string());
+        let ds = server.databases.db_mut(0);
+        assert_eq!(ds.get_string(b"dst").unwrap().unwrap(), &b"old".to_vec());
+        let result = copy(&["src", "dst", "replace"]).execute(&mut server, &mut session);
+        assert_eq!(result.unwrap().to_string(), "1".to_string());
+        let ds = server.databases.db_mut(0);
+        assert_eq!(ds.get_list_mut(b"dst").unwrap().unwrap().len(), 1);
+
+        let result = copy(&["missing", "dst2"]).execute(&mut server, &mut session);
+        assert_eq!(result.unwrap().to_string(), "0".to_string());
+        let err = copy(&["src", "src"])
+            .execute(&mut server, &mut session)
+            .err()
+            .unwrap();
+        assert_eq!(
+            err.to_string(),
+            "ERR source and destination objects are the same".to_string()
+        );
+    }
+
+    #[test]
+    fn should_copy_to_another_database() {
+        let mut server = Server::new(&ServerConfig {
+            databases: 2,
+            ..Default::default()
+        });
+        let mut session = Session::new();
+        server
+            .databases
+            .db_mut(0)
+            .set_string_overwrite(b"foo", b"bar");
+        server
+            .databases
+            .db_mut(1)
+            .set_string_overwrite(b"taken", b"old");
+
+        let result = copy(&["foo", "foo", "DB", "1"]).execute(&mut server, &mut session);
+        assert_eq!(result.unwrap().to_string(), "1".to_string());
+        assert_eq!(
+            server
+                .databases
+                .db_mut(1)
+                .get_string(b"foo")
+                .unwrap()
+                .unwrap(),
+            b"bar"
+        );
+        assert!(server.databases.db_mut(0).contains_key(b"foo"));
+
+        let result = copy(&["foo", "taken", "DB", "1"]).execute(&mut server, &mut session);
+        assert_eq!(result.unwrap().to_string(), "0".to_string());
+        let result =
+            copy(&["foo", "taken", "DB", "1", "REPLACE"]).execute(&mut server, &mut session);
+        assert_eq!(result.unwrap().to_string(), "1".to_string());
+        assert_eq!(
+            server
+                .databases
+                .db_mut(1)
+                .get_string(b"taken")
+                .unwrap()
+                .unwrap(),
+            b"bar"
+        );
+
+        let err = copy(&["foo", "bar", "DB", "2"])
+            .execute(&mut server, &mut session)
+            .err()
+            .unwrap();
+        assert_eq!(err.to_string(), "ERR DB index is out of range".to_string());
+    }
+}
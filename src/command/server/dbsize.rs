@@ -0,0 +1,62 @@
+use crate::command::ServerCommand;
+use crate::error::RequestError;
+use crate::execution_result::keyspace::DbSizeResult;
+use crate::execution_result::ExecutionResult;
+use crate::server::Server;
+use crate::session::Session;
+
+#[derive(Debug)]
+pub struct DbSizeCommand;
+
+impl DbSizeCommand {
+    pub fn new(tokens: Vec<Vec<u8>>) -> Result<Box<Self>, RequestError> {
+        if !tokens.is_empty() {
+            return Err(RequestError::IncorrectArgCount);
+        }
+        Ok(Box::new(DbSizeCommand))
+    }
+}
+
+impl ServerCommand for DbSizeCommand {
+    fn execute(
+        &self,
+        server: &mut Server,
+        session: &mut Session,
+    ) -> Result<Box<dyn ExecutionResult>, Box<dyn std::error::Error>> {
+        Ok(Box::new(DbSizeResult {
+            value: server.databases.db_mut(session.db).len(),
+        }))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::DbSizeCommand;
+    use crate::command::ServerCommand;
+    use crate::config::ServerConfig;
+    use crate::server::Server;
+    use crate::session::Session;
+
+    #[test]
+    fn should_count_keys_of_selected_database() {
+        let mut server = Server::new(&ServerConfig {
+            databases: 2,
+            ..Default::default()
+        });
+        let mut session = Session::new();
+        server.databases.db_mut(0).set_string_overwrite(b"a", b"v");
+        server.databases.db_mut(0).set_string_overwrite(b"b", b"v");
+        server.databases.db_mut(1).set_string_overwrite(b"a", b"v");
+        let cmd = DbSizeCommand::new(vec![]).unwrap();
+        assert_eq!(
+            cmd.execute(&mut server, &mut session).unwrap().to_string(),
+            "2".to_string()
+        );
+        session.db = 1;
+        assert_eq!(
+            cmd.execute(&mut server, &mut session).unwrap().to_string(),
+            "1".to_string()
+        );
+        assert!(DbSizeCommand::new(vec![b"x".to_vec()]).is_err());
+    }
+}
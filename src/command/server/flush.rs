@@ -0,0 +1,88 @@
+use crate::command::ServerCommand;
+use crate::error::RequestError;
+use crate::execution_result::server::OkResult;
+use crate::execution_result::ExecutionResult;
+use crate::server::Server;
+use crate::session::Session;
+
+/// `FLUSHDB`, or `FLUSHALL` when `all` is set.
+#[derive(Debug)]
+pub struct FlushCommand {
+    all: bool,
+}
+
+impl FlushCommand {
+    pub fn new(tokens: Vec<Vec<u8>>, all: bool) -> Result<Box<Self>, RequestError> {
+        // Values are always freed right away, so `ASYNC` and `SYNC` behave the same.
+        match tokens.as_slice() {
+            [] => (),
+            [mode] if mode.eq_ignore_ascii_case(b"async") || mode.eq_ignore_ascii_case(b"sync") => {
+            }
+            _ => return Err(RequestError::SyntaxError),
+        }
+        Ok(Box::new(FlushCommand { all }))
+    }
+}
+
+impl ServerCommand for FlushCommand {
+    fn execute(
+        &self,
+        server: &mut Server,
+        session: &mut Session,
+    ) -> Result<Box<dyn ExecutionResult>, Box<dyn std::error::Error>> {
+        match self.all {
+            true => server.databases.flush_all(),
+            false => server.databases.db_mut(session.db).flush(),
+        }
+        Ok(Box::new(OkResult))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::FlushCommand;
+    use crate::command::ServerCommand;
+    use crate::config::ServerConfig;
+    use crate::server::Server;
+    use crate::session::Session;
+
+    #[test]
+    fn should_accept_sync_mode() {
+        assert!(FlushCommand::new(vec![], false).is_ok());
+        assert!(FlushCommand::new(vec![b"ASYNC".to_vec()], true).is_ok());
+        assert!(FlushCommand::new(vec![b"sync".to_vec()], true).is_ok());
+        let err = FlushCommand::new(vec![b"later".to_vec()], false)
+            .err()
+            .unwrap();
+        assert_eq!(err.to_string(), "ERR syntax error".to_string());
+    }
+
+    #[test]
+    fn should_flush_selected_or_all_databases() {
+        let mut server = Server::new(&ServerConfig {
+            databases: 2,
+            ..Default::default()
+        });
+        let mut session = Session::new();
+        server
+            .databases
+            .db_mut(0)
+            .set_string_overwrite(b"foo", b"bar");
+        server
+            .databases
+            .db_mut(1)
+            .set_string_overwrite(b"foo", b"bar");
+        session.db = 1;
+        let cmd = FlushCommand::new(vec![], false).unwrap();
+        assert_eq!(
+            cmd.execute(&mut server, &mut session).unwrap().to_string(),
+            "OK".to_string()
+        );
+        assert!(server.databases.db_mut(1).is_empty());
+        assert!(!server.databases.db_mut(0).is_empty());
+
+        let cmd = FlushCommand::new(vec![], true).unwrap();
+        cmd.execute(&mut server, &mut session).unwrap();
+        assert!(server.databases.db_mut(0).is_empty());
+    }
+}
@@ -0,0 +1,83 @@
+use crate::command::ServerCommand;
+use crate::error::RequestError;
+use crate::execution_result::server::InfoResult;
+use crate::execution_result::ExecutionResult;
+use crate::server::Server;
+use crate::session::Session;
+
+/// `INFO [section ...]`. Without a section, or with `all`, `default` or `everything`, every
+/// section is reported.
+#[derive(Debug)]
+pub struct InfoCommand {
+    sections: Vec<String>,
+}
+
+impl InfoCommand {
+    pub fn new(tokens: Vec<Vec<u8>>) -> Result<Box<Self>, RequestError> {
+        Ok(Box::new(InfoCommand {
+            sections: tokens
+                .iter()
+                .map(|t| String::from_utf8_lossy(t).to_lowercase())
+                .collect(),
+        }))
+    }
+
+    fn includes(&self, section: &str) -> bool {
+        self.sections.is_empty()
+            || self
+                .sections
+                .iter()
+                .any(|s| s == section || ["all", "default", "everything"].contains(&s.as_str()))
+    }
+}
+
+impl ServerCommand for InfoCommand {
+    fn execute(
+        &self,
+        server: &mut Server,
+        _: &mut Session,
+    ) -> Result<Box<dyn ExecutionResult>, Box<dyn std::error::Error>> {
+        let mut sections = Vec::new();
+        if self.includes("replication") {
+            sections.push(server.replication.info());
+        }
+        if self.includes("cluster") {
+            sections.push(format!(
+                "# Cluster\r\ncluster_enabled:{}\r\n",
+                server.cluster.enabled as u8
+            ));
+        }
+        Ok(Box::new(InfoResult {
+            value: sections.join("\r\n"),
+        }))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::InfoCommand;
+    use crate::command::ServerCommand;
+    use crate::config::ServerConfig;
+    use crate::server::Server;
+    use crate::session::Session;
+
+    #[test]
+    fn should_report_requested_sections() {
+        let mut server = Server::new(&ServerConfig::default());
+        let mut session = Session::new();
+        let cmd = InfoCommand::new(vec![b"Replication".to_vec()]).unwrap();
+        let result = cmd.execute(&mut server, &mut session).unwrap();
+        assert!(result
+            .to_string()
+            .starts_with("# Replication\r\nrole:master\r\nconnected_slaves:0\r\n"));
+        let cmd = InfoCommand::new(vec![]).unwrap();
+        let result = cmd.execute(&mut server, &mut session).unwrap();
+        assert!(result.to_string().contains("master_repl_offset:0\r\n"));
+        assert!(result
+            .to_string()
+            .ends_with("\r\n# Cluster\r\ncluster_enabled:0\r\n"));
+        let cmd = InfoCommand::new(vec![b"unknown".to_vec()]).unwrap();
+        let result = cmd.execute(&mut server, &mut session).unwrap();
+        assert_eq!(result.to_string(), "".to_string());
+    }
+}
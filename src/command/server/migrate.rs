@@ -0,0 +1,408 @@
+use super::parse_db_index;
+use crate::command::ServerCommand;
+use crate::data_store::now_ms;
+use crate::error::{ExecutionError, RequestError};
+use crate::execution_result::server::MigrateResult;
+use crate::execution_result::ExecutionResult;
+use crate::persistence::aof::encode_command;
+use crate::persistence::rdb;
+use crate::server::Server;
+use crate::session::Session;
+use std::io::{BufRead, BufReader, Write};
+use std::net::{TcpStream, ToSocketAddrs};
+use std::time::Duration;
+
+/// The timeout used when `MIGRATE` is given one of 0 or less.
+const DEFAULT_TIMEOUT_MS: u64 = 1000;
+
+/// `MIGRATE host port key|"" db timeout [COPY] [REPLACE] [KEYS key ...]`, which moves keys to
+/// another instance by sending it the `RESTORE` of each one, and then deletes them here. The keys
+/// are dumped while the server is held, but sent once it is released, see `Migration`. The
+/// transfer fails once `timeout` milliseconds pass without progress.
+#[derive(Debug)]
+pub struct MigrateCommand {
+    host: String,
+    port: u16,
+    keys: Vec<Vec<u8>>,
+    db: i64,
+    timeout: Duration,
+    /// Whether to keep the keys here once they are moved.
+    copy: bool,
+    /// Whether to overwrite the keys that already exist on the target.
+    replace: bool,
+}
+
+/// The keys dumped by a `MIGRATE`, to be sent to the target without holding the server. The
+/// connection that ran the command sends them, and then finishes the migration with the server
+/// held again, which deletes the keys the target accepted.
+#[derive(Debug)]
+pub struct Migration {
+    host: String,
+    port: u16,
+    timeout: Duration,
+    copy: bool,
+    /// The database the keys are moved from.
+    db: usize,
+    /// The `SELECT` of the target database, followed by the `RESTORE` of each key.
+    request: Vec<u8>,
+    /// The keys to move, along with their version when they were dumped. They are watched until
+    /// the migration finishes, so that a key written to meanwhile is kept.
+    keys: Vec<(Vec<u8>, u64)>,
+}
+
+/// How the target answered a migration: whether it accepted each key, in order, and the error
+/// that made the transfer fail, if any.
+#[derive(Debug)]
+pub struct Transfer {
+    accepted: Vec<bool>,
+    error: Option<ExecutionError>,
+}
+
+impl MigrateCommand {
+    pub fn new(tokens: Vec<Vec<u8>>) -> Result<Box<Self>, RequestError> {
+        if tokens.len() < 5 {
+            return Err(RequestError::IncorrectArgCount);
+        }
+        let port = match String::from_utf8_lossy(&tokens[1]).parse::<u16>() {
+            Ok(v) => v,
+            Err(_) => return Err(RequestError::InvalidIntValue),
+        };
+        let db = parse_db_index(&tokens[3])?;
+        let timeout = match String::from_utf8_lossy(&tokens[4]).parse::<i64>() {
+            Ok(v) if v > 0 => v as u64,
+            Ok(_) => DEFAULT_TIMEOUT_MS,
+            Err(_) => return Err(RequestError::InvalidIntValue),
+        };
+        let (mut copy, mut replace) = (false, false);
+        let mut keys = vec![tokens[2].clone()];
+        let mut options = tokens[5..].iter();
+        while let Some(option) = options.next() {
+            match option.to_ascii_lowercase().as_slice() {
+                b"copy" => copy = true,
+                b"replace" => replace = true,
+                // The keys to move follow KEYS, and the key argument must then be empty.
+                b"keys" if tokens[2].is_empty() => {
+                    keys = options.cloned().collect();
+                    break;
+                }
+                _ => return Err(RequestError::SyntaxError),
+            }
+        }
+        Ok(Box::new(MigrateCommand {
+            host: String::from_utf8_lossy(&tokens[0]).to_string(),
+            port,
+            keys,
+            db,
+            timeout: Duration::from_millis(timeout),
+            copy,
+            replace,
+        }))
+    }
+}
+
+impl ServerCommand for MigrateCommand {
+    fn execute(
+        &self,
+        server: &mut Server,
+        session: &mut Session,
+    ) -> Result<Box<dyn ExecutionResult>, Box<dyn std::error::Error>> {
+        let restore = match server.cluster.enabled {
+            true => b"RESTORE-ASKING".to_vec(),
+            false => b"RESTORE".to_vec(),
+        };
+        let data_store = server.databases.db_mut(session.db);
+        let mut request = encode_command(&[b"SELECT".to_vec(), self.db.to_string().into_bytes()]);
+        let mut moved = Vec::new();
+        for key in &self.keys {
+            let Some((entry, deadline)) = data_store.dump(key) else {
+                continue;
+            };
+            // The TTL sent is what is left of it, and 0 means that the key does not expire.
+            let ttl = match deadline {
+                Some(deadline) => deadline.saturating_sub(now_ms()).max(1),
+                None => 0,
+            };
+            let mut command = vec![
+                restore.clone(),
+                key.clone(),
+                ttl.to_string().into_bytes(),
+                rdb::dump(&entry)?,
+            ];
+            if self.replace {
+                command.push(b"REPLACE".to_vec());
+            }
+            request.extend(encode_command(&command));
+            moved.push(key);
+        }
+        if moved.is_empty() {
+            return Ok(Box::new(MigrateResult { migrated: false }));
+        }
+        let keys = moved
+            .into_iter()
+            .map(|key| (key.clone(), data_store.watch(key)))
+            .collect();
+        let migration = Migration {
+            host: self.host.clone(),
+            port: self.port,
+            timeout: self.timeout,
+            copy: self.copy,
+            db: session.db,
+            request,
+            keys,
+        };
+        // A transaction or a script holds the server until it ends, so the keys are moved right
+        // away. Otherwise, the reply is the one the migration finishes with.
+        if server.is_atomic() {
+            let transfer = migration.transfer();
+            return migration.finish(server, transfer);
+        }
+        session.migration = Some(migration);
+        Ok(Box::new(MigrateResult { migrated: true }))
+    }
+}
+
+impl Migration {
+    /// Sends the keys to the target and reads its replies. This blocks until the transfer
+    /// completes or times out, so the server must not be held meanwhile.
+    pub fn transfer(&self) -> Transfer {
+        let mut accepted = Vec::new();
+        let error = self.send(&mut accepted).err();
+        Transfer { accepted, error }
+    }
+
+    fn send(&self, accepted: &mut Vec<bool>) -> Result<(), ExecutionError> {
+        let io_error = |action: &str, e: std::io::Error| {
+            log::debug!("MIGRATE to {}:{} failed: {}", self.host, self.port, e);
+            ExecutionError::MigrateIo(action.to_string())
+        };
+        let mut stream = self.connect().map_err(|e| io_error("connecting to", e))?;
+        stream
+            .write_all(&self.request)
+            .map_err(|e| io_error("writing to", e))?;
+        // The target replies with one line to the SELECT, and one to each RESTORE.
+        let mut reader = BufReader::new(stream);
+        let mut error = None;
+        for i in 0..=self.keys.len() {
+            let mut line = String::new();
+            match reader.read_line(&mut line) {
+                Ok(0) => {
+                    return Err(io_error(
+                        "reading from",
+                        std::io::ErrorKind::UnexpectedEof.into(),
+                    ))
+                }
+                Ok(_) => {}
+                Err(e) => return Err(io_error("reading from", e)),
+            }
+            let failed = match line.trim_end().strip_prefix('-') {
+                Some(message) => {
+                    error.get_or_insert_with(|| message.to_string());
+                    true
+                }
+                None => false,
+            };
+            match i {
+                0 if failed => break,
+                0 => {}
+                _ => accepted.push(!failed),
+            }
+        }
+        match error {
+            Some(message) => Err(ExecutionError::MigrateTarget(message)),
+            None => Ok(()),
+        }
+    }
+
+    /// Opens a connection to the target instance.
+    fn connect(&self) -> std::io::Result<TcpStream> {
+        let mut addresses = (self.host.as_str(), self.port).to_socket_addrs()?;
+        let Some(address) = addresses.next() else {
+            return Err(std::io::ErrorKind::AddrNotAvailable.into());
+        };
+        let stream = TcpStream::connect_timeout(&address, self.timeout)?;
+        stream.set_read_timeout(Some(self.timeout))?;
+        stream.set_write_timeout(Some(self.timeout))?;
+        Ok(stream)
+    }
+
+    /// Deletes the keys the target accepted, unless `COPY` was given or they changed while they
+    /// were sent, and returns the reply of the `MIGRATE`. The keys deleted are logged and streamed
+    /// to replicas even if the target rejected others.
+    pub fn finish(
+        self,
+        server: &mut Server,
+        transfer: Transfer,
+    ) -> Result<Box<dyn ExecutionResult>, Box<dyn std::error::Error>> {
+        let data_store = server.databases.db_mut(self.db);
+        let mut accepted = transfer.accepted.into_iter();
+        let mut deleted = Vec::new();
+        for (key, version) in self.keys {
+            let unchanged = data_store.version(&key) == version;
+            data_store.unwatch(&key);
+            if accepted.next() == Some(true) && unchanged && !self.copy {
+                data_store.drop_key(&key);
+                deleted.push(key);
+            }
+        }
+        server.record_deletions(self.db, deleted);
+        match transfer.error {
+            Some(e) => Err(Box::new(e)),
+            None => Ok(Box::new(MigrateResult { migrated: true })),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::MigrateCommand;
+    use crate::command::ServerCommand;
+    use crate::config::ServerConfig;
+    use crate::execution_result::RespVersion;
+    use crate::server::Server;
+    use crate::session::Session;
+    use crate::utils::tokens;
+    use std::io::{Read, Write};
+    use std::net::TcpListener;
+
+    /// Accepts one connection and answers `replies` once it has received `restores` keys.
+    fn target(replies: &'static str, restores: usize) -> (u16, std::thread::JoinHandle<Vec<u8>>) {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let port = listener.local_addr().unwrap().port();
+        let handle = std::thread::spawn(move || {
+            let (mut stream, _) = listener.accept().unwrap();
+            let mut received = Vec::new();
+            let mut buffer = [0; 4096];
+            while received.windows(7).filter(|c| c == b"RESTORE").count() < restores {
+                let read = stream.read(&mut buffer).unwrap();
+                received.extend_from_slice(&buffer[..read]);
+            }
+            stream.write_all(replies.as_bytes()).unwrap();
+            received
+        });
+        (port, handle)
+    }
+
+    /// Runs a `MIGRATE` the way a connection does, finishing the migration it leaves.
+    fn migrate(server: &mut Server, session: &mut Session, args: &[&str]) -> String {
+        let result = MigrateCommand::new(tokens(args))
+            .unwrap()
+            .execute(server, session);
+        let result = match session.migration.take() {
+            Some(migration) => {
+                let transfer = migration.transfer();
+                migration.finish(server, transfer)
+            }
+            None => result,
+        };
+        match result {
+            Ok(result) => result.to_string(),
+            Err(e) => e.to_string(),
+        }
+    }
+
+    #[test]
+    fn should_migrate_keys() {
+        let mut server = Server::new(&ServerConfig::default());
+        let mut session = Session::new();
+        let data_store = server.databases.db_mut(0);
+        data_store.set_string_overwrite(b"a", b"1");
+        data_store.set_string_overwrite(b"b", b"2");
+
+        let (port, handle) = target(
+            "+OK\r\n+OK\r\n-BUSYKEY Target key name already exists.\r\n",
+            2,
+        );
+        let port = port.to_string();
+        let args = ["127.0.0.1", &port, "", "2", "500", "KEYS", "a", "b", "c"];
+        assert_eq!(
+            migrate(&mut server, &mut session, &args),
+            "ERR Target instance replied with error: BUSYKEY Target key name already exists."
+                .to_string()
+        );
+        let received = String::from_utf8_lossy(&handle.join().unwrap()).to_string();
+        assert!(received.starts_with(
+            "*2\r\n$6\r\nSELECT\r\n$1\r\n2\r\n*4\r\n$7\r\nRESTORE\r\n$1\r\na\r\n$1\r\n0\r\n"
+        ));
+        let data_store = server.databases.db_mut(0);
+        assert!(!data_store.contains_key(b"a"));
+        assert!(data_store.contains_key(b"b"));
+
+        let (port, handle) = target("+OK\r\n+OK\r\n", 1);
+        let port = port.to_string();
+        let args = ["127.0.0.1", &port, "b", "0", "500", "COPY", "REPLACE"];
+        assert_eq!(migrate(&mut server, &mut session, &args), "OK".to_string());
+        assert!(String::from_utf8_lossy(&handle.join().unwrap()).contains("REPLACE"));
+        assert!(server.databases.db_mut(0).contains_key(b"b"));
+
+        let args = ["127.0.0.1", "1", "a", "0", "0"];
+        assert_eq!(
+            migrate(&mut server, &mut session, &args),
+            "NOKEY".to_string()
+        );
+        let err = MigrateCommand::new(tokens(&["127.0.0.1", "1", "a", "0", "0", "KEYS", "b"]))
+            .err()
+            .unwrap();
+        assert_eq!(err.to_string(), "ERR syntax error".to_string());
+    }
+
+    #[test]
+    fn should_propagate_keys_deleted_before_a_failure() {
+        let mut server = Server::new(&ServerConfig::default());
+        let mut session = Session::new();
+        let (_, feed) = server
+            .replication
+            .psync("?", -1, &server.databases, &session)
+            .unwrap();
+        let mut stream = feed.stream;
+        for key in ["a", "b", "c"] {
+            server
+                .execute(&tokens(&["SET", key, "1"]), &mut session)
+                .unwrap();
+        }
+        while stream.try_recv().is_some() {}
+        let (mut watcher, mut writer) = (Session::new(), Session::new());
+        server
+            .execute(&tokens(&["WATCH", "a"]), &mut watcher)
+            .unwrap();
+
+        let (port, handle) = target(
+            "+OK\r\n+OK\r\n-BUSYKEY Target key name already exists.\r\n+OK\r\n",
+            3,
+        );
+        let port = port.to_string();
+        let args = [
+            "MIGRATE",
+            "127.0.0.1",
+            &port,
+            "",
+            "0",
+            "500",
+            "KEYS",
+            "a",
+            "b",
+            "c",
+        ];
+        server.execute(&tokens(&args), &mut session).unwrap();
+        // A key written to while it is sent is kept.
+        server
+            .execute(&tokens(&["SET", "c", "2"]), &mut writer)
+            .unwrap();
+        while stream.try_recv().is_some() {}
+        let migration = session.migration.take().unwrap();
+        let transfer = migration.transfer();
+        let err = migration.finish(&mut server, transfer).err().unwrap();
+        assert!(err.to_string().contains("BUSYKEY"));
+        handle.join().unwrap();
+
+        assert_eq!(
+            stream.try_recv().unwrap(),
+            b"*2\r\n$3\r\nDEL\r\n$1\r\na\r\n".to_vec()
+        );
+        let data_store = server.databases.db_mut(0);
+        assert!(data_store.contains_key(b"b") && data_store.contains_key(b"c"));
+        // The deletion is seen by the clients watching the key.
+        server.execute(&tokens(&["MULTI"]), &mut watcher).unwrap();
+        let result = server.execute(&tokens(&["EXEC"]), &mut watcher).unwrap();
+        assert_eq!(result.serialise(RespVersion::Resp2), b"*-1\r\n".to_vec());
+    }
+}
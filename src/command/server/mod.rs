@@ -0,0 +1,47 @@
+mod cluster;
+pub use cluster::{AskingCommand, ClusterCommand};
+mod copy;
+pub use copy::CopyCommand;
+mod dbsize;
+pub use dbsize::DbSizeCommand;
+mod flush;
+pub use flush::FlushCommand;
+mod migrate;
+pub use migrate::{MigrateCommand, Migration};
+mod move_key;
+pub use move_key::MoveCommand;
+mod info;
+pub use info::InfoCommand;
+mod pubsub;
+pub use pubsub::{PubSubCommand, PublishCommand, SubscribeCommand, UnsubscribeCommand};
+mod replication;
+pub use replication::{PsyncCommand, ReplConfCommand, ReplicaOfCommand};
+mod save;
+pub use save::{BgRewriteAofCommand, LastSaveCommand, SaveCommand};
+mod script;
+pub use script::{EvalCommand, ScriptCommand};
+mod select;
+pub use select::SelectCommand;
+mod swapdb;
+pub use swapdb::SwapDbCommand;
+mod transaction;
+pub use transaction::{DiscardCommand, ExecCommand, MultiCommand, UnwatchCommand, WatchCommand};
+
+use crate::data_store::Databases;
+use crate::error::{ExecutionError, RequestError};
+
+/// Parses a database index, which is only checked against the number of databases once the
+/// command is executed.
+fn parse_db_index(token: &[u8]) -> Result<i64, RequestError> {
+    match String::from_utf8_lossy(token).parse::<i64>() {
+        Ok(v) => Ok(v),
+        Err(_) => Err(RequestError::InvalidIntValue),
+    }
+}
+
+fn check_db_index(databases: &Databases, index: i64) -> Result<usize, ExecutionError> {
+    match usize::try_from(index) {
+        Ok(v) if v < databases.count() => Ok(v),
+        _ => Err(ExecutionError::DbIndexOutOfRange),
+    }
+}
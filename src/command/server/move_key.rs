@@ -0,0 +1,121 @@
+use super::{check_db_index, parse_db_index};
+use crate::command::ServerCommand;
+use crate::error::{ExecutionError, RequestError};
+use crate::execution_result::keyspace::MoveResult;
+use crate::execution_result::ExecutionResult;
+use crate::server::Server;
+use crate::session::Session;
+
+#[derive(Debug)]
+pub struct MoveCommand {
+    key: Vec<u8>,
+    db: i64,
+}
+
+impl MoveCommand {
+    pub fn new(tokens: Vec<Vec<u8>>) -> Result<Box<Self>, RequestError> {
+        if tokens.len() != 2 {
+            return Err(RequestError::IncorrectArgCount);
+        }
+        Ok(Box::new(MoveCommand {
+            key: tokens[0].clone(),
+            db: parse_db_index(&tokens[1])?,
+        }))
+    }
+}
+
+impl ServerCommand for MoveCommand {
+    fn execute(
+        &self,
+        server: &mut Server,
+        session: &mut Session,
+    ) -> Result<Box<dyn ExecutionResult>, Box<dyn std::error::Error>> {
+        let db = check_db_index(&server.databases, self.db)?;
+        if db == session.db {
+            return Err(Box::new(ExecutionError::SameObject));
+        }
+        let (source, destination) = server.databases.pair_mut(session.db, db);
+        // The key is only moved if it does not exist in the destination yet.
+        if destination.contains_key(&self.key) {
+            return Ok(Box::new(MoveResult { value: false }));
+        }
+        let Some((entry, deadline)) = source.dump(&self.key) else {
+            return Ok(Box::new(MoveResult { value: false }));
+        };
+        source.drop_key(&self.key);
+        destination.restore(&self.key, entry, deadline);
+        Ok(Box::new(MoveResult { value: true }))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::MoveCommand;
+    use crate::command::ServerCommand;
+    use crate::config::ServerConfig;
+    use crate::data_store::now_ms;
+    use crate::server::Server;
+    use crate::session::Session;
+
+    fn move_key(key: &str, db: &str) -> Box<MoveCommand> {
+        MoveCommand::new(vec![key.as_bytes().to_vec(), db.as_bytes().to_vec()]).unwrap()
+    }
+
+    #[test]
+    fn should_move_key_with_ttl() {
+        let mut server = Server::new(&ServerConfig {
+            databases: 4,
+            ..Default::default()
+        });
+        let mut session = Session::new();
+        server
+            .databases
+            .db_mut(0)
+            .set_string_overwrite(b"foo", b"bar");
+        server
+            .databases
+            .db_mut(0)
+            .set_expire_at(b"foo", now_ms() + 10000);
+        server
+            .databases
+            .db_mut(0)
+            .set_string_overwrite(b"taken", b"0");
+        server
+            .databases
+            .db_mut(2)
+            .set_string_overwrite(b"taken", b"2");
+
+        let result = move_key("foo", "2").execute(&mut server, &mut session);
+        assert_eq!(result.unwrap().to_string(), "1".to_string());
+        assert!(!server.databases.db_mut(0).contains_key(b"foo"));
+        assert!(server.databases.db_mut(2).get_expire_at(b"foo").is_some());
+
+        let result = move_key("taken", "2").execute(&mut server, &mut session);
+        assert_eq!(result.unwrap().to_string(), "0".to_string());
+        assert_eq!(
+            server
+                .databases
+                .db_mut(0)
+                .get_string(b"taken")
+                .unwrap()
+                .unwrap(),
+            b"0"
+        );
+        let result = move_key("missing", "2").execute(&mut server, &mut session);
+        assert_eq!(result.unwrap().to_string(), "0".to_string());
+
+        let err = move_key("taken", "0")
+            .execute(&mut server, &mut session)
+            .err()
+            .unwrap();
+        assert_eq!(
+            err.to_string(),
+            "ERR source and destination objects are the same".to_string()
+        );
+        let err = move_key("taken", "4")
+            .execute(&mut server, &mut session)
+            .err()
+            .unwrap();
+        assert_eq!(err.to_string(), "ERR DB index is out of range".to_string());
+    }
+}
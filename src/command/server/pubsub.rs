@@ -0,0 +1,403 @@
+use crate::command::ServerCommand;
+use crate::error::RequestError;
+use crate::execution_result::server::{
+    NumSubResult, PubSubChannelsResult, PublishResult, SubscriptionResult,
+};
+use crate::execution_result::ExecutionResult;
+use crate::pubsub::Subscriber;
+use crate::server::Server;
+use crate::session::Session;
+
+/// `SUBSCRIBE channel [channel ...]`, which puts the connection in subscriber mode, where it is
+/// pushed the messages published to the channels, and `PSUBSCRIBE pattern [pattern ...]`, which
+/// does the same for every channel that matches the patterns.
+#[derive(Debug)]
+pub struct SubscribeCommand {
+    channels: Vec<Vec<u8>>,
+    pattern: bool,
+}
+
+impl SubscribeCommand {
+    pub fn new(tokens: Vec<Vec<u8>>, pattern: bool) -> Result<Box<Self>, RequestError> {
+        if tokens.is_empty() {
+            return Err(RequestError::IncorrectArgCount);
+        }
+        Ok(Box::new(SubscribeCommand {
+            channels: tokens,
+            pattern,
+        }))
+    }
+}
+
+impl ServerCommand for SubscribeCommand {
+    fn execute(
+        &self,
+        server: &mut Server,
+        session: &mut Session,
+    ) -> Result<Box<dyn ExecutionResult>, Box<dyn std::error::Error>> {
+        let id = session.id;
+        let subscriber = session.subscriber.get_or_insert_with(Subscriber::new);
+        let mut channels = Vec::new();
+        for channel in &self.channels {
+            match self.pattern {
+                true if subscriber.patterns.insert(channel.clone()) => {
+                    server.pubsub.psubscribe(channel, id, subscriber)
+                }
+                false if subscriber.channels.insert(channel.clone()) => {
+                    server.pubsub.subscribe(channel, id, subscriber)
+                }
+                _ => (),
+            }
+            channels.push((Some(channel.clone()), subscriber.count()));
+        }
+        Ok(Box::new(SubscriptionResult {
+            kind: match self.pattern {
+                true => "psubscribe",
+                false => "subscribe",
+            },
+            channels,
+        }))
+    }
+}
+
+/// `UNSUBSCRIBE [channel ...]`, which unsubscribes from the channels, or from every channel if
+/// none is given, and `PUNSUBSCRIBE [pattern ...]`, which does the same for patterns. The
+/// connection leaves subscriber mode once it has no subscriptions left.
+#[derive(Debug)]
+pub struct UnsubscribeCommand {
+    channels: Vec<Vec<u8>>,
+    pattern: bool,
+}
+
+impl UnsubscribeCommand {
+    pub fn new(tokens: Vec<Vec<u8>>, pattern: bool) -> Result<Box<Self>, RequestError> {
+        Ok(Box::new(UnsubscribeCommand {
+            channels: tokens,
+            pattern,
+        }))
+    }
+}
+
+impl ServerCommand for UnsubscribeCommand {
+    fn execute(
+        &self,
+        server: &mut Server,
+        session: &mut Session,
+    ) -> Result<Box<dyn ExecutionResult>, Box<dyn std::error::Error>> {
+        let kind = match self.pattern {
+            true => "punsubscribe",
+            false => "unsubscribe",
+        };
+        let Some(subscriber) = &mut session.subscriber else {
+            let channels = match self.channels.is_empty() {
+                true => vec![(None, 0)],
+                false => self.channels.iter().map(|c| (Some(c.clone()), 0)).collect(),
+            };
+            return Ok(Box::new(SubscriptionResult { kind, channels }));
+        };
+        let targets: Vec<_> = match (self.channels.is_empty(), self.pattern) {
+            (true, true) => subscriber.patterns.iter().cloned().collect(),
+            (true, false) => subscriber.channels.iter().cloned().collect(),
+            (false, _) => self.channels.clone(),
+        };
+        let mut channels = Vec::new();
+        for channel in targets {
+            let removed = match self.pattern {
+                true => subscriber.patterns.remove(&channel),
+                false => subscriber.channels.remove(&channel),
+            };
+            match (removed, self.pattern) {
+                (true, true) => server.pubsub.punsubscribe(&channel, session.id),
+                (true, false) => server.pubsub.unsubscribe(&channel, session.id),
+                (false, _) => (),
+            }
+            channels.push((Some(channel), subscriber.count()));
+        }
+        // Unsubscribing from every channel while only subscribed to patterns, or the other way
+        // round, still gets a reply.
+        let left = subscriber.count();
+        if channels.is_empty() {
+            channels.push((None, left));
+        }
+        if left == 0 {
+            session.subscriber = None;
+        }
+        Ok(Box::new(SubscriptionResult { kind, channels }))
+    }
+}
+
+/// `PUBLISH channel message`, which sends a message to the subscribers of a channel and of the
+/// patterns that match it.
+#[derive(Debug)]
+pub struct PublishCommand {
+    channel: Vec<u8>,
+    message: Vec<u8>,
+}
+
+impl PublishCommand {
+    pub fn new(tokens: Vec<Vec<u8>>) -> Result<Box<Self>, RequestError> {
+        let [channel, message]: [Vec<u8>; 2] = match tokens.try_into() {
+            Ok(v) => v,
+            Err(_) => return Err(RequestError::IncorrectArgCount),
+        };
+        Ok(Box::new(PublishCommand { channel, message }))
+    }
+}
+
+impl ServerCommand for PublishCommand {
+    fn execute(
+        &self,
+        server: &mut Server,
+        _: &mut Session,
+    ) -> Result<Box<dyn ExecutionResult>, Box<dyn std::error::Error>> {
+        Ok(Box::new(PublishResult {
+            value: server.pubsub.publish(&self.channel, &self.message),
+        }))
+    }
+}
+
+#[derive(Debug)]
+enum Subcommand {
+    Channels(Option<Vec<u8>>),
+    NumSub(Vec<Vec<u8>>),
+    NumPat,
+}
+
+/// `PUBSUB CHANNELS [pattern] | NUMSUB [channel ...] | NUMPAT`, which tells which channels have
+/// subscribers and how many.
+#[derive(Debug)]
+pub struct PubSubCommand {
+    subcommand: Subcommand,
+}
+
+impl PubSubCommand {
+    pub fn new(tokens: Vec<Vec<u8>>) -> Result<Box<Self>, RequestError> {
+        let Some((name, args)) = tokens.split_first() else {
+            return Err(RequestError::IncorrectArgCount);
+        };
+        let name = String::from_utf8_lossy(name).to_lowercase();
+        let subcommand = match (name.as_str(), args) {
+            ("channels", []) => Subcommand::Channels(None),
+            ("channels", [pattern]) => Subcommand::Channels(Some(pattern.clone())),
+            ("numsub", _) => Subcommand::NumSub(args.to_vec()),
+            ("numpat", []) => Subcommand::NumPat,
+            ("channels" | "numpat", _) => return Err(RequestError::IncorrectArgCount),
+            _ => return Err(RequestError::UnsupportedCommand(format!("pubsub {}", name))),
+        };
+        Ok(Box::new(PubSubCommand { subcommand }))
+    }
+}
+
+impl ServerCommand for PubSubCommand {
+    fn execute(
+        &self,
+        server: &mut Server,
+        _: &mut Session,
+    ) -> Result<Box<dyn ExecutionResult>, Box<dyn std::error::Error>> {
+        let pubsub = &server.pubsub;
+        match &self.subcommand {
+            Subcommand::Channels(pattern) => Ok(Box::new(PubSubChannelsResult {
+                values: pubsub.channels(pattern.as_deref()),
+            })),
+            Subcommand::NumSub(channels) => Ok(Box::new(NumSubResult {
+                values: channels
+                    .iter()
+                    .map(|channel| (channel.clone(), pubsub.subscribers(channel)))
+                    .collect(),
+            })),
+            Subcommand::NumPat => Ok(Box::new(PublishResult {
+                value: pubsub.numpat(),
+            })),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::config::ServerConfig;
+    use crate::execution_result::RespVersion;
+    use crate::server::Server;
+    use crate::session::Session;
+    use crate::utils::tokens;
+
+    fn reply(server: &mut Server, session: &mut Session, values: &[&str]) -> String {
+        match server.execute(&tokens(values), session) {
+            Ok(result) => String::from_utf8_lossy(&result.serialise(session.protocol)).to_string(),
+            Err(e) => format!("-{}\r\n", e),
+        }
+    }
+
+    #[test]
+    fn should_push_published_messages_to_subscribers() {
+        let mut server = Server::new(&ServerConfig::default());
+        let mut subscriber = Session::new();
+        let mut publisher = Session::new();
+        assert_eq!(
+            reply(&mut server, &mut subscriber, &["SUBSCRIBE", "a", "b", "a"]),
+            "*3\r\n$9\r\nsubscribe\r\n$1\r\na\r\n:1\r\n\
+             *3\r\n$9\r\nsubscribe\r\n$1\r\nb\r\n:2\r\n\
+             *3\r\n$9\r\nsubscribe\r\n$1\r\na\r\n:2\r\n"
+                .to_string()
+        );
+        assert_eq!(
+            reply(&mut server, &mut publisher, &["PUBLISH", "a", "hello"]),
+            ":1\r\n".to_string()
+        );
+        assert_eq!(
+            reply(&mut server, &mut publisher, &["PUBLISH", "c", "hello"]),
+            ":0\r\n".to_string()
+        );
+        let message = subscriber.subscriber.as_mut().unwrap().try_recv().unwrap();
+        assert_eq!(
+            message.serialise(RespVersion::Resp2),
+            b"*3\r\n$7\r\nmessage\r\n$1\r\na\r\n$5\r\nhello\r\n".to_vec()
+        );
+
+        assert_eq!(
+            reply(&mut server, &mut publisher, &["PUBSUB", "NUMSUB", "a", "c"]),
+            "*4\r\n$1\r\na\r\n:1\r\n$1\r\nc\r\n:0\r\n".to_string()
+        );
+        assert_eq!(
+            reply(&mut server, &mut publisher, &["PUBSUB", "CHANNELS", "b*"]),
+            "*1\r\n$1\r\nb\r\n".to_string()
+        );
+        assert_eq!(
+            reply(&mut server, &mut publisher, &["PUBSUB", "NUMPAT"]),
+            ":0\r\n".to_string()
+        );
+
+        assert_eq!(
+            reply(&mut server, &mut subscriber, &["UNSUBSCRIBE", "a"]),
+            "*3\r\n$11\r\nunsubscribe\r\n$1\r\na\r\n:1\r\n".to_string()
+        );
+        assert_eq!(
+            reply(&mut server, &mut subscriber, &["UNSUBSCRIBE"]),
+            "*3\r\n$11\r\nunsubscribe\r\n$1\r\nb\r\n:0\r\n".to_string()
+        );
+        assert!(subscriber.subscriber.is_none());
+        assert_eq!(
+            reply(&mut server, &mut subscriber, &["UNSUBSCRIBE"]),
+            "*3\r\n$11\r\nunsubscribe\r\n$-1\r\n:0\r\n".to_string()
+        );
+        assert_eq!(
+            reply(&mut server, &mut publisher, &["PUBSUB", "CHANNELS"]),
+            "*0\r\n".to_string()
+        );
+    }
+
+    #[test]
+    fn should_push_messages_of_matching_patterns() {
+        let mut server = Server::new(&ServerConfig::default());
+        let mut subscriber = Session::new();
+        let mut publisher = Session::new();
+        assert_eq!(
+            reply(
+                &mut server,
+                &mut subscriber,
+                &["PSUBSCRIBE", "orders.*", "user:*:events"]
+            ),
+            "*3\r\n$10\r\npsubscribe\r\n$8\r\norders.*\r\n:1\r\n\
+             *3\r\n$10\r\npsubscribe\r\n$13\r\nuser:*:events\r\n:2\r\n"
+                .to_string()
+        );
+        reply(&mut server, &mut subscriber, &["SUBSCRIBE", "orders.eu"]);
+        assert_eq!(
+            reply(&mut server, &mut publisher, &["PUBSUB", "NUMPAT"]),
+            ":2\r\n".to_string()
+        );
+        // The message is sent once for the channel and once for the pattern.
+        assert_eq!(
+            reply(&mut server, &mut publisher, &["PUBLISH", "orders.eu", "1"]),
+            ":2\r\n".to_string()
+        );
+        assert_eq!(
+            reply(
+                &mut server,
+                &mut publisher,
+                &["PUBLISH", "user:7:events", "2"]
+            ),
+            ":1\r\n".to_string()
+        );
+        assert_eq!(
+            reply(&mut server, &mut publisher, &["PUBLISH", "user:7", "3"]),
+            ":0\r\n".to_string()
+        );
+        let receiver = subscriber.subscriber.as_mut().unwrap();
+        let mut messages = Vec::new();
+        while let Some(message) = receiver.try_recv() {
+            messages
+                .push(String::from_utf8_lossy(&message.serialise(RespVersion::Resp2)).to_string());
+        }
+        messages[..2].sort();
+        assert_eq!(
+            messages,
+            vec![
+                "*3\r\n$7\r\nmessage\r\n$9\r\norders.eu\r\n$1\r\n1\r\n".to_string(),
+                "*4\r\n$8\r\npmessage\r\n$8\r\norders.*\r\n$9\r\norders.eu\r\n$1\r\n1\r\n"
+                    .to_string(),
+                "*4\r\n$8\r\npmessage\r\n$13\r\nuser:*:events\r\n$13\r\nuser:7:events\r\n$1\r\n2\r\n"
+                    .to_string(),
+            ]
+        );
+
+        assert_eq!(
+            reply(&mut server, &mut subscriber, &["PUNSUBSCRIBE", "orders.*"]),
+            "*3\r\n$12\r\npunsubscribe\r\n$8\r\norders.*\r\n:2\r\n".to_string()
+        );
+        assert_eq!(
+            reply(&mut server, &mut subscriber, &["UNSUBSCRIBE"]),
+            "*3\r\n$11\r\nunsubscribe\r\n$9\r\norders.eu\r\n:1\r\n".to_string()
+        );
+        assert_eq!(
+            reply(&mut server, &mut subscriber, &["UNSUBSCRIBE"]),
+            "*3\r\n$11\r\nunsubscribe\r\n$-1\r\n:1\r\n".to_string()
+        );
+        assert_eq!(
+            reply(&mut server, &mut subscriber, &["PUNSUBSCRIBE"]),
+            "*3\r\n$12\r\npunsubscribe\r\n$13\r\nuser:*:events\r\n:0\r\n".to_string()
+        );
+        assert!(subscriber.subscriber.is_none());
+        assert_eq!(
+            reply(&mut server, &mut publisher, &["PUBSUB", "NUMPAT"]),
+            ":0\r\n".to_string()
+        );
+    }
+
+    #[test]
+    fn should_restrict_commands_in_subscriber_mode() {
+        let mut server = Server::new(&ServerConfig::default());
+        let mut session = Session::new();
+        assert_eq!(
+            reply(&mut server, &mut session, &["PING"]),
+            "+OK\r\n".to_string()
+        );
+        reply(&mut server, &mut session, &["SUBSCRIBE", "a"]);
+        assert_eq!(
+            reply(&mut server, &mut session, &["GET", "a"]),
+            "-ERR Can't execute 'get': only (P)SUBSCRIBE / (P)UNSUBSCRIBE / PING / QUIT are allowed in this context\r\n"
+                .to_string()
+        );
+        assert_eq!(
+            reply(&mut server, &mut session, &["PING"]),
+            "*2\r\n$4\r\npong\r\n$0\r\n\r\n".to_string()
+        );
+
+        // RESP3 clients can run any command while subscribed, as pushes are told apart from
+        // replies.
+        let mut session = Session::new();
+        reply(&mut server, &mut session, &["HELLO", "3"]);
+        reply(&mut server, &mut session, &["SUBSCRIBE", "a"]);
+        assert_eq!(
+            reply(&mut server, &mut session, &["GET", "a"]),
+            "_\r\n".to_string()
+        );
+        assert_eq!(
+            reply(&mut server, &mut session, &["PING"]),
+            "+OK\r\n".to_string()
+        );
+        assert_eq!(
+            reply(&mut server, &mut session, &["UNSUBSCRIBE", "a"]),
+            ">3\r\n$11\r\nunsubscribe\r\n$1\r\na\r\n:0\r\n".to_string()
+        );
+    }
+}
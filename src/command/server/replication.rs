@@ -0,0 +1,223 @@
+use crate::command::ServerCommand;
+use crate::error::{ClusterError, RequestError};
+use crate::execution_result::server::{NoReplyResult, OkResult, ReplicaOfResult, SyncResult};
+use crate::execution_result::ExecutionResult;
+use crate::server::Server;
+use crate::session::Session;
+
+/// `REPLICAOF host port`, or `REPLICAOF NO ONE` to turn back into a master.
+#[derive(Debug)]
+pub struct ReplicaOfCommand {
+    master: Option<(String, u16)>,
+}
+
+impl ReplicaOfCommand {
+    pub fn new(tokens: Vec<Vec<u8>>) -> Result<Box<Self>, RequestError> {
+        if tokens.len() != 2 {
+            return Err(RequestError::IncorrectArgCount);
+        }
+        if tokens[0].eq_ignore_ascii_case(b"no") && tokens[1].eq_ignore_ascii_case(b"one") {
+            return Ok(Box::new(ReplicaOfCommand { master: None }));
+        }
+        let Ok(port) = String::from_utf8_lossy(&tokens[1]).parse::<u16>() else {
+            return Err(RequestError::InvalidIntValue);
+        };
+        Ok(Box::new(ReplicaOfCommand {
+            master: Some((String::from_utf8_lossy(&tokens[0]).to_string(), port)),
+        }))
+    }
+}
+
+impl ServerCommand for ReplicaOfCommand {
+    fn execute(
+        &self,
+        server: &mut Server,
+        _: &mut Session,
+    ) -> Result<Box<dyn ExecutionResult>, Box<dyn std::error::Error>> {
+        // In cluster mode, the master of a node is chosen with `CLUSTER REPLICATE`.
+        if server.cluster.enabled {
+            return Err(Box::new(ClusterError::ReplicaOfNotAllowed));
+        }
+        match &self.master {
+            Some((host, port)) => Ok(Box::new(ReplicaOfResult {
+                already_connected: !server.replication.set_master(host, *port),
+            })),
+            None => {
+                server.replication.promote();
+                Ok(Box::new(OkResult))
+            }
+        }
+    }
+}
+
+#[derive(Debug, PartialEq)]
+enum ReplConfOption {
+    ListeningPort(u16),
+    IpAddress(String),
+    Ack(u64),
+    /// Options that need no handling, such as the capabilities of the replica.
+    Ignored,
+}
+
+/// `REPLCONF option value [option value ...]`, which replicas use to describe themselves and to
+/// acknowledge the stream they processed.
+#[derive(Debug)]
+pub struct ReplConfCommand {
+    options: Vec<ReplConfOption>,
+}
+
+impl ReplConfCommand {
+    pub fn new(tokens: Vec<Vec<u8>>) -> Result<Box<Self>, RequestError> {
+        if tokens.is_empty() || !tokens.len().is_multiple_of(2) {
+            return Err(RequestError::IncorrectArgCount);
+        }
+        let mut options = Vec::new();
+        for pair in tokens.chunks(2) {
+            let value = String::from_utf8_lossy(&pair[1]);
+            let option = match String::from_utf8_lossy(&pair[0]).to_lowercase().as_str() {
+                "listening-port" => match value.parse() {
+                    Ok(v) => ReplConfOption::ListeningPort(v),
+                    Err(_) => return Err(RequestError::InvalidIntValue),
+                },
+                "ip-address" => ReplConfOption::IpAddress(value.to_string()),
+                "ack" => match value.parse() {
+                    Ok(v) => ReplConfOption::Ack(v),
+                    Err(_) => return Err(RequestError::InvalidIntValue),
+                },
+                "capa" | "getack" | "fack" => ReplConfOption::Ignored,
+                _ => return Err(RequestError::SyntaxError),
+            };
+            options.push(option);
+        }
+        Ok(Box::new(ReplConfCommand { options }))
+    }
+}
+
+impl ServerCommand for ReplConfCommand {
+    fn execute(
+        &self,
+        server: &mut Server,
+        session: &mut Session,
+    ) -> Result<Box<dyn ExecutionResult>, Box<dyn std::error::Error>> {
+        for option in &self.options {
+            match option {
+                ReplConfOption::ListeningPort(port) => session.replica_port = Some(*port),
+                ReplConfOption::IpAddress(ip) => session.addr = Some(ip.clone()),
+                // Acknowledgements are not answered.
+                ReplConfOption::Ack(offset) => {
+                    server.replication.ack(session.id, *offset);
+                    return Ok(Box::new(NoReplyResult));
+                }
+                ReplConfOption::Ignored => (),
+            }
+        }
+        Ok(Box::new(OkResult))
+    }
+}
+
+/// `PSYNC replid offset`, which a replica sends to start receiving the replication stream from
+/// `offset` on, or from a snapshot if that is not possible.
+#[derive(Debug)]
+pub struct PsyncCommand {
+    replid: String,
+    offset: i64,
+}
+
+impl PsyncCommand {
+    pub fn new(tokens: Vec<Vec<u8>>) -> Result<Box<Self>, RequestError> {
+        if tokens.len() != 2 {
+            return Err(RequestError::IncorrectArgCount);
+        }
+        let Ok(offset) = String::from_utf8_lossy(&tokens[1]).parse::<i64>() else {
+            return Err(RequestError::InvalidIntValue);
+        };
+        Ok(Box::new(PsyncCommand {
+            replid: String::from_utf8_lossy(&tokens[0]).to_string(),
+            offset,
+        }))
+    }
+}
+
+impl ServerCommand for PsyncCommand {
+    fn execute(
+        &self,
+        server: &mut Server,
+        session: &mut Session,
+    ) -> Result<Box<dyn ExecutionResult>, Box<dyn std::error::Error>> {
+        let (data, feed) =
+            server
+                .replication
+                .psync(&self.replid, self.offset, &server.databases, session)?;
+        session.replica_feed = Some(feed);
+        Ok(Box::new(SyncResult { data }))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{PsyncCommand, ReplConfCommand, ReplConfOption, ReplicaOfCommand};
+    use crate::command::ServerCommand;
+    use crate::config::ServerConfig;
+    use crate::execution_result::RespVersion;
+    use crate::server::Server;
+    use crate::session::Session;
+    use crate::utils::tokens;
+
+    #[test]
+    fn should_switch_between_master_and_replica() {
+        let mut server = Server::new(&ServerConfig::default());
+        let mut session = Session::new();
+        let cmd = ReplicaOfCommand::new(tokens(&["127.0.0.1", "6380"])).unwrap();
+        let result = cmd.execute(&mut server, &mut session).unwrap();
+        assert_eq!(result.serialise(RespVersion::Resp2), b"+OK\r\n".to_vec());
+        assert!(server.replication.is_replica());
+        let result = cmd.execute(&mut server, &mut session).unwrap();
+        assert_eq!(
+            result.to_string(),
+            "OK Already connected to specified master".to_string()
+        );
+        let cmd = ReplicaOfCommand::new(tokens(&["no", "ONE"])).unwrap();
+        cmd.execute(&mut server, &mut session).unwrap();
+        assert!(!server.replication.is_replica());
+
+        let err = ReplicaOfCommand::new(tokens(&["host", "port"]))
+            .err()
+            .unwrap();
+        assert_eq!(
+            err.to_string(),
+            "ERR value is not an integer or out of range".to_string()
+        );
+    }
+
+    #[test]
+    fn should_register_replica() {
+        let mut server = Server::new(&ServerConfig::default());
+        let mut session = Session::new();
+        let cmd =
+            ReplConfCommand::new(tokens(&["listening-port", "6381", "capa", "psync2"])).unwrap();
+        assert_eq!(
+            cmd.options,
+            vec![ReplConfOption::ListeningPort(6381), ReplConfOption::Ignored]
+        );
+        let result = cmd.execute(&mut server, &mut session).unwrap();
+        assert_eq!(result.serialise(RespVersion::Resp2), b"+OK\r\n".to_vec());
+
+        let cmd = PsyncCommand::new(tokens(&["?", "-1"])).unwrap();
+        let result = cmd.execute(&mut server, &mut session).unwrap();
+        assert!(result
+            .serialise(RespVersion::Resp2)
+            .starts_with(b"+FULLRESYNC "));
+        assert!(session.replica_feed.is_some());
+
+        let cmd = ReplConfCommand::new(tokens(&["ACK", "0"])).unwrap();
+        let result = cmd.execute(&mut server, &mut session).unwrap();
+        assert!(result.serialise(RespVersion::Resp2).is_empty());
+        assert!(server
+            .replication
+            .info()
+            .contains("slave0:ip=,port=6381,state=online,offset=0,lag=0\r\n"));
+
+        assert!(ReplConfCommand::new(tokens(&["capa"])).is_err());
+        assert!(ReplConfCommand::new(tokens(&["bogus", "1"])).is_err());
+    }
+}
@@ -0,0 +1,158 @@
+use crate::command::ServerCommand;
+use crate::error::RequestError;
+use crate::execution_result::server::{
+    BackgroundRewriteAofResult, BackgroundSaveResult, LastSaveResult, OkResult,
+};
+use crate::execution_result::ExecutionResult;
+use crate::server::Server;
+use crate::session::Session;
+
+/// `SAVE`, or `BGSAVE` when `background` is set.
+#[derive(Debug)]
+pub struct SaveCommand {
+    background: bool,
+}
+
+impl SaveCommand {
+    pub fn new(tokens: Vec<Vec<u8>>, background: bool) -> Result<Box<Self>, RequestError> {
+        if !tokens.is_empty() {
+            return Err(RequestError::IncorrectArgCount);
+        }
+        Ok(Box::new(SaveCommand { background }))
+    }
+}
+
+impl ServerCommand for SaveCommand {
+    fn execute(
+        &self,
+        server: &mut Server,
+        _: &mut Session,
+    ) -> Result<Box<dyn ExecutionResult>, Box<dyn std::error::Error>> {
+        match self.background {
+            true => {
+                server
+                    .persistence
+                    .start_background_save(&server.databases)?;
+                Ok(Box::new(BackgroundSaveResult))
+            }
+            false => {
+                server.persistence.save(&server.databases)?;
+                Ok(Box::new(OkResult))
+            }
+        }
+    }
+}
+
+#[derive(Debug)]
+pub struct LastSaveCommand;
+
+impl LastSaveCommand {
+    pub fn new(tokens: Vec<Vec<u8>>) -> Result<Box<Self>, RequestError> {
+        if !tokens.is_empty() {
+            return Err(RequestError::IncorrectArgCount);
+        }
+        Ok(Box::new(LastSaveCommand))
+    }
+}
+
+impl ServerCommand for LastSaveCommand {
+    fn execute(
+        &self,
+        server: &mut Server,
+        _: &mut Session,
+    ) -> Result<Box<dyn ExecutionResult>, Box<dyn std::error::Error>> {
+        Ok(Box::new(LastSaveResult {
+            value: server.persistence.last_save,
+        }))
+    }
+}
+
+/// `BGREWRITEAOF`, which compacts the append-only file from the current data.
+#[derive(Debug)]
+pub struct BgRewriteAofCommand;
+
+impl BgRewriteAofCommand {
+    pub fn new(tokens: Vec<Vec<u8>>) -> Result<Box<Self>, RequestError> {
+        if !tokens.is_empty() {
+            return Err(RequestError::IncorrectArgCount);
+        }
+        Ok(Box::new(BgRewriteAofCommand))
+    }
+}
+
+impl ServerCommand for BgRewriteAofCommand {
+    fn execute(
+        &self,
+        server: &mut Server,
+        _: &mut Session,
+    ) -> Result<Box<dyn ExecutionResult>, Box<dyn std::error::Error>> {
+        server.aof.start_rewrite(&server.databases)?;
+        Ok(Box::new(BackgroundRewriteAofResult))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{BgRewriteAofCommand, LastSaveCommand, SaveCommand};
+    use crate::command::ServerCommand;
+    use crate::config::ServerConfig;
+    use crate::execution_result::RespVersion;
+    use crate::server::Server;
+    use crate::session::Session;
+
+    #[test]
+    fn should_save_snapshot() {
+        let path = std::env::temp_dir().join(format!("redis-rust-cmd-{}.rdb", std::process::id()));
+        let mut server = Server::new(&ServerConfig {
+            dir: path.parent().unwrap().to_str().unwrap().to_string(),
+            dbfilename: path.file_name().unwrap().to_str().unwrap().to_string(),
+            ..Default::default()
+        });
+        let mut session = Session::new();
+        server.mark_dirty();
+        let cmd = SaveCommand::new(vec![], false).unwrap();
+        let result = cmd.execute(&mut server, &mut session).unwrap();
+        assert_eq!(result.serialise(RespVersion::Resp2), b"+OK\r\n".to_vec());
+        assert_eq!(server.persistence.dirty, 0);
+        assert!(path.exists());
+
+        let cmd = SaveCommand::new(vec![], true).unwrap();
+        let result = cmd.execute(&mut server, &mut session).unwrap();
+        assert_eq!(
+            result.serialise(RespVersion::Resp2),
+            b"+Background saving started\r\n".to_vec()
+        );
+        while server.persistence.is_saving() {
+            server.cron();
+        }
+        std::fs::remove_file(&path).unwrap();
+
+        let cmd = LastSaveCommand::new(vec![]).unwrap();
+        let result = cmd.execute(&mut server, &mut session).unwrap();
+        assert_eq!(result.to_string(), server.persistence.last_save.to_string());
+        assert!(SaveCommand::new(vec![b"x".to_vec()], false).is_err());
+    }
+
+    #[test]
+    fn should_rewrite_append_only_file() {
+        let path = std::env::temp_dir().join(format!("redis-rust-cmd-{}.aof", std::process::id()));
+        let mut server = Server::new(&ServerConfig {
+            dir: path.parent().unwrap().to_str().unwrap().to_string(),
+            appendfilename: path.file_name().unwrap().to_str().unwrap().to_string(),
+            ..Default::default()
+        });
+        let mut session = Session::new();
+        let cmd = BgRewriteAofCommand::new(vec![]).unwrap();
+        let result = cmd.execute(&mut server, &mut session).unwrap();
+        assert_eq!(
+            result.serialise(RespVersion::Resp2),
+            b"+Background append only file rewriting started\r\n".to_vec()
+        );
+        while server.aof.is_rewriting() {
+            server.cron();
+        }
+        assert!(path.exists());
+        std::fs::remove_file(&path).unwrap();
+        assert!(BgRewriteAofCommand::new(vec![b"x".to_vec()]).is_err());
+    }
+}
@@ -0,0 +1,310 @@
+use crate::command::ServerCommand;
+use crate::error::{RequestError, ScriptError};
+use crate::execution_result::server::{OkResult, ScriptExistsResult, ScriptLoadResult};
+use crate::execution_result::ExecutionResult;
+use crate::scripting;
+use crate::server::Server;
+use crate::session::Session;
+use crate::utils::sha1_hex;
+
+#[derive(Debug)]
+enum Script {
+    Body(Vec<u8>),
+    Sha(Vec<u8>),
+}
+
+/// `EVAL script numkeys [key ...] [arg ...]`, which runs a Lua script, and `EVALSHA sha1 numkeys
+/// [key ...] [arg ...]`, which runs a script that is already cached by its digest. The keys and
+/// arguments are available to the script as `KEYS` and `ARGV`.
+#[derive(Debug)]
+pub struct EvalCommand {
+    script: Script,
+    keys: Vec<Vec<u8>>,
+    args: Vec<Vec<u8>>,
+}
+
+impl EvalCommand {
+    pub fn new(tokens: Vec<Vec<u8>>, by_sha: bool) -> Result<Box<Self>, RequestError> {
+        if tokens.len() < 2 {
+            return Err(RequestError::IncorrectArgCount);
+        }
+        let numkeys = match String::from_utf8_lossy(&tokens[1]).parse::<i64>() {
+            Ok(v) if v < 0 => return Err(RequestError::NegativeKeyCount),
+            Ok(v) if v as usize > tokens.len() - 2 => return Err(RequestError::TooManyKeys),
+            Ok(v) => v as usize,
+            Err(_) => return Err(RequestError::InvalidIntValue),
+        };
+        let script = match by_sha {
+            true => Script::Sha(tokens[0].clone()),
+            false => Script::Body(tokens[0].clone()),
+        };
+        Ok(Box::new(EvalCommand {
+            script,
+            keys: tokens[2..2 + numkeys].to_vec(),
+            args: tokens[2 + numkeys..].to_vec(),
+        }))
+    }
+}
+
+impl ServerCommand for EvalCommand {
+    fn execute(
+        &self,
+        server: &mut Server,
+        session: &mut Session,
+    ) -> Result<Box<dyn ExecutionResult>, Box<dyn std::error::Error>> {
+        let (sha, body) = match &self.script {
+            // Scripts run with EVAL are cached too, so that EVALSHA can run them next time.
+            Script::Body(body) => (server.scripts.load(body)?, body.clone()),
+            Script::Sha(sha) => match server.scripts.get(sha) {
+                Some(body) => (sha1_hex(body), body.to_vec()),
+                None => return Err(Box::new(ScriptError::NoScript)),
+            },
+        };
+        scripting::eval(server, session, &sha, &body, &self.keys, &self.args)
+    }
+}
+
+#[derive(Debug)]
+enum Subcommand {
+    Load(Vec<u8>),
+    Exists(Vec<Vec<u8>>),
+    Flush,
+    Kill,
+}
+
+/// `SCRIPT LOAD|EXISTS|FLUSH|KILL`, which manages the script cache and stops scripts that run
+/// for too long.
+#[derive(Debug)]
+pub struct ScriptCommand {
+    subcommand: Subcommand,
+}
+
+impl ScriptCommand {
+    pub fn new(tokens: Vec<Vec<u8>>) -> Result<Box<Self>, RequestError> {
+        let Some((name, args)) = tokens.split_first() else {
+            return Err(RequestError::IncorrectArgCount);
+        };
+        let name = String::from_utf8_lossy(name).to_lowercase();
+        let subcommand = match (name.as_str(), args) {
+            ("load", [body]) => Subcommand::Load(body.clone()),
+            ("exists", [_, ..]) => Subcommand::Exists(args.to_vec()),
+            // Scripts are always flushed synchronously, as the cache is cheap to drop.
+            ("flush", []) => Subcommand::Flush,
+            ("flush", [mode])
+                if mode.eq_ignore_ascii_case(b"async") || mode.eq_ignore_ascii_case(b"sync") =>
+            {
+                Subcommand::Flush
+            }
+            ("flush", [_]) => return Err(RequestError::SyntaxError),
+            ("kill", []) => Subcommand::Kill,
+            ("load" | "exists" | "flush" | "kill", _) => {
+                return Err(RequestError::IncorrectArgCount)
+            }
+            _ => return Err(RequestError::UnsupportedCommand(format!("script {}", name))),
+        };
+        Ok(Box::new(ScriptCommand { subcommand }))
+    }
+}
+
+impl ServerCommand for ScriptCommand {
+    fn execute(
+        &self,
+        server: &mut Server,
+        _: &mut Session,
+    ) -> Result<Box<dyn ExecutionResult>, Box<dyn std::error::Error>> {
+        let scripts = &mut server.scripts;
+        match &self.subcommand {
+            Subcommand::Load(body) => Ok(Box::new(ScriptLoadResult {
+                value: Some(scripts.load(body)?.into_bytes()),
+            })),
+            Subcommand::Exists(shas) => Ok(Box::new(ScriptExistsResult {
+                values: shas.iter().map(|sha| scripts.get(sha).is_some()).collect(),
+            })),
+            Subcommand::Flush => {
+                scripts.flush();
+                Ok(Box::new(OkResult))
+            }
+            // A script that runs for too long is killed before the server is even locked, so
+            // there is none to kill once it is.
+            Subcommand::Kill => Err(Box::new(ScriptError::NotBusy)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::config::ServerConfig;
+    use crate::execution_result::RespVersion;
+    use crate::server::Server;
+    use crate::session::Session;
+    use crate::utils::tokens;
+    use std::sync::{Arc, Mutex};
+
+    fn reply(server: &mut Server, session: &mut Session, values: &[&str]) -> String {
+        match server.execute(&tokens(values), session) {
+            Ok(result) => {
+                String::from_utf8_lossy(&result.serialise(RespVersion::Resp2)).to_string()
+            }
+            Err(e) => format!("-{}\r\n", e),
+        }
+    }
+
+    #[test]
+    fn should_eval_scripts() {
+        let mut server = Server::new(&ServerConfig::default());
+        let mut session = Session::new();
+        let script = "redis.call('SET', KEYS[1], ARGV[1]); return {redis.call('GET', KEYS[1]), \
+            redis.call('INCR', KEYS[2]), redis.call('GET', 'missing'), 1.5, true, nil, 'unseen'}";
+        assert_eq!(
+            reply(
+                &mut server,
+                &mut session,
+                &["EVAL", script, "2", "a", "n", "v"]
+            ),
+            "*5\r\n$1\r\nv\r\n:1\r\n$-1\r\n:1\r\n:1\r\n".to_string()
+        );
+        assert_eq!(
+            reply(
+                &mut server,
+                &mut session,
+                &["EVAL", "return redis.call('LPUSH', KEYS[1], 1)", "1", "a"]
+            ),
+            "-WRONGTYPE Operation against a key holding the wrong kind of value\r\n".to_string()
+        );
+        assert_eq!(
+            reply(
+                &mut server,
+                &mut session,
+                &["EVAL", "return redis.pcall('LPUSH', 'a', 1).err", "0"]
+            ),
+            "$65\r\nWRONGTYPE Operation against a key holding the wrong kind of value\r\n"
+                .to_string()
+        );
+        assert_eq!(
+            reply(
+                &mut server,
+                &mut session,
+                &[
+                    "EVAL",
+                    "return {redis.status_reply('FINE'), redis.call('EXISTS', 'a')}",
+                    "0"
+                ]
+            ),
+            "*2\r\n+FINE\r\n:1\r\n".to_string()
+        );
+        // Status and error lines that span lines must not be read as several replies.
+        assert_eq!(
+            reply(
+                &mut server,
+                &mut session,
+                &["EVAL", "return {{ok='a\\r\\n:1'}, {err='b\\n:2'}}", "0"]
+            ),
+            "*2\r\n+a  :1\r\n-b :2\r\n".to_string()
+        );
+        assert_eq!(
+            reply(
+                &mut server,
+                &mut session,
+                &["EVAL", "return redis.call('EVAL', 'return 1', 0)", "0"]
+            ),
+            "-ERR This Redis command is not allowed from script\r\n".to_string()
+        );
+        assert_eq!(
+            reply(&mut server, &mut session, &["EVAL", "undefined()", "0"]),
+            "-ERR Error running script (call to f_888d777dc5d73597a3be7c2c014b5549d9224f70): user_script:1: Script attempted to access nonexistent global variable 'undefined'\r\n".to_string()
+        );
+        assert_eq!(
+            reply(&mut server, &mut session, &["EVAL", "return 1", "2", "a"]),
+            "-ERR Number of keys can't be greater than number of args\r\n".to_string()
+        );
+        // The database a script selects is only selected for the script.
+        assert_eq!(
+            reply(
+                &mut server,
+                &mut session,
+                &[
+                    "EVAL",
+                    "redis.call('SELECT', 1); return redis.call('EXISTS', 'a')",
+                    "0"
+                ]
+            ),
+            ":0\r\n".to_string()
+        );
+        assert_eq!(
+            reply(&mut server, &mut session, &["GET", "a"]),
+            "$1\r\nv\r\n".to_string()
+        );
+    }
+
+    #[test]
+    fn should_run_cached_scripts() {
+        let mut server = Server::new(&ServerConfig::default());
+        let mut session = Session::new();
+        let sha = "e0e1f9fabfc9d4800c877a703b823ac0578ff8db";
+        assert_eq!(
+            reply(&mut server, &mut session, &["EVALSHA", sha, "0"]),
+            "-NOSCRIPT No matching script. Please use EVAL.\r\n".to_string()
+        );
+        assert_eq!(
+            reply(&mut server, &mut session, &["SCRIPT", "LOAD", "return 1"]),
+            format!("$40\r\n{}\r\n", sha)
+        );
+        assert_eq!(
+            reply(&mut server, &mut session, &["EVALSHA", sha, "0"]),
+            ":1\r\n".to_string()
+        );
+        assert_eq!(
+            reply(
+                &mut server,
+                &mut session,
+                &["SCRIPT", "EXISTS", sha, "ffff"]
+            ),
+            "*2\r\n:1\r\n:0\r\n".to_string()
+        );
+        assert_eq!(
+            reply(&mut server, &mut session, &["SCRIPT", "FLUSH", "ASYNC"]),
+            "+OK\r\n".to_string()
+        );
+        assert_eq!(
+            reply(&mut server, &mut session, &["SCRIPT", "EXISTS", sha]),
+            "*1\r\n:0\r\n".to_string()
+        );
+        assert_eq!(
+            reply(&mut server, &mut session, &["SCRIPT", "KILL"]),
+            "-NOTBUSY No scripts in execution right now.\r\n".to_string()
+        );
+    }
+
+    #[test]
+    fn should_kill_scripts_that_run_for_too_long() {
+        let config = ServerConfig {
+            lua_time_limit: 10,
+            ..ServerConfig::default()
+        };
+        let server = Arc::new(Mutex::new(Server::new(&config)));
+        let monitor = server.lock().unwrap().scripts.monitor.clone();
+        let handle = {
+            let server = server.clone();
+            std::thread::spawn(move || {
+                let mut session = Session::new();
+                let mut server = server.lock().unwrap();
+                reply(
+                    &mut server,
+                    &mut session,
+                    &["EVAL", "while true do end", "0"],
+                )
+            })
+        };
+        while !monitor.is_busy() {
+            std::thread::sleep(std::time::Duration::from_millis(1));
+        }
+        assert!(server.try_lock().is_err());
+        let result = monitor.interrupt(&tokens(&["SCRIPT", "KILL"])).unwrap();
+        assert_eq!(result.to_string(), "OK".to_string());
+        assert_eq!(
+            handle.join().unwrap(),
+            "-ERR Script killed by user with SCRIPT KILL...\r\n".to_string()
+        );
+        assert!(!monitor.is_running());
+    }
+}
@@ -0,0 +1,72 @@
+use super::{check_db_index, parse_db_index};
+use crate::command::ServerCommand;
+use crate::error::{ClusterError, RequestError};
+use crate::execution_result::server::OkResult;
+use crate::execution_result::ExecutionResult;
+use crate::server::Server;
+use crate::session::Session;
+
+#[derive(Debug)]
+pub struct SelectCommand {
+    index: i64,
+}
+
+impl SelectCommand {
+    pub fn new(tokens: Vec<Vec<u8>>) -> Result<Box<Self>, RequestError> {
+        if tokens.len() != 1 {
+            return Err(RequestError::IncorrectArgCount);
+        }
+        Ok(Box::new(SelectCommand {
+            index: parse_db_index(&tokens[0])?,
+        }))
+    }
+}
+
+impl ServerCommand for SelectCommand {
+    fn execute(
+        &self,
+        server: &mut Server,
+        session: &mut Session,
+    ) -> Result<Box<dyn ExecutionResult>, Box<dyn std::error::Error>> {
+        // Every node of a cluster only has database 0.
+        if server.cluster.enabled && self.index != 0 {
+            return Err(Box::new(ClusterError::SelectNotAllowed));
+        }
+        session.db = check_db_index(&server.databases, self.index)?;
+        Ok(Box::new(OkResult))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::SelectCommand;
+    use crate::command::ServerCommand;
+    use crate::config::ServerConfig;
+    use crate::server::Server;
+    use crate::session::Session;
+
+    #[test]
+    fn should_switch_database() {
+        let mut server = Server::new(&ServerConfig {
+            databases: 16,
+            ..Default::default()
+        });
+        let mut session = Session::new();
+        let cmd = SelectCommand::new(vec![b"15".to_vec()]).unwrap();
+        let result = cmd.execute(&mut server, &mut session).unwrap();
+        assert_eq!(result.to_string(), "OK".to_string());
+        assert_eq!(session.db, 15);
+
+        for index in ["16", "-1"] {
+            let cmd = SelectCommand::new(vec![index.as_bytes().to_vec()]).unwrap();
+            let err = cmd.execute(&mut server, &mut session).err().unwrap();
+            assert_eq!(err.to_string(), "ERR DB index is out of range".to_string());
+        }
+        assert_eq!(session.db, 15);
+        let err = SelectCommand::new(vec![b"one".to_vec()]).err().unwrap();
+        assert_eq!(
+            err.to_string(),
+            "ERR value is not an integer or out of range".to_string()
+        );
+    }
+}
@@ -0,0 +1,95 @@
+use super::check_db_index;
+use crate::command::ServerCommand;
+use crate::error::RequestError;
+use crate::execution_result::server::OkResult;
+use crate::execution_result::ExecutionResult;
+use crate::server::Server;
+use crate::session::Session;
+
+#[derive(Debug)]
+pub struct SwapDbCommand {
+    first: i64,
+    second: i64,
+}
+
+impl SwapDbCommand {
+    pub fn new(tokens: Vec<Vec<u8>>) -> Result<Box<Self>, RequestError> {
+        if tokens.len() != 2 {
+            return Err(RequestError::IncorrectArgCount);
+        }
+        let Ok(first) = String::from_utf8_lossy(&tokens[0]).parse::<i64>() else {
+            return Err(RequestError::InvalidDbIndex("first".to_string()));
+        };
+        let Ok(second) = String::from_utf8_lossy(&tokens[1]).parse::<i64>() else {
+            return Err(RequestError::InvalidDbIndex("second".to_string()));
+        };
+        Ok(Box::new(SwapDbCommand { first, second }))
+    }
+}
+
+impl ServerCommand for SwapDbCommand {
+    fn execute(
+        &self,
+        server: &mut Server,
+        _: &mut Session,
+    ) -> Result<Box<dyn ExecutionResult>, Box<dyn std::error::Error>> {
+        let first = check_db_index(&server.databases, self.first)?;
+        let second = check_db_index(&server.databases, self.second)?;
+        server.databases.swap(first, second);
+        Ok(Box::new(OkResult))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::SwapDbCommand;
+    use crate::command::ServerCommand;
+    use crate::config::ServerConfig;
+    use crate::server::Server;
+    use crate::session::Session;
+
+    fn swapdb(first: &str, second: &str) -> Box<SwapDbCommand> {
+        SwapDbCommand::new(vec![first.as_bytes().to_vec(), second.as_bytes().to_vec()]).unwrap()
+    }
+
+    #[test]
+    fn should_reject_invalid_indexes() {
+        let err = SwapDbCommand::new(vec![b"a".to_vec(), b"1".to_vec()])
+            .err()
+            .unwrap();
+        assert_eq!(err.to_string(), "ERR invalid first DB index".to_string());
+        let err = SwapDbCommand::new(vec![b"0".to_vec(), b"b".to_vec()])
+            .err()
+            .unwrap();
+        assert_eq!(err.to_string(), "ERR invalid second DB index".to_string());
+        let err = swapdb("0", "2")
+            .execute(
+                &mut Server::new(&ServerConfig {
+                    databases: 2,
+                    ..Default::default()
+                }),
+                &mut Session::new(),
+            )
+            .err()
+            .unwrap();
+        assert_eq!(err.to_string(), "ERR DB index is out of range".to_string());
+    }
+
+    #[test]
+    fn should_swap_databases() {
+        let mut server = Server::new(&ServerConfig {
+            databases: 2,
+            ..Default::default()
+        });
+        let mut session = Session::new();
+        server
+            .databases
+            .db_mut(1)
+            .set_string_overwrite(b"foo", b"bar");
+        let result = swapdb("1", "0").execute(&mut server, &mut session).unwrap();
+        assert_eq!(result.to_string(), "OK".to_string());
+        assert!(server.databases.db_mut(0).contains_key(b"foo"));
+        assert!(server.databases.db_mut(1).is_empty());
+        assert_eq!(session.db, 0);
+    }
+}
@@ -0,0 +1,161 @@
+use crate::command::ServerCommand;
+use crate::error::{RequestError, TransactionError};
+use crate::execution_result::server::{ExecResult, OkResult};
+use crate::execution_result::ExecutionResult;
+use crate::server::Server;
+use crate::session::{Session, Transaction};
+
+/// `MULTI`, which starts a transaction. The commands that follow are queued instead of being
+/// run, until `EXEC` runs them all at once or `DISCARD` drops them.
+#[derive(Debug)]
+pub struct MultiCommand;
+
+impl MultiCommand {
+    pub fn new(tokens: Vec<Vec<u8>>) -> Result<Box<Self>, RequestError> {
+        if !tokens.is_empty() {
+            return Err(RequestError::IncorrectArgCount);
+        }
+        Ok(Box::new(MultiCommand))
+    }
+}
+
+impl ServerCommand for MultiCommand {
+    fn execute(
+        &self,
+        _: &mut Server,
+        session: &mut Session,
+    ) -> Result<Box<dyn ExecutionResult>, Box<dyn std::error::Error>> {
+        if session.transaction.is_some() {
+            return Err(Box::new(TransactionError::NestedMulti));
+        }
+        session.transaction = Some(Transaction::default());
+        Ok(Box::new(OkResult))
+    }
+}
+
+/// `EXEC`, which runs the commands queued since `MULTI` without any other client running
+/// commands in between, unless one of the keys watched with `WATCH` changed since.
+#[derive(Debug)]
+pub struct ExecCommand;
+
+impl ExecCommand {
+    pub fn new(tokens: Vec<Vec<u8>>) -> Result<Box<Self>, RequestError> {
+        if !tokens.is_empty() {
+            return Err(RequestError::IncorrectArgCount);
+        }
+        Ok(Box::new(ExecCommand))
+    }
+}
+
+impl ServerCommand for ExecCommand {
+    fn execute(
+        &self,
+        server: &mut Server,
+        session: &mut Session,
+    ) -> Result<Box<dyn ExecutionResult>, Box<dyn std::error::Error>> {
+        let Some(transaction) = session.transaction.take() else {
+            return Err(Box::new(TransactionError::ExecWithoutMulti));
+        };
+        let changed = server.unwatch(session);
+        if transaction.aborted {
+            return Err(Box::new(TransactionError::ExecAbort));
+        }
+        if changed {
+            return Ok(Box::new(ExecResult { values: None }));
+        }
+        Ok(Box::new(ExecResult {
+            values: Some(server.exec(&transaction.queue, session)),
+        }))
+    }
+}
+
+/// `DISCARD`, which ends a transaction without running the commands it queued.
+#[derive(Debug)]
+pub struct DiscardCommand;
+
+impl DiscardCommand {
+    pub fn new(tokens: Vec<Vec<u8>>) -> Result<Box<Self>, RequestError> {
+        if !tokens.is_empty() {
+            return Err(RequestError::IncorrectArgCount);
+        }
+        Ok(Box::new(DiscardCommand))
+    }
+}
+
+impl ServerCommand for DiscardCommand {
+    fn execute(
+        &self,
+        server: &mut Server,
+        session: &mut Session,
+    ) -> Result<Box<dyn ExecutionResult>, Box<dyn std::error::Error>> {
+        if session.transaction.take().is_none() {
+            return Err(Box::new(TransactionError::DiscardWithoutMulti));
+        }
+        server.unwatch(session);
+        Ok(Box::new(OkResult))
+    }
+}
+
+/// `WATCH key [key ...]`, which makes the next `EXEC` of the client fail if any of the keys
+/// changes before it.
+#[derive(Debug)]
+pub struct WatchCommand {
+    keys: Vec<Vec<u8>>,
+}
+
+impl WatchCommand {
+    pub fn new(tokens: Vec<Vec<u8>>) -> Result<Box<Self>, RequestError> {
+        if tokens.is_empty() {
+            return Err(RequestError::IncorrectArgCount);
+        }
+        Ok(Box::new(WatchCommand { keys: tokens }))
+    }
+}
+
+impl ServerCommand for WatchCommand {
+    fn execute(
+        &self,
+        server: &mut Server,
+        session: &mut Session,
+    ) -> Result<Box<dyn ExecutionResult>, Box<dyn std::error::Error>> {
+        if session.transaction.is_some() {
+            return Err(Box::new(TransactionError::WatchInsideMulti));
+        }
+        let data_store = server.databases.db_mut(session.db);
+        for key in &self.keys {
+            let watching = session
+                .watched
+                .iter()
+                .any(|(db, watched, _)| *db == session.db && watched == key);
+            if !watching {
+                let version = data_store.watch(key);
+                session.watched.push((session.db, key.clone(), version));
+            }
+        }
+        Ok(Box::new(OkResult))
+    }
+}
+
+/// `UNWATCH`, which forgets every key the client watched.
+#[derive(Debug)]
+pub struct UnwatchCommand;
+
+impl UnwatchCommand {
+    pub fn new(tokens: Vec<Vec<u8>>) -> Result<Box<Self>, RequestError> {
+        if !tokens.is_empty() {
+            return Err(RequestError::IncorrectArgCount);
+        }
+        Ok(Box::new(UnwatchCommand))
+    }
+}
+
+impl ServerCommand for UnwatchCommand {
+    fn execute(
+        &self,
+        server: &mut Server,
+        session: &mut Session,
+    ) -> Result<Box<dyn ExecutionResult>, Box<dyn std::error::Error>> {
+        server.unwatch(session);
+        Ok(Box::new(OkResult))
+    }
+}
@@ -10,3 +10,5 @@ mod scard;
 pub use scard::SCardCommand;
 mod sdiff;
 pub use sdiff::SDiffCommand;
+mod sscan;
+pub use sscan::SScanCommand;
@@ -8,12 +8,12 @@ use crate::execution_result::ExecutionResult;
 
 #[derive(Debug)]
 pub struct SAddCommand {
-    key: String,
-    values: HashSet<String>,
+    key: Vec<u8>,
+    values: HashSet<Vec<u8>>,
 }
 
 impl SAddCommand {
-    pub fn new(tokens: Vec<String>) -> Result<Box<Self>, RequestError> {
+    pub fn new(tokens: Vec<Vec<u8>>) -> Result<Box<Self>, RequestError> {
         if tokens.len() < 2 {
             return Err(RequestError::IncorrectArgCount);
         }
@@ -44,6 +44,9 @@ impl Command for SAddCommand {
         for value in &self.values {
             count += set.insert(value.clone()) as usize;
         }
+        if count > 0 {
+            data_store.touch(&self.key);
+        }
         Ok(Box::new(SAddResult { value: count }))
     }
 }
@@ -56,46 +59,40 @@ mod test {
 
     #[test]
     fn should_accept_at_least_two_tokens() {
-        let err = SAddCommand::new(vec!["foo".to_string()]).err().unwrap();
+        let err = SAddCommand::new(vec![b"foo".to_vec()]).err().unwrap();
         assert_eq!(
             err.to_string(),
             "ERR wrong number of arguments for command".to_string()
         );
-        let v = SAddCommand::new(vec![
-            "foo".to_string(),
-            "bar".to_string(),
-            "baz".to_string(),
-        ])
-        .unwrap();
-        assert_eq!(v.key, "foo".to_string());
+        let v = SAddCommand::new(vec![b"foo".to_vec(), b"bar".to_vec(), b"baz".to_vec()]).unwrap();
+        assert_eq!(v.key, b"foo".to_vec());
         assert_eq!(v.values.len(), 2);
-        assert!(v.values.contains(&"bar".to_string()));
-        assert!(v.values.contains(&"baz".to_string()));
+        assert!(v.values.contains(b"bar".as_slice()));
+        assert!(v.values.contains(b"baz".as_slice()));
     }
 
     #[test]
     fn should_add_item_if_key_does_not_exist() {
-        let key = "foo".to_string();
-        let cmd =
-            SAddCommand::new(vec![key.clone(), "bar".to_string(), "baz".to_string()]).unwrap();
+        let key = b"foo".to_vec();
+        let cmd = SAddCommand::new(vec![key.clone(), b"bar".to_vec(), b"baz".to_vec()]).unwrap();
         let mut ds = DataStore::new();
         let result = cmd.execute(&mut ds);
         assert_eq!(result.unwrap().to_string(), "2".to_string());
         let set = ds.get_set_mut(&key).unwrap().unwrap();
         assert_eq!(set.len(), 2);
-        assert!(set.contains(&"baz".to_string()));
-        assert!(set.contains(&"bar".to_string()));
+        assert!(set.contains(b"baz".as_slice()));
+        assert!(set.contains(b"bar".as_slice()));
     }
 
     #[test]
     fn should_not_add_duplicates() {
-        let key = "foo".to_string();
-        let cmd = SAddCommand::new(vec![key.clone(), "bar".to_string()]).unwrap();
+        let key = b"foo".to_vec();
+        let cmd = SAddCommand::new(vec![key.clone(), b"bar".to_vec()]).unwrap();
         let mut ds = DataStore::new();
         let result = cmd.execute(&mut ds);
         assert_eq!(result.unwrap().to_string(), "1".to_string());
         let set = ds.get_set_mut(&key).unwrap().unwrap();
-        assert!(set.contains(&"bar".to_string()));
+        assert!(set.contains(b"bar".as_slice()));
         let result = cmd.execute(&mut ds);
         assert_eq!(result.unwrap().to_string(), "0".to_string());
     }
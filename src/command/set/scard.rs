@@ -6,11 +6,11 @@ use crate::execution_result::ExecutionResult;
 
 #[derive(Debug)]
 pub struct SCardCommand {
-    key: String,
+    key: Vec<u8>,
 }
 
 impl SCardCommand {
-    pub fn new(tokens: Vec<String>) -> Result<Box<Self>, RequestError> {
+    pub fn new(tokens: Vec<Vec<u8>>) -> Result<Box<Self>, RequestError> {
         if tokens.len() != 1 {
             return Err(RequestError::IncorrectArgCount);
         }
@@ -41,20 +41,20 @@ mod test {
 
     #[test]
     fn should_accept_at_least_two_tokens() {
-        let err = SCardCommand::new(vec!["foo".to_string(), "bar".to_string()])
+        let err = SCardCommand::new(vec![b"foo".to_vec(), b"bar".to_vec()])
             .err()
             .unwrap();
         assert_eq!(
             err.to_string(),
             "ERR wrong number of arguments for command".to_string()
         );
-        let v = SCardCommand::new(vec!["foo".to_string()]).unwrap();
-        assert_eq!(v.key, "foo".to_string());
+        let v = SCardCommand::new(vec![b"foo".to_vec()]).unwrap();
+        assert_eq!(v.key, b"foo".to_vec());
     }
 
     #[test]
     fn should_return_0_if_key_does_not_exist() {
-        let cmd = SCardCommand::new(vec!["foo".to_string()]).unwrap();
+        let cmd = SCardCommand::new(vec![b"foo".to_vec()]).unwrap();
         let mut ds = DataStore::new();
         let result = cmd.execute(&mut ds);
         assert_eq!(result.unwrap().to_string(), "0".to_string());
@@ -63,8 +63,8 @@ mod test {
     #[test]
     fn should_return_card() {
         let mut ds = DataStore::new();
-        let key = "foo".to_string();
-        SAddCommand::new(vec![key.clone(), "bar".to_string()])
+        let key = b"foo".to_vec();
+        SAddCommand::new(vec![key.clone(), b"bar".to_vec()])
             .unwrap()
             .execute(&mut ds)
             .unwrap();
@@ -8,11 +8,11 @@ use crate::execution_result::ExecutionResult;
 
 #[derive(Debug)]
 pub struct SDiffCommand {
-    keys: Vec<String>,
+    keys: Vec<Vec<u8>>,
 }
 
 impl SDiffCommand {
-    pub fn new(tokens: Vec<String>) -> Result<Box<Self>, RequestError> {
+    pub fn new(tokens: Vec<Vec<u8>>) -> Result<Box<Self>, RequestError> {
         if tokens.is_empty() {
             return Err(RequestError::IncorrectArgCount);
         }
@@ -27,7 +27,7 @@ impl Command for SDiffCommand {
     ) -> Result<Box<dyn ExecutionResult>, Box<dyn std::error::Error>> {
         let values = match data_store.get_set_mut(&self.keys[0])? {
             Some(set) => {
-                let mut result: HashSet<String> = set.iter().cloned().collect();
+                let mut result: HashSet<Vec<u8>> = set.iter().cloned().collect();
                 for key in &self.keys[1..] {
                     if let Some(right_set) = data_store.get_set_mut(key)? {
                         for v in right_set.iter() {
@@ -37,11 +37,11 @@ impl Command for SDiffCommand {
                 }
                 result
             }
-            None => HashSet::<String>::new(),
+            None => HashSet::<Vec<u8>>::new(),
         }
         .iter()
         .cloned()
-        .collect::<Vec<String>>();
+        .collect::<Vec<Vec<u8>>>();
         Ok(Box::new(SDiffResult { values }))
     }
 }
@@ -59,13 +59,13 @@ mod test {
             err.to_string(),
             "ERR wrong number of arguments for command".to_string()
         );
-        let v = SDiffCommand::new(vec!["foo".to_string()]).unwrap();
-        assert_eq!(v.keys, vec!["foo".to_string()]);
+        let v = SDiffCommand::new(vec![b"foo".to_vec()]).unwrap();
+        assert_eq!(v.keys, vec![b"foo".to_vec()]);
     }
 
     #[test]
     fn should_return_empty_set_if_first_key_does_not_exist() {
-        let cmd = SDiffCommand::new(vec!["foo".to_string()]).unwrap();
+        let cmd = SDiffCommand::new(vec![b"foo".to_vec()]).unwrap();
         let mut ds = DataStore::new();
         let result = cmd.execute(&mut ds);
         assert_eq!(result.unwrap().to_string(), "".to_string());
@@ -73,14 +73,14 @@ mod test {
 
     #[test]
     fn should_return_diff_elements() {
-        let key1 = "foo".to_string();
-        let key2 = "bar".to_string();
+        let key1 = b"foo".to_vec();
+        let key2 = b"bar".to_vec();
         let mut ds = DataStore::new();
-        SAddCommand::new(vec![key1.clone(), "v1".to_string(), "v2".to_string()])
+        SAddCommand::new(vec![key1.clone(), b"v1".to_vec(), b"v2".to_vec()])
             .unwrap()
             .execute(&mut ds)
             .unwrap();
-        SAddCommand::new(vec![key2.clone(), "v2".to_string(), "v3".to_string()])
+        SAddCommand::new(vec![key2.clone(), b"v2".to_vec(), b"v3".to_vec()])
             .unwrap()
             .execute(&mut ds)
             .unwrap();
@@ -6,12 +6,12 @@ use crate::execution_result::ExecutionResult;
 
 #[derive(Debug)]
 pub struct SIsmemberCommand {
-    key: String,
-    value: String,
+    key: Vec<u8>,
+    value: Vec<u8>,
 }
 
 impl SIsmemberCommand {
-    pub fn new(tokens: Vec<String>) -> Result<Box<Self>, RequestError> {
+    pub fn new(tokens: Vec<Vec<u8>>) -> Result<Box<Self>, RequestError> {
         if tokens.len() != 2 {
             return Err(RequestError::IncorrectArgCount);
         }
@@ -46,24 +46,22 @@ mod test {
 
     #[test]
     fn should_accept_at_two_tokens() {
-        let err = SIsmemberCommand::new(vec!["foo".to_string()])
-            .err()
-            .unwrap();
+        let err = SIsmemberCommand::new(vec![b"foo".to_vec()]).err().unwrap();
         assert_eq!(
             err.to_string(),
             "ERR wrong number of arguments for command".to_string()
         );
-        let v = SIsmemberCommand::new(vec!["foo".to_string(), "bar".to_string()]).unwrap();
-        assert_eq!(v.key, "foo".to_string());
-        assert_eq!(v.value, "bar".to_string());
+        let v = SIsmemberCommand::new(vec![b"foo".to_vec(), b"bar".to_vec()]).unwrap();
+        assert_eq!(v.key, b"foo".to_vec());
+        assert_eq!(v.value, b"bar".to_vec());
     }
 
     #[test]
     fn should_throw_error_for_non_set_keys() {
-        let key = "foo".to_string();
-        let cmd = SIsmemberCommand::new(vec![key.clone(), "bar".to_string()]).unwrap();
+        let key = b"foo".to_vec();
+        let cmd = SIsmemberCommand::new(vec![key.clone(), b"bar".to_vec()]).unwrap();
         let mut ds = DataStore::new();
-        ds.set_string(&key, "something").unwrap();
+        ds.set_string(&key, b"something").unwrap();
         let result = cmd.execute(&mut ds);
         assert_eq!(
             result.err().unwrap().to_string(),
@@ -73,8 +71,8 @@ mod test {
 
     #[test]
     fn should_return_0_if_key_does_not_exist() {
-        let key = "foo".to_string();
-        let cmd = SIsmemberCommand::new(vec![key.clone(), "bar".to_string()]).unwrap();
+        let key = b"foo".to_vec();
+        let cmd = SIsmemberCommand::new(vec![key.clone(), b"bar".to_vec()]).unwrap();
         let mut ds = DataStore::new();
         let result = cmd.execute(&mut ds);
         assert_eq!(result.unwrap().to_string(), "0".to_string());
@@ -82,18 +80,18 @@ mod test {
 
     #[test]
     fn should_return_exists_or_not() {
-        let key = "foo".to_string();
+        let key = b"foo".to_vec();
         let mut ds = DataStore::new();
         // insert set
-        SAddCommand::new(vec![key.clone(), "v1".to_string()])
+        SAddCommand::new(vec![key.clone(), b"v1".to_vec()])
             .unwrap()
             .execute(&mut ds)
             .unwrap();
 
-        let cmd = SIsmemberCommand::new(vec![key.clone(), "v1".to_string()]).unwrap();
+        let cmd = SIsmemberCommand::new(vec![key.clone(), b"v1".to_vec()]).unwrap();
         let result = cmd.execute(&mut ds);
         assert_eq!(result.unwrap().to_string(), "1".to_string());
-        let cmd = SIsmemberCommand::new(vec![key.clone(), "v2".to_string()]).unwrap();
+        let cmd = SIsmemberCommand::new(vec![key.clone(), b"v2".to_vec()]).unwrap();
         let result = cmd.execute(&mut ds);
         assert_eq!(result.unwrap().to_string(), "0".to_string());
     }
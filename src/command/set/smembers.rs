@@ -6,11 +6,11 @@ use crate::execution_result::ExecutionResult;
 
 #[derive(Debug)]
 pub struct SMembersCommand {
-    key: String,
+    key: Vec<u8>,
 }
 
 impl SMembersCommand {
-    pub fn new(tokens: Vec<String>) -> Result<Box<Self>, RequestError> {
+    pub fn new(tokens: Vec<Vec<u8>>) -> Result<Box<Self>, RequestError> {
         if tokens.len() != 1 {
             return Err(RequestError::IncorrectArgCount);
         }
@@ -43,20 +43,20 @@ mod test {
 
     #[test]
     fn should_accept_one_token() {
-        let err = SMembersCommand::new(vec!["foo".to_string(), "bar".to_string()])
+        let err = SMembersCommand::new(vec![b"foo".to_vec(), b"bar".to_vec()])
             .err()
             .unwrap();
         assert_eq!(
             err.to_string(),
             "ERR wrong number of arguments for command".to_string()
         );
-        let v = SMembersCommand::new(vec!["foo".to_string()]).unwrap();
-        assert_eq!(v.key, "foo".to_string());
+        let v = SMembersCommand::new(vec![b"foo".to_vec()]).unwrap();
+        assert_eq!(v.key, b"foo".to_vec());
     }
 
     #[test]
     fn should_return_empty_list_if_key_does_not_exist() {
-        let cmd = SMembersCommand::new(vec!["foo".to_string()]).unwrap();
+        let cmd = SMembersCommand::new(vec![b"foo".to_vec()]).unwrap();
         let mut ds = DataStore::new();
         let result = cmd.execute(&mut ds);
         assert_eq!(result.unwrap().to_string(), "".to_string());
@@ -64,9 +64,9 @@ mod test {
 
     #[test]
     fn should_return_elements() {
-        let key = "foo".to_string();
+        let key = b"foo".to_vec();
         let mut ds = DataStore::new();
-        SAddCommand::new(vec![key.clone(), "v1".to_string(), "v2".to_string()])
+        SAddCommand::new(vec![key.clone(), b"v1".to_vec(), b"v2".to_vec()])
             .unwrap()
             .execute(&mut ds)
             .unwrap();
@@ -8,12 +8,12 @@ use crate::execution_result::ExecutionResult;
 
 #[derive(Debug)]
 pub struct SRemCommand {
-    key: String,
-    values: HashSet<String>,
+    key: Vec<u8>,
+    values: HashSet<Vec<u8>>,
 }
 
 impl SRemCommand {
-    pub fn new(tokens: Vec<String>) -> Result<Box<Self>, RequestError> {
+    pub fn new(tokens: Vec<Vec<u8>>) -> Result<Box<Self>, RequestError> {
         if tokens.len() < 2 {
             return Err(RequestError::IncorrectArgCount);
         }
@@ -43,6 +43,9 @@ impl Command for SRemCommand {
             }
             None => 0,
         };
+        if count > 0 {
+            data_store.touch(&self.key);
+        }
         Ok(Box::new(SRemResult { value: count }))
     }
 }
@@ -55,30 +58,24 @@ mod test {
 
     #[test]
     fn should_accept_at_least_two_tokens() {
-        let err = SRemCommand::new(vec!["foo".to_string()]).err().unwrap();
+        let err = SRemCommand::new(vec![b"foo".to_vec()]).err().unwrap();
         assert_eq!(
             err.to_string(),
             "ERR wrong number of arguments for command".to_string()
         );
-        let v = SRemCommand::new(vec![
-            "foo".to_string(),
-            "bar".to_string(),
-            "baz".to_string(),
-        ])
-        .unwrap();
-        assert_eq!(v.key, "foo".to_string());
+        let v = SRemCommand::new(vec![b"foo".to_vec(), b"bar".to_vec(), b"baz".to_vec()]).unwrap();
+        assert_eq!(v.key, b"foo".to_vec());
         assert_eq!(v.values.len(), 2);
-        assert!(v.values.contains(&"bar".to_string()));
-        assert!(v.values.contains(&"baz".to_string()));
+        assert!(v.values.contains(b"bar".as_slice()));
+        assert!(v.values.contains(b"baz".as_slice()));
     }
 
     #[test]
     fn should_throw_error_for_non_set_keys() {
-        let key = "foo".to_string();
-        let cmd =
-            SRemCommand::new(vec![key.clone(), "bar".to_string(), "baz".to_string()]).unwrap();
+        let key = b"foo".to_vec();
+        let cmd = SRemCommand::new(vec![key.clone(), b"bar".to_vec(), b"baz".to_vec()]).unwrap();
         let mut ds = DataStore::new();
-        ds.set_string(&key, "something").unwrap();
+        ds.set_string(&key, b"something").unwrap();
         let result = cmd.execute(&mut ds);
         assert_eq!(
             result.err().unwrap().to_string(),
@@ -88,9 +85,8 @@ mod test {
 
     #[test]
     fn should_return_0_if_key_does_not_exist() {
-        let key = "foo".to_string();
-        let cmd =
-            SRemCommand::new(vec![key.clone(), "bar".to_string(), "baz".to_string()]).unwrap();
+        let key = b"foo".to_vec();
+        let cmd = SRemCommand::new(vec![key.clone(), b"bar".to_vec(), b"baz".to_vec()]).unwrap();
         let mut ds = DataStore::new();
         let result = cmd.execute(&mut ds);
         assert_eq!(result.unwrap().to_string(), "0".to_string());
@@ -98,18 +94,18 @@ mod test {
 
     #[test]
     fn should_remove_values() {
-        let key = "foo".to_string();
+        let key = b"foo".to_vec();
         let mut ds = DataStore::new();
         // insert set
-        SAddCommand::new(vec![key.clone(), "v1".to_string(), "v3".to_string()])
+        SAddCommand::new(vec![key.clone(), b"v1".to_vec(), b"v3".to_vec()])
             .unwrap()
             .execute(&mut ds)
             .unwrap();
 
-        let cmd = SRemCommand::new(vec![key.clone(), "v1".to_string(), "v2".to_string()]).unwrap();
+        let cmd = SRemCommand::new(vec![key.clone(), b"v1".to_vec(), b"v2".to_vec()]).unwrap();
         let result = cmd.execute(&mut ds);
         assert_eq!(result.unwrap().to_string(), "1".to_string());
         let set = ds.get_set_mut(&key).unwrap().unwrap();
-        assert!(set.contains(&"v3".to_string()));
+        assert!(set.contains(b"v3".as_slice()));
     }
 }
@@ -0,0 +1,98 @@
+use crate::command::keyspace::ScanOptions;
+use crate::command::Command;
+use crate::data_store::DataStore;
+use crate::error::RequestError;
+use crate::execution_result::keyspace::ScanResult;
+use crate::execution_result::ExecutionResult;
+
+#[derive(Debug)]
+pub struct SScanCommand {
+    key: Vec<u8>,
+    options: ScanOptions,
+}
+
+impl SScanCommand {
+    pub fn new(tokens: Vec<Vec<u8>>) -> Result<Box<Self>, RequestError> {
+        if tokens.len() < 2 {
+            return Err(RequestError::IncorrectArgCount);
+        }
+        let options = ScanOptions::parse(&tokens[1..], |_, _| false)?;
+        Ok(Box::new(SScanCommand {
+            key: tokens[0].clone(),
+            options,
+        }))
+    }
+}
+
+impl Command for SScanCommand {
+    fn execute(
+        &self,
+        data_store: &mut DataStore,
+    ) -> Result<Box<dyn ExecutionResult>, Box<dyn std::error::Error>> {
+        let (values, cursor) = match data_store.get_set_mut(&self.key)? {
+            Some(set) => {
+                let (members, cursor) = set.scan(self.options.cursor, self.options.count);
+                let values = members
+                    .into_iter()
+                    .filter(|member| self.options.matches(member))
+                    .map(|member| member.to_vec())
+                    .collect();
+                (values, cursor)
+            }
+            None => (Vec::new(), 0),
+        };
+        Ok(Box::new(ScanResult { cursor, values }))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::SScanCommand;
+    use crate::command::set::SAddCommand;
+    use crate::command::Command;
+    use crate::data_store::DataStore;
+    use crate::utils::tokens;
+    use std::collections::HashSet;
+
+    #[test]
+    fn should_accept_key_and_cursor() {
+        let err = SScanCommand::new(tokens(&["foo"])).err().unwrap();
+        assert_eq!(
+            err.to_string(),
+            "ERR wrong number of arguments for command".to_string()
+        );
+        let err = SScanCommand::new(tokens(&["foo", "x"])).err().unwrap();
+        assert_eq!(err.to_string(), "ERR invalid cursor".to_string());
+        let err = SScanCommand::new(tokens(&["foo", "0", "NOVALUES"]))
+            .err()
+            .unwrap();
+        assert_eq!(err.to_string(), "ERR syntax error".to_string());
+    }
+
+    #[test]
+    fn should_iterate_over_all_members() {
+        let mut ds = DataStore::new();
+        let mut add = vec![b"foo".to_vec()];
+        add.extend((0..50).map(|i| format!("m{}", i).into_bytes()));
+        SAddCommand::new(add).unwrap().execute(&mut ds).unwrap();
+
+        let mut seen = HashSet::new();
+        let mut cursor = "0".to_string();
+        loop {
+            let cmd = SScanCommand::new(tokens(&["foo", &cursor, "MATCH", "m1*"])).unwrap();
+            let result = cmd.execute(&mut ds).unwrap().to_string();
+            let mut values = result.split(',');
+            cursor = values.next().unwrap().to_string();
+            for member in values {
+                assert!(seen.insert(member.to_string()));
+            }
+            if cursor == "0" {
+                break;
+            }
+        }
+        assert_eq!(seen.len(), 11);
+
+        let cmd = SScanCommand::new(tokens(&["missing", "0"])).unwrap();
+        assert_eq!(cmd.execute(&mut ds).unwrap().to_string(), "0".to_string());
+    }
+}
@@ -6,3 +6,5 @@ mod zrem;
 pub use zrem::ZRemCommand;
 mod zrank;
 pub use zrank::ZRankCommand;
+mod zscan;
+pub use zscan::ZScanCommand;
@@ -6,26 +6,33 @@ use crate::execution_result::ExecutionResult;
 
 #[derive(Debug)]
 pub struct ZRangeCommand {
-    key: String,
+    key: Vec<u8>,
     start: i64,
     stop: i64,
+    with_scores: bool,
 }
 
 impl ZRangeCommand {
-    pub fn new(tokens: Vec<String>) -> Result<Box<Self>, RequestError> {
-        if tokens.len() != 3 {
+    pub fn new(tokens: Vec<Vec<u8>>) -> Result<Box<Self>, RequestError> {
+        if tokens.len() != 3 && tokens.len() != 4 {
             return Err(RequestError::IncorrectArgCount);
         }
-        let Ok(start) = tokens[1].parse::<i64>() else {
+        let with_scores = match tokens.get(3) {
+            Some(v) if v.eq_ignore_ascii_case(b"withscores") => true,
+            Some(_) => return Err(RequestError::SyntaxError),
+            None => false,
+        };
+        let Ok(start) = String::from_utf8_lossy(&tokens[1]).parse::<i64>() else {
             return Err(RequestError::InvalidIntValue);
         };
-        let Ok(stop) = tokens[2].parse::<i64>() else {
+        let Ok(stop) = String::from_utf8_lossy(&tokens[2]).parse::<i64>() else {
             return Err(RequestError::InvalidIntValue);
         };
         Ok(Box::new(ZRangeCommand {
             key: tokens[0].clone(),
             start,
             stop,
+            with_scores,
         }))
     }
 }
@@ -46,11 +53,17 @@ impl Command for ZRangeCommand {
                     true => self.stop as u64,
                     false => (size as i64 + self.stop) as u64,
                 };
-                Ok(Box::new(ZRangeResult {
-                    values: sorted_set.get_values_by_rank(start, stop),
-                }))
+                let values = sorted_set.get_values_by_rank(start, stop);
+                let scores = match self.with_scores {
+                    true => Some(values.iter().map(|v| sorted_set.get(v).unwrap()).collect()),
+                    false => None,
+                };
+                Ok(Box::new(ZRangeResult { values, scores }))
             }
-            None => Ok(Box::new(ZRangeResult { values: Vec::new() })),
+            None => Ok(Box::new(ZRangeResult {
+                values: Vec::new(),
+                scores: None,
+            })),
         }
     }
 }
@@ -61,24 +74,42 @@ mod test {
     use crate::command::sorted_set::ZAddCommand;
     use crate::command::Command;
     use crate::data_store::DataStore;
+    use crate::execution_result::RespVersion;
 
     #[test]
     fn should_accept_exactly_3_tokens() {
-        let err = ZRangeCommand::new(vec!["foo".to_string()]).err().unwrap();
+        let err = ZRangeCommand::new(vec![b"foo".to_vec()]).err().unwrap();
         assert_eq!(
             err.to_string(),
             "ERR wrong number of arguments for command".to_string()
         );
-        let v =
-            ZRangeCommand::new(vec!["foo".to_string(), "1".to_string(), "2".to_string()]).unwrap();
-        assert_eq!(v.key, "foo".to_string());
+        let v = ZRangeCommand::new(vec![b"foo".to_vec(), b"1".to_vec(), b"2".to_vec()]).unwrap();
+        assert_eq!(v.key, b"foo".to_vec());
         assert_eq!(v.start, 1);
         assert_eq!(v.stop, 2);
+        assert!(!v.with_scores);
+        let v = ZRangeCommand::new(vec![
+            b"foo".to_vec(),
+            b"1".to_vec(),
+            b"2".to_vec(),
+            b"WITHSCORES".to_vec(),
+        ])
+        .unwrap();
+        assert!(v.with_scores);
+        let err = ZRangeCommand::new(vec![
+            b"foo".to_vec(),
+            b"1".to_vec(),
+            b"2".to_vec(),
+            b"bad".to_vec(),
+        ])
+        .err()
+        .unwrap();
+        assert_eq!(err.to_string(), "ERR syntax error".to_string());
     }
 
     #[test]
     fn should_reject_invalid_start_and_stop() {
-        let err = ZRangeCommand::new(vec!["foo".to_string(), "bad".to_string(), "2".to_string()])
+        let err = ZRangeCommand::new(vec![b"foo".to_vec(), b"bad".to_vec(), b"2".to_vec()])
             .err()
             .unwrap();
         assert_eq!(
@@ -90,28 +121,63 @@ mod test {
     #[test]
     fn should_list_items() {
         let mut ds = DataStore::new();
-        let key = "foo".to_string();
+        let key = b"foo".to_vec();
         let input = [(1.0, "a"), (0.5, "b"), (1.0, "aa"), (1.5, "c"), (1.2, "d")];
         for (score, item) in input {
-            ZAddCommand::new(vec![key.clone(), score.to_string(), item.to_string()])
-                .unwrap()
-                .execute(&mut ds)
-                .unwrap();
+            ZAddCommand::new(vec![
+                key.clone(),
+                score.to_string().into_bytes(),
+                item.as_bytes().to_vec(),
+            ])
+            .unwrap()
+            .execute(&mut ds)
+            .unwrap();
         }
-        let cmd = ZRangeCommand::new(vec![key.clone(), "0".to_string(), "1".to_string()]).unwrap();
+        let cmd = ZRangeCommand::new(vec![key.clone(), b"0".to_vec(), b"1".to_vec()]).unwrap();
         let result = cmd.execute(&mut ds);
         assert_eq!(result.unwrap().to_string(), "b,a".to_string());
 
         // negative start/stop
-        let cmd =
-            ZRangeCommand::new(vec![key.clone(), "-4".to_string(), "-2".to_string()]).unwrap();
+        let cmd = ZRangeCommand::new(vec![key.clone(), b"-4".to_vec(), b"-2".to_vec()]).unwrap();
         let result = cmd.execute(&mut ds);
         assert_eq!(result.unwrap().to_string(), "a,aa,d".to_string());
 
         // non-existent key
-        let cmd = ZRangeCommand::new(vec!["bar".to_string(), "-4".to_string(), "-2".to_string()])
-            .unwrap();
+        let cmd =
+            ZRangeCommand::new(vec![b"bar".to_vec(), b"-4".to_vec(), b"-2".to_vec()]).unwrap();
         let result = cmd.execute(&mut ds);
         assert_eq!(result.unwrap().to_string(), "".to_string());
     }
+
+    #[test]
+    fn should_list_items_with_scores() {
+        let mut ds = DataStore::new();
+        ZAddCommand::new(vec![
+            b"foo".to_vec(),
+            b"1.5".to_vec(),
+            b"a".to_vec(),
+            b"2".to_vec(),
+            b"b".to_vec(),
+        ])
+        .unwrap()
+        .execute(&mut ds)
+        .unwrap();
+        let cmd = ZRangeCommand::new(vec![
+            b"foo".to_vec(),
+            b"0".to_vec(),
+            b"-1".to_vec(),
+            b"withscores".to_vec(),
+        ])
+        .unwrap();
+        let result = cmd.execute(&mut ds).unwrap();
+        assert_eq!(result.to_string(), "a,1.5,b,2".to_string());
+        assert_eq!(
+            result.serialise(RespVersion::Resp2),
+            b"*4\r\n$1\r\na\r\n$3\r\n1.5\r\n$1\r\nb\r\n$1\r\n2\r\n".to_vec()
+        );
+        assert_eq!(
+            result.serialise(RespVersion::Resp3),
+            b"*2\r\n*2\r\n$1\r\na\r\n,1.5\r\n*2\r\n$1\r\nb\r\n,2\r\n".to_vec()
+        );
+    }
 }
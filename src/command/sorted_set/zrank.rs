@@ -6,12 +6,12 @@ use crate::execution_result::ExecutionResult;
 
 #[derive(Debug)]
 pub struct ZRankCommand {
-    key: String,
-    value: String,
+    key: Vec<u8>,
+    value: Vec<u8>,
 }
 
 impl ZRankCommand {
-    pub fn new(tokens: Vec<String>) -> Result<Box<Self>, RequestError> {
+    pub fn new(tokens: Vec<Vec<u8>>) -> Result<Box<Self>, RequestError> {
         if tokens.len() != 2 {
             return Err(RequestError::IncorrectArgCount);
         }
@@ -47,41 +47,46 @@ mod test {
 
         #[test]
         fn should_accept_correct_amount_of_tokens() {
-            let err = ZRankCommand::new(vec!["foo".to_string()]).err().unwrap();
+            let err = ZRankCommand::new(vec![b"foo".to_vec()]).err().unwrap();
             assert_eq!(
                 err.to_string(),
                 "ERR wrong number of arguments for command".to_string()
             );
-            let v = ZRankCommand::new(vec!["foo".to_string(), "baz".to_string()]).unwrap();
-            assert_eq!(v.key, "foo".to_string());
-            assert_eq!(v.value, "baz".to_string());
+            let v = ZRankCommand::new(vec![b"foo".to_vec(), b"baz".to_vec()]).unwrap();
+            assert_eq!(v.key, b"foo".to_vec());
+            assert_eq!(v.value, b"baz".to_vec());
         }
 
         #[test]
         fn should_get_rank() {
             let mut ds = DataStore::new();
-            let key = "foo".to_string();
+            let key = b"foo".to_vec();
             let elements = ["b", "a", "aa", "d", "c"];
             let scores = [0.5, 1.0, 1.0, 1.2, 1.5];
             for (score, item) in zip(scores, elements) {
-                ZAddCommand::new(vec![key.clone(), score.to_string(), item.to_string()])
-                    .unwrap()
-                    .execute(&mut ds)
-                    .unwrap();
+                ZAddCommand::new(vec![
+                    key.clone(),
+                    score.to_string().into_bytes(),
+                    item.as_bytes().to_vec(),
+                ])
+                .unwrap()
+                .execute(&mut ds)
+                .unwrap();
             }
 
             // Should return null because key test does not exist
-            let cmd = ZRankCommand::new(vec!["test".to_string(), "a".to_owned()]).unwrap();
+            let cmd = ZRankCommand::new(vec![b"test".to_vec(), b"a".to_vec()]).unwrap();
             let result = cmd.execute(&mut ds);
             assert_eq!(result.unwrap().to_string(), "".to_string());
 
             // Should return null because element something does not exist
-            let cmd = ZRankCommand::new(vec![key.clone(), "something".to_owned()]).unwrap();
+            let cmd = ZRankCommand::new(vec![key.clone(), b"something".to_vec()]).unwrap();
             let result = cmd.execute(&mut ds);
             assert_eq!(result.unwrap().to_string(), "".to_string());
 
             for (i, element) in elements.iter().cloned().enumerate() {
-                let cmd = ZRankCommand::new(vec![key.clone(), element.to_owned()]).unwrap();
+                let cmd =
+                    ZRankCommand::new(vec![key.clone(), element.as_bytes().to_vec()]).unwrap();
                 let result = cmd.execute(&mut ds);
                 assert_eq!(result.unwrap().to_string(), i.to_string());
             }
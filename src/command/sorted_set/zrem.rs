@@ -6,12 +6,12 @@ use crate::execution_result::ExecutionResult;
 
 #[derive(Debug)]
 pub struct ZRemCommand {
-    key: String,
-    values: Vec<String>,
+    key: Vec<u8>,
+    values: Vec<Vec<u8>>,
 }
 
 impl ZRemCommand {
-    pub fn new(tokens: Vec<String>) -> Result<Box<Self>, RequestError> {
+    pub fn new(tokens: Vec<Vec<u8>>) -> Result<Box<Self>, RequestError> {
         if tokens.len() < 2 {
             return Err(RequestError::IncorrectArgCount);
         }
@@ -27,7 +27,6 @@ impl Command for ZRemCommand {
         &self,
         data_store: &mut DataStore,
     ) -> Result<Box<dyn ExecutionResult>, Box<dyn std::error::Error>> {
-        // TODO: atomicity
         let count = match data_store.get_sorted_set_mut(&self.key)? {
             Some(sorted_set) => {
                 let mut count = 0;
@@ -38,7 +37,9 @@ impl Command for ZRemCommand {
             }
             None => 0,
         };
-
+        if count > 0 {
+            data_store.touch(&self.key);
+        }
         Ok(Box::new(ZRemResult { value: count }))
     }
 }
@@ -52,43 +53,43 @@ mod test {
 
         #[test]
         fn should_accept_correct_amount_of_tokens() {
-            let err = ZRemCommand::new(vec!["foo".to_string()]).err().unwrap();
+            let err = ZRemCommand::new(vec![b"foo".to_vec()]).err().unwrap();
             assert_eq!(
                 err.to_string(),
                 "ERR wrong number of arguments for command".to_string()
             );
-            let v = ZRemCommand::new(vec!["foo".to_string(), "baz".to_string()]).unwrap();
-            assert_eq!(v.key, "foo".to_string());
-            assert_eq!(v.values, vec!["baz".to_string()]);
+            let v = ZRemCommand::new(vec![b"foo".to_vec(), b"baz".to_vec()]).unwrap();
+            assert_eq!(v.key, b"foo".to_vec());
+            assert_eq!(v.values, vec![b"baz".to_vec()]);
         }
 
         #[test]
         fn should_remove_value() {
             let mut ds = DataStore::new();
-            let key = "foo".to_string();
+            let key = b"foo".to_vec();
             ZAddCommand::new(vec![
                 key.clone(),
-                "2.0".to_string(),
-                "v1".to_string(),
-                "1.0".to_string(),
-                "v2".to_string(),
+                b"2.0".to_vec(),
+                b"v1".to_vec(),
+                b"1.0".to_vec(),
+                b"v2".to_vec(),
             ])
             .unwrap()
             .execute(&mut ds)
             .unwrap();
 
-            let cmd = ZRemCommand::new(vec![key.clone(), "v1".to_string()]).unwrap();
+            let cmd = ZRemCommand::new(vec![key.clone(), b"v1".to_vec()]).unwrap();
             let result = cmd.execute(&mut ds);
             assert_eq!(result.unwrap().to_string(), "1".to_string());
             let sorted_set = ds.get_sorted_set_mut(&key).unwrap().unwrap();
-            assert!(sorted_set.get("v1").is_none());
+            assert!(sorted_set.get(b"v1").is_none());
 
             // Should return 0 because key "v1" is already gone
             let result = cmd.execute(&mut ds);
             assert_eq!(result.unwrap().to_string(), "0".to_string());
 
             // v3 does not exist
-            let cmd = ZRemCommand::new(vec![key.clone(), "v3".to_string()]).unwrap();
+            let cmd = ZRemCommand::new(vec![key.clone(), b"v3".to_vec()]).unwrap();
             let result = cmd.execute(&mut ds);
             assert_eq!(result.unwrap().to_string(), "0".to_string());
         }
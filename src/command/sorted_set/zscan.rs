@@ -0,0 +1,106 @@
+use crate::command::keyspace::ScanOptions;
+use crate::command::Command;
+use crate::data_store::DataStore;
+use crate::error::RequestError;
+use crate::execution_result::keyspace::ScanResult;
+use crate::execution_result::ExecutionResult;
+
+#[derive(Debug)]
+pub struct ZScanCommand {
+    key: Vec<u8>,
+    options: ScanOptions,
+}
+
+impl ZScanCommand {
+    pub fn new(tokens: Vec<Vec<u8>>) -> Result<Box<Self>, RequestError> {
+        if tokens.len() < 2 {
+            return Err(RequestError::IncorrectArgCount);
+        }
+        let options = ScanOptions::parse(&tokens[1..], |_, _| false)?;
+        Ok(Box::new(ZScanCommand {
+            key: tokens[0].clone(),
+            options,
+        }))
+    }
+}
+
+impl Command for ZScanCommand {
+    fn execute(
+        &self,
+        data_store: &mut DataStore,
+    ) -> Result<Box<dyn ExecutionResult>, Box<dyn std::error::Error>> {
+        let (values, cursor) = match data_store.get_sorted_set_mut(&self.key)? {
+            Some(sorted_set) => {
+                let (pairs, cursor) = sorted_set.scan(self.options.cursor, self.options.count);
+                let mut values = Vec::new();
+                for (element, score) in pairs {
+                    if self.options.matches(element) {
+                        values.push(element.to_vec());
+                        values.push(score.to_string().into_bytes());
+                    }
+                }
+                (values, cursor)
+            }
+            None => (Vec::new(), 0),
+        };
+        Ok(Box::new(ScanResult { cursor, values }))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::ZScanCommand;
+    use crate::command::sorted_set::{ZAddCommand, ZRemCommand};
+    use crate::command::Command;
+    use crate::data_store::DataStore;
+    use crate::utils::tokens;
+    use std::collections::HashMap;
+
+    #[test]
+    fn should_accept_key_and_cursor() {
+        let err = ZScanCommand::new(tokens(&["foo"])).err().unwrap();
+        assert_eq!(
+            err.to_string(),
+            "ERR wrong number of arguments for command".to_string()
+        );
+        let err = ZScanCommand::new(tokens(&["foo", "0", "COUNT", "x"]))
+            .err()
+            .unwrap();
+        assert_eq!(
+            err.to_string(),
+            "ERR value is not an integer or out of range".to_string()
+        );
+    }
+
+    #[test]
+    fn should_iterate_over_elements_with_scores() {
+        let mut ds = DataStore::new();
+        for i in 0..20 {
+            ZAddCommand::new(tokens(&["foo", &format!("{}.5", i), &format!("e{}", i)]))
+                .unwrap()
+                .execute(&mut ds)
+                .unwrap();
+        }
+        ZRemCommand::new(tokens(&["foo", "e0"]))
+            .unwrap()
+            .execute(&mut ds)
+            .unwrap();
+
+        let mut seen = HashMap::new();
+        let mut cursor = "0".to_string();
+        loop {
+            let cmd = ZScanCommand::new(tokens(&["foo", &cursor, "COUNT", "3"])).unwrap();
+            let result = cmd.execute(&mut ds).unwrap().to_string();
+            let values: Vec<String> = result.split(',').map(|v| v.to_string()).collect();
+            cursor = values[0].clone();
+            for pair in values[1..].chunks(2) {
+                assert!(seen.insert(pair[0].clone(), pair[1].clone()).is_none());
+            }
+            if cursor == "0" {
+                break;
+            }
+        }
+        assert_eq!(seen.len(), 19);
+        assert_eq!(seen["e7"], "7.5".to_string());
+    }
+}
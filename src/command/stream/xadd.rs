@@ -6,18 +6,18 @@ use crate::execution_result::ExecutionResult;
 
 #[derive(Debug)]
 pub struct XAddCommand {
-    key: String,
-    values: Vec<(f64, String)>,
+    key: Vec<u8>,
+    values: Vec<(f64, Vec<u8>)>,
 }
 
 impl XAddCommand {
-    pub fn new(tokens: Vec<String>) -> Result<Box<Self>, RequestError> {
+    pub fn new(tokens: Vec<Vec<u8>>) -> Result<Box<Self>, RequestError> {
         if tokens.len() % 2 != 1 || tokens.len() < 3 {
             return Err(RequestError::IncorrectArgCount);
         }
         let mut values = Vec::new();
         for i in 0..tokens.len() / 2 {
-            match tokens[2 * i + 1].parse::<f64>() {
+            match String::from_utf8_lossy(&tokens[2 * i + 1]).parse::<f64>() {
                 Ok(score) => values.push((score, tokens[2 * i + 2].clone())),
                 Err(_) => return Err(RequestError::InvalidFloatValue),
             };
@@ -34,7 +34,6 @@ impl Command for XAddCommand {
         &self,
         data_store: &mut DataStore,
     ) -> Result<Box<dyn ExecutionResult>, Box<dyn std::error::Error>> {
-        // TODO: atomicity
         let sorted_set = match data_store.get_sorted_set_mut(&self.key)? {
             Some(sorted_set) => sorted_set,
             None => {
@@ -43,9 +42,14 @@ impl Command for XAddCommand {
             }
         };
         let mut count = 0;
+        let mut changed = false;
         for (score, element) in &self.values {
+            changed |= sorted_set.get(element) != Some(*score);
             count += sorted_set.insert(*score, element.clone()) as u64;
         }
+        if changed {
+            data_store.touch(&self.key);
+        }
         Ok(Box::new(ZAddResult { value: count }))
     }
 }
@@ -6,11 +6,11 @@ use crate::execution_result::{string::GetResult, ExecutionResult};
 
 #[derive(Debug)]
 pub struct GetCommand {
-    key: String,
+    key: Vec<u8>,
 }
 
 impl GetCommand {
-    pub fn new(tokens: Vec<String>) -> Result<Box<Self>, RequestError> {
+    pub fn new(tokens: Vec<Vec<u8>>) -> Result<Box<Self>, RequestError> {
         if tokens.len() != 1 {
             return Err(RequestError::IncorrectArgCount);
         }
@@ -34,11 +34,11 @@ impl Command for GetCommand {
 
 #[derive(Debug)]
 pub struct MgetCommand {
-    keys: Vec<String>,
+    keys: Vec<Vec<u8>>,
 }
 
 impl MgetCommand {
-    pub fn new(tokens: Vec<String>) -> Result<Box<Self>, RequestError> {
+    pub fn new(tokens: Vec<Vec<u8>>) -> Result<Box<Self>, RequestError> {
         if tokens.is_empty() {
             return Err(RequestError::IncorrectArgCount);
         }
@@ -68,12 +68,13 @@ mod test {
         use crate::command::string::GetCommand;
         use crate::command::Command;
         use crate::data_store::DataStore;
+        use crate::execution_result::RespVersion;
 
         #[test]
         fn should_accept_exactly_one_token() {
-            let v = GetCommand::new(vec!["foo".to_string()]).unwrap();
-            assert_eq!(v.key, "foo".to_string());
-            let err = GetCommand::new(vec!["foo".to_string(), "bar".to_string()])
+            let v = GetCommand::new(vec![b"foo".to_vec()]).unwrap();
+            assert_eq!(v.key, b"foo".to_vec());
+            let err = GetCommand::new(vec![b"foo".to_vec(), b"bar".to_vec()])
                 .err()
                 .unwrap();
             assert_eq!(
@@ -84,16 +85,28 @@ mod test {
 
         #[test]
         fn should_get_value_if_key_exists() {
-            let cmd = GetCommand::new(vec!["foo".to_string()]).unwrap();
+            let cmd = GetCommand::new(vec![b"foo".to_vec()]).unwrap();
             let mut ds = DataStore::new();
-            ds.set_string_overwrite("foo", "bar");
+            ds.set_string_overwrite(b"foo", b"bar");
             let result = cmd.execute(&mut ds);
             assert_eq!(result.unwrap().to_string(), "bar".to_string());
         }
 
+        #[test]
+        fn should_serialise_binary_value_as_bulk_string() {
+            let cmd = GetCommand::new(vec![b"foo".to_vec()]).unwrap();
+            let mut ds = DataStore::new();
+            ds.set_string_overwrite(b"foo", b"a\r\nb\xff");
+            let result = cmd.execute(&mut ds);
+            assert_eq!(
+                result.unwrap().serialise(RespVersion::Resp2),
+                b"$5\r\na\r\nb\xff\r\n".to_vec()
+            );
+        }
+
         #[test]
         fn should_return_null_if_key_does_not_exist() {
-            let cmd = GetCommand::new(vec!["foo".to_string()]).unwrap();
+            let cmd = GetCommand::new(vec![b"foo".to_vec()]).unwrap();
             let mut ds = DataStore::new();
             let result = cmd.execute(&mut ds);
             assert_eq!(result.unwrap().to_string(), "".to_string());
@@ -106,11 +119,11 @@ mod test {
 
         #[test]
         fn should_get_values_if_keys_exist() {
-            let cmd = MgetCommand::new(vec!["k1".to_string(), "k2".to_string(), "k3".to_string()])
-                .unwrap();
+            let cmd =
+                MgetCommand::new(vec![b"k1".to_vec(), b"k2".to_vec(), b"k3".to_vec()]).unwrap();
             let mut ds = DataStore::new();
-            ds.set_string_overwrite("k1", "v1");
-            ds.set_string_overwrite("k3", "v3");
+            ds.set_string_overwrite(b"k1", b"v1");
+            ds.set_string_overwrite(b"k3", b"v3");
             let result = cmd.execute(&mut ds);
             assert_eq!(result.unwrap().to_string(), "v1,,v3".to_string());
         }
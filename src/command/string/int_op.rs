@@ -9,19 +9,19 @@ pub enum NumOperator {
 }
 
 fn _execute(
-    key: &String,
+    key: &[u8],
     value: i64,
     data_store: &mut DataStore,
 ) -> Result<Box<dyn ExecutionResult>, Box<dyn std::error::Error>> {
-    let default = "0".to_string();
+    let default = b"0".to_vec();
     let curr_value = match data_store.get_string(key)? {
         Some(v) => v,
         None => &default,
     };
-    match curr_value.parse::<i64>() {
+    match String::from_utf8_lossy(curr_value).parse::<i64>() {
         Ok(v) => match v.checked_add(value) {
             Some(updated) => {
-                let _ = data_store.set_string(key, &updated.to_string());
+                let _ = data_store.set_string(key, updated.to_string().as_bytes());
                 Ok(Box::new(IntOpResult { value: updated }))
             }
             None => Err(Box::new(IncrCommandError::ResultOverflow)),
@@ -32,12 +32,12 @@ fn _execute(
 
 #[derive(Debug)]
 pub struct IncrCommand {
-    key: String,
+    key: Vec<u8>,
     value: i64,
 }
 
 impl IncrCommand {
-    pub fn new(tokens: Vec<String>, op: NumOperator) -> Result<Box<Self>, RequestError> {
+    pub fn new(tokens: Vec<Vec<u8>>, op: NumOperator) -> Result<Box<Self>, RequestError> {
         if tokens.len() != 1 {
             return Err(RequestError::IncorrectArgCount);
         }
@@ -62,17 +62,17 @@ impl Command for IncrCommand {
 
 #[derive(Debug)]
 pub struct IncrbyCommand {
-    key: String,
+    key: Vec<u8>,
     value: i64,
 }
 
 impl IncrbyCommand {
-    pub fn new(tokens: Vec<String>, op: NumOperator) -> Result<Box<Self>, RequestError> {
+    pub fn new(tokens: Vec<Vec<u8>>, op: NumOperator) -> Result<Box<Self>, RequestError> {
         if tokens.len() != 2 {
             return Err(RequestError::IncorrectArgCount);
         }
 
-        match tokens[1].parse::<i64>() {
+        match String::from_utf8_lossy(&tokens[1]).parse::<i64>() {
             Ok(increment) => {
                 let value = match op {
                     NumOperator::Decr => match increment.checked_neg() {
@@ -112,55 +112,52 @@ mod test {
 
         #[test]
         fn should_accept_exactly_one_token() {
-            let err = IncrCommand::new(
-                vec!["foo".to_string(), "bar".to_string()],
-                NumOperator::Incr,
-            )
-            .err()
-            .unwrap();
+            let err = IncrCommand::new(vec![b"foo".to_vec(), b"bar".to_vec()], NumOperator::Incr)
+                .err()
+                .unwrap();
             assert_eq!(
                 err.to_string(),
                 "ERR wrong number of arguments for command".to_string()
             );
-            let v = IncrCommand::new(vec!["foo".to_string()], NumOperator::Incr).unwrap();
-            assert_eq!(v.key, "foo".to_string());
+            let v = IncrCommand::new(vec![b"foo".to_vec()], NumOperator::Incr).unwrap();
+            assert_eq!(v.key, b"foo".to_vec());
         }
 
         #[test]
         fn should_insert_value_when_key_is_not_set() {
-            let key = "foo".to_string();
+            let key = b"foo".to_vec();
             let cmd = IncrCommand::new(vec![key.clone()], NumOperator::Incr).unwrap();
             let mut ds = DataStore::new();
             assert!(ds.get_string(&key).unwrap().is_none());
             cmd.execute(&mut ds).unwrap();
-            assert_eq!(ds.get_string(&key).unwrap().unwrap(), &"1".to_string());
+            assert_eq!(ds.get_string(&key).unwrap().unwrap(), &b"1".to_vec());
         }
 
         #[test]
         fn should_insert_value_when_key_is_not_set_decr() {
-            let key = "foo".to_string();
+            let key = b"foo".to_vec();
             let cmd = IncrCommand::new(vec![key.clone()], NumOperator::Decr).unwrap();
             let mut ds = DataStore::new();
             assert!(ds.get_string(&key).unwrap().is_none());
             cmd.execute(&mut ds).unwrap();
-            assert_eq!(ds.get_string(&key).unwrap().unwrap(), &"-1".to_string());
+            assert_eq!(ds.get_string(&key).unwrap().unwrap(), &b"-1".to_vec());
         }
 
         #[test]
         fn should_throw_error_when_value_is_not_int() {
-            let key = "foo".to_string();
+            let key = b"foo".to_vec();
             let cmd = IncrCommand::new(vec![key.clone()], NumOperator::Incr).unwrap();
             let mut ds = DataStore::new();
-            ds.set_string_overwrite(&key, "bar");
+            ds.set_string_overwrite(&key, b"bar");
             assert!(cmd.execute(&mut ds).is_err());
         }
 
         #[test]
         fn should_throw_error_when_value_overflows_incr() {
-            let key = "foo".to_string();
+            let key = b"foo".to_vec();
             let cmd = IncrCommand::new(vec![key.clone()], NumOperator::Incr).unwrap();
             let mut ds = DataStore::new();
-            ds.set_string_overwrite(&key, &i64::MAX.to_string());
+            ds.set_string_overwrite(&key, i64::MAX.to_string().as_bytes());
             let err = cmd.execute(&mut ds).err().unwrap();
             assert_eq!(
                 err.to_string(),
@@ -170,10 +167,10 @@ mod test {
 
         #[test]
         fn should_throw_error_when_value_overflows_decr() {
-            let key = "foo".to_string();
+            let key = b"foo".to_vec();
             let cmd = IncrCommand::new(vec![key.clone()], NumOperator::Decr).unwrap();
             let mut ds = DataStore::new();
-            ds.set_string_overwrite(&key, &i64::MIN.to_string());
+            ds.set_string_overwrite(&key, i64::MIN.to_string().as_bytes());
             let err = cmd.execute(&mut ds).err().unwrap();
             assert_eq!(
                 err.to_string(),
@@ -190,7 +187,7 @@ mod test {
 
         #[test]
         fn should_accept_exactly_two_tokens() {
-            let err = IncrbyCommand::new(vec!["foo".to_string()], NumOperator::Incr)
+            let err = IncrbyCommand::new(vec![b"foo".to_vec()], NumOperator::Incr)
                 .err()
                 .unwrap();
             assert_eq!(
@@ -198,30 +195,27 @@ mod test {
                 "ERR wrong number of arguments for command".to_string()
             );
 
-            let v = IncrbyCommand::new(vec!["foo".to_string(), "5".to_string()], NumOperator::Incr)
+            let v = IncrbyCommand::new(vec![b"foo".to_vec(), b"5".to_vec()], NumOperator::Incr)
                 .unwrap();
-            assert_eq!(v.key, "foo".to_string());
+            assert_eq!(v.key, b"foo".to_vec());
             assert_eq!(v.value, 5);
         }
 
         #[test]
         fn should_reject_non_int_increment() {
-            let err = IncrbyCommand::new(
-                vec!["foo".to_string(), "bar".to_string()],
-                NumOperator::Incr,
-            )
-            .err()
-            .unwrap();
+            let err = IncrbyCommand::new(vec![b"foo".to_vec(), b"bar".to_vec()], NumOperator::Incr)
+                .err()
+                .unwrap();
             assert_eq!(err.to_string(), RequestError::InvalidIntValue.to_string());
         }
 
         #[test]
         fn should_throw_error_when_value_overflows_incr() {
-            let key = "foo".to_string();
+            let key = b"foo".to_vec();
             let cmd =
-                IncrbyCommand::new(vec![key.clone(), "5".to_string()], NumOperator::Incr).unwrap();
+                IncrbyCommand::new(vec![key.clone(), b"5".to_vec()], NumOperator::Incr).unwrap();
             let mut ds = DataStore::new();
-            ds.set_string_overwrite(&key, &i64::MAX.to_string());
+            ds.set_string_overwrite(&key, i64::MAX.to_string().as_bytes());
             let err = cmd.execute(&mut ds).err().unwrap();
             assert_eq!(
                 err.to_string(),
@@ -231,11 +225,11 @@ mod test {
 
         #[test]
         fn should_throw_error_when_value_overflows_decr() {
-            let key = "foo".to_string();
+            let key = b"foo".to_vec();
             let cmd =
-                IncrbyCommand::new(vec![key.clone(), "5".to_string()], NumOperator::Decr).unwrap();
+                IncrbyCommand::new(vec![key.clone(), b"5".to_vec()], NumOperator::Decr).unwrap();
             let mut ds = DataStore::new();
-            ds.set_string_overwrite(&key, &i64::MIN.to_string());
+            ds.set_string_overwrite(&key, i64::MIN.to_string().as_bytes());
             let err = cmd.execute(&mut ds).err().unwrap();
             assert_eq!(
                 err.to_string(),
@@ -1,23 +1,101 @@
+use crate::command::keyspace::TimeUnit;
 use crate::command::Command;
-use crate::data_store::DataStore;
+use crate::data_store::{now_ms, DataStore};
 use crate::error::RequestError;
 use crate::execution_result::string::{MsetResult, SetResult};
 use crate::execution_result::ExecutionResult;
 
+#[derive(Debug, PartialEq)]
+enum SetCondition {
+    /// Only set the key if it does not exist.
+    Nx,
+    /// Only set the key if it already exists.
+    Xx,
+}
+
+#[derive(Debug, PartialEq)]
+enum SetExpiry {
+    /// Expire the key after the given number of milliseconds.
+    In(i64),
+    /// Expire the key at the given Unix time in milliseconds.
+    At(i64),
+    /// Keep the TTL the key already has.
+    KeepTtl,
+}
+
 #[derive(Debug)]
 pub struct SetCommand {
-    key: String,
-    value: String,
+    key: Vec<u8>,
+    value: Vec<u8>,
+    condition: Option<SetCondition>,
+    /// Whether to reply with the value previously stored at the key.
+    get: bool,
+    /// Without an expiry the key is stored without a TTL.
+    expiry: Option<SetExpiry>,
 }
 
 impl SetCommand {
-    pub fn new(tokens: Vec<String>) -> Result<Box<Self>, RequestError> {
-        if tokens.len() != 2 {
+    pub fn new(tokens: Vec<Vec<u8>>) -> Result<Box<Self>, RequestError> {
+        if tokens.len() < 2 {
             return Err(RequestError::IncorrectArgCount);
         }
+        let mut condition = None;
+        let mut get = false;
+        let mut expiry = None;
+        let mut options = tokens[2..].iter();
+        while let Some(option) = options.next() {
+            let option = option.to_ascii_lowercase();
+            match option.as_slice() {
+                b"nx" | b"xx" => {
+                    let new = match option.as_slice() {
+                        b"nx" => SetCondition::Nx,
+                        _ => SetCondition::Xx,
+                    };
+                    if condition.as_ref().is_some_and(|c| *c != new) {
+                        return Err(RequestError::SyntaxError);
+                    }
+                    condition = Some(new);
+                }
+                b"get" => get = true,
+                b"keepttl" => {
+                    if expiry.is_some() {
+                        return Err(RequestError::SyntaxError);
+                    }
+                    expiry = Some(SetExpiry::KeepTtl);
+                }
+                b"ex" | b"px" | b"exat" | b"pxat" => {
+                    let Some(value) = options.next() else {
+                        return Err(RequestError::SyntaxError);
+                    };
+                    if expiry.is_some() {
+                        return Err(RequestError::SyntaxError);
+                    }
+                    let value = match String::from_utf8_lossy(value).parse::<i64>() {
+                        Ok(v) => v,
+                        Err(_) => return Err(RequestError::InvalidIntValue),
+                    };
+                    let unit = match option.as_slice() {
+                        b"ex" | b"exat" => TimeUnit::Seconds,
+                        _ => TimeUnit::Milliseconds,
+                    };
+                    let ms = match value.checked_mul(unit.as_ms()) {
+                        Some(v) if v > 0 => v,
+                        _ => return Err(RequestError::InvalidExpireTime("set".to_string())),
+                    };
+                    expiry = match option.as_slice() {
+                        b"ex" | b"px" => Some(SetExpiry::In(ms)),
+                        _ => Some(SetExpiry::At(ms)),
+                    };
+                }
+                _ => return Err(RequestError::SyntaxError),
+            }
+        }
         Ok(Box::new(SetCommand {
             key: tokens[0].clone(),
             value: tokens[1].clone(),
+            condition,
+            get,
+            expiry,
         }))
     }
 }
@@ -27,19 +105,52 @@ impl Command for SetCommand {
         &self,
         data_store: &mut DataStore,
     ) -> Result<Box<dyn ExecutionResult>, Box<dyn std::error::Error>> {
+        // The old value is read first so that a key holding another type fails the whole command.
+        let old_value = match self.get {
+            true => data_store.get_string(&self.key)?.cloned(),
+            false => None,
+        };
+        let exists = data_store.contains_key(&self.key);
+        let allowed = match self.condition {
+            None => true,
+            Some(SetCondition::Nx) => !exists,
+            Some(SetCondition::Xx) => exists,
+        };
+        if !allowed {
+            return Ok(Box::new(match self.get {
+                true => SetResult::OldValue(old_value),
+                false => SetResult::Aborted,
+            }));
+        }
+
+        let deadline = match self.expiry {
+            Some(SetExpiry::In(v)) => match v.checked_add(now_ms() as i64) {
+                Some(deadline) => Some(deadline as u64),
+                None => return Err(Box::new(RequestError::InvalidExpireTime("set".to_string()))),
+            },
+            Some(SetExpiry::At(v)) => Some(v as u64),
+            Some(SetExpiry::KeepTtl) => data_store.get_expire_at(&self.key),
+            None => None,
+        };
         data_store.set_string_overwrite(&self.key, &self.value);
-        Ok(Box::new(SetResult {}))
+        if let Some(deadline) = deadline {
+            data_store.set_expire_at(&self.key, deadline);
+        }
+        Ok(Box::new(match self.get {
+            true => SetResult::OldValue(old_value),
+            false => SetResult::Ok,
+        }))
     }
 }
 
 #[derive(Debug)]
 pub struct MsetCommand {
-    pairs: Vec<(String, String)>,
+    pairs: Vec<(Vec<u8>, Vec<u8>)>,
 }
 
 impl MsetCommand {
-    pub fn new(tokens: Vec<String>) -> Result<Box<Self>, RequestError> {
-        if tokens.len() % 2 != 0 {
+    pub fn new(tokens: Vec<Vec<u8>>) -> Result<Box<Self>, RequestError> {
+        if !tokens.len().is_multiple_of(2) {
             return Err(RequestError::IncorrectArgCount);
         }
         let mut pairs = Vec::new();
@@ -55,7 +166,6 @@ impl Command for MsetCommand {
         &self,
         data_store: &mut DataStore,
     ) -> Result<Box<dyn ExecutionResult>, Box<dyn std::error::Error>> {
-        // TODO: atomicity
         for p in &self.pairs {
             data_store.set_string_overwrite(&p.0, &p.1);
         }
@@ -66,41 +176,162 @@ impl Command for MsetCommand {
 #[cfg(test)]
 mod test {
     mod test_set {
+        use super::super::{SetCondition, SetExpiry};
         use crate::command::string::SetCommand;
         use crate::command::Command;
-        use crate::data_store::DataStore;
+        use crate::data_store::{now_ms, DataStore};
+        use crate::execution_result::RespVersion;
 
         #[test]
-        fn should_accept_exactly_two_tokens() {
-            let err = SetCommand::new(vec!["foo".to_string()]).err().unwrap();
+        fn should_accept_key_and_value() {
+            let err = SetCommand::new(vec![b"foo".to_vec()]).err().unwrap();
             assert_eq!(
                 err.to_string(),
                 "ERR wrong number of arguments for command".to_string()
             );
-            let err = SetCommand::new(vec![
-                "foo".to_string(),
-                "bar".to_string(),
-                "baz".to_string(),
-            ])
-            .err()
-            .unwrap();
+            let err = SetCommand::new(vec![b"foo".to_vec(), b"bar".to_vec(), b"baz".to_vec()])
+                .err()
+                .unwrap();
+            assert_eq!(err.to_string(), "ERR syntax error".to_string());
+            let v = SetCommand::new(vec![b"foo".to_vec(), b"bar".to_vec()]).unwrap();
+            assert_eq!(v.key, b"foo".to_vec());
+            assert_eq!(v.value, b"bar".to_vec());
+        }
+
+        #[test]
+        fn should_insert_value() {
+            let key = b"foo".to_vec();
+            let cmd = SetCommand::new(vec![key.clone(), b"bar".to_vec()]).unwrap();
+            let mut ds = DataStore::new();
+            assert!(ds.get_string(&key).unwrap().is_none());
+            cmd.execute(&mut ds).unwrap();
+            assert_eq!(ds.get_string(&key).unwrap().unwrap(), &b"bar".to_vec());
+        }
+
+        #[test]
+        fn should_insert_binary_value() {
+            let key = b"\x00foo\r\n".to_vec();
+            let value = vec![0xff, 0xfe, b'\r', b'\n', 0x00];
+            let cmd = SetCommand::new(vec![key.clone(), value.clone()]).unwrap();
+            let mut ds = DataStore::new();
+            cmd.execute(&mut ds).unwrap();
+            assert_eq!(ds.get_string(&key).unwrap().unwrap(), &value);
+        }
+
+        fn tokens(options: &[&str]) -> Vec<Vec<u8>> {
+            let mut tokens = vec![b"foo".to_vec(), b"bar".to_vec()];
+            tokens.extend(options.iter().map(|o| o.as_bytes().to_vec()));
+            tokens
+        }
+
+        #[test]
+        fn should_parse_options() {
+            let v = SetCommand::new(tokens(&["nx", "GET", "EX", "10"])).unwrap();
+            assert_eq!(v.condition, Some(SetCondition::Nx));
+            assert!(v.get);
+            assert_eq!(v.expiry, Some(SetExpiry::In(10000)));
+            let v = SetCommand::new(tokens(&["px", "10", "XX", "xx"])).unwrap();
+            assert_eq!(v.condition, Some(SetCondition::Xx));
+            assert!(!v.get);
+            assert_eq!(v.expiry, Some(SetExpiry::In(10)));
+            let v = SetCommand::new(tokens(&["EXAT", "10"])).unwrap();
+            assert_eq!(v.expiry, Some(SetExpiry::At(10000)));
+            let v = SetCommand::new(tokens(&["PXAT", "10"])).unwrap();
+            assert_eq!(v.expiry, Some(SetExpiry::At(10)));
+            let v = SetCommand::new(tokens(&["KEEPTTL"])).unwrap();
+            assert_eq!(v.expiry, Some(SetExpiry::KeepTtl));
+
+            for options in [
+                &["NX", "XX"][..],
+                &["EX", "1", "PX", "1"],
+                &["KEEPTTL", "EXAT", "1"],
+                &["PX", "1", "KEEPTTL"],
+                &["EX"],
+                &["IF"],
+            ] {
+                let err = SetCommand::new(tokens(options)).err().unwrap();
+                assert_eq!(err.to_string(), "ERR syntax error".to_string());
+            }
+            let err = SetCommand::new(tokens(&["EX", "0"])).err().unwrap();
             assert_eq!(
                 err.to_string(),
-                "ERR wrong number of arguments for command".to_string()
+                "ERR invalid expire time in 'set' command".to_string()
+            );
+            let err = SetCommand::new(tokens(&["PXAT", "soon"])).err().unwrap();
+            assert_eq!(
+                err.to_string(),
+                "ERR value is not an integer or out of range".to_string()
             );
-            let v = SetCommand::new(vec!["foo".to_string(), "bar".to_string()]).unwrap();
-            assert_eq!(v.key, "foo".to_string());
-            assert_eq!(v.value, "bar".to_string());
         }
 
         #[test]
-        fn should_insert_value() {
-            let key = "foo".to_string();
-            let cmd = SetCommand::new(vec![key.clone(), "bar".to_string()]).unwrap();
+        fn should_replace_expiry() {
             let mut ds = DataStore::new();
-            assert!(ds.get_string(&key).unwrap().is_none());
+            let cmd = SetCommand::new(tokens(&["PX", "5000"])).unwrap();
+            cmd.execute(&mut ds).unwrap();
+            let deadline = ds.get_expire_at(b"foo").unwrap();
+            assert!(deadline > now_ms() + 4000 && deadline <= now_ms() + 5000);
+            let cmd = SetCommand::new(tokens(&["KEEPTTL"])).unwrap();
+            cmd.execute(&mut ds).unwrap();
+            assert_eq!(ds.get_expire_at(b"foo"), Some(deadline));
+            let cmd = SetCommand::new(tokens(&["PXAT", "4000000000000"])).unwrap();
             cmd.execute(&mut ds).unwrap();
-            assert_eq!(ds.get_string(&key).unwrap().unwrap(), &"bar".to_string());
+            assert_eq!(ds.get_expire_at(b"foo"), Some(4000000000000));
+            let cmd = SetCommand::new(tokens(&[])).unwrap();
+            cmd.execute(&mut ds).unwrap();
+            assert_eq!(ds.get_expire_at(b"foo"), None);
+        }
+
+        #[test]
+        fn should_only_set_when_condition_holds() {
+            let mut ds = DataStore::new();
+            let xx = SetCommand::new(tokens(&["XX"])).unwrap();
+            let result = xx.execute(&mut ds).unwrap();
+            assert_eq!(result.serialise(RespVersion::Resp2), b"$-1\r\n".to_vec());
+            assert!(!ds.contains_key(b"foo"));
+
+            let nx = SetCommand::new(tokens(&["NX"])).unwrap();
+            let result = nx.execute(&mut ds).unwrap();
+            assert_eq!(result.serialise(RespVersion::Resp2), b"+OK\r\n".to_vec());
+            ds.set_string_overwrite(b"foo", b"old");
+            let result = nx.execute(&mut ds).unwrap();
+            assert_eq!(result.serialise(RespVersion::Resp2), b"$-1\r\n".to_vec());
+            assert_eq!(ds.get_string(b"foo").unwrap().unwrap(), &b"old".to_vec());
+            let result = xx.execute(&mut ds).unwrap();
+            assert_eq!(result.serialise(RespVersion::Resp2), b"+OK\r\n".to_vec());
+            assert_eq!(ds.get_string(b"foo").unwrap().unwrap(), &b"bar".to_vec());
+        }
+
+        #[test]
+        fn should_reply_with_old_value() {
+            let mut ds = DataStore::new();
+            let cmd = SetCommand::new(tokens(&["GET"])).unwrap();
+            let result = cmd.execute(&mut ds).unwrap();
+            assert_eq!(result.serialise(RespVersion::Resp2), b"$-1\r\n".to_vec());
+            ds.set_string_overwrite(b"foo", b"old");
+            let result = cmd.execute(&mut ds).unwrap();
+            assert_eq!(
+                result.serialise(RespVersion::Resp2),
+                b"$3\r\nold\r\n".to_vec()
+            );
+            assert_eq!(ds.get_string(b"foo").unwrap().unwrap(), &b"bar".to_vec());
+
+            // The old value is replied even if the condition prevents the update.
+            let cmd = SetCommand::new(tokens(&["NX", "GET"])).unwrap();
+            let result = cmd.execute(&mut ds).unwrap();
+            assert_eq!(
+                result.serialise(RespVersion::Resp2),
+                b"$3\r\nbar\r\n".to_vec()
+            );
+
+            let _ = ds.insert_list(b"list");
+            let cmd =
+                SetCommand::new(vec![b"list".to_vec(), b"v".to_vec(), b"GET".to_vec()]).unwrap();
+            assert_eq!(
+                cmd.execute(&mut ds).err().unwrap().to_string(),
+                "WRONGTYPE Operation against a key holding the wrong kind of value".to_string()
+            );
+            assert!(ds.get_list_mut(b"list").unwrap().is_some());
         }
     }
 
@@ -112,24 +343,18 @@ mod test {
         #[test]
         fn should_insert_values() {
             let cmd = MsetCommand::new(vec![
-                "k1".to_string(),
-                "v1".to_string(),
-                "k2".to_string(),
-                "v2".to_string(),
+                b"k1".to_vec(),
+                b"v1".to_vec(),
+                b"k2".to_vec(),
+                b"v2".to_vec(),
             ])
             .unwrap();
             let mut ds = DataStore::new();
-            assert!(ds.get_string(&"k1".to_string()).unwrap().is_none());
-            assert!(ds.get_string(&"k2".to_string()).unwrap().is_none());
+            assert!(ds.get_string(b"k1").unwrap().is_none());
+            assert!(ds.get_string(b"k2").unwrap().is_none());
             cmd.execute(&mut ds).unwrap();
-            assert_eq!(
-                ds.get_string(&"k1".to_string()).unwrap().unwrap(),
-                &"v1".to_string()
-            );
-            assert_eq!(
-                ds.get_string(&"k2".to_string()).unwrap().unwrap(),
-                &"v2".to_string()
-            );
+            assert_eq!(ds.get_string(b"k1").unwrap().unwrap(), &b"v1".to_vec());
+            assert_eq!(ds.get_string(b"k2").unwrap().unwrap(), &b"v2".to_vec());
         }
     }
 }
@@ -27,6 +27,7 @@ pub enum SetCommandType {
     IsMember,
     Card,
     Diff,
+    Scan,
 }
 
 pub enum HashCommandType {
@@ -34,6 +35,7 @@ pub enum HashCommandType {
     Get,
     GetAll,
     IncrBy,
+    Scan,
 }
 
 pub enum SortedSetCommandType {
@@ -41,12 +43,75 @@ pub enum SortedSetCommandType {
     Range,
     Rem,
     Rank,
+    Scan,
 }
 
 pub enum StreamCommandType {
     Add,
 }
 
+pub enum KeyspaceCommandType {
+    Del,
+    Unlink,
+    Exists,
+    Touch,
+    Type,
+    Rename,
+    RenameNx,
+    Keys,
+    Scan,
+    Expire,
+    PExpire,
+    ExpireAt,
+    PExpireAt,
+    Ttl,
+    PTtl,
+    Persist,
+    Dump,
+    Restore,
+    RestoreAsking,
+}
+
+pub enum ConnectionCommandType {
+    Hello,
+    Quit,
+}
+
+pub enum ServerCommandType {
+    Select,
+    Move,
+    Copy,
+    SwapDb,
+    FlushDb,
+    FlushAll,
+    DbSize,
+    Save,
+    BgSave,
+    LastSave,
+    BgRewriteAof,
+    ReplicaOf,
+    ReplConf,
+    Psync,
+    Info,
+    Cluster,
+    Asking,
+    Migrate,
+    Multi,
+    Exec,
+    Discard,
+    Watch,
+    Unwatch,
+    Eval,
+    EvalSha,
+    Script,
+    Subscribe,
+    Unsubscribe,
+    PSubscribe,
+    PUnsubscribe,
+    Publish,
+    PubSub,
+}
+
 pub enum CommandType {
     Ping,
     String(StringCommandType),
@@ -55,16 +120,219 @@ pub enum CommandType {
     Hash(HashCommandType),
     SortedSet(SortedSetCommandType),
     Stream(StreamCommandType),
+    Keyspace(KeyspaceCommandType),
+    Connection(ConnectionCommandType),
+    Server(ServerCommandType),
 }
 
 const STRING_COMMANDS: &[&str] = &[
     "set", "get", "incr", "decr", "incrby", "decrby", "mget", "mset",
 ];
 const LIST_COMMANDS: &[&str] = &["lpush", "lpop", "lrange", "llen", "rpush", "rpop"];
-const SET_COMMANDS: &[&str] = &["sadd", "srem", "smembers", "sismember", "scard", "sdiff"];
-const HASH_COMMANDS: &[&str] = &["hset", "hget", "hgetall", "hincrby"];
-const SORTED_SET_COMMANDS: &[&str] = &["zadd", "zrange", "zrem", "zrank"];
+const SET_COMMANDS: &[&str] = &[
+    "sadd",
+    "srem",
+    "smembers",
+    "sismember",
+    "scard",
+    "sdiff",
+    "sscan",
+];
+const HASH_COMMANDS: &[&str] = &["hset", "hget", "hgetall", "hincrby", "hscan"];
+const SORTED_SET_COMMANDS: &[&str] = &["zadd", "zrange", "zrem", "zrank", "zscan"];
 const STREAM_COMMANDS: &[&str] = &["xadd"];
+const KEYSPACE_COMMANDS: &[&str] = &[
+    "del",
+    "unlink",
+    "exists",
+    "touch",
+    "type",
+    "rename",
+    "renamenx",
+    "keys",
+    "scan",
+    "expire",
+    "pexpire",
+    "expireat",
+    "pexpireat",
+    "ttl",
+    "pttl",
+    "persist",
+    "dump",
+    "restore",
+    "restore-asking",
+];
+const CONNECTION_COMMANDS: &[&str] = &["hello", "quit"];
+const SERVER_COMMANDS: &[&str] = &[
+    "select",
+    "move",
+    "copy",
+    "swapdb",
+    "flushdb",
+    "flushall",
+    "dbsize",
+    "save",
+    "bgsave",
+    "lastsave",
+    "bgrewriteaof",
+    "replicaof",
+    "slaveof",
+    "replconf",
+    "psync",
+    "info",
+    "cluster",
+    "asking",
+    "migrate",
+    "multi",
+    "exec",
+    "discard",
+    "watch",
+    "unwatch",
+    "eval",
+    "evalsha",
+    "script",
+    "subscribe",
+    "unsubscribe",
+    "psubscribe",
+    "punsubscribe",
+    "publish",
+    "pubsub",
+];
+/// The commands that can change the data.
+const WRITE_COMMANDS: &[&str] = &[
+    "set",
+    "incr",
+    "decr",
+    "incrby",
+    "decrby",
+    "mset",
+    "lpush",
+    "lpop",
+    "rpush",
+    "rpop",
+    "sadd",
+    "srem",
+    "hset",
+    "hincrby",
+    "zadd",
+    "zrem",
+    "xadd",
+    "del",
+    "unlink",
+    "rename",
+    "renamenx",
+    "expire",
+    "pexpire",
+    "expireat",
+    "pexpireat",
+    "persist",
+    "move",
+    "copy",
+    "swapdb",
+    "flushdb",
+    "flushall",
+    "restore",
+    "restore-asking",
+    "migrate",
+];
+
+/// Returns whether the command called `name` controls a transaction rather than being queued in
+/// it.
+pub fn is_transaction_command(name: &[u8]) -> bool {
+    let name = String::from_utf8_lossy(name).to_lowercase();
+    matches!(name.as_str(), "multi" | "exec" | "discard" | "watch")
+}
+
+/// Returns whether the command called `name` can run a script, which may hold the server for as
+/// long as the script runs. `EXEC` can run the scripts of a transaction.
+pub fn is_script_command(name: &[u8]) -> bool {
+    let name = String::from_utf8_lossy(name).to_lowercase();
+    matches!(name.as_str(), "eval" | "evalsha" | "exec")
+}
+
+/// Returns whether the command called `name` can be run by a RESP2 client in subscriber mode,
+/// whose connection is otherwise only used for pushing messages.
+pub fn is_subscriber_command(name: &[u8]) -> bool {
+    let name = String::from_utf8_lossy(name).to_lowercase();
+    matches!(
+        name.as_str(),
+        "subscribe" | "unsubscribe" | "psubscribe" | "punsubscribe" | "ping" | "quit"
+    )
+}
+
+/// Returns whether the command called `name` can change the data.
+pub fn is_write_command(name: &[u8]) -> bool {
+    let name = String::from_utf8_lossy(name).to_lowercase();
+    WRITE_COMMANDS.contains(&name.as_str())
+}
+
+/// Returns the keys among the arguments of a request, which must all be served by this node in
+/// cluster mode.
+pub fn command_keys(tokens: &[Vec<u8>]) -> Vec<&[u8]> {
+    let name = String::from_utf8_lossy(&tokens[0]).to_lowercase();
+    // The positions of the first and last key, counting from the end if negative, and the step
+    // between keys.
+    let (first, last, step): (usize, isize, usize) = match name.as_str() {
+        "mget" | "sdiff" | "del" | "unlink" | "exists" | "touch" | "watch" => (1, -1, 1),
+        "mset" => (1, -1, 2),
+        "rename" | "renamenx" | "copy" => (1, 2, 1),
+        "keys" | "scan" => return Vec::new(),
+        // MIGRATE host port key db timeout, or with an empty key and the keys after a KEYS option.
+        "migrate" => match tokens.get(3) {
+            Some(key) if key.is_empty() => {
+                let keys = tokens.iter().position(|t| t.eq_ignore_ascii_case(b"keys"));
+                return match keys {
+                    Some(pos) if pos > 5 => {
+                        tokens[pos + 1..].iter().map(|t| t.as_slice()).collect()
+                    }
+                    _ => Vec::new(),
+                };
+            }
+            _ => (3, 3, 1),
+        },
+        "move" => (1, 1, 1),
+        // EVAL script numkeys key ... arg ..., and the same with the digest of the script.
+        "eval" | "evalsha" => {
+            let numkeys = match tokens.get(2) {
+                Some(v) => String::from_utf8_lossy(v).parse::<usize>().unwrap_or(0),
+                None => 0,
+            };
+            return tokens
+                .iter()
+                .skip(3)
+                .take(numkeys)
+                .map(|t| t.as_slice())
+                .collect();
+        }
+        s if [
+            STRING_COMMANDS,
+            LIST_COMMANDS,
+            SET_COMMANDS,
+            HASH_COMMANDS,
+            SORTED_SET_COMMANDS,
+            STREAM_COMMANDS,
+            KEYSPACE_COMMANDS,
+        ]
+        .iter()
+        .any(|commands| commands.contains(&s)) =>
+        {
+            (1, 1, 1)
+        }
+        _ => return Vec::new(),
+    };
+    let last = match last < 0 {
+        true => tokens.len() as isize + last,
+        false => last,
+    };
+    tokens
+        .iter()
+        .enumerate()
+        .take(last.max(0) as usize + 1)
+        .skip(first)
+        .step_by(step)
+        .map(|(_, token)| token.as_slice())
+        .collect()
+}
 
 impl FromStr for CommandType {
     type Err = ();
@@ -84,6 +352,15 @@ impl FromStr for CommandType {
             s if STREAM_COMMANDS.contains(&s) => {
                 Ok(CommandType::Stream(StreamCommandType::from_str(s)?))
             }
+            s if KEYSPACE_COMMANDS.contains(&s) => {
+                Ok(CommandType::Keyspace(KeyspaceCommandType::from_str(s)?))
+            }
+            s if CONNECTION_COMMANDS.contains(&s) => {
+                Ok(CommandType::Connection(ConnectionCommandType::from_str(s)?))
+            }
+            s if SERVER_COMMANDS.contains(&s) => {
+                Ok(CommandType::Server(ServerCommandType::from_str(s)?))
+            }
             _ => Err(()),
         }
     }
@@ -134,6 +411,7 @@ impl FromStr for SetCommandType {
             "sismember" => Ok(SetCommandType::IsMember),
             "scard" => Ok(SetCommandType::Card),
             "sdiff" => Ok(SetCommandType::Diff),
+            "sscan" => Ok(SetCommandType::Scan),
             _ => Err(()),
         }
     }
@@ -148,6 +426,7 @@ impl FromStr for HashCommandType {
             "hget" => Ok(HashCommandType::Get),
             "hgetall" => Ok(HashCommandType::GetAll),
             "hincrby" => Ok(HashCommandType::IncrBy),
+            "hscan" => Ok(HashCommandType::Scan),
             _ => Err(()),
         }
     }
@@ -162,6 +441,7 @@ impl FromStr for SortedSetCommandType {
             "zrange" => Ok(SortedSetCommandType::Range),
             "zrem" => Ok(SortedSetCommandType::Rem),
             "zrank" => Ok(SortedSetCommandType::Rank),
+            "zscan" => Ok(SortedSetCommandType::Scan),
             _ => Err(()),
         }
     }
@@ -177,3 +457,86 @@ impl FromStr for StreamCommandType {
         }
     }
 }
+
+impl FromStr for KeyspaceCommandType {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<KeyspaceCommandType, Self::Err> {
+        match s {
+            "del" => Ok(KeyspaceCommandType::Del),
+            "unlink" => Ok(KeyspaceCommandType::Unlink),
+            "exists" => Ok(KeyspaceCommandType::Exists),
+            "touch" => Ok(KeyspaceCommandType::Touch),
+            "type" => Ok(KeyspaceCommandType::Type),
+            "rename" => Ok(KeyspaceCommandType::Rename),
+            "renamenx" => Ok(KeyspaceCommandType::RenameNx),
+            "keys" => Ok(KeyspaceCommandType::Keys),
+            "scan" => Ok(KeyspaceCommandType::Scan),
+            "expire" => Ok(KeyspaceCommandType::Expire),
+            "pexpire" => Ok(KeyspaceCommandType::PExpire),
+            "expireat" => Ok(KeyspaceCommandType::ExpireAt),
+            "pexpireat" => Ok(KeyspaceCommandType::PExpireAt),
+            "ttl" => Ok(KeyspaceCommandType::Ttl),
+            "pttl" => Ok(KeyspaceCommandType::PTtl),
+            "persist" => Ok(KeyspaceCommandType::Persist),
+            "dump" => Ok(KeyspaceCommandType::Dump),
+            "restore" => Ok(KeyspaceCommandType::Restore),
+            "restore-asking" => Ok(KeyspaceCommandType::RestoreAsking),
+            _ => Err(()),
+        }
+    }
+}
+
+impl FromStr for ConnectionCommandType {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<ConnectionCommandType, Self::Err> {
+        match s {
+            "hello" => Ok(ConnectionCommandType::Hello),
+            "quit" => Ok(ConnectionCommandType::Quit),
+            _ => Err(()),
+        }
+    }
+}
+
+impl FromStr for ServerCommandType {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<ServerCommandType, Self::Err> {
+        match s {
+            "select" => Ok(ServerCommandType::Select),
+            "move" => Ok(ServerCommandType::Move),
+            "copy" => Ok(ServerCommandType::Copy),
+            "swapdb" => Ok(ServerCommandType::SwapDb),
+            "flushdb" => Ok(ServerCommandType::FlushDb),
+            "flushall" => Ok(ServerCommandType::FlushAll),
+            "dbsize" => Ok(ServerCommandType::DbSize),
+            "save" => Ok(ServerCommandType::Save),
+            "bgsave" => Ok(ServerCommandType::BgSave),
+            "lastsave" => Ok(ServerCommandType::LastSave),
+            "bgrewriteaof" => Ok(ServerCommandType::BgRewriteAof),
+            "replicaof" | "slaveof" => Ok(ServerCommandType::ReplicaOf),
+            "replconf" => Ok(ServerCommandType::ReplConf),
+            "psync" => Ok(ServerCommandType::Psync),
+            "info" => Ok(ServerCommandType::Info),
+            "cluster" => Ok(ServerCommandType::Cluster),
+            "asking" => Ok(ServerCommandType::Asking),
+            "migrate" => Ok(ServerCommandType::Migrate),
+            "multi" => Ok(ServerCommandType::Multi),
+            "exec" => Ok(ServerCommandType::Exec),
+            "discard" => Ok(ServerCommandType::Discard),
+            "watch" => Ok(ServerCommandType::Watch),
+            "unwatch" => Ok(ServerCommandType::Unwatch),
+            "eval" => Ok(ServerCommandType::Eval),
+            "evalsha" => Ok(ServerCommandType::EvalSha),
+            "script" => Ok(ServerCommandType::Script),
+            "subscribe" => Ok(ServerCommandType::Subscribe),
+            "unsubscribe" => Ok(ServerCommandType::Unsubscribe),
+            "psubscribe" => Ok(ServerCommandType::PSubscribe),
+            "punsubscribe" => Ok(ServerCommandType::PUnsubscribe),
+            "publish" => Ok(ServerCommandType::Publish),
+            "pubsub" => Ok(ServerCommandType::PubSub),
+            _ => Err(()),
+        }
+    }
+}
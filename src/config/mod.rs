@@ -0,0 +1,554 @@
+use crate::error::ConfigError;
+use crate::utils::split_args;
+
+/// When to flush the append-only file to disk.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum AppendFsync {
+    /// After every write, which is the safest but slowest.
+    Always,
+    /// Once per second, so that at most a second of writes can be lost.
+    EverySec,
+    /// Whenever the operating system decides to.
+    No,
+}
+
+/// The file format snapshots are saved in. Either format is accepted when loading.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum SnapshotFormat {
+    /// The format of this server.
+    Native,
+    /// The RDB format of upstream Redis, which Redis can load.
+    Redis,
+}
+
+/// Save a snapshot once at least `changes` writes happened within `seconds`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SaveRule {
+    pub seconds: u64,
+    pub changes: u64,
+}
+
+/// Settings the server is started with. They are read from a `redis.conf`-style file, one
+/// directive per line, and from command-line options which override the file.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ServerConfig {
+    /// The addresses to listen on. An address prefixed with `-` is skipped if it is unavailable
+    /// instead of failing the startup.
+    pub bind: Vec<String>,
+    /// The TCP port to listen on, or 0 to not listen on TCP at all.
+    pub port: u16,
+    /// The path of a Unix domain socket to listen on in addition to TCP.
+    pub unixsocket: Option<String>,
+    /// The permissions of the Unix domain socket file, e.g. `0o700`.
+    pub unixsocketperm: Option<u32>,
+    /// The number of logical databases clients can `SELECT` from.
+    pub databases: usize,
+    /// The directory snapshots are written to.
+    pub dir: String,
+    /// The file name of snapshots within `dir`.
+    pub dbfilename: String,
+    /// When to save snapshots automatically. Snapshots are only saved on demand if there is none.
+    pub save: Vec<SaveRule>,
+    pub snapshot_format: SnapshotFormat,
+    /// Whether to log every write to an append-only file, which is replayed on startup instead of
+    /// loading the snapshot.
+    pub appendonly: bool,
+    /// The file name of the append-only file within `dir`.
+    pub appendfilename: String,
+    pub appendfsync: AppendFsync,
+    /// The master to replicate from on startup, as a host and port.
+    pub replicaof: Option<(String, u16)>,
+    /// Whether replicas reject writes from their own clients.
+    pub replica_read_only: bool,
+    /// How many bytes of the replication stream are kept for replicas to resume from.
+    pub repl_backlog_size: usize,
+    /// Whether the server is a node of a cluster, which serves only the keys of its hash slots.
+    pub cluster_enabled: bool,
+    /// The file name within `dir` where the node keeps its view of the cluster.
+    pub cluster_config_file: String,
+    /// The address other nodes and clients reach this node at. Defaults to the first `bind`
+    /// address.
+    pub cluster_announce_ip: Option<String>,
+    /// The port of the cluster bus, or 0 for `port` + 10000.
+    pub cluster_port: u16,
+    /// How long a node may not answer pings before it is considered failing, in milliseconds.
+    pub cluster_node_timeout: u64,
+    /// How long a script may run, in milliseconds, before other clients are told the server is
+    /// busy and the script can be stopped with `SCRIPT KILL`.
+    pub lua_time_limit: u64,
+    /// How many bytes of messages may be queued for a subscriber that does not read them before
+    /// it is disconnected, or 0 for no limit. This is the hard limit of
+    /// `client-output-buffer-limit pubsub`, whose soft limit is not applied.
+    pub pubsub_output_buffer_limit: usize,
+}
+
+impl Default for ServerConfig {
+    fn default() -> Self {
+        ServerConfig {
+            bind: vec!["127.0.0.1".to_string()],
+            port: 6379,
+            unixsocket: None,
+            unixsocketperm: None,
+            databases: 16,
+            dir: ".".to_string(),
+            dbfilename: "dump.rdb".to_string(),
+            save: vec![
+                SaveRule {
+                    seconds: 3600,
+                    changes: 1,
+                },
+                SaveRule {
+                    seconds: 300,
+                    changes: 100,
+                },
+                SaveRule {
+                    seconds: 60,
+                    changes: 10000,
+                },
+            ],
+            snapshot_format: SnapshotFormat::Native,
+            appendonly: false,
+            appendfilename: "appendonly.aof".to_string(),
+            appendfsync: AppendFsync::EverySec,
+            replicaof: None,
+            replica_read_only: true,
+            repl_backlog_size: 1024 * 1024,
+            cluster_enabled: false,
+            cluster_config_file: "nodes.conf".to_string(),
+            cluster_announce_ip: None,
+            cluster_port: 0,
+            cluster_node_timeout: 15000,
+            lua_time_limit: 5000,
+            pubsub_output_buffer_limit: 32 * 1024 * 1024,
+        }
+    }
+}
+
+impl ServerConfig {
+    /// Builds the config from command-line arguments, excluding the program name. They take the
+    /// form `[config-file] [--directive arg...]...`, e.g. `redis.conf --port 6380 --bind ::1`.
+    pub fn from_args(args: &[String]) -> Result<Self, ConfigError> {
+        let mut config = ServerConfig::default();
+        let mut args = args.iter().peekable();
+        if let Some(path) = args.next_if(|a| !a.starts_with("--")) {
+            let text = std::fs::read_to_string(path)
+                .map_err(|e| ConfigError::ReadFailed(path.clone(), e.to_string()))?;
+            config.load_str(&text)?;
+        }
+        while let Some(arg) = args.next() {
+            let Some(directive) = arg.strip_prefix("--") else {
+                return Err(ConfigError::InvalidOption(arg.clone()));
+            };
+            let mut values = Vec::new();
+            while let Some(v) = args.next_if(|a| !a.starts_with("--")) {
+                values.push(v.clone());
+            }
+            config
+                .apply(directive, &values)
+                .map_err(|_| ConfigError::InvalidOption(arg.clone()))?;
+        }
+        Ok(config)
+    }
+
+    /// Applies every directive of a config file on top of the current settings.
+    pub fn load_str(&mut self, text: &str) -> Result<(), ConfigError> {
+        for (i, line) in text.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let invalid = |details: String| ConfigError::InvalidDirective(i + 1, details);
+            let tokens = split_args(line.as_bytes()).map_err(|e| invalid(e.to_string()))?;
+            let tokens = tokens
+                .into_iter()
+                .map(|t| String::from_utf8(t).map_err(|e| invalid(e.to_string())))
+                .collect::<Result<Vec<_>, _>>()?;
+            self.apply(&tokens[0], &tokens[1..]).map_err(invalid)?;
+        }
+        Ok(())
+    }
+
+    fn apply(&mut self, directive: &str, args: &[String]) -> Result<(), String> {
+        match (directive.to_lowercase().as_str(), args) {
+            ("bind", [_, ..]) => self.bind = args.to_vec(),
+            ("port", [v]) => self.port = v.parse().map_err(|_| format!("invalid port `{}`", v))?,
+            ("unixsocket", [v]) => self.unixsocket = Some(v.clone()),
+            ("unixsocketperm", [v]) => {
+                self.unixsocketperm = Some(
+                    u32::from_str_radix(v, 8)
+                        .map_err(|_| format!("invalid unixsocketperm `{}`", v))?,
+                )
+            }
+            ("databases", [v]) => {
+                self.databases = match v.parse() {
+                    Ok(n) if n >= 1 => n,
+                    _ => return Err(format!("invalid databases `{}`", v)),
+                }
+            }
+            ("dir", [v]) => self.dir = v.clone(),
+            ("dbfilename", [v]) => self.dbfilename = v.clone(),
+            // `save ""` disables automatic snapshots.
+            ("save", [v]) if v.is_empty() => self.save = Vec::new(),
+            ("save", [_, _, ..]) if args.len().is_multiple_of(2) => {
+                let parse = |v: &String| {
+                    v.parse::<u64>()
+                        .map_err(|_| format!("invalid save parameter `{}`", v))
+                };
+                self.save = args
+                    .chunks(2)
+                    .map(|rule| {
+                        Ok(SaveRule {
+                            seconds: parse(&rule[0])?,
+                            changes: parse(&rule[1])?,
+                        })
+                    })
+                    .collect::<Result<_, String>>()?;
+            }
+            ("snapshot-format", [v]) => {
+                self.snapshot_format = match v.to_lowercase().as_str() {
+                    "native" => SnapshotFormat::Native,
+                    "redis" => SnapshotFormat::Redis,
+                    _ => return Err(format!("invalid snapshot-format `{}`", v)),
+                }
+            }
+            ("appendonly", [v]) => {
+                self.appendonly = match v.to_lowercase().as_str() {
+                    "yes" => true,
+                    "no" => false,
+                    _ => return Err(format!("invalid appendonly `{}`", v)),
+                }
+            }
+            ("appendfilename", [v]) => self.appendfilename = v.clone(),
+            ("appendfsync", [v]) => {
+                self.appendfsync = match v.to_lowercase().as_str() {
+                    "always" => AppendFsync::Always,
+                    "everysec" => AppendFsync::EverySec,
+                    "no" => AppendFsync::No,
+                    _ => return Err(format!("invalid appendfsync `{}`", v)),
+                }
+            }
+            ("replicaof" | "slaveof", [host, port]) => {
+                let port = port
+                    .parse()
+                    .map_err(|_| format!("invalid replicaof port `{}`", port))?;
+                self.replicaof = Some((host.clone(), port));
+            }
+            ("replica-read-only" | "slave-read-only", [v]) => {
+                self.replica_read_only = match v.to_lowercase().as_str() {
+                    "yes" => true,
+                    "no" => false,
+                    _ => return Err(format!("invalid replica-read-only `{}`", v)),
+                }
+            }
+            ("repl-backlog-size", [v]) => {
+                self.repl_backlog_size =
+                    parse_memory(v).ok_or_else(|| format!("invalid repl-backlog-size `{}`", v))?
+            }
+            ("cluster-enabled", [v]) => {
+                self.cluster_enabled = match v.to_lowercase().as_str() {
+                    "yes" => true,
+                    "no" => false,
+                    _ => return Err(format!("invalid cluster-enabled `{}`", v)),
+                }
+            }
+            ("cluster-config-file", [v]) => self.cluster_config_file = v.clone(),
+            ("cluster-announce-ip", [v]) => self.cluster_announce_ip = Some(v.clone()),
+            ("cluster-port", [v]) => {
+                self.cluster_port = v
+                    .parse()
+                    .map_err(|_| format!("invalid cluster-port `{}`", v))?
+            }
+            ("cluster-node-timeout", [v]) => {
+                self.cluster_node_timeout = match v.parse() {
+                    Ok(n) if n > 0 => n,
+                    _ => return Err(format!("invalid cluster-node-timeout `{}`", v)),
+                }
+            }
+            ("lua-time-limit" | "busy-reply-threshold", [v]) => {
+                self.lua_time_limit = match v.parse() {
+                    Ok(n) => n,
+                    _ => return Err(format!("invalid {} `{}`", directive, v)),
+                }
+            }
+            ("client-output-buffer-limit", [class, hard, soft, seconds]) => {
+                let hard = parse_memory(hard)
+                    .ok_or_else(|| format!("invalid client-output-buffer-limit `{}`", hard))?;
+                if parse_memory(soft).is_none() || seconds.parse::<u64>().is_err() {
+                    return Err(format!(
+                        "invalid client-output-buffer-limit `{} {}`",
+                        soft, seconds
+                    ));
+                }
+                match class.to_lowercase().as_str() {
+                    "pubsub" => self.pubsub_output_buffer_limit = hard,
+                    _ => {
+                        return Err(format!(
+                            "invalid client-output-buffer-limit class `{}`",
+                            class
+                        ))
+                    }
+                }
+            }
+            _ => {
+                return Err(format!(
+                    "bad directive or wrong number of arguments `{}`",
+                    directive
+                ))
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Parses an amount of memory such as `1mb`, in bytes.
+fn parse_memory(value: &str) -> Option<usize> {
+    let value = value.to_lowercase();
+    let digits = value.trim_end_matches(|c: char| c.is_ascii_alphabetic());
+    let unit = match &value[digits.len()..] {
+        "" | "b" => 1,
+        "k" => 1000,
+        "kb" => 1024,
+        "m" => 1000 * 1000,
+        "mb" => 1024 * 1024,
+        "g" => 1000 * 1000 * 1000,
+        "gb" => 1024 * 1024 * 1024,
+        _ => return None,
+    };
+    digits.parse::<usize>().ok()?.checked_mul(unit)
+}
+
+#[cfg(test)]
+mod test {
+    use super::{AppendFsync, SaveRule, ServerConfig, SnapshotFormat};
+
+    fn args(values: &[&str]) -> Vec<String> {
+        values.iter().map(|v| v.to_string()).collect()
+    }
+
+    #[test]
+    fn should_listen_on_localhost_by_default() {
+        let config = ServerConfig::from_args(&[]).unwrap();
+        assert_eq!(config.bind, vec!["127.0.0.1".to_string()]);
+        assert_eq!(config.port, 6379);
+        assert_eq!(config.unixsocket, None);
+        assert_eq!(config.databases, 16);
+    }
+
+    #[test]
+    fn should_read_command_line_options() {
+        let config =
+            ServerConfig::from_args(&args(&["--port", "6380", "--bind", "0.0.0.0", "::1"]))
+                .unwrap();
+        assert_eq!(config.bind, args(&["0.0.0.0", "::1"]));
+        assert_eq!(config.port, 6380);
+
+        let err = ServerConfig::from_args(&args(&["--port", "abc"]))
+            .err()
+            .unwrap();
+        assert_eq!(err.to_string(), "invalid option `--port`".to_string());
+        let err = ServerConfig::from_args(&args(&["--port"])).err().unwrap();
+        assert_eq!(err.to_string(), "invalid option `--port`".to_string());
+    }
+
+    #[test]
+    fn should_read_config_file() {
+        let mut config = ServerConfig::default();
+        config
+            .load_str("# comment\n\nPORT 7000\n  bind 10.0.0.1 \"-::1\"\n")
+            .unwrap();
+        assert_eq!(config.bind, args(&["10.0.0.1", "-::1"]));
+        assert_eq!(config.port, 7000);
+        assert_eq!(config.lua_time_limit, 5000);
+        config.load_str("busy-reply-threshold 100\n").unwrap();
+        assert_eq!(config.lua_time_limit, 100);
+
+        let err = config.load_str("port 1\nunknown yes\n").err().unwrap();
+        assert_eq!(
+            err.to_string(),
+            "invalid config at line 2: bad directive or wrong number of arguments `unknown`"
+                .to_string()
+        );
+    }
+
+    #[test]
+    fn should_read_unix_socket_options() {
+        let config = ServerConfig::from_args(&args(&[
+            "--port",
+            "0",
+            "--unixsocket",
+            "/tmp/redis.sock",
+            "--unixsocketperm",
+            "770",
+        ]))
+        .unwrap();
+        assert_eq!(config.port, 0);
+        assert_eq!(config.unixsocket, Some("/tmp/redis.sock".to_string()));
+        assert_eq!(config.unixsocketperm, Some(0o770));
+        let err = ServerConfig::from_args(&args(&["--unixsocketperm", "999"]))
+            .err()
+            .unwrap();
+        assert_eq!(
+            err.to_string(),
+            "invalid option `--unixsocketperm`".to_string()
+        );
+    }
+
+    #[test]
+    fn should_let_command_line_override_config_file() {
+        let path = std::env::temp_dir().join(format!("redis-rust-{}.conf", std::process::id()));
+        std::fs::write(&path, "port 7000\nbind 10.0.0.1\n").unwrap();
+        let config =
+            ServerConfig::from_args(&args(&[path.to_str().unwrap(), "--port", "7001"])).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(config.bind, args(&["10.0.0.1"]));
+        assert_eq!(config.port, 7001);
+
+        let err = ServerConfig::from_args(&args(&["/nonexistent/redis.conf"]))
+            .err()
+            .unwrap();
+        assert!(err
+            .to_string()
+            .starts_with("failed to read config file `/nonexistent/redis.conf`"));
+    }
+
+    #[test]
+    fn should_read_number_of_databases() {
+        let mut config = ServerConfig::default();
+        config.load_str("databases 4\n").unwrap();
+        assert_eq!(config.databases, 4);
+        let err = config.load_str("databases 0\n").err().unwrap();
+        assert_eq!(
+            err.to_string(),
+            "invalid config at line 1: invalid databases `0`".to_string()
+        );
+    }
+
+    #[test]
+    fn should_read_snapshot_options() {
+        let mut config = ServerConfig::default();
+        assert_eq!(config.save.len(), 3);
+        config
+            .load_str("dir /var/lib/redis\ndbfilename snap.rdb\nsave 900 1 60 1000\n")
+            .unwrap();
+        assert_eq!(config.dir, "/var/lib/redis".to_string());
+        assert_eq!(config.dbfilename, "snap.rdb".to_string());
+        assert_eq!(
+            config.save,
+            vec![
+                SaveRule {
+                    seconds: 900,
+                    changes: 1
+                },
+                SaveRule {
+                    seconds: 60,
+                    changes: 1000
+                }
+            ]
+        );
+        config.load_str("save \"\"\n").unwrap();
+        assert!(config.save.is_empty());
+
+        let err = config.load_str("save 900\n").err().unwrap();
+        assert_eq!(
+            err.to_string(),
+            "invalid config at line 1: bad directive or wrong number of arguments `save`"
+                .to_string()
+        );
+        let err = config.load_str("save 900 x\n").err().unwrap();
+        assert_eq!(
+            err.to_string(),
+            "invalid config at line 1: invalid save parameter `x`".to_string()
+        );
+
+        assert_eq!(config.snapshot_format, SnapshotFormat::Native);
+        config.load_str("snapshot-format redis\n").unwrap();
+        assert_eq!(config.snapshot_format, SnapshotFormat::Redis);
+        assert!(config.load_str("snapshot-format rdb\n").is_err());
+    }
+
+    #[test]
+    fn should_read_append_only_options() {
+        let config = ServerConfig::from_args(&args(&[
+            "--appendonly",
+            "yes",
+            "--appendfsync",
+            "always",
+            "--appendfilename",
+            "log.aof",
+        ]))
+        .unwrap();
+        assert!(config.appendonly);
+        assert_eq!(config.appendfsync, AppendFsync::Always);
+        assert_eq!(config.appendfilename, "log.aof".to_string());
+        assert_eq!(ServerConfig::default().appendfsync, AppendFsync::EverySec);
+        let err = ServerConfig::from_args(&args(&["--appendfsync", "sometimes"]))
+            .err()
+            .unwrap();
+        assert_eq!(
+            err.to_string(),
+            "invalid option `--appendfsync`".to_string()
+        );
+    }
+
+    #[test]
+    fn should_read_replication_options() {
+        let mut config = ServerConfig::default();
+        assert_eq!(config.replicaof, None);
+        assert!(config.replica_read_only);
+        config
+            .load_str("replicaof 10.0.0.1 6380\nreplica-read-only no\nrepl-backlog-size 2mb\n")
+            .unwrap();
+        assert_eq!(config.replicaof, Some(("10.0.0.1".to_string(), 6380)));
+        assert!(!config.replica_read_only);
+        assert_eq!(config.repl_backlog_size, 2 * 1024 * 1024);
+        config.load_str("repl-backlog-size 1000\n").unwrap();
+        assert_eq!(config.repl_backlog_size, 1000);
+        let err = config.load_str("repl-backlog-size 1xb\n").err().unwrap();
+        assert_eq!(
+            err.to_string(),
+            "invalid config at line 1: invalid repl-backlog-size `1xb`".to_string()
+        );
+    }
+
+    #[test]
+    fn should_read_cluster_options() {
+        let mut config = ServerConfig::default();
+        assert!(!config.cluster_enabled);
+        config
+            .load_str("cluster-enabled yes\ncluster-config-file node-7000.conf\n")
+            .unwrap();
+        assert!(config.cluster_enabled);
+        assert_eq!(config.cluster_config_file, "node-7000.conf".to_string());
+        assert_eq!(config.cluster_node_timeout, 15000);
+        config
+            .load_str("cluster-node-timeout 5000\ncluster-port 17100\n")
+            .unwrap();
+        assert_eq!(config.cluster_node_timeout, 5000);
+        assert_eq!(config.cluster_port, 17100);
+        assert!(config.load_str("cluster-node-timeout 0\n").is_err());
+        let err = config.load_str("cluster-enabled maybe\n").err().unwrap();
+        assert_eq!(
+            err.to_string(),
+            "invalid config at line 1: invalid cluster-enabled `maybe`".to_string()
+        );
+    }
+
+    #[test]
+    fn should_read_output_buffer_limits() {
+        let mut config = ServerConfig::default();
+        assert_eq!(config.pubsub_output_buffer_limit, 32 * 1024 * 1024);
+        config
+            .load_str("client-output-buffer-limit pubsub 1mb 256kb 60\n")
+            .unwrap();
+        assert_eq!(config.pubsub_output_buffer_limit, 1024 * 1024);
+        let err = config
+            .load_str("client-output-buffer-limit normal 0 0 0\n")
+            .err()
+            .unwrap();
+        assert_eq!(
+            err.to_string(),
+            "invalid config at line 1: invalid client-output-buffer-limit class `normal`"
+                .to_string()
+        );
+    }
+}
@@ -0,0 +1,131 @@
+use super::scan::ScanMap;
+use std::ops::Deref;
+
+/// The members of a set, which `SSCAN` goes through with a cursor.
+#[derive(Default, Clone)]
+pub struct RedisSet {
+    members: ScanMap<()>,
+}
+
+impl RedisSet {
+    /// Adds `member`, returning false if it was already in the set.
+    pub fn insert(&mut self, member: Vec<u8>) -> bool {
+        self.members.insert(member, ()).is_none()
+    }
+
+    pub fn remove(&mut self, member: &[u8]) -> bool {
+        self.members.remove(member).is_some()
+    }
+
+    pub fn contains(&self, member: &[u8]) -> bool {
+        self.members.contains_key(member)
+    }
+
+    pub fn len(&self) -> usize {
+        self.members.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.members.is_empty()
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &Vec<u8>> {
+        self.members.keys()
+    }
+
+    /// Returns about `count` members from `cursor` on, along with the cursor to continue from,
+    /// which is 0 once every member has been returned.
+    pub fn scan(&self, cursor: u64, count: usize) -> (Vec<&[u8]>, u64) {
+        let (members, cursor) = self.members.scan(cursor, count);
+        (
+            members.into_iter().map(|(member, _)| member).collect(),
+            cursor,
+        )
+    }
+}
+
+/// A field of a hash along with its value.
+pub type HashPair<'a> = (&'a [u8], &'a [u8]);
+
+/// The fields of a hash, which `HSCAN` goes through with a cursor. Reads go straight to the
+/// underlying map, while changes go through `insert` and `remove`.
+#[derive(Default, Clone)]
+pub struct RedisHash {
+    fields: ScanMap<Vec<u8>>,
+}
+
+impl Deref for RedisHash {
+    type Target = ScanMap<Vec<u8>>;
+
+    fn deref(&self) -> &Self::Target {
+        &self.fields
+    }
+}
+
+impl RedisHash {
+    /// Sets `field` to `value`, returning the value it replaced.
+    pub fn insert(&mut self, field: Vec<u8>, value: Vec<u8>) -> Option<Vec<u8>> {
+        self.fields.insert(field, value)
+    }
+
+    pub fn remove(&mut self, field: &[u8]) -> Option<Vec<u8>> {
+        self.fields.remove(field)
+    }
+
+    /// Returns about `count` fields and their values from `cursor` on, along with the cursor to
+    /// continue from, which is 0 once every field has been returned.
+    pub fn scan(&self, cursor: u64, count: usize) -> (Vec<HashPair<'_>>, u64) {
+        let (fields, cursor) = self.fields.scan(cursor, count);
+        let pairs = fields
+            .into_iter()
+            .map(|(field, value)| (field, value.as_slice()))
+            .collect();
+        (pairs, cursor)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{RedisHash, RedisSet};
+    use std::collections::HashSet;
+
+    #[test]
+    fn should_add_and_remove_set_members() {
+        let mut set = RedisSet::default();
+        assert!(set.insert(b"a".to_vec()));
+        assert!(set.insert(b"b".to_vec()));
+        assert!(!set.insert(b"a".to_vec()));
+        assert!(set.remove(b"b"));
+        assert!(!set.remove(b"b"));
+        assert_eq!(set.len(), 1);
+        assert_eq!(set.scan(0, 10), (vec![b"a".as_slice()], 0));
+    }
+
+    #[test]
+    fn should_scan_hash_fields_with_values() {
+        let mut hash = RedisHash::default();
+        for i in 0..20 {
+            hash.insert(format!("f{}", i).into_bytes(), b"old".to_vec());
+        }
+        assert_eq!(
+            hash.insert(b"f0".to_vec(), b"new".to_vec()),
+            Some(b"old".to_vec())
+        );
+        assert_eq!(hash.remove(b"f1"), Some(b"old".to_vec()));
+
+        let mut seen = HashSet::new();
+        let mut cursor = 0;
+        loop {
+            let (pairs, next) = hash.scan(cursor, 3);
+            for (field, value) in pairs {
+                assert_eq!(value, hash.get(field).unwrap().as_slice());
+                assert!(seen.insert(field.to_vec()));
+            }
+            if next == 0 {
+                break;
+            }
+            cursor = next;
+        }
+        assert_eq!(seen.len(), 19);
+    }
+}
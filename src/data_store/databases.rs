@@ -0,0 +1,177 @@
+use super::{DataStore, RedisEntry};
+
+/// The data a snapshot is written from: the keys of every database along with their value and
+/// expiry deadline, including expired keys that have not been evicted yet.
+pub trait Dataset {
+    /// The number of databases.
+    fn count(&self) -> usize;
+
+    fn entries(&self, index: usize) -> impl Iterator<Item = (&[u8], &RedisEntry, Option<u64>)>;
+}
+
+/// A copy of the keys of every database, which a background thread writes out while the
+/// databases keep changing. Only the values and their expiry deadlines are copied, and none of
+/// the indexes kept alongside them.
+pub struct Snapshot {
+    dbs: Vec<Vec<SnapshotEntry>>,
+}
+
+/// A key of a snapshot, along with its value and expiry deadline.
+type SnapshotEntry = (Vec<u8>, RedisEntry, Option<u64>);
+
+impl std::fmt::Debug for Snapshot {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let keys: Vec<usize> = self.dbs.iter().map(|db| db.len()).collect();
+        f.debug_struct("Snapshot").field("keys", &keys).finish()
+    }
+}
+
+impl Dataset for Snapshot {
+    fn count(&self) -> usize {
+        self.dbs.len()
+    }
+
+    fn entries(&self, index: usize) -> impl Iterator<Item = (&[u8], &RedisEntry, Option<u64>)> {
+        self.dbs[index]
+            .iter()
+            .map(|(key, entry, deadline)| (key.as_slice(), entry, *deadline))
+    }
+}
+
+/// The logical databases of the server, which clients switch between with `SELECT`. Each one is
+/// an independent keyspace.
+pub struct Databases {
+    dbs: Vec<DataStore>,
+}
+
+impl Databases {
+    pub fn new(count: usize) -> Self {
+        Databases {
+            dbs: (0..count).map(|_| DataStore::new()).collect(),
+        }
+    }
+
+    /// The number of databases, which never changes after startup.
+    pub fn count(&self) -> usize {
+        self.dbs.len()
+    }
+
+    /// Returns the database at `index`, which must be lower than `count()`.
+    pub fn db(&self, index: usize) -> &DataStore {
+        &self.dbs[index]
+    }
+
+    /// Returns the database at `index`, which must be lower than `count()`.
+    pub fn db_mut(&mut self, index: usize) -> &mut DataStore {
+        &mut self.dbs[index]
+    }
+
+    /// Returns two distinct databases at once, e.g. to move a key from one to the other.
+    pub fn pair_mut(&mut self, first: usize, second: usize) -> (&mut DataStore, &mut DataStore) {
+        assert_ne!(first, second);
+        if first < second {
+            let (left, right) = self.dbs.split_at_mut(second);
+            (&mut left[first], &mut right[0])
+        } else {
+            let (left, right) = self.dbs.split_at_mut(first);
+            (&mut right[0], &mut left[second])
+        }
+    }
+
+    /// Swaps the contents of two databases, so that clients connected to one of them see the data
+    /// of the other right away. The keys watched in either database are then seen as changed.
+    pub fn swap(&mut self, first: usize, second: usize) {
+        if first == second {
+            return;
+        }
+        self.dbs.swap(first, second);
+        let (first, second) = self.pair_mut(first, second);
+        std::mem::swap(&mut first.watched, &mut second.watched);
+        first.touch_all();
+        second.touch_all();
+    }
+
+    /// Returns the number of changes made to the keys of every database so far.
+    pub fn changes(&self) -> u64 {
+        self.dbs.iter().map(|db| db.changes()).sum()
+    }
+
+    pub fn flush_all(&mut self) {
+        for db in &mut self.dbs {
+            db.flush();
+        }
+    }
+
+    /// Copies the keys of every database, to write them out without holding the databases.
+    pub fn snapshot(&self) -> Snapshot {
+        Snapshot {
+            dbs: self
+                .dbs
+                .iter()
+                .map(|db| {
+                    db.entries()
+                        .map(|(key, entry, deadline)| (key.to_vec(), entry.clone(), deadline))
+                        .collect()
+                })
+                .collect(),
+        }
+    }
+
+    /// Runs the active expiry cycle of every database. Returns the number of evicted keys.
+    pub fn expire_cycle(&mut self) -> usize {
+        self.dbs.iter_mut().map(|db| db.expire_cycle()).sum()
+    }
+}
+
+impl Dataset for Databases {
+    fn count(&self) -> usize {
+        self.dbs.len()
+    }
+
+    fn entries(&self, index: usize) -> impl Iterator<Item = (&[u8], &RedisEntry, Option<u64>)> {
+        self.dbs[index].entries()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{Databases, Dataset};
+
+    #[test]
+    fn should_keep_databases_independent() {
+        let mut dbs = Databases::new(3);
+        assert_eq!(dbs.count(), 3);
+        dbs.db_mut(0).set_string_overwrite(b"foo", b"0");
+        let (second, first) = dbs.pair_mut(2, 1);
+        second.set_string_overwrite(b"foo", b"2");
+        first.set_string_overwrite(b"bar", b"1");
+        assert_eq!(dbs.db_mut(0).len(), 1);
+        assert_eq!(dbs.db_mut(2).get_string(b"foo").unwrap().unwrap(), b"2");
+
+        dbs.swap(0, 2);
+        assert_eq!(dbs.db_mut(0).get_string(b"foo").unwrap().unwrap(), b"2");
+        assert_eq!(dbs.db_mut(2).get_string(b"foo").unwrap().unwrap(), b"0");
+
+        dbs.flush_all();
+        assert!((0..3).all(|i| dbs.db_mut(i).is_empty()));
+    }
+
+    #[test]
+    fn should_snapshot_keys_at_one_point_in_time() {
+        let mut dbs = Databases::new(2);
+        dbs.db_mut(1).set_string_overwrite(b"foo", b"1");
+        dbs.db_mut(1).set_expire_at(b"foo", 4_000_000_000_000);
+        let snapshot = dbs.snapshot();
+        dbs.db_mut(1).set_string_overwrite(b"foo", b"2");
+        dbs.db_mut(0).set_string_overwrite(b"bar", b"3");
+
+        assert_eq!(snapshot.count(), 2);
+        assert_eq!(snapshot.entries(0).count(), 0);
+        let entries: Vec<_> = snapshot.entries(1).collect();
+        assert_eq!(entries.len(), 1);
+        let (key, entry, deadline) = entries[0];
+        assert_eq!(key, b"foo");
+        assert_eq!(entry.string.as_deref(), Some(&b"1"[..]));
+        assert_eq!(deadline, Some(4_000_000_000_000));
+    }
+}
@@ -0,0 +1,87 @@
+use rand::Rng;
+use std::collections::HashMap;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// Returns the current Unix time in milliseconds, which is how expiry deadlines are expressed.
+pub fn now_ms() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_millis() as u64)
+        .unwrap_or(0)
+}
+
+/// The expiry deadlines of the keys that have a TTL.
+///
+/// Keys are also kept in a vector so that the active expiry cycle can sample random keys in
+/// constant time; removal swaps the last key into the freed slot.
+#[derive(Default)]
+pub struct Expires {
+    deadlines: HashMap<Vec<u8>, (u64, usize)>,
+    keys: Vec<Vec<u8>>,
+}
+
+impl Expires {
+    pub fn get(&self, key: &[u8]) -> Option<u64> {
+        self.deadlines.get(key).map(|(deadline, _)| *deadline)
+    }
+
+    pub fn insert(&mut self, key: &[u8], deadline: u64) {
+        match self.deadlines.get_mut(key) {
+            Some(v) => v.0 = deadline,
+            None => {
+                self.deadlines
+                    .insert(key.to_vec(), (deadline, self.keys.len()));
+                self.keys.push(key.to_vec());
+            }
+        }
+    }
+
+    pub fn remove(&mut self, key: &[u8]) -> Option<u64> {
+        let (deadline, index) = self.deadlines.remove(key)?;
+        self.keys.swap_remove(index);
+        if let Some(moved) = self.keys.get(index) {
+            self.deadlines.get_mut(moved).unwrap().1 = index;
+        }
+        Some(deadline)
+    }
+
+    /// Picks a random key with a TTL along with its deadline.
+    pub fn sample(&self) -> Option<(&[u8], u64)> {
+        if self.keys.is_empty() {
+            return None;
+        }
+        let key = &self.keys[rand::thread_rng().gen_range(0..self.keys.len())];
+        Some((key, self.deadlines[key].0))
+    }
+
+    pub fn len(&self) -> usize {
+        self.keys.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.keys.is_empty()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::Expires;
+
+    #[test]
+    fn should_keep_index_consistent_on_removal() {
+        let mut expires = Expires::default();
+        expires.insert(b"a", 1);
+        expires.insert(b"b", 2);
+        expires.insert(b"c", 3);
+        expires.insert(b"a", 4);
+        assert_eq!(expires.len(), 3);
+        assert_eq!(expires.remove(b"a"), Some(4));
+        assert_eq!(expires.remove(b"a"), None);
+        assert_eq!(expires.get(b"c"), Some(3));
+        assert_eq!(expires.remove(b"c"), Some(3));
+        assert_eq!(expires.sample(), Some((b"b".as_slice(), 2)));
+        assert_eq!(expires.remove(b"b"), Some(2));
+        assert!(expires.is_empty());
+        assert_eq!(expires.sample(), None);
+    }
+}
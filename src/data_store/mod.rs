@@ -1,24 +1,297 @@
+mod collection;
+mod databases;
+mod expiry;
+mod scan;
 mod sorted_set;
 mod stream;
 
 use crate::error::{ExecutionError, InternalError};
-
-use sorted_set::SortedSet;
-use std::collections::{HashMap, HashSet, LinkedList};
+use crate::utils::glob_match;
+
+pub use collection::{RedisHash, RedisSet};
+pub use databases::{Databases, Dataset, Snapshot};
+pub use expiry::now_ms;
+use expiry::Expires;
+use scan::ScanMap;
+pub use sorted_set::SortedSet;
+use std::collections::{HashMap, LinkedList};
 use std::fmt::{Display, Formatter};
+use std::sync::atomic::{AtomicU64, Ordering};
+
+pub use self::stream::Stream;
+
+/// The number of keys with a TTL sampled by each round of the active expiry cycle.
+const EXPIRE_CYCLE_SAMPLE_SIZE: usize = 20;
+/// Bounds the time the active expiry cycle holds the data store for.
+const EXPIRE_CYCLE_MAX_ROUNDS: usize = 16;
 
-use self::stream::Stream;
+/// The last version given to a watched key. It is shared by every database, so that versions
+/// stay unique when `SWAPDB` exchanges their contents.
+static LAST_VERSION: AtomicU64 = AtomicU64::new(0);
 
 pub struct DataStore {
-    ds: HashMap<String, RedisEntry>,
+    /// The value of each key, which `SCAN` goes through with a cursor.
+    ds: ScanMap<RedisEntry>,
+    /// Expiry deadlines, in Unix milliseconds, of the keys that have a TTL.
+    expires: Expires,
+    /// The version of each key clients `WATCH`, which changes whenever the key does, along with
+    /// the number of clients watching it.
+    watched: HashMap<Vec<u8>, (u64, usize)>,
+    /// The number of changes made to the keys, which tells whether a command modified any.
+    changes: u64,
 }
 
 impl DataStore {
     pub fn new() -> Self {
-        Self { ds: HashMap::new() }
+        Self {
+            ds: ScanMap::default(),
+            expires: Expires::default(),
+            watched: HashMap::new(),
+            changes: 0,
+        }
+    }
+
+    /// Stores `entry` at `key`. Every key is added through here so that its watchers see it.
+    fn insert_entry(&mut self, key: &[u8], entry: RedisEntry) {
+        self.ds.insert(key.to_vec(), entry);
+        self.touch(key);
+    }
+
+    fn remove_entry(&mut self, key: &[u8]) -> Option<RedisEntry> {
+        let entry = self.ds.remove(key)?;
+        self.touch(key);
+        Some(entry)
+    }
+
+    /// Starts watching `key` for changes. Returns its current version.
+    pub fn watch(&mut self, key: &[u8]) -> u64 {
+        self.expire_if_needed(key);
+        let watched = self.watched.entry(key.to_vec()).or_insert((0, 0));
+        watched.1 += 1;
+        watched.0
+    }
+
+    /// Stops watching `key` for one of the clients that watched it.
+    pub fn unwatch(&mut self, key: &[u8]) {
+        if let Some(watched) = self.watched.get_mut(key) {
+            watched.1 -= 1;
+            if watched.1 == 0 {
+                self.watched.remove(key);
+            }
+        }
+    }
+
+    /// Returns the version of a watched key, which changes when the key is written to, deleted
+    /// or expires.
+    pub fn version(&mut self, key: &[u8]) -> u64 {
+        self.expire_if_needed(key);
+        self.watched.get(key).map_or(0, |watched| watched.0)
+    }
+
+    /// Records that `key` changed. Commands that modify a value in place call this once they did
+    /// change it, and the other writes go through the methods here, which call it themselves.
+    pub fn touch(&mut self, key: &[u8]) {
+        self.changes += 1;
+        if let Some(watched) = self.watched.get_mut(key) {
+            watched.0 = LAST_VERSION.fetch_add(1, Ordering::Relaxed) + 1;
+        }
+    }
+
+    /// Records that every watched key changed.
+    fn touch_all(&mut self) {
+        self.changes += 1;
+        for watched in self.watched.values_mut() {
+            watched.0 = LAST_VERSION.fetch_add(1, Ordering::Relaxed) + 1;
+        }
+    }
+
+    /// Removes `key` if its TTL has elapsed. Every access to a key goes through this first, so an
+    /// expired key is never visible even if the active expiry cycle has not evicted it yet.
+    fn expire_if_needed(&mut self, key: &[u8]) {
+        if let Some(deadline) = self.expires.get(key) {
+            if deadline <= now_ms() {
+                self.drop_key(key);
+            }
+        }
+    }
+
+    /// Returns the number of changes made to the keys so far.
+    pub fn changes(&self) -> u64 {
+        self.changes
+    }
+
+    /// The number of keys, including expired ones that have not been evicted yet.
+    pub fn len(&self) -> usize {
+        self.ds.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.ds.is_empty()
+    }
+
+    /// Returns every key along with its value and expiry deadline, including expired keys that
+    /// have not been evicted yet.
+    pub fn entries(&self) -> impl Iterator<Item = (&[u8], &RedisEntry, Option<u64>)> {
+        self.ds
+            .iter()
+            .map(|(key, entry)| (key.as_slice(), entry, self.expires.get(key)))
+    }
+
+    /// Removes every key. The clients watching keys keep doing so, and see the keys that
+    /// existed as changed. Flushing counts as a change even if there were no keys.
+    pub fn flush(&mut self) {
+        let watched = std::mem::take(&mut self.watched);
+        let existing: Vec<Vec<u8>> = watched
+            .keys()
+            .filter(|key| self.ds.contains_key(key))
+            .cloned()
+            .collect();
+        let changes = self.changes + 1;
+        *self = DataStore::new();
+        self.watched = watched;
+        self.changes = changes;
+        for key in existing {
+            self.touch(&key);
+        }
+    }
+
+    pub fn contains_key(&mut self, key: &[u8]) -> bool {
+        self.expire_if_needed(key);
+        self.ds.contains_key(key)
+    }
+
+    /// Returns the expiry deadline of `key` in Unix milliseconds, if it has one.
+    pub fn get_expire_at(&mut self, key: &[u8]) -> Option<u64> {
+        self.expire_if_needed(key);
+        self.expires.get(key)
+    }
+
+    /// Sets the expiry deadline of `key` in Unix milliseconds. Returns false if the key does not
+    /// exist.
+    pub fn set_expire_at(&mut self, key: &[u8], deadline: u64) -> bool {
+        if !self.contains_key(key) {
+            return false;
+        }
+        self.expires.insert(key, deadline);
+        self.touch(key);
+        true
+    }
+
+    /// Removes the TTL of `key`. Returns false if the key does not exist or has no TTL.
+    pub fn persist(&mut self, key: &[u8]) -> bool {
+        self.expire_if_needed(key);
+        let persisted = self.expires.remove(key).is_some();
+        if persisted {
+            self.touch(key);
+        }
+        persisted
     }
 
-    pub fn get_string(&self, key: &String) -> Result<Option<&String>, Box<dyn std::error::Error>> {
+    /// Evicts expired keys by sampling random keys with a TTL, and keeps going for as long as a
+    /// large share of the sample turns out to be expired. Returns the number of evicted keys.
+    pub fn expire_cycle(&mut self) -> usize {
+        let mut evicted = 0;
+        for _ in 0..EXPIRE_CYCLE_MAX_ROUNDS {
+            if self.expires.is_empty() {
+                break;
+            }
+            let now = now_ms();
+            let mut expired = 0;
+            for _ in 0..EXPIRE_CYCLE_SAMPLE_SIZE.min(self.expires.len()) {
+                let Some((key, deadline)) = self.expires.sample() else {
+                    break;
+                };
+                if deadline <= now {
+                    let key = key.to_vec();
+                    self.drop_key(&key);
+                    expired += 1;
+                }
+            }
+            evicted += expired;
+            if expired <= EXPIRE_CYCLE_SAMPLE_SIZE / 4 {
+                break;
+            }
+        }
+        evicted
+    }
+
+    /// Returns the keys matching the glob-style `pattern`.
+    pub fn keys(&mut self, pattern: &[u8]) -> Vec<Vec<u8>> {
+        let keys: Vec<Vec<u8>> = self
+            .ds
+            .keys()
+            .filter(|key| glob_match(pattern, key))
+            .cloned()
+            .collect();
+        keys.into_iter()
+            .filter(|key| self.contains_key(key))
+            .collect()
+    }
+
+    /// Returns about `count` keys from `cursor` on, along with the cursor to continue from, which
+    /// is 0 once the whole keyspace has been scanned.
+    pub fn scan(&mut self, cursor: u64, count: usize) -> (Vec<Vec<u8>>, u64) {
+        let (keys, cursor) = self.ds.scan(cursor, count);
+        let keys: Vec<Vec<u8>> = keys.into_iter().map(|(key, _)| key.to_vec()).collect();
+        let keys = keys
+            .into_iter()
+            .filter(|key| self.contains_key(key))
+            .collect();
+        (keys, cursor)
+    }
+
+    pub fn get_type(&mut self, key: &[u8]) -> Option<&RedisEntryType> {
+        self.expire_if_needed(key);
+        self.ds.get(key).map(|entry| &entry.type_)
+    }
+
+    /// Moves the value and TTL of `from` to `to`, replacing whatever `to` held. Returns false if
+    /// `from` does not exist.
+    pub fn rename(&mut self, from: &[u8], to: &[u8]) -> bool {
+        self.expire_if_needed(from);
+        let Some(entry) = self.remove_entry(from) else {
+            return false;
+        };
+        let deadline = self.expires.remove(from);
+        self.drop_key(to);
+        self.insert_entry(to, entry);
+        if let Some(deadline) = deadline {
+            self.expires.insert(to, deadline);
+        }
+        true
+    }
+
+    /// Copies the value and TTL of `from` to `to`, replacing whatever `to` held. Returns false if
+    /// `from` does not exist.
+    pub fn copy(&mut self, from: &[u8], to: &[u8]) -> bool {
+        let Some((entry, deadline)) = self.dump(from) else {
+            return false;
+        };
+        self.restore(to, entry, deadline);
+        true
+    }
+
+    /// Returns a copy of the value of `key` along with its expiry deadline, if it has one.
+    pub fn dump(&mut self, key: &[u8]) -> Option<(RedisEntry, Option<u64>)> {
+        self.expire_if_needed(key);
+        let entry = self.ds.get(key)?.clone();
+        Some((entry, self.expires.get(key)))
+    }
+
+    /// Stores `entry` at `key` with the given expiry deadline, replacing whatever `key` held.
+    pub fn restore(&mut self, key: &[u8], entry: RedisEntry, deadline: Option<u64>) {
+        self.drop_key(key);
+        self.insert_entry(key, entry);
+        if let Some(deadline) = deadline {
+            self.expires.insert(key, deadline);
+        }
+    }
+
+    pub fn get_string(
+        &mut self,
+        key: &[u8],
+    ) -> Result<Option<&Vec<u8>>, Box<dyn std::error::Error>> {
+        self.expire_if_needed(key);
         match self.ds.get(key) {
             Some(entry) => match entry.type_ {
                 RedisEntryType::String => match &entry.string {
@@ -31,21 +304,24 @@ impl DataStore {
         }
     }
 
-    pub fn set_string_overwrite(&mut self, key: &str, value: &str) {
-        self.ds
-            .insert(key.to_owned(), RedisEntry::create_string(value));
+    /// Stores `value` at `key`, replacing any value of any type along with its TTL.
+    pub fn set_string_overwrite(&mut self, key: &[u8], value: &[u8]) {
+        self.expires.remove(key);
+        self.insert_entry(key, RedisEntry::create_string(value));
     }
 
     pub fn set_string(
         &mut self,
-        key: &String,
-        value: &str,
+        key: &[u8],
+        value: &[u8],
     ) -> Result<(), Box<dyn std::error::Error>> {
+        self.expire_if_needed(key);
         match self.ds.get_mut(key) {
             Some(entry) => match entry.type_ {
                 RedisEntryType::String => match &entry.string {
                     Some(_) => {
-                        entry.string = Some(value.to_owned());
+                        entry.string = Some(value.to_vec());
+                        self.touch(key);
                         Ok(())
                     }
                     None => Err(Self::throw_integration_error(key, RedisEntryType::String)),
@@ -61,8 +337,9 @@ impl DataStore {
 
     pub fn get_list_mut(
         &mut self,
-        key: &String,
-    ) -> Result<Option<&mut LinkedList<String>>, Box<dyn std::error::Error>> {
+        key: &[u8],
+    ) -> Result<Option<&mut LinkedList<Vec<u8>>>, Box<dyn std::error::Error>> {
+        self.expire_if_needed(key);
         match self.ds.get_mut(key) {
             Some(entry) => match entry.type_ {
                 RedisEntryType::List => match &mut entry.list {
@@ -75,12 +352,13 @@ impl DataStore {
         }
     }
 
-    pub fn insert_list(&mut self, key: &String) -> Result<(), Box<dyn std::error::Error>> {
+    pub fn insert_list(&mut self, key: &[u8]) -> Result<(), Box<dyn std::error::Error>> {
+        self.expire_if_needed(key);
         match self.ds.get(key) {
             Some(_) => Err(Box::new(InternalError::KeyError)),
             None => {
                 let s = RedisEntry::init_list();
-                self.ds.insert(key.clone(), s);
+                self.insert_entry(key, s);
                 Ok(())
             }
         }
@@ -88,8 +366,9 @@ impl DataStore {
 
     pub fn get_set_mut(
         &mut self,
-        key: &String,
-    ) -> Result<Option<&mut HashSet<String>>, Box<dyn std::error::Error>> {
+        key: &[u8],
+    ) -> Result<Option<&mut RedisSet>, Box<dyn std::error::Error>> {
+        self.expire_if_needed(key);
         match self.ds.get_mut(key) {
             Some(entry) => match entry.type_ {
                 RedisEntryType::Set => match &mut entry.set {
@@ -102,12 +381,13 @@ impl DataStore {
         }
     }
 
-    pub fn insert_set(&mut self, key: &String) -> Result<(), Box<dyn std::error::Error>> {
+    pub fn insert_set(&mut self, key: &[u8]) -> Result<(), Box<dyn std::error::Error>> {
+        self.expire_if_needed(key);
         match self.ds.get(key) {
             Some(_) => Err(Box::new(InternalError::KeyError)),
             None => {
                 let s = RedisEntry::init_set();
-                self.ds.insert(key.clone(), s);
+                self.insert_entry(key, s);
                 Ok(())
             }
         }
@@ -115,8 +395,9 @@ impl DataStore {
 
     pub fn get_hash_mut(
         &mut self,
-        key: &String,
-    ) -> Result<Option<&mut HashMap<String, String>>, Box<dyn std::error::Error>> {
+        key: &[u8],
+    ) -> Result<Option<&mut RedisHash>, Box<dyn std::error::Error>> {
+        self.expire_if_needed(key);
         match self.ds.get_mut(key) {
             Some(entry) => match entry.type_ {
                 RedisEntryType::Hash => match &mut entry.hash {
@@ -129,12 +410,13 @@ impl DataStore {
         }
     }
 
-    pub fn insert_hash(&mut self, key: &String) -> Result<(), Box<dyn std::error::Error>> {
+    pub fn insert_hash(&mut self, key: &[u8]) -> Result<(), Box<dyn std::error::Error>> {
+        self.expire_if_needed(key);
         match self.ds.get(key) {
             Some(_) => Err(Box::new(InternalError::KeyError)),
             None => {
                 let s = RedisEntry::init_hash();
-                self.ds.insert(key.clone(), s);
+                self.insert_entry(key, s);
                 Ok(())
             }
         }
@@ -142,8 +424,9 @@ impl DataStore {
 
     pub fn get_sorted_set_mut(
         &mut self,
-        key: &String,
+        key: &[u8],
     ) -> Result<Option<&mut SortedSet>, Box<dyn std::error::Error>> {
+        self.expire_if_needed(key);
         match self.ds.get_mut(key) {
             Some(entry) => match entry.type_ {
                 RedisEntryType::SortedSet => match &mut entry.sorted_set {
@@ -159,12 +442,13 @@ impl DataStore {
         }
     }
 
-    pub fn insert_sorted_set(&mut self, key: &String) -> Result<(), Box<dyn std::error::Error>> {
+    pub fn insert_sorted_set(&mut self, key: &[u8]) -> Result<(), Box<dyn std::error::Error>> {
+        self.expire_if_needed(key);
         match self.ds.get(key) {
             Some(_) => Err(Box::new(InternalError::KeyError)),
             None => {
                 let s = RedisEntry::init_sorted_set();
-                self.ds.insert(key.clone(), s);
+                self.insert_entry(key, s);
                 Ok(())
             }
         }
@@ -172,8 +456,9 @@ impl DataStore {
 
     pub fn get_stream_mut(
         &mut self,
-        key: &String,
+        key: &[u8],
     ) -> Result<Option<&mut Stream>, Box<dyn std::error::Error>> {
+        self.expire_if_needed(key);
         match self.ds.get_mut(key) {
             Some(entry) => match entry.type_ {
                 RedisEntryType::Stream => match &mut entry.stream {
@@ -186,28 +471,31 @@ impl DataStore {
         }
     }
 
-    pub fn insert_stream(&mut self, key: &String) -> Result<(), Box<dyn std::error::Error>> {
+    pub fn insert_stream(&mut self, key: &[u8]) -> Result<(), Box<dyn std::error::Error>> {
+        self.expire_if_needed(key);
         match self.ds.get(key) {
             Some(_) => Err(Box::new(InternalError::KeyError)),
             None => {
                 let s = RedisEntry::init_stream();
-                self.ds.insert(key.clone(), s);
+                self.insert_entry(key, s);
                 Ok(())
             }
         }
     }
 
-    pub fn drop_key(&mut self, key: &String) {
-        self.ds.remove(key);
+    /// Removes `key` along with its TTL. Returns false if the key does not exist.
+    pub fn drop_key(&mut self, key: &[u8]) -> bool {
+        self.expires.remove(key);
+        self.remove_entry(key).is_some()
     }
 
     fn throw_integration_error(
-        key: &String,
+        key: &[u8],
         expected_type: RedisEntryType,
     ) -> Box<dyn std::error::Error> {
         log::error!(
             "Integration error at key '{}': expecting type '{}' but data is not found",
-            key,
+            String::from_utf8_lossy(key),
             expected_type.to_string()
         );
         Box::new(InternalError::Error)
@@ -220,7 +508,7 @@ impl Default for DataStore {
     }
 }
 
-#[derive(Default, Debug)]
+#[derive(Default, Debug, Clone, PartialEq)]
 pub enum RedisEntryType {
     #[default]
     Unknown,
@@ -232,28 +520,43 @@ pub enum RedisEntryType {
     Stream,
 }
 
+impl RedisEntryType {
+    /// The name of the type as reported by the `TYPE` command.
+    pub fn name(&self) -> &'static str {
+        match self {
+            RedisEntryType::Unknown => "none",
+            RedisEntryType::String => "string",
+            RedisEntryType::List => "list",
+            RedisEntryType::Set => "set",
+            RedisEntryType::Hash => "hash",
+            RedisEntryType::SortedSet => "zset",
+            RedisEntryType::Stream => "stream",
+        }
+    }
+}
+
 impl Display for RedisEntryType {
     fn fmt(&self, f: &mut Formatter) -> std::fmt::Result {
         write!(f, "{:?}", self)
     }
 }
 
-#[derive(Default)]
+#[derive(Default, Clone)]
 pub struct RedisEntry {
     pub type_: RedisEntryType,
-    pub string: Option<String>,
-    pub list: Option<LinkedList<String>>,
-    pub set: Option<HashSet<String>>,
-    pub hash: Option<HashMap<String, String>>,
+    pub string: Option<Vec<u8>>,
+    pub list: Option<LinkedList<Vec<u8>>>,
+    pub set: Option<RedisSet>,
+    pub hash: Option<RedisHash>,
     pub sorted_set: Option<SortedSet>,
     pub stream: Option<Stream>,
 }
 
 impl RedisEntry {
-    pub fn create_string(value: &str) -> Self {
+    pub fn create_string(value: &[u8]) -> Self {
         RedisEntry {
             type_: RedisEntryType::String,
-            string: Some(value.to_owned()),
+            string: Some(value.to_vec()),
             ..Default::default()
         }
     }
@@ -269,7 +572,7 @@ impl RedisEntry {
     pub fn init_set() -> Self {
         RedisEntry {
             type_: RedisEntryType::Set,
-            set: Some(HashSet::new()),
+            set: Some(RedisSet::default()),
             ..Default::default()
         }
     }
@@ -277,7 +580,7 @@ impl RedisEntry {
     pub fn init_hash() -> Self {
         RedisEntry {
             type_: RedisEntryType::Hash,
-            hash: Some(HashMap::new()),
+            hash: Some(RedisHash::default()),
             ..Default::default()
         }
     }
@@ -305,20 +608,103 @@ pub fn get_data_store() -> DataStore {
 
 #[cfg(test)]
 mod test {
-    use super::get_data_store;
+    use super::{get_data_store, now_ms};
 
     #[test]
     fn test_list_store() {
         let mut ds = get_data_store();
-        let key = "foo".to_string();
+        let key = b"foo".to_vec();
         let _ = ds.insert_list(&key);
 
         let v = ds.get_list_mut(&key).unwrap().unwrap();
-        v.push_front("aaa".to_string());
-        v.push_front("bbb".to_string());
+        v.push_front(b"aaa".to_vec());
+        v.push_front(b"bbb".to_vec());
 
-        let v = ds.ds.get(&"foo".to_string()).unwrap();
+        let v = ds.ds.get(b"foo".as_slice()).unwrap();
         assert_eq!(v.list.as_ref().unwrap().len(), 2);
-        assert_eq!(v.list.as_ref().unwrap().back().unwrap(), &"aaa".to_string());
+        assert_eq!(v.list.as_ref().unwrap().back().unwrap(), &b"aaa".to_vec());
+    }
+
+    #[test]
+    fn should_hide_expired_keys() {
+        let mut ds = get_data_store();
+        ds.set_string_overwrite(b"foo", b"bar");
+        let _ = ds.insert_list(b"list");
+        assert!(ds.set_expire_at(b"foo", now_ms() - 1));
+        assert!(ds.set_expire_at(b"list", now_ms() - 1));
+        assert!(!ds.set_expire_at(b"missing", now_ms() - 1));
+        assert!(ds.ds.contains_key(b"foo".as_slice()));
+
+        assert!(ds.get_string(b"foo").unwrap().is_none());
+        assert!(ds.get_list_mut(b"list").unwrap().is_none());
+        assert!(ds.ds.is_empty());
+        assert!(ds.expires.is_empty());
+    }
+
+    #[test]
+    fn should_evict_expired_keys_in_expire_cycle() {
+        let mut ds = get_data_store();
+        for i in 0..100 {
+            let key = format!("key{}", i).into_bytes();
+            ds.set_string_overwrite(&key, b"v");
+            ds.set_expire_at(&key, now_ms() - 1);
+        }
+        ds.set_string_overwrite(b"live", b"v");
+        ds.set_expire_at(b"live", now_ms() + 100000);
+        ds.set_string_overwrite(b"persistent", b"v");
+
+        let mut evicted = 0;
+        while ds.expires.len() > 1 {
+            evicted += ds.expire_cycle();
+        }
+        assert_eq!(evicted, 100);
+        assert_eq!(ds.ds.len(), 2);
+        assert_eq!(ds.expire_cycle(), 0);
+    }
+
+    #[test]
+    fn should_move_ttl_with_renamed_and_copied_keys() {
+        let mut ds = get_data_store();
+        ds.set_string_overwrite(b"foo", b"bar");
+        ds.set_expire_at(b"foo", now_ms() + 10000);
+        ds.set_string_overwrite(b"dst", b"old");
+        ds.set_expire_at(b"dst", now_ms() + 20000);
+
+        assert!(ds.copy(b"foo", b"copy"));
+        assert!(ds.rename(b"foo", b"dst"));
+        assert!(!ds.contains_key(b"foo"));
+        assert!(!ds.rename(b"foo", b"dst"));
+        assert!(!ds.copy(b"foo", b"dst"));
+        assert_eq!(ds.get_string(b"dst").unwrap().unwrap(), &b"bar".to_vec());
+        assert_eq!(ds.get_expire_at(b"dst"), ds.get_expire_at(b"copy"));
+        assert!(ds.get_expire_at(b"dst").unwrap() <= now_ms() + 10000);
+        assert_eq!(ds.expires.len(), 2);
+    }
+
+    #[test]
+    fn should_change_versions_of_watched_keys() {
+        let mut ds = get_data_store();
+        ds.set_string_overwrite(b"foo", b"bar");
+        let version = ds.watch(b"foo");
+        assert_eq!(ds.watch(b"foo"), version);
+        ds.get_string(b"foo").unwrap();
+        assert_eq!(ds.version(b"foo"), version);
+        ds.set_expire_at(b"foo", now_ms() + 10000);
+        let version = ds.version(b"foo");
+        ds.persist(b"foo");
+        assert_ne!(ds.version(b"foo"), version);
+
+        let version = ds.version(b"foo");
+        ds.flush();
+        assert_ne!(ds.version(b"foo"), version);
+        let version = ds.version(b"foo");
+        ds.flush();
+        assert_eq!(ds.version(b"foo"), version);
+
+        // Keys are only tracked while someone watches them.
+        ds.unwatch(b"foo");
+        assert!(ds.watched.contains_key(b"foo".as_slice()));
+        ds.unwatch(b"foo");
+        assert!(ds.watched.is_empty());
     }
 }
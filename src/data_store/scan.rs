@@ -0,0 +1,253 @@
+use std::collections::hash_map::DefaultHasher;
+use std::hash::{Hash, Hasher};
+
+/// The number of buckets a map starts with once it holds a key.
+const MIN_BUCKETS: usize = 4;
+/// The number of empty buckets a call to `scan` may go through for each key it was asked for,
+/// so that a call does not go through the whole of a sparse map.
+const EMPTY_VISITS_PER_KEY: usize = 10;
+
+/// Hashes `value` with fixed keys, so the result is the same for the lifetime of the process.
+fn scan_hash(value: &[u8]) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    value.hash(&mut hasher);
+    hasher.finish()
+}
+
+/// Returns the cursor of the bucket to visit after the one at `cursor` in a map with `mask + 1`
+/// buckets, or 0 once every bucket has been visited.
+fn next_cursor(cursor: u64, mask: u64) -> u64 {
+    (cursor | !mask)
+        .reverse_bits()
+        .wrapping_add(1)
+        .reverse_bits()
+}
+
+/// A hash table from byte strings to `V` whose keys `SCAN` goes through with a cursor, as Redis
+/// does: the cursor is the index of the next bucket, and buckets are visited in the order of
+/// their index with its bits reversed. As the number of buckets is a power of two, a bucket
+/// splits into, or merges with, buckets that are visited at the same point of that order when
+/// the table grows or shrinks. So every key that is in the map for the whole iteration is
+/// returned, although keys may be returned twice if the table shrinks meanwhile.
+#[derive(Clone)]
+pub struct ScanMap<V> {
+    buckets: Vec<Vec<(Vec<u8>, V)>>,
+    len: usize,
+}
+
+impl<V> Default for ScanMap<V> {
+    fn default() -> Self {
+        ScanMap {
+            buckets: Vec::new(),
+            len: 0,
+        }
+    }
+}
+
+impl<V> ScanMap<V> {
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Returns the index of the bucket of the keys with the given hash. There must be buckets.
+    fn index(&self, hash: u64) -> usize {
+        hash as usize & (self.buckets.len() - 1)
+    }
+
+    /// Moves every key to a table of `count` buckets, which must be a power of two.
+    fn resize(&mut self, count: usize) {
+        let buckets = (0..count).map(|_| Vec::new()).collect();
+        for (key, value) in std::mem::replace(&mut self.buckets, buckets)
+            .into_iter()
+            .flatten()
+        {
+            let index = self.index(scan_hash(&key));
+            self.buckets[index].push((key, value));
+        }
+    }
+
+    pub fn get(&self, key: &[u8]) -> Option<&V> {
+        if self.buckets.is_empty() {
+            return None;
+        }
+        self.buckets[self.index(scan_hash(key))]
+            .iter()
+            .find(|(k, _)| k.as_slice() == key)
+            .map(|(_, value)| value)
+    }
+
+    pub fn get_mut(&mut self, key: &[u8]) -> Option<&mut V> {
+        if self.buckets.is_empty() {
+            return None;
+        }
+        let index = self.index(scan_hash(key));
+        self.buckets[index]
+            .iter_mut()
+            .find(|(k, _)| k.as_slice() == key)
+            .map(|(_, value)| value)
+    }
+
+    pub fn contains_key(&self, key: &[u8]) -> bool {
+        self.get(key).is_some()
+    }
+
+    /// Sets `key` to `value`, returning the value it replaced. The table doubles once there are
+    /// as many keys as buckets.
+    pub fn insert(&mut self, key: Vec<u8>, value: V) -> Option<V> {
+        let hash = scan_hash(&key);
+        if !self.buckets.is_empty() {
+            let index = self.index(hash);
+            if let Some((_, current)) = self.buckets[index].iter_mut().find(|(k, _)| *k == key) {
+                return Some(std::mem::replace(current, value));
+            }
+        }
+        if self.len >= self.buckets.len() {
+            self.resize((self.buckets.len() * 2).max(MIN_BUCKETS));
+        }
+        let index = self.index(hash);
+        self.buckets[index].push((key, value));
+        self.len += 1;
+        None
+    }
+
+    /// Removes `key`, returning its value. The table halves once it is mostly empty.
+    pub fn remove(&mut self, key: &[u8]) -> Option<V> {
+        if self.buckets.is_empty() {
+            return None;
+        }
+        let index = self.index(scan_hash(key));
+        let bucket = &mut self.buckets[index];
+        let position = bucket.iter().position(|(k, _)| k.as_slice() == key)?;
+        let (_, value) = bucket.swap_remove(position);
+        self.len -= 1;
+        if self.len == 0 {
+            self.buckets = Vec::new();
+        } else if self.buckets.len() > MIN_BUCKETS && self.len * 8 < self.buckets.len() {
+            self.resize(self.buckets.len() / 2);
+        }
+        Some(value)
+    }
+
+    /// Returns every key along with its value, in no particular order.
+    pub fn iter(&self) -> impl Iterator<Item = (&Vec<u8>, &V)> {
+        self.buckets
+            .iter()
+            .flatten()
+            .map(|(key, value)| (key, value))
+    }
+
+    pub fn keys(&self) -> impl Iterator<Item = &Vec<u8>> {
+        self.iter().map(|(key, _)| key)
+    }
+
+    /// Returns about `count` keys from `cursor` on, with their values, along with the cursor to
+    /// continue from, which is 0 once every key has been returned. The keys of a bucket are
+    /// returned together, so there may be a few more than `count`, and there may be fewer when
+    /// the map is sparse.
+    pub fn scan(&self, cursor: u64, count: usize) -> (Vec<(&[u8], &V)>, u64) {
+        let mut found = Vec::new();
+        if self.buckets.is_empty() {
+            return (found, 0);
+        }
+        let mask = (self.buckets.len() - 1) as u64;
+        let mut cursor = cursor;
+        let mut empty_visits = count.saturating_mul(EMPTY_VISITS_PER_KEY);
+        loop {
+            let bucket = &self.buckets[(cursor & mask) as usize];
+            if bucket.is_empty() {
+                empty_visits = empty_visits.saturating_sub(1);
+            }
+            found.extend(bucket.iter().map(|(key, value)| (key.as_slice(), value)));
+            cursor = next_cursor(cursor, mask);
+            if cursor == 0 || found.len() >= count || empty_visits == 0 {
+                return (found, cursor);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::ScanMap;
+    use std::collections::HashSet;
+
+    /// Scans `map` from `cursor` until the end, returning the keys in the order they came.
+    fn scan_from(map: &ScanMap<()>, mut cursor: u64, count: usize) -> Vec<Vec<u8>> {
+        let mut keys = Vec::new();
+        loop {
+            let (entries, next) = map.scan(cursor, count);
+            keys.extend(entries.into_iter().map(|(key, _)| key.to_vec()));
+            if next == 0 {
+                return keys;
+            }
+            cursor = next;
+        }
+    }
+
+    #[test]
+    fn should_return_every_key_once() {
+        let mut map = ScanMap::default();
+        for i in 0..100 {
+            map.insert(format!("key{}", i).into_bytes(), i);
+        }
+        assert_eq!(map.get(b"key42"), Some(&42));
+        assert_eq!(map.insert(b"key42".to_vec(), 420), Some(42));
+        assert_eq!(map.remove(b"key42"), Some(420));
+        assert_eq!(map.remove(b"key42"), None);
+        *map.get_mut(b"key7").unwrap() = 70;
+        let mut seen = HashSet::new();
+        let mut cursor = 0;
+        loop {
+            let (entries, next) = map.scan(cursor, 7);
+            for (key, value) in entries {
+                assert_eq!(map.get(key), Some(value));
+                assert!(seen.insert(key.to_vec()));
+            }
+            if next == 0 {
+                break;
+            }
+            cursor = next;
+        }
+        assert_eq!(seen.len(), 99);
+        assert_eq!(map.len(), 99);
+        assert_eq!(map.iter().count(), 99);
+    }
+
+    #[test]
+    fn should_tolerate_changes_between_calls() {
+        let mut map = ScanMap::default();
+        for i in 0..50 {
+            map.insert(format!("old{}", i).into_bytes(), ());
+        }
+        let (first, cursor) = map.scan(0, 10);
+        let mut seen: HashSet<Vec<u8>> = first.iter().map(|(k, _)| k.to_vec()).collect();
+        for i in 0..1000 {
+            map.insert(format!("new{}", i).into_bytes(), ());
+        }
+        map.remove(b"old0");
+        // Keys are not returned twice while the map grows.
+        for key in scan_from(&map, cursor, 10) {
+            assert!(seen.insert(key));
+        }
+        for i in 1..50 {
+            assert!(seen.contains(format!("old{}", i).as_bytes()));
+        }
+
+        // Nor are they missed while it shrinks.
+        let (first, cursor) = map.scan(0, 100);
+        let mut seen: HashSet<Vec<u8>> = first.iter().map(|(k, _)| k.to_vec()).collect();
+        for i in 0..1000 {
+            map.remove(format!("new{}", i).as_bytes());
+        }
+        seen.extend(scan_from(&map, cursor, 10));
+        for i in 1..50 {
+            assert!(seen.contains(format!("old{}", i).as_bytes()));
+        }
+        assert_eq!(map.len(), 49);
+        assert!(map.buckets.len() <= 8 * map.len());
+    }
+}
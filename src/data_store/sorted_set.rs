@@ -1,3 +1,4 @@
+use super::scan::ScanMap;
 use rand::Rng;
 use std::cell::RefCell;
 use std::collections::{BTreeSet, HashMap};
@@ -5,8 +6,10 @@ use std::collections::{BTreeSet, HashMap};
 const SKIP_LIST_MAX_LEVEL: u8 = 32;
 const SKIP_LIST_PROB: f64 = 0.5;
 
+#[derive(Clone)]
 pub struct SortedSet {
-    elements: HashMap<String, f64>,
+    /// The score of each element, which `ZSCAN` goes through with a cursor.
+    elements: ScanMap<f64>,
     skip_list: SkipList,
 }
 
@@ -19,12 +22,12 @@ impl Default for SortedSet {
 impl SortedSet {
     pub fn new() -> Self {
         Self {
-            elements: HashMap::new(),
+            elements: ScanMap::default(),
             skip_list: SkipList::new(SKIP_LIST_MAX_LEVEL),
         }
     }
 
-    pub fn insert(&mut self, score: f64, element: String) -> bool {
+    pub fn insert(&mut self, score: f64, element: Vec<u8>) -> bool {
         let mut is_new_element = true;
         if let Some(current_score) = self.elements.get(&element) {
             self.skip_list.remove(*current_score, &element);
@@ -35,7 +38,7 @@ impl SortedSet {
         is_new_element
     }
 
-    pub fn remove(&mut self, element: &str) -> bool {
+    pub fn remove(&mut self, element: &[u8]) -> bool {
         if let Some(score) = self.elements.get(element) {
             self.skip_list.remove(*score, element);
             self.elements.remove(element);
@@ -48,21 +51,44 @@ impl SortedSet {
         self.elements.len()
     }
 
-    pub fn get(&self, element: &str) -> Option<f64> {
+    pub fn is_empty(&self) -> bool {
+        self.elements.is_empty()
+    }
+
+    /// Returns every element along with its score, in no particular order.
+    pub fn iter(&self) -> impl Iterator<Item = (&[u8], f64)> {
+        self.elements
+            .iter()
+            .map(|(element, score)| (element.as_slice(), *score))
+    }
+
+    pub fn get(&self, element: &[u8]) -> Option<f64> {
         self.elements.get(element).cloned()
     }
 
-    pub fn get_values_by_rank(&self, start: u64, stop: u64) -> Vec<String> {
+    pub fn get_values_by_rank(&self, start: u64, stop: u64) -> Vec<Vec<u8>> {
         self.skip_list.get_values_by_rank(start, stop)
     }
 
-    pub fn get_rank(&self, element: &str) -> Option<u64> {
+    pub fn get_rank(&self, element: &[u8]) -> Option<u64> {
         self.elements
             .get(element)
             .map(|score| self.skip_list.get_rank(score, element))
     }
+
+    /// Returns about `count` elements and their scores from `cursor` on, along with the cursor to
+    /// continue from, which is 0 once every element has been returned.
+    pub fn scan(&self, cursor: u64, count: usize) -> (Vec<(&[u8], f64)>, u64) {
+        let (elements, cursor) = self.elements.scan(cursor, count);
+        let pairs = elements
+            .into_iter()
+            .map(|(element, score)| (element, *score))
+            .collect();
+        (pairs, cursor)
+    }
 }
 
+#[derive(Clone)]
 struct ListNode {
     id: u64,
     level: u8,
@@ -70,7 +96,7 @@ struct ListNode {
     prev: Vec<Option<u64>>,
     span: Vec<u64>,
     score: f64,
-    values: BTreeSet<String>,
+    values: BTreeSet<Vec<u8>>,
 }
 
 impl ListNode {
@@ -90,11 +116,11 @@ impl ListNode {
         self.level = level;
     }
 
-    pub fn add_value(&mut self, value: String) -> bool {
+    pub fn add_value(&mut self, value: Vec<u8>) -> bool {
         self.values.insert(value)
     }
 
-    pub fn remove_value(&mut self, value: &str) -> bool {
+    pub fn remove_value(&mut self, value: &[u8]) -> bool {
         self.values.remove(value)
     }
 
@@ -123,6 +149,7 @@ impl ListNode {
     }
 }
 
+#[derive(Clone)]
 struct SkipList {
     max_level: u8,
     prob: f64,
@@ -209,16 +236,16 @@ impl SkipList {
         new_node.borrow_mut().set_prev(current_level, current_node);
     }
 
-    fn create_new_node(&mut self, level: u8, score: f64, value: &str) -> u64 {
+    fn create_new_node(&mut self, level: u8, score: f64, value: &[u8]) -> u64 {
         let new_node_id = self.next_node_id;
         let new_node = RefCell::new(ListNode::new(new_node_id, level, score));
-        new_node.borrow_mut().add_value(value.to_owned());
+        new_node.borrow_mut().add_value(value.to_vec());
         self.nodes.insert(new_node_id, new_node);
         self.next_node_id += 1;
         new_node_id
     }
 
-    pub fn insert(&mut self, score: f64, value: String) {
+    pub fn insert(&mut self, score: f64, value: Vec<u8>) {
         let (node_exists, mut previous_nodes) = self.check_if_node_exists(score);
         if node_exists {
             let (_, current_node_id) = previous_nodes.last().unwrap();
@@ -253,7 +280,8 @@ impl SkipList {
         }
     }
 
-    pub fn get_values_by_score(&self, start_score: f64, stop_score: f64) -> Vec<String> {
+    #[allow(dead_code)]
+    pub fn get_values_by_score(&self, start_score: f64, stop_score: f64) -> Vec<Vec<u8>> {
         let mut result = Vec::new();
         let mut level: i16 = self.max_level as i16;
         let mut current_node_id = self.head_id;
@@ -296,7 +324,7 @@ impl SkipList {
         result
     }
 
-    pub fn get_values_by_rank(&self, start_rank: u64, stop_rank: u64) -> Vec<String> {
+    pub fn get_values_by_rank(&self, start_rank: u64, stop_rank: u64) -> Vec<Vec<u8>> {
         // input rank numbers are 0-based
         let start_rank = start_rank + 1;
         let stop_rank = stop_rank + 1;
@@ -343,7 +371,7 @@ impl SkipList {
         result
     }
 
-    pub fn get_rank(&self, score: &f64, value: &str) -> u64 {
+    pub fn get_rank(&self, score: &f64, value: &[u8]) -> u64 {
         let mut level: i16 = self.max_level as i16;
         let mut current_node_id = self.head_id;
         let mut num_seen_values = 0;
@@ -375,7 +403,7 @@ impl SkipList {
         num_seen_values - 1
     }
 
-    pub fn remove(&mut self, score: f64, value: &str) {
+    pub fn remove(&mut self, score: f64, value: &[u8]) {
         let (node_exists, previous_nodes) = self.check_if_node_exists(score);
         if !node_exists {
             return;
@@ -432,17 +460,17 @@ mod test {
         #[test]
         fn should_set_values_and_return_sorted_values() {
             let n0 = RefCell::new(ListNode::new(0, 0, 1.0));
-            n0.borrow_mut().add_value("c".to_string());
-            n0.borrow_mut().add_value("b".to_string());
-            n0.borrow_mut().add_value("a".to_string());
-            n0.borrow_mut().add_value("aa".to_string());
+            n0.borrow_mut().add_value(b"c".to_vec());
+            n0.borrow_mut().add_value(b"b".to_vec());
+            n0.borrow_mut().add_value(b"a".to_vec());
+            n0.borrow_mut().add_value(b"aa".to_vec());
             assert_eq!(
                 n0.borrow().values.iter().collect::<Vec<_>>(),
                 [
-                    &"a".to_string(),
-                    &"aa".to_string(),
-                    &"b".to_string(),
-                    &"c".to_string()
+                    &b"a".to_vec(),
+                    &b"aa".to_vec(),
+                    &b"b".to_vec(),
+                    &b"c".to_vec()
                 ]
             );
         }
@@ -457,20 +485,20 @@ mod test {
             // set prob to -1 so that nodes are always created in order to remove randomness
             list.prob = -1.0;
 
-            list.insert(1.0, "foo".to_string());
-            list.insert(3.0, "bar".to_string());
-            list.insert(2.0, "baz".to_string());
-            list.insert(1.0, "foobar".to_string());
-            list.insert(f64::INFINITY, "inf".to_string());
+            list.insert(1.0, b"foo".to_vec());
+            list.insert(3.0, b"bar".to_vec());
+            list.insert(2.0, b"baz".to_vec());
+            list.insert(1.0, b"foobar".to_vec());
+            list.insert(f64::INFINITY, b"inf".to_vec());
             let mut nodes = list.nodes.values().collect::<Vec<_>>();
-            nodes.sort_by(|a, b| a.borrow().id.cmp(&b.borrow().id));
+            nodes.sort_by_key(|a| a.borrow().id);
             assert_eq!(nodes.len(), 5);
-            let expected = [
+            let expected: [(f64, Vec<&[u8]>); 5] = [
                 (-f64::INFINITY, vec![]),
-                (f64::INFINITY, vec!["inf"]),
-                (1.0, vec!["foo", "foobar"]),
-                (3.0, vec!["bar"]),
-                (2.0, vec!["baz"]),
+                (f64::INFINITY, vec![b"inf"]),
+                (1.0, vec![b"foo", b"foobar"]),
+                (3.0, vec![b"bar"]),
+                (2.0, vec![b"baz"]),
             ];
             for i in 0..nodes.len() {
                 assert_eq!(nodes[i].borrow().score, expected[i].0);
@@ -487,7 +515,7 @@ mod test {
             // set prob to 1 so that only the head node has level > 0
             list.prob = 1.0;
             for i in 0..5 {
-                list.insert(i as f64, i.to_string());
+                list.insert(i as f64, i.to_string().into_bytes());
             }
             let n0 = list.nodes.get(&2).unwrap();
             assert_eq!(n0.borrow().get_span(0), 1);
@@ -497,7 +525,7 @@ mod test {
             assert_eq!(head.borrow().get_span(1), 5);
             assert_eq!(head.borrow().get_span(0), 0);
 
-            list.insert(-f64::INFINITY, "inf".to_string());
+            list.insert(-f64::INFINITY, b"inf".to_vec());
             let head = list.nodes.get(&0).unwrap();
             assert_eq!(head.borrow().get_span(2), 6);
             assert_eq!(head.borrow().get_span(1), 6);
@@ -512,7 +540,7 @@ mod test {
 
             let res = list.check_if_node_exists(1.0);
             assert_eq!(res, (false, vec![(2, 0), (1, 0), (0, 0)]));
-            list.insert(1.0, "a".to_string());
+            list.insert(1.0, b"a".to_vec());
             let res = list.check_if_node_exists(1.0);
             assert_eq!(res, (true, vec![(2, 2), (1, 2), (0, 2)]));
             let res = list.check_if_node_exists(3.0);
@@ -525,7 +553,7 @@ mod test {
         fn should_create_new_node() {
             let mut list = SkipList::new(2);
             assert_eq!(list.next_node_id, 2);
-            let node_id = list.create_new_node(0, 1.0, "foo");
+            let node_id = list.create_new_node(0, 1.0, b"foo");
             assert_eq!(node_id, 2);
             assert_eq!(list.next_node_id, 3);
         }
@@ -534,9 +562,9 @@ mod test {
         fn should_insert_node_at_level() {
             let mut list = SkipList::new(2);
             list.prob = 1.0;
-            list.create_new_node(0, 1.0, "a");
-            list.create_new_node(0, 3.0, "b");
-            list.create_new_node(0, 2.0, "c");
+            list.create_new_node(0, 1.0, b"a");
+            list.create_new_node(0, 3.0, b"b");
+            list.create_new_node(0, 2.0, b"c");
             let n0 = list.nodes.get(&2).unwrap();
             let n1 = list.nodes.get(&3).unwrap();
             let n2 = list.nodes.get(&4).unwrap();
@@ -554,31 +582,31 @@ mod test {
             // Remove randomness
             list.prob = 1.0;
             let input = [
-                (1.0, "a"),
-                (3.0, "b"),
-                (2.0, "c"),
-                (1.0, "d"),
-                (3.9, "e"),
-                (f64::INFINITY, "f"),
-                (-f64::INFINITY, "g"),
+                (1.0, b"a"),
+                (3.0, b"b"),
+                (2.0, b"c"),
+                (1.0, b"d"),
+                (3.9, b"e"),
+                (f64::INFINITY, b"f"),
+                (-f64::INFINITY, b"g"),
             ];
             for (score, value) in input {
-                list.insert(score, value.to_string());
+                list.insert(score, value.to_vec());
             }
             let values = list.get_values_by_score(-1.0, 4.0);
-            assert_eq!(values, ["a", "d", "c", "b", "e"]);
+            assert_eq!(values, [b"a", b"d", b"c", b"b", b"e"]);
             let values = list.get_values_by_score(1.5, 4.0);
-            assert_eq!(values, ["c", "b", "e"]);
+            assert_eq!(values, [b"c", b"b", b"e"]);
             let values = list.get_values_by_score(1.5, 3.5);
-            assert_eq!(values, ["c", "b"]);
+            assert_eq!(values, [b"c", b"b"]);
             let values = list.get_values_by_score(1.5, 1.9);
             assert!(values.is_empty());
             let values = list.get_values_by_score(2.0, 1.9);
             assert!(values.is_empty());
             let values = list.get_values_by_score(4.0, f64::INFINITY);
-            assert_eq!(values, ["f"]);
+            assert_eq!(values, [b"f"]);
             let values = list.get_values_by_score(-f64::INFINITY, 1.0000001);
-            assert_eq!(values, ["g", "a", "d"]);
+            assert_eq!(values, [b"g", b"a", b"d"]);
         }
 
         #[test]
@@ -587,31 +615,31 @@ mod test {
             // Remove randomness
             list.prob = 1.0;
             let input = [
-                (1.0, "a"),
-                (3.0, "b"),
-                (2.0, "c"),
-                (1.0, "d"),
-                (3.9, "e"),
-                (f64::INFINITY, "f"),
+                (1.0, b"a"),
+                (3.0, b"b"),
+                (2.0, b"c"),
+                (1.0, b"d"),
+                (3.9, b"e"),
+                (f64::INFINITY, b"f"),
             ];
             for (score, value) in input {
-                list.insert(score, value.to_string());
+                list.insert(score, value.to_vec());
             }
             let values = list.get_values_by_rank(1, 4);
-            assert_eq!(values, ["d", "c", "b", "e"]);
+            assert_eq!(values, [b"d", b"c", b"b", b"e"]);
             let values = list.get_values_by_rank(3, 8);
-            assert_eq!(values, ["b", "e", "f"]);
+            assert_eq!(values, [b"b", b"e", b"f"]);
             let values = list.get_values_by_rank(0, 1);
-            assert_eq!(values, ["a", "d"]);
+            assert_eq!(values, [b"a", b"d"]);
             let values = list.get_values_by_rank(0, 0);
-            assert_eq!(values, ["a"]);
+            assert_eq!(values, [b"a"]);
             let values = list.get_values_by_rank(2, 0);
             assert!(values.is_empty());
 
             // Test if code behaves when the start node holds values
-            list.insert(-f64::INFINITY, "g".to_string());
+            list.insert(-f64::INFINITY, b"g".to_vec());
             let values = list.get_values_by_rank(0, 1);
-            assert_eq!(values, ["g", "a"]);
+            assert_eq!(values, [b"g", b"a"]);
         }
 
         #[test]
@@ -620,10 +648,10 @@ mod test {
             // set prob to -1 so that nodes are always created in order to remove randomness
             list.prob = 1.0;
 
-            list.insert(1.0, "foo".to_string());
-            list.insert(1.0, "bar".to_string());
-            list.insert(0.0, "baz".to_string());
-            assert_eq!(list.get_values_by_score(1.0, 1.0), ["bar", "foo"]);
+            list.insert(1.0, b"foo".to_vec());
+            list.insert(1.0, b"bar".to_vec());
+            list.insert(0.0, b"baz".to_vec());
+            assert_eq!(list.get_values_by_score(1.0, 1.0), [b"bar", b"foo"]);
             let node = list.nodes.get(&2).unwrap();
             assert_eq!(node.borrow().get_span(0), 2);
             let head = list.nodes.get(&0).unwrap();
@@ -631,8 +659,8 @@ mod test {
             assert_eq!(head.borrow().get_span(1), 3);
             assert_eq!(head.borrow().get_span(2), 3);
 
-            list.remove(1.0, "bar");
-            assert_eq!(list.get_values_by_score(1.0, 1.0), ["foo"]);
+            list.remove(1.0, b"bar");
+            assert_eq!(list.get_values_by_score(1.0, 1.0), [b"foo"]);
 
             let node = list.nodes.get(&2).unwrap();
             assert_eq!(node.borrow().get_span(0), 1);
@@ -648,14 +676,14 @@ mod test {
             // Remove randomness
             list.prob = 1.0;
 
-            list.insert(1.0, "foo".to_string());
-            assert_eq!(list.get_values_by_score(1.0, 1.0), ["foo"]);
+            list.insert(1.0, b"foo".to_vec());
+            assert_eq!(list.get_values_by_score(1.0, 1.0), [b"foo"]);
 
-            list.remove(1.0, "foo");
+            list.remove(1.0, b"foo");
             assert!(list.get_values_by_score(1.0, 1.0).is_empty());
 
             assert_eq!(list.nodes.len(), 2);
-            assert!(list.nodes.get(&2).is_none());
+            assert!(!list.nodes.contains_key(&2));
             let head = list.nodes.get(&0).unwrap();
             let tail = list.nodes.get(&1).unwrap();
             assert_eq!(head.borrow().get_next(0).unwrap(), 1);
@@ -5,6 +5,10 @@ use crate::error::StreamError;
 use radix_tree::RadixTree;
 use tree_node::TreeNodeId;
 
+/// The ID of a stream entry along with its field-value pairs.
+pub type StreamEntry<'a> = ([u64; 2], &'a [[Vec<u8>; 2]]);
+
+#[derive(Clone)]
 pub struct Stream {
     tree: RadixTree,
 }
@@ -25,7 +29,7 @@ impl Stream {
     pub fn insert(
         &mut self,
         id: Option<[u64; 2]>,
-        values: Vec<[String; 2]>,
+        values: Vec<[Vec<u8>; 2]>,
     ) -> Result<String, Box<dyn std::error::Error>> {
         let new_id = match id {
             Some(v) => TreeNodeId(v),
@@ -41,4 +45,9 @@ impl Stream {
 
         self.tree.insert(new_id, values)
     }
+
+    /// Returns every entry in ID order.
+    pub fn entries(&self) -> Vec<StreamEntry<'_>> {
+        self.tree.entries()
+    }
 }
@@ -1,7 +1,9 @@
 use std::collections::HashMap;
 
 use super::tree_node::{TreeNode, TreeNodeId};
+use super::StreamEntry;
 
+#[derive(Clone)]
 pub struct RadixTree {
     root: Box<TreeNode>,
     top_id: TreeNodeId,
@@ -43,7 +45,7 @@ impl RadixTree {
     pub fn insert(
         &mut self,
         new_id: TreeNodeId,
-        values: Vec<[String; 2]>,
+        values: Vec<[Vec<u8>; 2]>,
     ) -> Result<String, Box<dyn std::error::Error>> {
         let mut words = new_id.words();
         let word = words.next().unwrap();
@@ -53,6 +55,13 @@ impl RadixTree {
         Ok(self.top_id.to_string())
     }
 
+    pub fn entries(&self) -> Vec<StreamEntry<'_>> {
+        let mut entries = Vec::new();
+        self.root.collect_entries(&mut entries);
+        entries
+    }
+
+    #[allow(dead_code)]
     pub fn remove(&mut self, id: [u64; 2]) {
         let id = TreeNodeId(id);
         self.root.remove_child(id.words());
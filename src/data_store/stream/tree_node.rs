@@ -1,3 +1,4 @@
+use super::StreamEntry;
 use crate::error::InternalError;
 use std::{
     collections::HashMap,
@@ -5,10 +6,12 @@ use std::{
     ops::{Deref, DerefMut},
 };
 
+#[allow(dead_code)]
+#[derive(Clone)]
 pub struct TreeNode {
     id: Option<TreeNodeId>,
     key: u8,
-    values: Option<Vec<[String; 2]>>,
+    values: Option<Vec<[Vec<u8>; 2]>>,
     children: HashMap<u8, Box<TreeNode>>,
 }
 
@@ -16,7 +19,7 @@ impl TreeNode {
     pub fn new(
         id: Option<TreeNodeId>,
         key: u8,
-        values: Option<Vec<[String; 2]>>,
+        values: Option<Vec<[Vec<u8>; 2]>>,
         children: HashMap<u8, Box<TreeNode>>,
     ) -> Self {
         Self {
@@ -36,7 +39,7 @@ impl TreeNode {
             return self;
         }
         let max_key = self.children.keys().max().unwrap();
-        return self.children.get(max_key).unwrap().get_greatest_child();
+        self.children.get(max_key).unwrap().get_greatest_child()
     }
 
     pub fn get_child(&self, key: &u8) -> Option<&TreeNode> {
@@ -55,7 +58,7 @@ impl TreeNode {
         key: u8,
         mut words: TreeNodeIdIterator,
         id: TreeNodeId,
-        values: Option<Vec<[String; 2]>>,
+        values: Option<Vec<[Vec<u8>; 2]>>,
     ) -> Result<(), Box<dyn std::error::Error>> {
         // `key` is the current node index. Using `words.next()` here in a weird way to know
         // if we have reached the leaf node.
@@ -91,6 +94,20 @@ impl TreeNode {
         }
     }
 
+    /// Appends the entries of this node and its descendants to `entries`. Child keys are the bytes
+    /// of the IDs from the most significant one, so visiting them in order yields the IDs in order.
+    pub fn collect_entries<'a>(&'a self, entries: &mut Vec<StreamEntry<'a>>) {
+        if let (Some(id), Some(values)) = (&self.id, &self.values) {
+            entries.push((id.0, values.as_slice()));
+        }
+        let mut keys: Vec<&u8> = self.children.keys().collect();
+        keys.sort();
+        for key in keys {
+            self.children[key].collect_entries(entries);
+        }
+    }
+
+    #[allow(dead_code)]
     pub fn remove_child(&mut self, mut words: TreeNodeIdIterator) {
         if let Some(key) = words.next() {
             if let Some(child) = self.get_child_mut(&key) {
@@ -282,7 +299,7 @@ mod test {
             let id = TreeNodeId([0x0908070605040302, 0xf9f8f7f6f5f4f3f2]);
             let mut words = id.words();
             let key = words.next().unwrap();
-            let values = vec![["foo".to_string(), "bar".to_string()]];
+            let values = vec![[b"foo".to_vec(), b"bar".to_vec()]];
             let mut root = TreeNode::new(None, 0, None, HashMap::new());
             root.insert_child(key, words, id, Some(values.clone()))
                 .unwrap();
@@ -18,6 +18,38 @@ pub enum RequestError {
     InvalidNegValue,
     #[error("ERR wrong number of arguments for command")]
     IncorrectArgCount,
+    #[error("ERR syntax error")]
+    SyntaxError,
+    #[error("ERR invalid cursor")]
+    InvalidCursor,
+    #[error("ERR Protocol version is not an integer or out of range")]
+    InvalidProtocolVersion,
+    #[error("NOPROTO unsupported protocol version")]
+    UnsupportedProtocolVersion,
+    #[error("ERR Client names cannot contain spaces, newlines or special characters.")]
+    InvalidClientName,
+    #[error("ERR invalid expire time in '{0}' command")]
+    InvalidExpireTime(String),
+    #[error("ERR NX and XX, GT or LT options at the same time are not compatible")]
+    IncompatibleNxOption,
+    #[error("ERR GT and LT options at the same time are not compatible")]
+    IncompatibleGtLtOptions,
+    #[error("ERR invalid {0} DB index")]
+    InvalidDbIndex(String),
+    #[error("ERR Invalid TTL value, must be >= 0")]
+    InvalidTtl,
+    #[error("ERR Invalid or out of range slot")]
+    InvalidSlot,
+    #[error("ERR Number of keys can't be greater than number of args")]
+    TooManyKeys,
+    #[error("ERR Number of keys can't be negative")]
+    NegativeKeyCount,
+    #[error("ERR Protocol error: invalid multibulk length")]
+    InvalidMultibulkLength,
+    #[error("ERR Protocol error: invalid bulk length")]
+    InvalidBulkLength,
+    #[error("ERR Protocol error: too big inline request")]
+    InlineRequestTooBig,
     #[error("unknown request error")]
     Unknown,
 }
@@ -26,6 +58,135 @@ pub enum RequestError {
 pub enum ExecutionError {
     #[error("WRONGTYPE Operation against a key holding the wrong kind of value")]
     IncorrectType,
+    #[error("ERR no such key")]
+    NoSuchKey,
+    #[error("ERR source and destination objects are the same")]
+    SameObject,
+    #[error("ERR DB index is out of range")]
+    DbIndexOutOfRange,
+    #[error("ERR Background save already in progress")]
+    BackgroundSaveInProgress,
+    #[error("ERR failed to save snapshot. Details: {0}")]
+    SaveFailed(String),
+    #[error("ERR Background append only file rewriting already in progress")]
+    AofRewriteInProgress,
+    #[error("MISCONF Errors writing to the AOF file: {0}")]
+    AofWriteFailed(String),
+    #[error("READONLY You can't write against a read only replica.")]
+    ReadOnlyReplica,
+    #[error("BUSYKEY Target key name already exists.")]
+    BusyKey,
+    #[error("ERR DUMP payload version or checksum are wrong")]
+    InvalidDumpPayload,
+    #[error("IOERR error or timeout {0} target instance")]
+    MigrateIo(String),
+    #[error("ERR Target instance replied with error: {0}")]
+    MigrateTarget(String),
+    #[error("NOMASTERLINK Can't SYNC while not connected with my master")]
+    NoMasterLink,
+}
+
+#[derive(Error, Debug)]
+pub enum ClusterError {
+    #[error("ERR This instance has cluster support disabled")]
+    Disabled,
+    #[error("ERR SELECT is not allowed in cluster mode")]
+    SelectNotAllowed,
+    #[error("MOVED {0} {1}")]
+    Moved(u16, String),
+    #[error("ASK {0} {1}")]
+    Ask(u16, String),
+    #[error("CROSSSLOT Keys in request don't hash to the same slot")]
+    CrossSlot,
+    #[error("TRYAGAIN Multiple keys request during rehashing of slot")]
+    TryAgain,
+    #[error("CLUSTERDOWN Hash slot not served")]
+    SlotNotServed,
+    #[error("ERR Slot {0} is already busy")]
+    SlotBusy(u16),
+    #[error("ERR Slot {0} specified multiple times")]
+    DuplicateSlot(u16),
+    #[error("ERR Unknown node {0}")]
+    UnknownNode(String),
+    #[error("ERR Can't replicate myself")]
+    ReplicateMyself,
+    #[error("ERR I can only replicate a master, not a replica.")]
+    ReplicateReplica,
+    #[error("ERR To set a master the node must be empty and without assigned slots.")]
+    NotEmpty,
+    #[error("ERR Invalid node address specified: {0}")]
+    InvalidNodeAddress(String),
+    #[error("ERR REPLICAOF not allowed in cluster mode.")]
+    ReplicaOfNotAllowed,
+    #[error("ERR Please use SETSLOT only with masters.")]
+    SetSlotOnReplica,
+    #[error("ERR I'm not the owner of hash slot {0}")]
+    NotSlotOwner(u16),
+    #[error("ERR I'm already the owner of hash slot {0}")]
+    AlreadySlotOwner(u16),
+    #[error("ERR Target node is not a master")]
+    TargetNotMaster,
+    #[error(
+        "ERR Can't assign hashslot {0} to a different node while I still hold keys for this hash slot."
+    )]
+    SlotNotEmpty(u16),
+    #[error("ERR failed to save cluster config. Details: {0}")]
+    SaveFailed(String),
+    #[error("failed to read cluster config file `{0}`. Details: {1}")]
+    ReadFailed(String, String),
+    #[error("invalid cluster config at line {0}: {1}")]
+    InvalidConfig(usize, String),
+    #[error("invalid cluster config: no node is flagged as myself")]
+    MissingMyself,
+}
+
+#[derive(Error, Debug)]
+pub enum TransactionError {
+    #[error("ERR MULTI calls can not be nested")]
+    NestedMulti,
+    #[error("ERR EXEC without MULTI")]
+    ExecWithoutMulti,
+    #[error("ERR DISCARD without MULTI")]
+    DiscardWithoutMulti,
+    #[error("EXECABORT Transaction discarded because of previous errors.")]
+    ExecAbort,
+    #[error("ERR WATCH inside MULTI is not allowed")]
+    WatchInsideMulti,
+}
+
+#[derive(Error, Debug)]
+pub enum ScriptError {
+    #[error("NOSCRIPT No matching script. Please use EVAL.")]
+    NoScript,
+    #[error("ERR Error compiling script (new function): {0}")]
+    Compile(String),
+    #[error("ERR Error running script (call to f_{0}): {1}")]
+    Runtime(String, String),
+    /// An error reply returned by the script, e.g. the one of a command it called.
+    #[error("{0}")]
+    Reply(String),
+    #[error("ERR Please specify at least one argument for this redis lib call")]
+    NoArguments,
+    #[error("ERR Lua redis lib command arguments must be strings or integers")]
+    InvalidArgument,
+    #[error("ERR This Redis command is not allowed from script")]
+    NotAllowed,
+    #[error(
+        "BUSY Redis is busy running a script. You can only call SCRIPT KILL or SHUTDOWN NOSAVE."
+    )]
+    Busy,
+    #[error("NOTBUSY No scripts in execution right now.")]
+    NotBusy,
+    #[error("UNKILLABLE Sorry the script already executed write commands against the dataset. You can either wait the script termination or kill the server in a hard way using the SHUTDOWN NOSAVE command.")]
+    Unkillable,
+    #[error("ERR Script killed by user with SCRIPT KILL...")]
+    Killed,
+}
+
+#[derive(Error, Debug)]
+pub enum PubSubError {
+    #[error("ERR Can't execute '{0}': only (P)SUBSCRIBE / (P)UNSUBSCRIBE / PING / QUIT are allowed in this context")]
+    SubscriberContext(String),
 }
 
 #[derive(Error, Debug)]
@@ -57,3 +218,41 @@ pub enum StreamError {
     #[error("The stream has exhausted the last possible ID, unable to add more items")]
     IdExhausted,
 }
+
+#[derive(Error, Debug)]
+pub enum ConfigError {
+    #[error("failed to read config file `{0}`. Details: {1}")]
+    ReadFailed(String, String),
+    #[error("invalid config at line {0}: {1}")]
+    InvalidDirective(usize, String),
+    #[error("invalid option `{0}`")]
+    InvalidOption(String),
+}
+
+#[derive(Error, Debug)]
+pub enum SnapshotError {
+    #[error("failed to read snapshot `{0}`. Details: {1}")]
+    ReadFailed(String, String),
+    #[error("not a snapshot file")]
+    InvalidHeader,
+    #[error("unsupported snapshot version {0}")]
+    UnsupportedVersion(u32),
+    #[error("unexpected end of snapshot")]
+    UnexpectedEnd,
+    #[error("invalid snapshot data. Details: {0}")]
+    InvalidData(String),
+    #[error("snapshot database index {0} is out of range")]
+    DbIndexOutOfRange(u64),
+    #[error("unsupported snapshot object type {0}")]
+    UnsupportedType(u8),
+    #[error("snapshot checksum mismatch: expected {0:#018x}, got {1:#018x}")]
+    ChecksumMismatch(u64, u64),
+}
+
+#[derive(Error, Debug)]
+pub enum AofError {
+    #[error("failed to read append-only file `{0}`. Details: {1}")]
+    ReadFailed(String, String),
+    #[error("invalid append-only file at offset {0}. Details: {1}")]
+    InvalidRecord(usize, String),
+}
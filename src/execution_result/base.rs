@@ -1,4 +1,6 @@
+use crate::execution_result::RespVersion;
+
 pub trait ExecutionResult {
     fn to_string(&self) -> String;
-    fn serialise(&self) -> String;
+    fn serialise(&self, protocol: RespVersion) -> Vec<u8>;
 }
@@ -0,0 +1,60 @@
+use crate::execution_result::{
+    ArrayReply, BulkStringReply, ExecutionResult, IntegerReply, MapReply, RespReply, RespVersion,
+};
+
+pub struct HelloResult {
+    pub id: u64,
+    pub protocol: RespVersion,
+}
+
+impl HelloResult {
+    fn protocol_number(&self) -> i64 {
+        match self.protocol {
+            RespVersion::Resp2 => 2,
+            RespVersion::Resp3 => 3,
+        }
+    }
+}
+
+fn bulk_string(value: &str) -> Box<dyn RespReply> {
+    Box::new(BulkStringReply {
+        value: value.as_bytes().to_vec(),
+    })
+}
+
+impl ExecutionResult for HelloResult {
+    fn to_string(&self) -> String {
+        format!(
+            "server,redis,version,7.2.0,proto,{},id,{},mode,standalone,role,master,modules,",
+            self.protocol_number(),
+            self.id
+        )
+    }
+    fn serialise(&self, protocol: RespVersion) -> Vec<u8> {
+        MapReply {
+            values: vec![
+                (bulk_string("server"), bulk_string("redis")),
+                (bulk_string("version"), bulk_string("7.2.0")),
+                (
+                    bulk_string("proto"),
+                    Box::new(IntegerReply {
+                        value: self.protocol_number(),
+                    }),
+                ),
+                (
+                    bulk_string("id"),
+                    Box::new(IntegerReply {
+                        value: self.id as i64,
+                    }),
+                ),
+                (bulk_string("mode"), bulk_string("standalone")),
+                (bulk_string("role"), bulk_string("master")),
+                (
+                    bulk_string("modules"),
+                    Box::new(ArrayReply { values: vec![] }),
+                ),
+            ],
+        }
+        .serialise(protocol)
+    }
+}
@@ -0,0 +1,2 @@
+mod hello;
+pub use hello::HelloResult;
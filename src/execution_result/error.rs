@@ -1,17 +1,31 @@
-use crate::execution_result::{ExecutionResult, RespReply, SimpleErrorReply};
+use crate::execution_result::{ExecutionResult, RespReply, RespVersion, SimpleErrorReply};
 
 pub struct ErrorResult {
     pub message: String,
 }
 
+impl ErrorResult {
+    /// Clients read the first word of an error reply as its error code, e.g. `ERR` or
+    /// `WRONGTYPE`, so messages that do not start with one are reported as generic errors.
+    fn has_error_code(&self) -> bool {
+        match self.message.split(' ').next() {
+            Some(code) => !code.is_empty() && code.chars().all(|c| c.is_ascii_uppercase()),
+            None => false,
+        }
+    }
+}
+
 impl ExecutionResult for ErrorResult {
     fn to_string(&self) -> String {
         self.message.clone()
     }
-    fn serialise(&self) -> String {
+    fn serialise(&self, protocol: RespVersion) -> Vec<u8> {
         SimpleErrorReply {
-            message: self.to_string(),
+            message: match self.has_error_code() {
+                true => self.to_string(),
+                false => format!("ERR {}", self.message),
+            },
         }
-        .serialise()
+        .serialise(protocol)
     }
 }
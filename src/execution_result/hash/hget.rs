@@ -1,20 +1,22 @@
-use crate::execution_result::{ExecutionResult, NullReply, RespReply, SimpleStringReply};
+use crate::execution_result::{
+    BulkStringReply, ExecutionResult, NullReply, RespReply, RespVersion,
+};
 
 pub struct HGetResult {
-    pub value: Option<String>,
+    pub value: Option<Vec<u8>>,
 }
 
 impl ExecutionResult for HGetResult {
     fn to_string(&self) -> String {
         match &self.value {
-            Some(v) => v.clone(),
+            Some(v) => String::from_utf8_lossy(v).to_string(),
             None => "".to_string(),
         }
     }
-    fn serialise(&self) -> String {
+    fn serialise(&self, protocol: RespVersion) -> Vec<u8> {
         match &self.value {
-            Some(v) => SimpleStringReply { value: v.clone() }.serialise(),
-            None => NullReply {}.serialise(),
+            Some(v) => BulkStringReply { value: v.clone() }.serialise(protocol),
+            None => NullReply {}.serialise(protocol),
         }
     }
 }
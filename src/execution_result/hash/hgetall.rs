@@ -1,18 +1,30 @@
-use crate::execution_result::{ArrayReply, BulkStringReply, ExecutionResult, RespReply};
+use crate::execution_result::{BulkStringReply, ExecutionResult, MapReply, RespReply, RespVersion};
 
 pub struct HGetAllResult {
-    pub values: Vec<String>,
+    pub values: Vec<Vec<u8>>,
 }
 
 impl ExecutionResult for HGetAllResult {
     fn to_string(&self) -> String {
-        self.values.join(",")
+        self.values
+            .iter()
+            .map(|v| String::from_utf8_lossy(v))
+            .collect::<Vec<_>>()
+            .join(",")
     }
-    fn serialise(&self) -> String {
-        let mut rs: Vec<Box<dyn RespReply>> = Vec::new();
-        for vv in &self.values {
-            rs.push(Box::new(BulkStringReply { value: vv.clone() }))
+    fn serialise(&self, protocol: RespVersion) -> Vec<u8> {
+        // `values` holds the fields and their values interleaved.
+        let mut rs: Vec<(Box<dyn RespReply>, Box<dyn RespReply>)> = Vec::new();
+        for pair in self.values.chunks(2) {
+            rs.push((
+                Box::new(BulkStringReply {
+                    value: pair[0].clone(),
+                }),
+                Box::new(BulkStringReply {
+                    value: pair[1].clone(),
+                }),
+            ))
         }
-        ArrayReply { values: rs }.serialise()
+        MapReply { values: rs }.serialise(protocol)
     }
 }
@@ -0,0 +1,21 @@
+use crate::execution_result::{ExecutionResult, IntegerReply, RespReply, RespVersion};
+
+/// The number of keys a command found or acted on.
+pub struct DelResult {
+    pub value: usize,
+}
+
+impl ExecutionResult for DelResult {
+    fn to_string(&self) -> String {
+        self.value.to_string()
+    }
+    fn serialise(&self, protocol: RespVersion) -> Vec<u8> {
+        IntegerReply {
+            value: self.value as i64,
+        }
+        .serialise(protocol)
+    }
+}
+
+pub type ExistsResult = DelResult;
+pub type DbSizeResult = DelResult;
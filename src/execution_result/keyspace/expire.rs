@@ -0,0 +1,22 @@
+use crate::execution_result::{ExecutionResult, IntegerReply, RespReply, RespVersion};
+
+pub struct ExpireResult {
+    pub value: bool,
+}
+
+impl ExecutionResult for ExpireResult {
+    fn to_string(&self) -> String {
+        (self.value as u8).to_string()
+    }
+    fn serialise(&self, protocol: RespVersion) -> Vec<u8> {
+        IntegerReply {
+            value: self.value as i64,
+        }
+        .serialise(protocol)
+    }
+}
+
+pub type PersistResult = ExpireResult;
+pub type RenameNxResult = ExpireResult;
+pub type CopyResult = ExpireResult;
+pub type MoveResult = ExpireResult;
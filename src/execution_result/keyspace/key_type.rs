@@ -0,0 +1,18 @@
+use crate::data_store::RedisEntryType;
+use crate::execution_result::{ExecutionResult, RespReply, RespVersion, SimpleStringReply};
+
+pub struct TypeResult {
+    pub value: RedisEntryType,
+}
+
+impl ExecutionResult for TypeResult {
+    fn to_string(&self) -> String {
+        self.value.name().to_string()
+    }
+    fn serialise(&self, protocol: RespVersion) -> Vec<u8> {
+        SimpleStringReply {
+            value: self.to_string(),
+        }
+        .serialise(protocol)
+    }
+}
@@ -0,0 +1,24 @@
+use crate::execution_result::{
+    ArrayReply, BulkStringReply, ExecutionResult, RespReply, RespVersion,
+};
+
+pub struct KeysResult {
+    pub values: Vec<Vec<u8>>,
+}
+
+impl ExecutionResult for KeysResult {
+    fn to_string(&self) -> String {
+        self.values
+            .iter()
+            .map(|v| String::from_utf8_lossy(v))
+            .collect::<Vec<_>>()
+            .join(",")
+    }
+    fn serialise(&self, protocol: RespVersion) -> Vec<u8> {
+        let mut rs: Vec<Box<dyn RespReply>> = Vec::new();
+        for vv in &self.values {
+            rs.push(Box::new(BulkStringReply { value: vv.clone() }))
+        }
+        ArrayReply { values: rs }.serialise(protocol)
+    }
+}
@@ -0,0 +1,17 @@
+mod count;
+pub use count::{DbSizeResult, DelResult, ExistsResult};
+mod expire;
+pub use expire::{CopyResult, ExpireResult, MoveResult, PersistResult, RenameNxResult};
+mod keys;
+pub use keys::KeysResult;
+mod key_type;
+pub use key_type::TypeResult;
+mod rename;
+pub use rename::RenameResult;
+mod scan;
+pub use scan::ScanResult;
+mod ttl;
+pub use ttl::TtlResult;
+
+/// The reply of `DUMP`: the serialised value, or nil if the key does not exist.
+pub type DumpResult = crate::execution_result::string::GetResult;
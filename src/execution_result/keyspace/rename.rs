@@ -0,0 +1,15 @@
+use crate::execution_result::{ExecutionResult, RespReply, RespVersion, SimpleStringReply};
+
+pub struct RenameResult;
+
+impl ExecutionResult for RenameResult {
+    fn to_string(&self) -> String {
+        "OK".to_string()
+    }
+    fn serialise(&self, protocol: RespVersion) -> Vec<u8> {
+        SimpleStringReply {
+            value: self.to_string(),
+        }
+        .serialise(protocol)
+    }
+}
@@ -0,0 +1,37 @@
+use crate::execution_result::{
+    ArrayReply, BulkStringReply, ExecutionResult, RespReply, RespVersion,
+};
+
+/// A page of a `SCAN` family iteration.
+pub struct ScanResult {
+    /// The cursor to continue from, or 0 once the iteration is complete.
+    pub cursor: u64,
+    pub values: Vec<Vec<u8>>,
+}
+
+impl ExecutionResult for ScanResult {
+    fn to_string(&self) -> String {
+        let mut rs = vec![self.cursor.to_string()];
+        rs.extend(
+            self.values
+                .iter()
+                .map(|v| String::from_utf8_lossy(v).to_string()),
+        );
+        rs.join(",")
+    }
+    fn serialise(&self, protocol: RespVersion) -> Vec<u8> {
+        let mut rs: Vec<Box<dyn RespReply>> = Vec::new();
+        for vv in &self.values {
+            rs.push(Box::new(BulkStringReply { value: vv.clone() }))
+        }
+        ArrayReply {
+            values: vec![
+                Box::new(BulkStringReply {
+                    value: self.cursor.to_string().into_bytes(),
+                }),
+                Box::new(ArrayReply { values: rs }),
+            ],
+        }
+        .serialise(protocol)
+    }
+}
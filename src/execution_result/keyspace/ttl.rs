@@ -0,0 +1,14 @@
+use crate::execution_result::{ExecutionResult, IntegerReply, RespReply, RespVersion};
+
+pub struct TtlResult {
+    pub value: i64,
+}
+
+impl ExecutionResult for TtlResult {
+    fn to_string(&self) -> String {
+        self.value.to_string()
+    }
+    fn serialise(&self, protocol: RespVersion) -> Vec<u8> {
+        IntegerReply { value: self.value }.serialise(protocol)
+    }
+}
@@ -1,22 +1,29 @@
 use crate::execution_result::{
-    ArrayReply, BulkStringReply, ExecutionResult, NullReply, RespReply, SimpleStringReply,
+    ArrayReply, BulkStringReply, ExecutionResult, NullArrayReply, NullReply, RespReply, RespVersion,
 };
 
 pub struct PopResult {
-    pub values: Vec<String>,
+    pub values: Vec<Vec<u8>>,
+    /// Whether a count was given, in which case the values are always replied as an array.
+    pub with_count: bool,
 }
 
 impl ExecutionResult for PopResult {
     fn to_string(&self) -> String {
-        self.values.join(",")
+        self.values
+            .iter()
+            .map(|v| String::from_utf8_lossy(v))
+            .collect::<Vec<_>>()
+            .join(",")
     }
-    fn serialise(&self) -> String {
-        let v: Box<dyn RespReply> = match self.values.len() {
-            0 => Box::new(NullReply {}),
-            1 => Box::new(SimpleStringReply {
+    fn serialise(&self, protocol: RespVersion) -> Vec<u8> {
+        let v: Box<dyn RespReply> = match (self.with_count, self.values.len()) {
+            (false, 0) => Box::new(NullReply {}),
+            (false, _) => Box::new(BulkStringReply {
                 value: self.values[0].clone(),
             }),
-            _ => {
+            (true, 0) => Box::new(NullArrayReply {}),
+            (true, _) => {
                 let mut rs: Vec<Box<dyn RespReply>> = Vec::new();
                 for vv in &self.values {
                     rs.push(Box::new(BulkStringReply { value: vv.clone() }))
@@ -24,6 +31,6 @@ impl ExecutionResult for PopResult {
                 Box::new(ArrayReply { values: rs })
             }
         };
-        v.serialise()
+        v.serialise(protocol)
     }
 }
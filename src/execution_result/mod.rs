@@ -7,8 +7,11 @@ pub use error::ErrorResult;
 mod ping;
 pub use ping::PingResult;
 
+pub mod connection;
 pub mod hash;
+pub mod keyspace;
 pub mod list;
+pub mod server;
 pub mod set;
 pub mod sorted_set;
 pub mod string;
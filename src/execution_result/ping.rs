@@ -1,14 +1,31 @@
-pub struct PingResult;
-use crate::execution_result::{ExecutionResult, RespReply, SimpleStringReply};
+/// The reply of `PING`. A RESP2 connection in subscriber mode can only carry pushes, so it is
+/// answered with a `pong` push instead.
+pub struct PingResult {
+    pub subscribed: bool,
+}
+use crate::execution_result::{
+    BulkStringReply, ExecutionResult, PushReply, RespReply, RespVersion, SimpleStringReply,
+};
 
 impl ExecutionResult for PingResult {
     fn to_string(&self) -> String {
         "OK".to_string()
     }
-    fn serialise(&self) -> String {
-        SimpleStringReply {
-            value: "OK".to_string(),
+    fn serialise(&self, protocol: RespVersion) -> Vec<u8> {
+        match (self.subscribed, protocol) {
+            (true, RespVersion::Resp2) => PushReply {
+                values: vec![
+                    Box::new(BulkStringReply {
+                        value: b"pong".to_vec(),
+                    }),
+                    Box::new(BulkStringReply { value: Vec::new() }),
+                ],
+            }
+            .serialise(protocol),
+            _ => SimpleStringReply {
+                value: "OK".to_string(),
+            }
+            .serialise(protocol),
         }
-        .serialise()
     }
 }
@@ -1,5 +1,33 @@
+/// The version of the RESP protocol negotiated with a client. Replies are encoded as RESP2
+/// unless the client switches to RESP3 with `HELLO 3`.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum RespVersion {
+    #[default]
+    Resp2,
+    Resp3,
+}
+
 pub trait RespReply {
-    fn serialise(&self) -> String;
+    fn serialise(&self, version: RespVersion) -> Vec<u8>;
+}
+
+fn serialise_aggregate(
+    prefix: &str,
+    len: usize,
+    values: &[Box<dyn RespReply>],
+    version: RespVersion,
+) -> Vec<u8> {
+    let mut res = format!("{}{}\r\n", prefix, len).into_bytes();
+    for v in values {
+        res.extend(v.serialise(version));
+    }
+    res
+}
+
+/// Replaces the line breaks of a status or error line with spaces, as the client would read the
+/// first one as the end of the reply, and the rest as other replies.
+fn single_line(line: &str) -> String {
+    line.replace(['\r', '\n'], " ")
 }
 
 pub struct SimpleStringReply {
@@ -7,8 +35,8 @@ pub struct SimpleStringReply {
 }
 
 impl RespReply for SimpleStringReply {
-    fn serialise(&self) -> String {
-        format!("+{}\r\n", &self.value)
+    fn serialise(&self, _: RespVersion) -> Vec<u8> {
+        format!("+{}\r\n", single_line(&self.value)).into_bytes()
     }
 }
 
@@ -17,15 +45,30 @@ pub struct SimpleErrorReply {
 }
 
 impl RespReply for SimpleErrorReply {
-    fn serialise(&self) -> String {
-        format!("-{}\r\n", &self.message)
+    fn serialise(&self, _: RespVersion) -> Vec<u8> {
+        format!("-{}\r\n", single_line(&self.message)).into_bytes()
     }
 }
 
+/// A missing value. RESP2 has no dedicated null type and uses the null bulk string instead.
 pub struct NullReply;
 impl RespReply for NullReply {
-    fn serialise(&self) -> String {
-        "_\r\n".to_string()
+    fn serialise(&self, version: RespVersion) -> Vec<u8> {
+        match version {
+            RespVersion::Resp2 => b"$-1\r\n".to_vec(),
+            RespVersion::Resp3 => b"_\r\n".to_vec(),
+        }
+    }
+}
+
+/// A missing aggregate. RESP2 has no dedicated null type and uses the null array instead.
+pub struct NullArrayReply;
+impl RespReply for NullArrayReply {
+    fn serialise(&self, version: RespVersion) -> Vec<u8> {
+        match version {
+            RespVersion::Resp2 => b"*-1\r\n".to_vec(),
+            RespVersion::Resp3 => b"_\r\n".to_vec(),
+        }
     }
 }
 
@@ -33,8 +76,8 @@ pub struct IntegerReply {
     pub value: i64,
 }
 impl RespReply for IntegerReply {
-    fn serialise(&self) -> String {
-        format!(":{}\r\n", self.value)
+    fn serialise(&self, _: RespVersion) -> Vec<u8> {
+        format!(":{}\r\n", self.value).into_bytes()
     }
 }
 
@@ -42,17 +85,64 @@ pub struct UnsignedIntegerReply {
     pub value: u64,
 }
 impl RespReply for UnsignedIntegerReply {
-    fn serialise(&self) -> String {
-        format!(":{}\r\n", self.value)
+    fn serialise(&self, _: RespVersion) -> Vec<u8> {
+        format!(":{}\r\n", self.value).into_bytes()
+    }
+}
+
+/// A floating point number. RESP2 clients receive it as a bulk string.
+pub struct DoubleReply {
+    pub value: f64,
+}
+impl DoubleReply {
+    fn format(&self) -> String {
+        if self.value.is_nan() {
+            "nan".to_string()
+        } else if self.value.is_infinite() {
+            match self.value.is_sign_positive() {
+                true => "inf".to_string(),
+                false => "-inf".to_string(),
+            }
+        } else {
+            self.value.to_string()
+        }
+    }
+}
+impl RespReply for DoubleReply {
+    fn serialise(&self, version: RespVersion) -> Vec<u8> {
+        match version {
+            RespVersion::Resp2 => BulkStringReply {
+                value: self.format().into_bytes(),
+            }
+            .serialise(version),
+            RespVersion::Resp3 => format!(",{}\r\n", self.format()).into_bytes(),
+        }
+    }
+}
+
+/// A boolean. RESP2 clients receive it as the integer 1 or 0.
+pub struct BooleanReply {
+    pub value: bool,
+}
+impl RespReply for BooleanReply {
+    fn serialise(&self, version: RespVersion) -> Vec<u8> {
+        match (version, self.value) {
+            (RespVersion::Resp2, v) => format!(":{}\r\n", v as u8).into_bytes(),
+            (RespVersion::Resp3, true) => b"#t\r\n".to_vec(),
+            (RespVersion::Resp3, false) => b"#f\r\n".to_vec(),
+        }
     }
 }
 
 pub struct BulkStringReply {
-    pub value: String,
+    pub value: Vec<u8>,
 }
 impl RespReply for BulkStringReply {
-    fn serialise(&self) -> String {
-        format!("${}\r\n{}\r\n", self.value.len(), &self.value)
+    fn serialise(&self, _: RespVersion) -> Vec<u8> {
+        let mut res = format!("${}\r\n", self.value.len()).into_bytes();
+        res.extend_from_slice(&self.value);
+        res.extend_from_slice(b"\r\n");
+        res
     }
 }
 
@@ -60,11 +150,108 @@ pub struct ArrayReply {
     pub values: Vec<Box<dyn RespReply>>,
 }
 impl RespReply for ArrayReply {
-    fn serialise(&self) -> String {
-        let mut res = format!("*{}\r\n", self.values.len());
-        for v in &self.values {
-            res += v.serialise().as_str();
+    fn serialise(&self, version: RespVersion) -> Vec<u8> {
+        serialise_aggregate("*", self.values.len(), &self.values, version)
+    }
+}
+
+/// An unordered collection of distinct elements. RESP2 clients receive it as an array.
+pub struct SetReply {
+    pub values: Vec<Box<dyn RespReply>>,
+}
+impl RespReply for SetReply {
+    fn serialise(&self, version: RespVersion) -> Vec<u8> {
+        let prefix = match version {
+            RespVersion::Resp2 => "*",
+            RespVersion::Resp3 => "~",
+        };
+        serialise_aggregate(prefix, self.values.len(), &self.values, version)
+    }
+}
+
+/// An out-of-band message pushed to the client. RESP2 clients receive it as an array.
+pub struct PushReply {
+    pub values: Vec<Box<dyn RespReply>>,
+}
+impl RespReply for PushReply {
+    fn serialise(&self, version: RespVersion) -> Vec<u8> {
+        let prefix = match version {
+            RespVersion::Resp2 => "*",
+            RespVersion::Resp3 => ">",
+        };
+        serialise_aggregate(prefix, self.values.len(), &self.values, version)
+    }
+}
+
+/// Key-value pairs. RESP2 clients receive them as a flat array of alternating keys and values.
+pub struct MapReply {
+    pub values: Vec<(Box<dyn RespReply>, Box<dyn RespReply>)>,
+}
+impl RespReply for MapReply {
+    fn serialise(&self, version: RespVersion) -> Vec<u8> {
+        let mut res = match version {
+            RespVersion::Resp2 => format!("*{}\r\n", self.values.len() * 2).into_bytes(),
+            RespVersion::Resp3 => format!("%{}\r\n", self.values.len()).into_bytes(),
+        };
+        for (k, v) in &self.values {
+            res.extend(k.serialise(version));
+            res.extend(v.serialise(version));
         }
         res
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn should_serialise_null_per_version() {
+        assert_eq!(NullReply {}.serialise(RespVersion::Resp2), b"$-1\r\n");
+        assert_eq!(NullReply {}.serialise(RespVersion::Resp3), b"_\r\n");
+        assert_eq!(NullArrayReply {}.serialise(RespVersion::Resp2), b"*-1\r\n");
+        assert_eq!(NullArrayReply {}.serialise(RespVersion::Resp3), b"_\r\n");
+    }
+
+    #[test]
+    fn should_serialise_double_per_version() {
+        let reply = DoubleReply { value: 1.5 };
+        assert_eq!(reply.serialise(RespVersion::Resp2), b"$3\r\n1.5\r\n");
+        assert_eq!(reply.serialise(RespVersion::Resp3), b",1.5\r\n");
+        let reply = DoubleReply {
+            value: -f64::INFINITY,
+        };
+        assert_eq!(reply.serialise(RespVersion::Resp3), b",-inf\r\n");
+    }
+
+    #[test]
+    fn should_serialise_map_per_version() {
+        let reply = MapReply {
+            values: vec![(
+                Box::new(BulkStringReply {
+                    value: b"k".to_vec(),
+                }),
+                Box::new(IntegerReply { value: 1 }),
+            )],
+        };
+        assert_eq!(
+            reply.serialise(RespVersion::Resp2),
+            b"*2\r\n$1\r\nk\r\n:1\r\n"
+        );
+        assert_eq!(
+            reply.serialise(RespVersion::Resp3),
+            b"%1\r\n$1\r\nk\r\n:1\r\n"
+        );
+    }
+
+    #[test]
+    fn should_serialise_set_per_version() {
+        let reply = SetReply {
+            values: vec![Box::new(BulkStringReply {
+                value: b"a".to_vec(),
+            })],
+        };
+        assert_eq!(reply.serialise(RespVersion::Resp2), b"*1\r\n$1\r\na\r\n");
+        assert_eq!(reply.serialise(RespVersion::Resp3), b"~1\r\n$1\r\na\r\n");
+    }
+}
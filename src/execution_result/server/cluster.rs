@@ -0,0 +1,152 @@
+use crate::cluster::{Node, Shard, SlotRange};
+use crate::execution_result::keyspace::DelResult;
+use crate::execution_result::server::InfoResult;
+use crate::execution_result::{
+    ArrayReply, BulkStringReply, ExecutionResult, IntegerReply, MapReply, RespReply, RespVersion,
+    UnsignedIntegerReply,
+};
+
+pub type KeySlotResult = DelResult;
+/// The reply of `CLUSTER COUNTKEYSINSLOT`.
+pub type CountKeysInSlotResult = DelResult;
+/// The reply of `CLUSTER GETKEYSINSLOT`.
+pub type GetKeysInSlotResult = crate::execution_result::keyspace::KeysResult;
+/// The replies of `CLUSTER INFO`, `CLUSTER NODES` and `CLUSTER MYID`.
+pub type ClusterTextResult = InfoResult;
+
+fn bulk(value: &str) -> Box<dyn RespReply> {
+    Box::new(BulkStringReply {
+        value: value.as_bytes().to_vec(),
+    })
+}
+
+pub struct ClusterSlotsResult {
+    pub ranges: Vec<SlotRange>,
+}
+
+impl ExecutionResult for ClusterSlotsResult {
+    fn to_string(&self) -> String {
+        self.ranges
+            .iter()
+            .map(|range| {
+                let nodes: Vec<String> = range.nodes.iter().map(Node::addr).collect();
+                format!("{}-{} {}", range.start, range.end, nodes.join(","))
+            })
+            .collect::<Vec<String>>()
+            .join("\n")
+    }
+    fn serialise(&self, protocol: RespVersion) -> Vec<u8> {
+        let mut ranges: Vec<Box<dyn RespReply>> = Vec::new();
+        for range in &self.ranges {
+            let mut values: Vec<Box<dyn RespReply>> = vec![
+                Box::new(IntegerReply {
+                    value: range.start as i64,
+                }),
+                Box::new(IntegerReply {
+                    value: range.end as i64,
+                }),
+            ];
+            for node in &range.nodes {
+                values.push(Box::new(ArrayReply {
+                    values: vec![
+                        bulk(&node.host),
+                        Box::new(IntegerReply {
+                            value: node.port as i64,
+                        }),
+                        bulk(&node.id),
+                    ],
+                }));
+            }
+            ranges.push(Box::new(ArrayReply { values }));
+        }
+        ArrayReply { values: ranges }.serialise(protocol)
+    }
+}
+
+pub struct ClusterShardsResult {
+    pub shards: Vec<Shard>,
+    /// The ID of this node, whose replication offset is the current one rather than the one it
+    /// last gossiped.
+    pub myself: String,
+    pub offset: u64,
+}
+
+impl ClusterShardsResult {
+    fn node_reply(&self, node: &Node) -> Box<dyn RespReply> {
+        let role = match node.is_master() {
+            true => "master",
+            false => "replica",
+        };
+        let offset = match node.id == self.myself {
+            true => self.offset,
+            false => node.offset,
+        };
+        let fields: Vec<(&str, Box<dyn RespReply>)> = vec![
+            ("id", bulk(&node.id)),
+            (
+                "port",
+                Box::new(IntegerReply {
+                    value: node.port as i64,
+                }),
+            ),
+            ("ip", bulk(&node.host)),
+            ("endpoint", bulk(&node.host)),
+            ("role", bulk(role)),
+            (
+                "replication-offset",
+                Box::new(UnsignedIntegerReply { value: offset }),
+            ),
+            (
+                "health",
+                bulk(match node.fail || node.pfail {
+                    true => "fail",
+                    false => "online",
+                }),
+            ),
+        ];
+        Box::new(MapReply {
+            values: fields
+                .into_iter()
+                .map(|(name, value)| (bulk(name), value))
+                .collect(),
+        })
+    }
+}
+
+impl ExecutionResult for ClusterShardsResult {
+    fn to_string(&self) -> String {
+        self.shards
+            .iter()
+            .map(|shard| {
+                let slots: Vec<String> = shard
+                    .slots
+                    .iter()
+                    .map(|(start, end)| format!("{}-{}", start, end))
+                    .collect();
+                let nodes: Vec<String> = shard.nodes.iter().map(Node::addr).collect();
+                format!("{} {}", slots.join(","), nodes.join(","))
+            })
+            .collect::<Vec<String>>()
+            .join("\n")
+    }
+    fn serialise(&self, protocol: RespVersion) -> Vec<u8> {
+        let mut shards: Vec<Box<dyn RespReply>> = Vec::new();
+        for shard in &self.shards {
+            let mut slots: Vec<Box<dyn RespReply>> = Vec::new();
+            for &(start, end) in &shard.slots {
+                slots.push(Box::new(IntegerReply {
+                    value: start as i64,
+                }));
+                slots.push(Box::new(IntegerReply { value: end as i64 }));
+            }
+            let nodes = shard.nodes.iter().map(|n| self.node_reply(n)).collect();
+            shards.push(Box::new(MapReply {
+                values: vec![
+                    (bulk("slots"), Box::new(ArrayReply { values: slots })),
+                    (bulk("nodes"), Box::new(ArrayReply { values: nodes })),
+                ],
+            }));
+        }
+        ArrayReply { values: shards }.serialise(protocol)
+    }
+}
@@ -0,0 +1,21 @@
+use crate::execution_result::{ExecutionResult, RespReply, RespVersion, SimpleStringReply};
+
+/// The reply of `MIGRATE`, which is `NOKEY` when none of the keys to move exist.
+pub struct MigrateResult {
+    pub migrated: bool,
+}
+
+impl ExecutionResult for MigrateResult {
+    fn to_string(&self) -> String {
+        match self.migrated {
+            true => "OK".to_string(),
+            false => "NOKEY".to_string(),
+        }
+    }
+    fn serialise(&self, protocol: RespVersion) -> Vec<u8> {
+        SimpleStringReply {
+            value: self.to_string(),
+        }
+        .serialise(protocol)
+    }
+}
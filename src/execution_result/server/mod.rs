@@ -0,0 +1,19 @@
+mod cluster;
+pub use cluster::{
+    ClusterShardsResult, ClusterSlotsResult, ClusterTextResult, CountKeysInSlotResult,
+    GetKeysInSlotResult, KeySlotResult,
+};
+mod migrate;
+pub use migrate::MigrateResult;
+mod ok;
+pub use ok::OkResult;
+mod pubsub;
+pub use pubsub::{NumSubResult, PubSubChannelsResult, PublishResult, SubscriptionResult};
+mod replication;
+pub use replication::{InfoResult, NoReplyResult, ReplicaOfResult, SyncResult};
+mod save;
+pub use save::{BackgroundRewriteAofResult, BackgroundSaveResult, LastSaveResult};
+mod script;
+pub use script::{ScriptExistsResult, ScriptLoadResult, ScriptResult};
+mod transaction;
+pub use transaction::{ExecResult, QueuedResult};
@@ -0,0 +1,16 @@
+use crate::execution_result::{ExecutionResult, RespReply, RespVersion, SimpleStringReply};
+
+/// The reply of commands that have nothing to report but their success.
+pub struct OkResult;
+
+impl ExecutionResult for OkResult {
+    fn to_string(&self) -> String {
+        "OK".to_string()
+    }
+    fn serialise(&self, protocol: RespVersion) -> Vec<u8> {
+        SimpleStringReply {
+            value: self.to_string(),
+        }
+        .serialise(protocol)
+    }
+}
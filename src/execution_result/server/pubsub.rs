@@ -0,0 +1,94 @@
+use crate::execution_result::keyspace::DelResult;
+use crate::execution_result::{
+    BulkStringReply, ExecutionResult, IntegerReply, MapReply, NullReply, PushReply, RespReply,
+    RespVersion,
+};
+
+/// The reply of `PUBLISH`, which is the number of clients that received the message.
+pub type PublishResult = DelResult;
+/// The reply of `PUBSUB CHANNELS`.
+pub type PubSubChannelsResult = crate::execution_result::keyspace::KeysResult;
+
+/// The replies of `SUBSCRIBE` and `UNSUBSCRIBE`: one push per channel, with the number of
+/// subscriptions the client has left afterwards. Unsubscribing from everything while subscribed
+/// to nothing gets a single reply without a channel.
+pub struct SubscriptionResult {
+    pub kind: &'static str,
+    pub channels: Vec<(Option<Vec<u8>>, usize)>,
+}
+
+impl ExecutionResult for SubscriptionResult {
+    fn to_string(&self) -> String {
+        self.channels
+            .iter()
+            .map(|(channel, count)| {
+                let channel = channel.as_deref().unwrap_or_default();
+                format!(
+                    "{},{},{}",
+                    self.kind,
+                    String::from_utf8_lossy(channel),
+                    count
+                )
+            })
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+    fn serialise(&self, protocol: RespVersion) -> Vec<u8> {
+        let mut res = Vec::new();
+        for (channel, count) in &self.channels {
+            let channel: Box<dyn RespReply> = match channel {
+                Some(channel) => Box::new(BulkStringReply {
+                    value: channel.clone(),
+                }),
+                None => Box::new(NullReply),
+            };
+            let reply = PushReply {
+                values: vec![
+                    Box::new(BulkStringReply {
+                        value: self.kind.as_bytes().to_vec(),
+                    }),
+                    channel,
+                    Box::new(IntegerReply {
+                        value: *count as i64,
+                    }),
+                ],
+            };
+            res.extend(reply.serialise(protocol));
+        }
+        res
+    }
+}
+
+/// The reply of `PUBSUB NUMSUB`, which is the number of subscribers of each channel.
+pub struct NumSubResult {
+    pub values: Vec<(Vec<u8>, usize)>,
+}
+
+impl ExecutionResult for NumSubResult {
+    fn to_string(&self) -> String {
+        self.values
+            .iter()
+            .map(|(channel, count)| format!("{},{}", String::from_utf8_lossy(channel), count))
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+    fn serialise(&self, protocol: RespVersion) -> Vec<u8> {
+        MapReply {
+            values: self
+                .values
+                .iter()
+                .map(|(channel, count)| {
+                    (
+                        Box::new(BulkStringReply {
+                            value: channel.clone(),
+                        }) as Box<dyn RespReply>,
+                        Box::new(IntegerReply {
+                            value: *count as i64,
+                        }) as Box<dyn RespReply>,
+                    )
+                })
+                .collect(),
+        }
+        .serialise(protocol)
+    }
+}
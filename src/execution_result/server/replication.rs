@@ -0,0 +1,66 @@
+use crate::execution_result::{
+    BulkStringReply, ExecutionResult, RespReply, RespVersion, SimpleStringReply,
+};
+
+pub struct ReplicaOfResult {
+    /// Whether the server already replicated from the requested master.
+    pub already_connected: bool,
+}
+
+impl ExecutionResult for ReplicaOfResult {
+    fn to_string(&self) -> String {
+        match self.already_connected {
+            true => "OK Already connected to specified master".to_string(),
+            false => "OK".to_string(),
+        }
+    }
+    fn serialise(&self, protocol: RespVersion) -> Vec<u8> {
+        SimpleStringReply {
+            value: self.to_string(),
+        }
+        .serialise(protocol)
+    }
+}
+
+/// The reply to `PSYNC`, which is not a regular reply: a status line followed by either a
+/// snapshot or the part of the replication stream the replica missed.
+pub struct SyncResult {
+    pub data: Vec<u8>,
+}
+
+impl ExecutionResult for SyncResult {
+    fn to_string(&self) -> String {
+        String::from_utf8_lossy(&self.data).to_string()
+    }
+    fn serialise(&self, _: RespVersion) -> Vec<u8> {
+        self.data.clone()
+    }
+}
+
+/// The outcome of commands that are not answered, such as the acknowledgements of replicas.
+pub struct NoReplyResult;
+
+impl ExecutionResult for NoReplyResult {
+    fn to_string(&self) -> String {
+        String::new()
+    }
+    fn serialise(&self, _: RespVersion) -> Vec<u8> {
+        Vec::new()
+    }
+}
+
+pub struct InfoResult {
+    pub value: String,
+}
+
+impl ExecutionResult for InfoResult {
+    fn to_string(&self) -> String {
+        self.value.clone()
+    }
+    fn serialise(&self, protocol: RespVersion) -> Vec<u8> {
+        BulkStringReply {
+            value: self.value.clone().into_bytes(),
+        }
+        .serialise(protocol)
+    }
+}
@@ -0,0 +1,45 @@
+use crate::execution_result::{
+    ExecutionResult, RespReply, RespVersion, SimpleStringReply, UnsignedIntegerReply,
+};
+
+pub struct BackgroundSaveResult;
+
+impl ExecutionResult for BackgroundSaveResult {
+    fn to_string(&self) -> String {
+        "Background saving started".to_string()
+    }
+    fn serialise(&self, protocol: RespVersion) -> Vec<u8> {
+        SimpleStringReply {
+            value: self.to_string(),
+        }
+        .serialise(protocol)
+    }
+}
+
+pub struct BackgroundRewriteAofResult;
+
+impl ExecutionResult for BackgroundRewriteAofResult {
+    fn to_string(&self) -> String {
+        "Background append only file rewriting started".to_string()
+    }
+    fn serialise(&self, protocol: RespVersion) -> Vec<u8> {
+        SimpleStringReply {
+            value: self.to_string(),
+        }
+        .serialise(protocol)
+    }
+}
+
+/// When the last successful save happened, in Unix seconds.
+pub struct LastSaveResult {
+    pub value: u64,
+}
+
+impl ExecutionResult for LastSaveResult {
+    fn to_string(&self) -> String {
+        self.value.to_string()
+    }
+    fn serialise(&self, protocol: RespVersion) -> Vec<u8> {
+        UnsignedIntegerReply { value: self.value }.serialise(protocol)
+    }
+}
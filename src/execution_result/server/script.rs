@@ -0,0 +1,43 @@
+use crate::execution_result::{ArrayReply, ExecutionResult, IntegerReply, RespReply, RespVersion};
+
+/// The reply of `SCRIPT LOAD`, which is the digest of the script.
+pub type ScriptLoadResult = crate::execution_result::string::GetResult;
+
+/// The value returned by a script, converted to a reply.
+pub struct ScriptResult {
+    pub reply: Box<dyn RespReply>,
+}
+
+impl ExecutionResult for ScriptResult {
+    fn to_string(&self) -> String {
+        String::from_utf8_lossy(&self.reply.serialise(RespVersion::Resp2)).to_string()
+    }
+    fn serialise(&self, protocol: RespVersion) -> Vec<u8> {
+        self.reply.serialise(protocol)
+    }
+}
+
+/// The reply of `SCRIPT EXISTS`, which tells for each digest whether its script is cached.
+pub struct ScriptExistsResult {
+    pub values: Vec<bool>,
+}
+
+impl ExecutionResult for ScriptExistsResult {
+    fn to_string(&self) -> String {
+        self.values
+            .iter()
+            .map(|v| (*v as u8).to_string())
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+    fn serialise(&self, protocol: RespVersion) -> Vec<u8> {
+        ArrayReply {
+            values: self
+                .values
+                .iter()
+                .map(|v| Box::new(IntegerReply { value: *v as i64 }) as Box<dyn RespReply>)
+                .collect(),
+        }
+        .serialise(protocol)
+    }
+}
@@ -0,0 +1,48 @@
+use crate::execution_result::{
+    ExecutionResult, NullArrayReply, RespReply, RespVersion, SimpleStringReply,
+};
+
+/// The reply to a command queued in a transaction.
+pub struct QueuedResult;
+
+impl ExecutionResult for QueuedResult {
+    fn to_string(&self) -> String {
+        "QUEUED".to_string()
+    }
+    fn serialise(&self, protocol: RespVersion) -> Vec<u8> {
+        SimpleStringReply {
+            value: self.to_string(),
+        }
+        .serialise(protocol)
+    }
+}
+
+/// The replies of the commands run by `EXEC`, in the order they were queued. A command that
+/// failed has an error reply, which does not stop the commands after it. There are none when
+/// a watched key changed, in which case no command ran.
+pub struct ExecResult {
+    pub values: Option<Vec<Box<dyn ExecutionResult>>>,
+}
+
+impl ExecutionResult for ExecResult {
+    fn to_string(&self) -> String {
+        match &self.values {
+            Some(values) => values
+                .iter()
+                .map(|v| v.to_string())
+                .collect::<Vec<_>>()
+                .join("\n"),
+            None => String::new(),
+        }
+    }
+    fn serialise(&self, protocol: RespVersion) -> Vec<u8> {
+        let Some(values) = &self.values else {
+            return NullArrayReply.serialise(protocol);
+        };
+        let mut res = format!("*{}\r\n", values.len()).into_bytes();
+        for v in values {
+            res.extend(v.serialise(protocol));
+        }
+        res
+    }
+}
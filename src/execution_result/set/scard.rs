@@ -1,4 +1,4 @@
-use crate::execution_result::{ExecutionResult, IntegerReply, RespReply};
+use crate::execution_result::{ExecutionResult, IntegerReply, RespReply, RespVersion};
 
 pub struct SCardResult {
     pub value: usize,
@@ -8,10 +8,10 @@ impl ExecutionResult for SCardResult {
     fn to_string(&self) -> String {
         self.value.to_string()
     }
-    fn serialise(&self) -> String {
+    fn serialise(&self, protocol: RespVersion) -> Vec<u8> {
         IntegerReply {
             value: self.value as i64,
         }
-        .serialise()
+        .serialise(protocol)
     }
 }
@@ -1,20 +1,24 @@
-use crate::execution_result::{ArrayReply, BulkStringReply, ExecutionResult, RespReply};
+use crate::execution_result::{BulkStringReply, ExecutionResult, RespReply, RespVersion, SetReply};
 
 pub struct SMembersResult {
-    pub values: Vec<String>,
+    pub values: Vec<Vec<u8>>,
 }
 
 impl ExecutionResult for SMembersResult {
     fn to_string(&self) -> String {
-        self.values.to_vec().join(",")
+        self.values
+            .iter()
+            .map(|v| String::from_utf8_lossy(v))
+            .collect::<Vec<_>>()
+            .join(",")
     }
-    fn serialise(&self) -> String {
+    fn serialise(&self, protocol: RespVersion) -> Vec<u8> {
         let mut replies: Vec<Box<dyn RespReply>> = Vec::new();
         for value in &self.values {
             replies.push(Box::new(BulkStringReply {
                 value: value.clone(),
             }));
         }
-        ArrayReply { values: replies }.serialise()
+        SetReply { values: replies }.serialise(protocol)
     }
 }
@@ -1,18 +1,41 @@
-use crate::execution_result::{ArrayReply, BulkStringReply, ExecutionResult, RespReply};
+use crate::execution_result::{
+    ArrayReply, BulkStringReply, DoubleReply, ExecutionResult, RespReply, RespVersion,
+};
 
 pub struct ZRangeResult {
-    pub values: Vec<String>,
+    pub values: Vec<Vec<u8>>,
+    /// The score of each member in `values`, present when `WITHSCORES` was requested.
+    pub scores: Option<Vec<f64>>,
 }
 
 impl ExecutionResult for ZRangeResult {
     fn to_string(&self) -> String {
-        self.values.join(",")
+        let mut rs = Vec::new();
+        for (i, v) in self.values.iter().enumerate() {
+            rs.push(String::from_utf8_lossy(v).to_string());
+            if let Some(scores) = &self.scores {
+                rs.push(scores[i].to_string());
+            }
+        }
+        rs.join(",")
     }
-    fn serialise(&self) -> String {
+    fn serialise(&self, protocol: RespVersion) -> Vec<u8> {
         let mut rs: Vec<Box<dyn RespReply>> = Vec::new();
-        for vv in &self.values {
-            rs.push(Box::new(BulkStringReply { value: vv.clone() }))
+        for (i, vv) in self.values.iter().enumerate() {
+            let member = Box::new(BulkStringReply { value: vv.clone() });
+            match (&self.scores, protocol) {
+                (None, _) => rs.push(member),
+                // RESP2 clients get members and scores interleaved in a flat array, while RESP3
+                // clients get a [member, score] pair per member.
+                (Some(scores), RespVersion::Resp2) => {
+                    rs.push(member);
+                    rs.push(Box::new(DoubleReply { value: scores[i] }));
+                }
+                (Some(scores), RespVersion::Resp3) => rs.push(Box::new(ArrayReply {
+                    values: vec![member, Box::new(DoubleReply { value: scores[i] })],
+                })),
+            }
         }
-        ArrayReply { values: rs }.serialise()
+        ArrayReply { values: rs }.serialise(protocol)
     }
 }
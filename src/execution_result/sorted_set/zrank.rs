@@ -1,4 +1,6 @@
-use crate::execution_result::{ExecutionResult, NullReply, RespReply, UnsignedIntegerReply};
+use crate::execution_result::{
+    ExecutionResult, NullReply, RespReply, RespVersion, UnsignedIntegerReply,
+};
 
 pub struct ZRankResult {
     pub value: Option<u64>,
@@ -11,10 +13,10 @@ impl ExecutionResult for ZRankResult {
             None => "".to_string(),
         }
     }
-    fn serialise(&self) -> String {
+    fn serialise(&self, protocol: RespVersion) -> Vec<u8> {
         match &self.value {
-            Some(v) => UnsignedIntegerReply { value: *v }.serialise(),
-            None => NullReply {}.serialise(),
+            Some(v) => UnsignedIntegerReply { value: *v }.serialise(protocol),
+            None => NullReply {}.serialise(protocol),
         }
     }
 }
@@ -1,4 +1,4 @@
-use crate::execution_result::{ExecutionResult, RespReply, UnsignedIntegerReply};
+use crate::execution_result::{ExecutionResult, RespReply, RespVersion, UnsignedIntegerReply};
 
 pub struct ZRemResult {
     pub value: u64,
@@ -8,7 +8,7 @@ impl ExecutionResult for ZRemResult {
     fn to_string(&self) -> String {
         self.value.to_string()
     }
-    fn serialise(&self) -> String {
-        UnsignedIntegerReply { value: self.value }.serialise()
+    fn serialise(&self, protocol: RespVersion) -> Vec<u8> {
+        UnsignedIntegerReply { value: self.value }.serialise(protocol)
     }
 }
@@ -1,28 +1,28 @@
 use crate::execution_result::{
-    ArrayReply, BulkStringReply, ExecutionResult, NullReply, RespReply, SimpleStringReply,
+    ArrayReply, BulkStringReply, ExecutionResult, NullReply, RespReply, RespVersion,
 };
 
 pub struct GetResult {
-    pub value: Option<String>,
+    pub value: Option<Vec<u8>>,
 }
 
 impl ExecutionResult for GetResult {
     fn to_string(&self) -> String {
         match &self.value {
-            Some(v) => v.clone(),
+            Some(v) => String::from_utf8_lossy(v).to_string(),
             None => "".to_string(),
         }
     }
-    fn serialise(&self) -> String {
+    fn serialise(&self, protocol: RespVersion) -> Vec<u8> {
         match &self.value {
-            Some(v) => SimpleStringReply { value: v.clone() }.serialise(),
-            None => NullReply {}.serialise(),
+            Some(v) => BulkStringReply { value: v.clone() }.serialise(protocol),
+            None => NullReply {}.serialise(protocol),
         }
     }
 }
 
 pub struct MgetResult {
-    pub values: Vec<Option<String>>,
+    pub values: Vec<Option<Vec<u8>>>,
 }
 
 impl ExecutionResult for MgetResult {
@@ -30,13 +30,13 @@ impl ExecutionResult for MgetResult {
         self.values
             .iter()
             .map(|v| match v {
-                Some(v) => v.clone(),
+                Some(v) => String::from_utf8_lossy(v).to_string(),
                 None => "".to_string(),
             })
             .collect::<Vec<String>>()
             .join(",")
     }
-    fn serialise(&self) -> String {
+    fn serialise(&self, protocol: RespVersion) -> Vec<u8> {
         let mut replies: Vec<Box<dyn RespReply>> = Vec::new();
         for value in &self.values {
             replies.push(match value {
@@ -44,6 +44,6 @@ impl ExecutionResult for MgetResult {
                 None => Box::new(NullReply {}),
             });
         }
-        ArrayReply { values: replies }.serialise()
+        ArrayReply { values: replies }.serialise(protocol)
     }
 }
@@ -1,4 +1,4 @@
-use crate::execution_result::{ExecutionResult, IntegerReply, RespReply};
+use crate::execution_result::{ExecutionResult, IntegerReply, RespReply, RespVersion};
 
 pub struct IntOpResult {
     pub value: i64,
@@ -8,7 +8,7 @@ impl ExecutionResult for IntOpResult {
     fn to_string(&self) -> String {
         self.value.to_string()
     }
-    fn serialise(&self) -> String {
-        IntegerReply { value: self.value }.serialise()
+    fn serialise(&self, protocol: RespVersion) -> Vec<u8> {
+        IntegerReply { value: self.value }.serialise(protocol)
     }
 }
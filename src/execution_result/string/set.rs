@@ -1,17 +1,48 @@
-use crate::execution_result::{ExecutionResult, RespReply, SimpleStringReply};
+use crate::execution_result::{
+    BulkStringReply, ExecutionResult, NullReply, RespReply, RespVersion, SimpleStringReply,
+};
 
-pub struct SetResult;
+pub enum SetResult {
+    /// The value was stored.
+    Ok,
+    /// The value was not stored because of an `NX` or `XX` condition.
+    Aborted,
+    /// The value previously stored at the key, replied instead of `OK` when `GET` is given.
+    OldValue(Option<Vec<u8>>),
+}
 
 impl ExecutionResult for SetResult {
+    fn to_string(&self) -> String {
+        match self {
+            SetResult::Ok => "OK".to_string(),
+            SetResult::Aborted | SetResult::OldValue(None) => "".to_string(),
+            SetResult::OldValue(Some(v)) => String::from_utf8_lossy(v).to_string(),
+        }
+    }
+    fn serialise(&self, protocol: RespVersion) -> Vec<u8> {
+        match self {
+            SetResult::Ok => SimpleStringReply {
+                value: self.to_string(),
+            }
+            .serialise(protocol),
+            SetResult::Aborted | SetResult::OldValue(None) => NullReply {}.serialise(protocol),
+            SetResult::OldValue(Some(v)) => {
+                BulkStringReply { value: v.clone() }.serialise(protocol)
+            }
+        }
+    }
+}
+
+pub struct MsetResult;
+
+impl ExecutionResult for MsetResult {
     fn to_string(&self) -> String {
         "OK".to_string()
     }
-    fn serialise(&self) -> String {
+    fn serialise(&self, protocol: RespVersion) -> Vec<u8> {
         SimpleStringReply {
             value: self.to_string(),
         }
-        .serialise()
+        .serialise(protocol)
     }
 }
-
-pub type MsetResult = SetResult;
@@ -1,5 +1,13 @@
+pub mod cluster;
 pub mod command;
+pub mod config;
 pub mod data_store;
 pub mod error;
 pub mod execution_result;
+pub mod persistence;
+pub mod pubsub;
+pub mod replication;
+pub mod scripting;
+pub mod server;
+pub mod session;
 pub mod utils;
@@ -1,23 +1,184 @@
-use redis_rust::data_store;
+use redis_rust::cluster;
+use redis_rust::config::ServerConfig;
+use redis_rust::replication;
+use redis_rust::scripting::ScriptMonitor;
+use redis_rust::server::Server;
 use redis_rust::utils;
 
+use std::os::unix::fs::PermissionsExt;
 use std::sync::{Arc, Mutex};
-use tokio::net::TcpListener;
+use std::time::Duration;
+use tokio::io::{AsyncRead, AsyncWrite};
+use tokio::net::{TcpListener, UnixListener};
 
-#[tokio::main(flavor = "current_thread")]
+#[tokio::main(flavor = "multi_thread")]
 async fn main() {
     env_logger::init();
-    let data_store = Arc::new(Mutex::new(data_store::DataStore::new()));
-    let listener = TcpListener::bind("127.0.0.1:6379").await.unwrap();
-    while let Ok((mut stream, _address)) = listener.accept().await {
-        // Clone the arc here so that `data_store` does not get moved during the first spawn.
-        let ds_clone = data_store.clone();
-        tokio::spawn(async move {
-            let (rx, tx) = stream.split();
-            match utils::handle_connection(rx, &tx, ds_clone).await {
-                Ok(_) => (),
-                Err(e) => utils::handle_error(&tx, e),
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    let config = match ServerConfig::from_args(&args) {
+        Ok(v) => v,
+        Err(e) => exit_with_error(e.to_string()),
+    };
+    let mut server = Server::new(&config);
+    let started = std::time::Instant::now();
+    match server.load() {
+        Ok(true) => log::info!("Data loaded from disk in {:?}", started.elapsed()),
+        Ok(false) => (),
+        Err(e) => exit_with_error(e.to_string()),
+    }
+    let bus_port = server.cluster.enabled.then(|| server.cluster.bus_port());
+    let monitor = server.scripts.monitor.clone();
+    let server = Arc::new(Mutex::new(server));
+
+    let mut tasks = Vec::new();
+    // Port 0 disables TCP, which only makes sense when serving over a Unix domain socket.
+    for address in config.bind.iter().filter(|_| config.port != 0) {
+        let (address, optional) = match address.strip_prefix('-') {
+            Some(v) => (v, true),
+            None => (address.as_str(), false),
+        };
+        let host = match address {
+            "*" => "0.0.0.0",
+            "::*" => "::",
+            v => v,
+        };
+        let listener = match TcpListener::bind((host, config.port)).await {
+            Ok(v) => v,
+            Err(e) if optional => {
+                log::warn!("Skipping {}:{}: {}", host, config.port, e);
+                continue;
+            }
+            Err(e) => exit_with_error(format!(
+                "failed to listen on {}:{}: {}",
+                host, config.port, e
+            )),
+        };
+        log::info!("Listening on {}", listener.local_addr().unwrap());
+        tasks.push(tokio::spawn(accept_tcp_connections(
+            listener,
+            server.clone(),
+            monitor.clone(),
+        )));
+        if let Some(bus_port) = bus_port {
+            let listener = match TcpListener::bind((host, bus_port)).await {
+                Ok(v) => v,
+                Err(e) => exit_with_error(format!(
+                    "failed to listen on {}:{} for the cluster bus: {}",
+                    host, bus_port, e
+                )),
             };
-        });
+            log::info!(
+                "Cluster bus listening on {}",
+                listener.local_addr().unwrap()
+            );
+            tasks.push(tokio::spawn(cluster::bus::accept(
+                listener,
+                server.clone(),
+                monitor.clone(),
+            )));
+        }
+    }
+    if let Some(path) = &config.unixsocket {
+        // A socket file left behind by a previous run would make the bind fail.
+        let _ = std::fs::remove_file(path);
+        let listener = match UnixListener::bind(path) {
+            Ok(v) => v,
+            Err(e) => exit_with_error(format!("failed to listen on {}: {}", path, e)),
+        };
+        if let Some(mode) = config.unixsocketperm {
+            if let Err(e) = std::fs::set_permissions(path, std::fs::Permissions::from_mode(mode)) {
+                exit_with_error(format!("failed to set permissions of {}: {}", path, e));
+            }
+        }
+        log::info!("Listening on {}", path);
+        tasks.push(tokio::spawn(accept_unix_connections(
+            listener,
+            server.clone(),
+            monitor.clone(),
+        )));
+    }
+    if tasks.is_empty() {
+        exit_with_error("no address to listen on".to_string());
+    }
+    tokio::spawn(run_cron(server.clone(), monitor));
+    for task in tasks {
+        let _ = task.await;
+    }
+}
+
+fn exit_with_error(message: String) -> ! {
+    eprintln!("Error: {}", message);
+    std::process::exit(1);
+}
+
+/// Periodically runs the background work of the server, such as evicting expired keys that are
+/// not being accessed, which would otherwise stay in memory forever, connecting replicas to
+/// their master and connecting to the other nodes of the cluster. It waits for scripts to
+/// finish, as it runs next to the clients that may need to kill them.
+async fn run_cron(server: Arc<Mutex<Server>>, monitor: Arc<ScriptMonitor>) {
+    let mut interval = tokio::time::interval(Duration::from_millis(100));
+    loop {
+        interval.tick().await;
+        let (link, bus_links) = {
+            let mut server = utils::lock_server(&server, &monitor).await;
+            server.cron();
+            let offset = server.replication.offset;
+            (
+                server.replication.take_pending_link(),
+                server.cluster.take_pending_links(offset),
+            )
+        };
+        if let Some(link) = link {
+            tokio::spawn(replication::link::run(
+                server.clone(),
+                monitor.clone(),
+                link,
+            ));
+        }
+        for link in bus_links {
+            tokio::spawn(cluster::bus::connect(server.clone(), monitor.clone(), link));
+        }
     }
 }
+
+async fn accept_tcp_connections(
+    listener: TcpListener,
+    server: Arc<Mutex<Server>>,
+    monitor: Arc<ScriptMonitor>,
+) {
+    while let Ok((stream, address)) = listener.accept().await {
+        let (rx, tx) = stream.into_split();
+        // Clone the arc here so that `server` does not get moved during the first spawn.
+        tokio::spawn(serve(
+            rx,
+            tx,
+            server.clone(),
+            monitor.clone(),
+            Some(address.ip().to_string()),
+        ));
+    }
+}
+
+async fn accept_unix_connections(
+    listener: UnixListener,
+    server: Arc<Mutex<Server>>,
+    monitor: Arc<ScriptMonitor>,
+) {
+    while let Ok((stream, _address)) = listener.accept().await {
+        let (rx, tx) = stream.into_split();
+        tokio::spawn(serve(rx, tx, server.clone(), monitor.clone(), None));
+    }
+}
+
+async fn serve<R: AsyncRead + Unpin, W: AsyncWrite + Unpin>(
+    rx: R,
+    mut tx: W,
+    server: Arc<Mutex<Server>>,
+    monitor: Arc<ScriptMonitor>,
+    addr: Option<String>,
+) {
+    match utils::handle_connection(rx, &mut tx, server, monitor, addr).await {
+        Ok(_) => (),
+        Err(e) => utils::handle_error(&mut tx, e).await,
+    };
+}
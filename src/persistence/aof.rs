@@ -0,0 +1,382 @@
+//! The append-only file, which logs every write in the form clients send commands in. It is
+//! compacted by rewriting it as a snapshot of the data, followed by the writes made since.
+
+use super::snapshot;
+use crate::config::{AppendFsync, ServerConfig};
+use crate::data_store::{Databases, Dataset};
+use crate::error::{AofError, ExecutionError};
+use crate::execution_result::{ArrayReply, BulkStringReply, RespReply, RespVersion};
+use std::fs::{File, OpenOptions};
+use std::io::{BufWriter, Write};
+use std::path::{Path, PathBuf};
+use std::thread::JoinHandle;
+use std::time::{Duration, Instant};
+
+const FSYNC_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Encodes a command the way clients send it, as an array of bulk strings.
+pub fn encode_command(tokens: &[Vec<u8>]) -> Vec<u8> {
+    ArrayReply {
+        values: tokens
+            .iter()
+            .map(|t| Box::new(BulkStringReply { value: t.clone() }) as Box<dyn RespReply>)
+            .collect(),
+    }
+    .serialise(RespVersion::Resp2)
+}
+
+/// Appends `command` to `out`, preceded by a `SELECT` if it runs against another database than
+/// the previous one.
+fn append(out: &mut Vec<u8>, selected_db: &mut Option<usize>, db: usize, command: &[u8]) {
+    if *selected_db != Some(db) {
+        out.extend(encode_command(&[
+            b"SELECT".to_vec(),
+            db.to_string().into_bytes(),
+        ]));
+        *selected_db = Some(db);
+    }
+    out.extend_from_slice(command);
+}
+
+/// Writes a snapshot of `databases` to `path`, as the start of a new append-only file.
+fn write_snapshot(path: &Path, databases: &impl Dataset) -> std::io::Result<File> {
+    let file = File::create(path)?;
+    let mut out = BufWriter::new(file);
+    snapshot::write(&mut out, databases)?;
+    let file = out.into_inner()?;
+    file.sync_all()?;
+    Ok(file)
+}
+
+/// An append-only file being rewritten by a background thread.
+struct BackgroundRewrite {
+    handle: JoinHandle<std::io::Result<File>>,
+    temp_path: PathBuf,
+    /// The writes made since the rewrite started, which are appended to the new file once the
+    /// snapshot is written.
+    buffer: Vec<u8>,
+    selected_db: Option<usize>,
+}
+
+pub struct Aof {
+    pub enabled: bool,
+    path: PathBuf,
+    fsync: AppendFsync,
+    /// The file writes are appended to. It is only opened once the existing file is loaded.
+    file: Option<File>,
+    /// The database the last command in the file runs against.
+    selected_db: Option<usize>,
+    /// Whether there are writes that have not been flushed to disk yet.
+    unsynced: bool,
+    last_fsync: Instant,
+    rewrite: Option<BackgroundRewrite>,
+    /// The length of the file, which only holds complete records.
+    len: u64,
+    /// The records that could not be written yet, which are written before any other.
+    pending: Vec<u8>,
+    /// Why the last write to the file failed, if it did. Writes are refused until the pending
+    /// records can be written.
+    write_error: Option<String>,
+}
+
+impl Aof {
+    pub fn new(config: &ServerConfig) -> Self {
+        Aof {
+            enabled: config.appendonly,
+            path: PathBuf::from(&config.dir).join(&config.appendfilename),
+            fsync: config.appendfsync,
+            file: None,
+            selected_db: None,
+            unsynced: false,
+            last_fsync: Instant::now(),
+            rewrite: None,
+            len: 0,
+            pending: Vec::new(),
+            write_error: None,
+        }
+    }
+
+    /// Returns the content of the append-only file, or `None` if there is none yet.
+    pub fn read(&self) -> Result<Option<Vec<u8>>, AofError> {
+        match std::fs::read(&self.path) {
+            Ok(v) => Ok(Some(v)),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(None),
+            Err(e) => Err(AofError::ReadFailed(
+                self.path.display().to_string(),
+                e.to_string(),
+            )),
+        }
+    }
+
+    /// Cuts the file at `len` bytes, e.g. to drop a record that was only partially written.
+    pub fn truncate(&self, len: usize) -> std::io::Result<()> {
+        OpenOptions::new()
+            .write(true)
+            .open(&self.path)?
+            .set_len(len as u64)
+    }
+
+    /// Opens the existing file to append writes to it.
+    pub fn open(&mut self) -> std::io::Result<()> {
+        let file = OpenOptions::new().append(true).open(&self.path)?;
+        self.len = file.metadata()?.len();
+        self.file = Some(file);
+        Ok(())
+    }
+
+    /// Creates the file from a snapshot of `databases` and opens it to append writes to it.
+    pub fn create(&mut self, databases: &Databases) -> std::io::Result<()> {
+        let file = write_snapshot(&self.path, databases)?;
+        // The file is opened again in append mode so that writes always go to its end.
+        drop(file);
+        self.open()
+    }
+
+    pub fn is_rewriting(&self) -> bool {
+        self.rewrite.is_some()
+    }
+
+    /// Logs a write made to database `db`.
+    pub fn feed(&mut self, db: usize, tokens: &[Vec<u8>]) {
+        if self.file.is_none() && self.rewrite.is_none() {
+            return;
+        }
+        let command = encode_command(tokens);
+        if let Some(rewrite) = &mut self.rewrite {
+            append(&mut rewrite.buffer, &mut rewrite.selected_db, db, &command);
+        }
+        if self.file.is_none() {
+            return;
+        }
+        append(&mut self.pending, &mut self.selected_db, db, &command);
+        self.write_pending();
+    }
+
+    /// Returns why the last write to the file failed, if it did and no write succeeded since.
+    pub fn write_error(&self) -> Option<&str> {
+        self.write_error.as_deref()
+    }
+
+    /// Writes the pending records to the file. If that fails, whatever part of them was written
+    /// is cut off again, and they are kept to be written later.
+    fn write_pending(&mut self) {
+        let Some(file) = &mut self.file else {
+            return;
+        };
+        if self.pending.is_empty() {
+            return;
+        }
+        let result = file
+            .write_all(&self.pending)
+            .and_then(|()| match self.fsync {
+                AppendFsync::Always => file.sync_data(),
+                _ => Ok(()),
+            });
+        match result {
+            Ok(()) => {
+                self.len += self.pending.len() as u64;
+                self.pending.clear();
+                self.unsynced |= self.fsync == AppendFsync::EverySec;
+                if self.write_error.take().is_some() {
+                    log::info!("Writing to the append-only file succeeded again");
+                }
+            }
+            Err(e) => {
+                log::error!("Failed to write to the append-only file: {}", e);
+                let _ = file.set_len(self.len);
+                self.write_error = Some(e.to_string());
+            }
+        }
+    }
+
+    /// Rewrites the file from a snapshot of `databases` in a background thread. Writes keep being
+    /// appended to the current file until the new one replaces it.
+    pub fn start_rewrite(&mut self, databases: &Databases) -> Result<(), ExecutionError> {
+        if self.is_rewriting() {
+            return Err(ExecutionError::AofRewriteInProgress);
+        }
+        let snapshot = databases.snapshot();
+        let temp_path = self
+            .path
+            .with_file_name(format!("temp-rewriteaof-{}.aof", std::process::id()));
+        let path = temp_path.clone();
+        let handle = std::thread::spawn(move || write_snapshot(&path, &snapshot));
+        self.rewrite = Some(BackgroundRewrite {
+            handle,
+            temp_path,
+            buffer: Vec::new(),
+            selected_db: None,
+        });
+        log::info!("Background append only file rewriting started");
+        Ok(())
+    }
+
+    /// Retries the writes that failed, flushes the file to disk once per second under the
+    /// `everysec` policy, and switches to the rewritten file once it is ready. This is meant to be
+    /// called periodically.
+    pub fn cron(&mut self) {
+        self.write_pending();
+        if self.unsynced && self.last_fsync.elapsed() >= FSYNC_INTERVAL {
+            if let Some(file) = &self.file {
+                if let Err(e) = file.sync_data() {
+                    log::error!("Failed to fsync the append-only file: {}", e);
+                }
+            }
+            self.unsynced = false;
+            self.last_fsync = Instant::now();
+        }
+        let Some(rewrite) = self.rewrite.take_if(|r| r.handle.is_finished()) else {
+            return;
+        };
+        let temp_path = rewrite.temp_path.clone();
+        match self.finish_rewrite(rewrite) {
+            Ok(()) => log::info!("Background AOF rewrite finished successfully"),
+            Err(e) => {
+                log::error!("Background AOF rewrite failed: {}", e);
+                let _ = std::fs::remove_file(temp_path);
+            }
+        }
+    }
+
+    fn finish_rewrite(&mut self, rewrite: BackgroundRewrite) -> std::io::Result<()> {
+        let mut file = match rewrite.handle.join() {
+            Ok(v) => v?,
+            Err(_) => return Err(std::io::Error::other("the rewrite thread panicked")),
+        };
+        file.write_all(&rewrite.buffer)?;
+        file.sync_data()?;
+        std::fs::rename(&rewrite.temp_path, &self.path)?;
+        if self.file.is_some() {
+            self.open()?;
+            self.selected_db = rewrite.selected_db;
+            self.unsynced = false;
+            // The new file already holds the records that could not be written to the old one.
+            self.pending.clear();
+            self.write_error = None;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{encode_command, Aof};
+    use crate::config::ServerConfig;
+    use crate::data_store::Databases;
+    use crate::server::Server;
+    use crate::session::Session;
+    use crate::utils::tokens;
+    use std::fs::File;
+
+    fn aof(name: &str) -> Aof {
+        Aof::new(&ServerConfig {
+            dir: std::env::temp_dir().to_str().unwrap().to_string(),
+            appendfilename: format!("redis-rust-{}-{}.aof", name, std::process::id()),
+            appendonly: true,
+            ..Default::default()
+        })
+    }
+
+    #[test]
+    fn should_log_writes_with_database_changes() {
+        let mut aof = aof("feed");
+        assert!(aof.read().unwrap().is_none());
+        aof.create(&Databases::new(16)).unwrap();
+        let preamble = aof.read().unwrap().unwrap();
+
+        aof.feed(0, &tokens(&["SET", "a", "1"]));
+        aof.feed(0, &tokens(&["DEL", "a"]));
+        aof.feed(2, &tokens(&["SET", "b", "2"]));
+        let data = aof.read().unwrap().unwrap();
+        let mut expected = preamble.clone();
+        for command in [
+            &["SELECT", "0"][..],
+            &["SET", "a", "1"],
+            &["DEL", "a"],
+            &["SELECT", "2"],
+            &["SET", "b", "2"],
+        ] {
+            expected.extend(encode_command(&tokens(command)));
+        }
+        assert_eq!(data, expected);
+
+        aof.truncate(preamble.len()).unwrap();
+        assert_eq!(aof.read().unwrap().unwrap(), preamble);
+        std::fs::remove_file(&aof.path).unwrap();
+    }
+
+    #[test]
+    fn should_rewrite_in_background() {
+        let mut aof = aof("rewrite");
+        let mut dbs = Databases::new(16);
+        aof.create(&dbs).unwrap();
+        aof.feed(0, &tokens(&["SET", "a", "1"]));
+        dbs.db_mut(0).set_string_overwrite(b"a", b"1");
+
+        aof.start_rewrite(&dbs).unwrap();
+        let err = aof.start_rewrite(&dbs).err().unwrap();
+        assert_eq!(
+            err.to_string(),
+            "ERR Background append only file rewriting already in progress".to_string()
+        );
+        // Writes made during the rewrite end up in the new file.
+        aof.feed(1, &tokens(&["SET", "b", "2"]));
+        while aof.is_rewriting() {
+            aof.cron();
+        }
+        aof.feed(1, &tokens(&["DEL", "b"]));
+
+        let data = aof.read().unwrap().unwrap();
+        let mut restored = Databases::new(16);
+        let len = super::snapshot::read(&data, &mut restored).unwrap();
+        assert!(restored.db_mut(0).contains_key(b"a"));
+        let mut expected = Vec::new();
+        for command in [&["SELECT", "1"][..], &["SET", "b", "2"], &["DEL", "b"]] {
+            expected.extend(encode_command(&tokens(command)));
+        }
+        assert_eq!(&data[len..], expected.as_slice());
+        std::fs::remove_file(&aof.path).unwrap();
+    }
+
+    #[test]
+    fn should_refuse_writes_until_the_file_can_be_written_again() {
+        let config = ServerConfig {
+            dir: std::env::temp_dir().to_str().unwrap().to_string(),
+            appendfilename: format!("redis-rust-misconf-{}.aof", std::process::id()),
+            appendonly: true,
+            ..Default::default()
+        };
+        let mut server = Server::new(&config);
+        server.load().unwrap();
+        let preamble = server.aof.read().unwrap().unwrap();
+        let mut session = Session::new();
+        let mut run = |server: &mut Server, command: &[&str]| match server
+            .execute(&tokens(command), &mut session)
+        {
+            Ok(result) => result.to_string(),
+            Err(e) => e.to_string(),
+        };
+
+        // A file that cannot be written to makes the write fail.
+        server.aof.file = Some(File::open(&server.aof.path).unwrap());
+        assert_eq!(run(&mut server, &["SET", "a", "1"]), "OK".to_string());
+        assert!(run(&mut server, &["SET", "b", "2"])
+            .starts_with("MISCONF Errors writing to the AOF file: "));
+        assert_eq!(run(&mut server, &["GET", "a"]), "1".to_string());
+        server.cron();
+        assert!(server.aof.write_error().is_some());
+
+        // The write that failed is retried, and writes are accepted again once it succeeds.
+        server.aof.open().unwrap();
+        server.cron();
+        assert!(server.aof.write_error().is_none());
+        assert_eq!(run(&mut server, &["SET", "b", "2"]), "OK".to_string());
+        let data = server.aof.read().unwrap().unwrap();
+        let mut expected = preamble;
+        for command in [&["SELECT", "0"][..], &["SET", "a", "1"], &["SET", "b", "2"]] {
+            expected.extend(encode_command(&tokens(command)));
+        }
+        assert_eq!(data, expected);
+        std::fs::remove_file(&server.aof.path).unwrap();
+    }
+}
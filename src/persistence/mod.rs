@@ -0,0 +1,200 @@
+pub mod aof;
+pub mod rdb;
+pub mod snapshot;
+
+use crate::config::{SaveRule, ServerConfig, SnapshotFormat};
+use crate::data_store::Databases;
+use crate::error::{ExecutionError, SnapshotError};
+use std::path::PathBuf;
+use std::thread::JoinHandle;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// Returns the current time in Unix seconds.
+pub fn now_secs() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0)
+}
+
+/// A snapshot being written by a background thread.
+struct BackgroundSave {
+    /// The number of changes the snapshot covers.
+    dirty: u64,
+    handle: JoinHandle<std::io::Result<()>>,
+}
+
+/// Keeps track of when snapshots were saved and of the changes made since.
+pub struct Persistence {
+    path: PathBuf,
+    rules: Vec<SaveRule>,
+    format: SnapshotFormat,
+    /// The number of changes since the last successful save.
+    pub dirty: u64,
+    /// When the last successful save happened, in Unix seconds.
+    pub last_save: u64,
+    background_save: Option<BackgroundSave>,
+}
+
+impl Persistence {
+    pub fn new(config: &ServerConfig) -> Self {
+        Persistence {
+            path: PathBuf::from(&config.dir).join(&config.dbfilename),
+            rules: config.save.clone(),
+            format: config.snapshot_format,
+            dirty: 0,
+            last_save: now_secs(),
+            background_save: None,
+        }
+    }
+
+    pub fn is_saving(&self) -> bool {
+        self.background_save.is_some()
+    }
+
+    /// Loads the snapshot file into `databases`, if there is one.
+    pub fn load(&mut self, databases: &mut Databases) -> Result<bool, SnapshotError> {
+        snapshot::load(&self.path, databases)
+    }
+
+    /// Saves a snapshot of `databases`, blocking until it is written.
+    pub fn save(&mut self, databases: &Databases) -> Result<(), ExecutionError> {
+        if self.is_saving() {
+            return Err(ExecutionError::BackgroundSaveInProgress);
+        }
+        snapshot::save(&self.path, databases, self.format).map_err(|e| {
+            log::error!("Failed to save snapshot: {}", e);
+            ExecutionError::SaveFailed(e.to_string())
+        })?;
+        self.dirty = 0;
+        self.last_save = now_secs();
+        Ok(())
+    }
+
+    /// Saves a snapshot of `databases` from a background thread. The keys are copied first, so the
+    /// databases can keep changing while the snapshot is being written.
+    pub fn start_background_save(&mut self, databases: &Databases) -> Result<(), ExecutionError> {
+        if self.is_saving() {
+            return Err(ExecutionError::BackgroundSaveInProgress);
+        }
+        let snapshot = databases.snapshot();
+        let path = self.path.clone();
+        let format = self.format;
+        let handle = std::thread::spawn(move || snapshot::save(&path, &snapshot, format));
+        self.background_save = Some(BackgroundSave {
+            dirty: self.dirty,
+            handle,
+        });
+        log::info!("Background saving started");
+        Ok(())
+    }
+
+    /// Collects a background save that has finished, and starts a new one if a save rule is met.
+    /// This is meant to be called periodically.
+    pub fn cron(&mut self, databases: &Databases) {
+        if let Some(save) = self
+            .background_save
+            .take_if(|save| save.handle.is_finished())
+        {
+            match save.handle.join() {
+                Ok(Ok(())) => {
+                    // Changes made while the snapshot was being written are not part of it.
+                    self.dirty -= save.dirty;
+                    self.last_save = now_secs();
+                    log::info!("Background saving terminated with success");
+                }
+                Ok(Err(e)) => log::error!("Background saving failed: {}", e),
+                Err(_) => log::error!("Background saving panicked"),
+            }
+        }
+        if self.is_saving() {
+            return;
+        }
+        let elapsed = now_secs().saturating_sub(self.last_save);
+        if let Some(rule) = self
+            .rules
+            .iter()
+            .find(|rule| self.dirty >= rule.changes && elapsed >= rule.seconds)
+        {
+            log::info!(
+                "{} changes in {} seconds. Saving...",
+                rule.changes,
+                rule.seconds
+            );
+            let _ = self.start_background_save(databases);
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::Persistence;
+    use crate::config::{SaveRule, ServerConfig};
+    use crate::data_store::Databases;
+
+    fn config(name: &str) -> ServerConfig {
+        ServerConfig {
+            dir: std::env::temp_dir().to_str().unwrap().to_string(),
+            dbfilename: format!("redis-rust-{}-{}.rdb", name, std::process::id()),
+            save: vec![SaveRule {
+                seconds: 0,
+                changes: 2,
+            }],
+            ..Default::default()
+        }
+    }
+
+    fn wait_for_background_save(persistence: &mut Persistence, databases: &Databases) {
+        while persistence.is_saving() {
+            std::thread::sleep(std::time::Duration::from_millis(1));
+            persistence.cron(databases);
+        }
+    }
+
+    #[test]
+    fn should_save_and_load_snapshot() {
+        let mut persistence = Persistence::new(&config("save"));
+        let mut dbs = Databases::new(16);
+        assert!(!persistence.load(&mut dbs).unwrap());
+        dbs.db_mut(1).set_string_overwrite(b"foo", b"bar");
+        persistence.dirty = 1;
+        persistence.save(&dbs).unwrap();
+        assert_eq!(persistence.dirty, 0);
+
+        let mut restored = Databases::new(16);
+        assert!(persistence.load(&mut restored).unwrap());
+        assert_eq!(
+            restored.db_mut(1).get_string(b"foo").unwrap().unwrap(),
+            b"bar"
+        );
+        std::fs::remove_file(&persistence.path).unwrap();
+    }
+
+    #[test]
+    fn should_save_in_background_when_rule_is_met() {
+        let mut persistence = Persistence::new(&config("bgsave"));
+        let mut dbs = Databases::new(16);
+        dbs.db_mut(0).set_string_overwrite(b"foo", b"bar");
+        persistence.dirty = 1;
+        persistence.cron(&dbs);
+        assert!(!persistence.is_saving());
+
+        persistence.dirty = 2;
+        persistence.cron(&dbs);
+        assert!(persistence.is_saving());
+        let err = persistence.save(&dbs).err().unwrap();
+        assert_eq!(
+            err.to_string(),
+            "ERR Background save already in progress".to_string()
+        );
+        // Changes made during the save are still pending afterwards.
+        persistence.dirty = 3;
+        wait_for_background_save(&mut persistence, &dbs);
+        assert_eq!(persistence.dirty, 1);
+
+        let mut restored = Databases::new(16);
+        assert!(persistence.load(&mut restored).unwrap());
+        assert!(restored.db_mut(0).contains_key(b"foo"));
+        std::fs::remove_file(&persistence.path).unwrap();
+    }
+}
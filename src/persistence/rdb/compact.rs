@@ -0,0 +1,282 @@
+//! The compact encodings Redis serialises small collections and stream nodes with: listpacks,
+//! ziplists from older versions, and integer sets. Integer elements are returned in their decimal
+//! form, the way Redis hands them out.
+
+/// Returns the elements of an encoded collection.
+pub type Parser = fn(&[u8]) -> Result<Vec<Vec<u8>>, String>;
+
+const LISTPACK_HEADER_LEN: usize = 6;
+const ZIPLIST_HEADER_LEN: usize = 10;
+const END: u8 = 0xff;
+
+struct Reader<'a> {
+    data: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> Reader<'a> {
+    fn take(&mut self, len: usize) -> Result<&'a [u8], String> {
+        if self.data.len() - self.pos < len {
+            return Err("truncated element".to_string());
+        }
+        let v = &self.data[self.pos..self.pos + len];
+        self.pos += len;
+        Ok(v)
+    }
+
+    fn u8(&mut self) -> Result<u8, String> {
+        Ok(self.take(1)?[0])
+    }
+
+    /// Reads a little-endian signed integer of `len` bytes.
+    fn int(&mut self, len: usize) -> Result<i64, String> {
+        let mut bytes = [0u8; 8];
+        bytes[..len].copy_from_slice(self.take(len)?);
+        let shift = 64 - 8 * len as u32;
+        Ok(i64::from_le_bytes(bytes) << shift >> shift)
+    }
+
+    fn at_end(&mut self) -> Result<bool, String> {
+        match self.data.get(self.pos) {
+            Some(&END) => Ok(true),
+            Some(_) => Ok(false),
+            None => Err("missing end marker".to_string()),
+        }
+    }
+}
+
+fn int_element(v: i64) -> Vec<u8> {
+    v.to_string().into_bytes()
+}
+
+/// The size of the back-length a listpack element of `len` bytes ends with.
+fn backlen_size(len: usize) -> usize {
+    match len {
+        0..=127 => 1,
+        128..=16382 => 2,
+        16383..=2097150 => 3,
+        2097151..=268435454 => 4,
+        _ => 5,
+    }
+}
+
+/// Returns the elements of a listpack.
+pub fn listpack(data: &[u8]) -> Result<Vec<Vec<u8>>, String> {
+    if data.len() < LISTPACK_HEADER_LEN + 1 {
+        return Err("listpack is too short".to_string());
+    }
+    let mut reader = Reader {
+        data,
+        pos: LISTPACK_HEADER_LEN,
+    };
+    let mut elements = Vec::new();
+    while !reader.at_end()? {
+        let start = reader.pos;
+        let b = reader.u8()?;
+        let element = match b {
+            0x00..=0x7f => int_element(b as i64),
+            0x80..=0xbf => reader.take((b & 0x3f) as usize)?.to_vec(),
+            0xc0..=0xdf => {
+                let v = ((b as i64 & 0x1f) << 8) | reader.u8()? as i64;
+                int_element(if v >= 1 << 12 { v - (1 << 13) } else { v })
+            }
+            0xe0..=0xef => {
+                let len = ((b as usize & 0x0f) << 8) | reader.u8()? as usize;
+                reader.take(len)?.to_vec()
+            }
+            0xf0 => {
+                let len = u32::from_le_bytes(reader.take(4)?.try_into().unwrap());
+                reader.take(len as usize)?.to_vec()
+            }
+            0xf1 => int_element(reader.int(2)?),
+            0xf2 => int_element(reader.int(3)?),
+            0xf3 => int_element(reader.int(4)?),
+            0xf4 => int_element(reader.int(8)?),
+            _ => return Err(format!("invalid listpack encoding {:#04x}", b)),
+        };
+        reader.take(backlen_size(reader.pos - start))?;
+        elements.push(element);
+    }
+    Ok(elements)
+}
+
+/// Returns the elements of a ziplist, the encoding listpacks replaced in Redis 7.
+pub fn ziplist(data: &[u8]) -> Result<Vec<Vec<u8>>, String> {
+    if data.len() < ZIPLIST_HEADER_LEN + 1 {
+        return Err("ziplist is too short".to_string());
+    }
+    let mut reader = Reader {
+        data,
+        pos: ZIPLIST_HEADER_LEN,
+    };
+    let mut elements = Vec::new();
+    while !reader.at_end()? {
+        // Skip the length of the previous element.
+        if reader.u8()? == 0xfe {
+            reader.take(4)?;
+        }
+        let b = reader.u8()?;
+        let element = match b {
+            0x00..=0x3f => reader.take(b as usize)?.to_vec(),
+            0x40..=0x7f => {
+                let len = ((b as usize & 0x3f) << 8) | reader.u8()? as usize;
+                reader.take(len)?.to_vec()
+            }
+            0x80 => {
+                let len = u32::from_be_bytes(reader.take(4)?.try_into().unwrap());
+                reader.take(len as usize)?.to_vec()
+            }
+            0xc0 => int_element(reader.int(2)?),
+            0xd0 => int_element(reader.int(4)?),
+            0xe0 => int_element(reader.int(8)?),
+            0xf0 => int_element(reader.int(3)?),
+            0xfe => int_element(reader.int(1)?),
+            0xf1..=0xfd => int_element((b & 0x0f) as i64 - 1),
+            _ => return Err(format!("invalid ziplist encoding {:#04x}", b)),
+        };
+        elements.push(element);
+    }
+    Ok(elements)
+}
+
+/// Returns the members of an integer set.
+pub fn intset(data: &[u8]) -> Result<Vec<Vec<u8>>, String> {
+    let mut reader = Reader { data, pos: 0 };
+    let width = u32::from_le_bytes(reader.take(4)?.try_into().unwrap()) as usize;
+    if ![2, 4, 8].contains(&width) {
+        return Err(format!("invalid intset encoding {}", width));
+    }
+    let len = u32::from_le_bytes(reader.take(4)?.try_into().unwrap());
+    (0..len)
+        .map(|_| Ok(int_element(reader.int(width)?)))
+        .collect()
+}
+
+/// Builds a listpack element by element.
+pub struct ListpackWriter {
+    data: Vec<u8>,
+    len: usize,
+}
+
+impl ListpackWriter {
+    pub fn new() -> Self {
+        ListpackWriter {
+            data: vec![0; LISTPACK_HEADER_LEN],
+            len: 0,
+        }
+    }
+
+    fn push(&mut self, element: &[u8]) {
+        self.data.extend_from_slice(element);
+        let len = element.len();
+        // The back-length is stored most significant group first, with the high bit set on every
+        // byte but the first, so that the listpack can be walked backwards.
+        let size = backlen_size(len);
+        for i in (0..size).rev() {
+            let group = ((len >> (7 * i)) & 0x7f) as u8;
+            self.data
+                .push(if i == size - 1 { group } else { group | 0x80 });
+        }
+        self.len += 1;
+    }
+
+    pub fn push_int(&mut self, v: i64) {
+        let element = match v {
+            0..=127 => vec![v as u8],
+            -4096..=4095 => {
+                let v = v as u16 & 0x1fff;
+                vec![0xc0 | (v >> 8) as u8, v as u8]
+            }
+            _ if i16::try_from(v).is_ok() => [&[0xf1][..], &(v as i16).to_le_bytes()].concat(),
+            _ if (-(1 << 23)..1 << 23).contains(&v) => {
+                [&[0xf2][..], &(v as i32).to_le_bytes()[..3]].concat()
+            }
+            _ if i32::try_from(v).is_ok() => [&[0xf3][..], &(v as i32).to_le_bytes()].concat(),
+            _ => [&[0xf4][..], &v.to_le_bytes()].concat(),
+        };
+        self.push(&element);
+    }
+
+    pub fn push_str(&mut self, v: &[u8]) {
+        let len = v.len();
+        let header = match len {
+            0..=63 => vec![0x80 | len as u8],
+            64..=4095 => vec![0xe0 | (len >> 8) as u8, len as u8],
+            _ => [&[0xf0][..], &(len as u32).to_le_bytes()].concat(),
+        };
+        self.push(&[&header, v].concat());
+    }
+
+    pub fn finish(mut self) -> Vec<u8> {
+        self.data.push(END);
+        let total = self.data.len() as u32;
+        self.data[..4].copy_from_slice(&total.to_le_bytes());
+        // The element count saturates, after which readers have to walk the listpack.
+        let count = self.len.min(u16::MAX as usize) as u16;
+        self.data[4..6].copy_from_slice(&count.to_le_bytes());
+        self.data
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{intset, listpack, ziplist, ListpackWriter};
+
+    #[test]
+    fn should_read_written_listpack() {
+        let ints = [
+            0,
+            127,
+            128,
+            -1,
+            4095,
+            -4096,
+            30000,
+            -8_000_000,
+            1 << 30,
+            i64::MIN,
+        ];
+        let long = vec![b'x'; 5000];
+        let mut writer = ListpackWriter::new();
+        for v in ints {
+            writer.push_int(v);
+        }
+        for v in [&b""[..], b"field", &[b'y'; 200], &long] {
+            writer.push_str(v);
+        }
+        let data = writer.finish();
+        assert_eq!(
+            u32::from_le_bytes(data[..4].try_into().unwrap()) as usize,
+            data.len()
+        );
+        let mut expected: Vec<Vec<u8>> = ints.iter().map(|v| v.to_string().into_bytes()).collect();
+        expected.extend([b"".to_vec(), b"field".to_vec(), vec![b'y'; 200], long]);
+        assert_eq!(listpack(&data).unwrap(), expected);
+        assert!(listpack(&data[..data.len() - 1]).is_err());
+    }
+
+    #[test]
+    fn should_read_ziplist_and_intset() {
+        // A ziplist of "ab", 5 (immediate), 300 (int16) and -2 (int8), as written by Redis.
+        let data = [
+            0x1a, 0, 0, 0, 0x15, 0, 0, 0, 4, 0, // header
+            0x00, 0x02, b'a', b'b', // "ab"
+            0x04, 0xf6, // 5
+            0x02, 0xc0, 0x2c, 0x01, // 300
+            0x04, 0xfe, 0xfe, // -2
+            0xff,
+        ];
+        assert_eq!(
+            ziplist(&data).unwrap(),
+            vec![
+                b"ab".to_vec(),
+                b"5".to_vec(),
+                b"300".to_vec(),
+                b"-2".to_vec()
+            ]
+        );
+        let data = [2, 0, 0, 0, 2, 0, 0, 0, 0xff, 0xff, 7, 0];
+        assert_eq!(intset(&data).unwrap(), vec![b"-1".to_vec(), b"7".to_vec()]);
+        assert!(intset(&[3, 0, 0, 0, 0, 0, 0, 0]).is_err());
+    }
+}
@@ -0,0 +1,43 @@
+//! The CRC-64 variant Redis checksums RDB files with (Jones polynomial, reflected).
+
+const POLY: u64 = 0x95ac_9329_ac4b_c9b5;
+
+const fn table() -> [u64; 256] {
+    let mut table = [0u64; 256];
+    let mut i = 0;
+    while i < 256 {
+        let mut crc = i as u64;
+        let mut bit = 0;
+        while bit < 8 {
+            crc = match crc & 1 {
+                1 => (crc >> 1) ^ POLY,
+                _ => crc >> 1,
+            };
+            bit += 1;
+        }
+        table[i] = crc;
+        i += 1;
+    }
+    table
+}
+
+const TABLE: [u64; 256] = table();
+
+/// Continues the checksum `crc` over `data`. A new checksum starts from 0.
+pub fn update(mut crc: u64, data: &[u8]) -> u64 {
+    for &byte in data {
+        crc = TABLE[((crc ^ byte as u64) & 0xff) as usize] ^ (crc >> 8);
+    }
+    crc
+}
+
+#[cfg(test)]
+mod test {
+    use super::update;
+
+    #[test]
+    fn should_match_redis_test_vector() {
+        assert_eq!(update(0, b"123456789"), 0xe9c6d914c4b8d9ca);
+        assert_eq!(update(update(0, b"1234"), b"56789"), 0xe9c6d914c4b8d9ca);
+    }
+}
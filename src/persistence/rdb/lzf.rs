@@ -0,0 +1,133 @@
+//! The LZF compression Redis applies to long strings.
+
+/// The longest literal run a control byte can describe.
+const MAX_LITERAL: usize = 32;
+/// The longest match a back reference can describe.
+const MAX_MATCH: usize = 264;
+/// How far back a back reference can point.
+const MAX_OFFSET: usize = 8192;
+const HASH_BITS: u32 = 14;
+
+fn hash(data: &[u8]) -> usize {
+    let v = (data[0] as u32) << 16 | (data[1] as u32) << 8 | data[2] as u32;
+    (v.wrapping_mul(2654435761) >> (32 - HASH_BITS)) as usize
+}
+
+fn flush_literals(out: &mut Vec<u8>, literals: &mut Vec<u8>) {
+    if literals.is_empty() {
+        return;
+    }
+    out.push(literals.len() as u8 - 1);
+    out.append(literals);
+}
+
+/// Compresses `data`. The result may be larger than `data` when it does not compress well.
+pub fn compress(data: &[u8]) -> Vec<u8> {
+    let mut out = Vec::with_capacity(data.len());
+    let mut literals = Vec::with_capacity(MAX_LITERAL);
+    // The position after the last occurrence of each 3-byte sequence, 0 for none.
+    let mut table = vec![0usize; 1 << HASH_BITS];
+    let mut i = 0;
+    while i + 2 < data.len() {
+        let h = hash(&data[i..]);
+        let candidate = table[h];
+        table[h] = i + 1;
+        if candidate > 0 && i - candidate < MAX_OFFSET {
+            let from = candidate - 1;
+            if data[from..from + 3] == data[i..i + 3] {
+                let max = MAX_MATCH.min(data.len() - i);
+                let mut len = 3;
+                while len < max && data[from + len] == data[i + len] {
+                    len += 1;
+                }
+                flush_literals(&mut out, &mut literals);
+                let offset = i - from - 1;
+                let encoded_len = len - 2;
+                if encoded_len < 7 {
+                    out.push((encoded_len << 5) as u8 | (offset >> 8) as u8);
+                } else {
+                    out.push((7 << 5) | (offset >> 8) as u8);
+                    out.push((encoded_len - 7) as u8);
+                }
+                out.push(offset as u8);
+                i += len;
+                continue;
+            }
+        }
+        literals.push(data[i]);
+        if literals.len() == MAX_LITERAL {
+            flush_literals(&mut out, &mut literals);
+        }
+        i += 1;
+    }
+    for &byte in &data[i..] {
+        literals.push(byte);
+        if literals.len() == MAX_LITERAL {
+            flush_literals(&mut out, &mut literals);
+        }
+    }
+    flush_literals(&mut out, &mut literals);
+    out
+}
+
+/// Decompresses `data`, which must expand to exactly `len` bytes.
+pub fn decompress(data: &[u8], len: usize) -> Option<Vec<u8>> {
+    let mut out = Vec::with_capacity(len);
+    let mut i = 0;
+    while i < data.len() {
+        let control = data[i] as usize;
+        i += 1;
+        if control < MAX_LITERAL {
+            let run = data.get(i..i + control + 1)?;
+            out.extend_from_slice(run);
+            i += run.len();
+        } else {
+            let mut run = control >> 5;
+            if run == 7 {
+                run += *data.get(i)? as usize;
+                i += 1;
+            }
+            let offset = ((control & 0x1f) << 8) + *data.get(i)? as usize + 1;
+            i += 1;
+            let from = out.len().checked_sub(offset)?;
+            // The reference may overlap the bytes it produces, so it is copied byte by byte.
+            for j in 0..run + 2 {
+                out.push(out[from + j]);
+            }
+        }
+        if out.len() > len {
+            return None;
+        }
+    }
+    (out.len() == len).then_some(out)
+}
+
+#[cfg(test)]
+mod test {
+    use super::{compress, decompress};
+
+    #[test]
+    fn should_round_trip() {
+        let repetitive = b"abcabcabcabcabcabcabcabcabcabcabcabc".repeat(20);
+        let mut mixed = Vec::new();
+        for i in 0u32..5000 {
+            mixed.extend_from_slice(&(i.wrapping_mul(2654435761) % 7).to_le_bytes());
+        }
+        for data in [&b""[..], b"a", b"abc", &repetitive, &mixed] {
+            let compressed = compress(data);
+            assert_eq!(decompress(&compressed, data.len()).unwrap(), data);
+        }
+        assert!(compress(&repetitive).len() < repetitive.len() / 10);
+    }
+
+    #[test]
+    fn should_decompress_reference_output() {
+        // "aaaaaaaaaa" as compressed by liblzf: a literal 'a' then a match of 9 at offset 1.
+        assert_eq!(
+            decompress(&[0x00, b'a', 0xe0, 0x00, 0x00], 10).unwrap(),
+            b"aaaaaaaaaa".to_vec()
+        );
+        assert!(decompress(&[0x00, b'a', 0xe0, 0x00, 0x00], 9).is_none());
+        assert!(decompress(&[0x20, 0x05], 3).is_none());
+    }
+}
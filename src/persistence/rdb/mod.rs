@@ -0,0 +1,1082 @@
+//! The RDB file format of upstream Redis, so that datasets can be moved between Redis and this
+//! server. Files from version 9 on are read, including the compact encodings newer versions
+//! save small collections with, and files are written as version 9, which every Redis since 5.0
+//! can load:
+//!
+//! ```text
+//! "REDIS0009" [AUX name value]...
+//! SELECTDB index RESIZEDB keys expires
+//!   [EXPIRETIME_MS deadline] type key value
+//!   ...
+//! EOF checksum
+//! ```
+//!
+//! Lengths use a variable-size encoding, strings may be stored as integers or LZF-compressed, and
+//! the file ends with a CRC-64 of everything before it.
+
+mod compact;
+mod crc64;
+mod lzf;
+
+use crate::data_store::{
+    now_ms, Databases, Dataset, RedisEntry, RedisEntryType, RedisHash, RedisSet, SortedSet, Stream,
+};
+use crate::error::SnapshotError;
+use compact::ListpackWriter;
+use std::collections::LinkedList;
+use std::io::Write;
+
+pub const MAGIC: &[u8] = b"REDIS";
+const VERSION: u32 = 9;
+const MIN_VERSION: u32 = 9;
+const MAX_VERSION: u32 = 12;
+
+const OPCODE_SLOT_INFO: u8 = 0xf4;
+const OPCODE_FUNCTION: u8 = 0xf5;
+const OPCODE_FUNCTION2: u8 = 0xf6;
+const OPCODE_MODULE_AUX: u8 = 0xf7;
+const OPCODE_IDLE: u8 = 0xf8;
+const OPCODE_FREQ: u8 = 0xf9;
+const OPCODE_AUX: u8 = 0xfa;
+const OPCODE_RESIZE_DB: u8 = 0xfb;
+const OPCODE_EXPIRE_TIME_MS: u8 = 0xfc;
+const OPCODE_EXPIRE_TIME: u8 = 0xfd;
+const OPCODE_SELECT_DB: u8 = 0xfe;
+const OPCODE_EOF: u8 = 0xff;
+
+const TYPE_STRING: u8 = 0;
+const TYPE_LIST: u8 = 1;
+const TYPE_SET: u8 = 2;
+const TYPE_ZSET: u8 = 3;
+const TYPE_HASH: u8 = 4;
+const TYPE_ZSET_2: u8 = 5;
+const TYPE_LIST_ZIPLIST: u8 = 10;
+const TYPE_SET_INTSET: u8 = 11;
+const TYPE_ZSET_ZIPLIST: u8 = 12;
+const TYPE_HASH_ZIPLIST: u8 = 13;
+const TYPE_LIST_QUICKLIST: u8 = 14;
+const TYPE_STREAM_LISTPACKS: u8 = 15;
+const TYPE_HASH_LISTPACK: u8 = 16;
+const TYPE_ZSET_LISTPACK: u8 = 17;
+const TYPE_LIST_QUICKLIST_2: u8 = 18;
+const TYPE_STREAM_LISTPACKS_2: u8 = 19;
+const TYPE_SET_LISTPACK: u8 = 20;
+const TYPE_STREAM_LISTPACKS_3: u8 = 21;
+
+/// The two high bits of a length's first byte that mark the length as a special string encoding.
+const ENCODED_LEN: u8 = 0xc0;
+const ENCODING_INT8: u8 = 0;
+const ENCODING_INT16: u8 = 1;
+const ENCODING_INT32: u8 = 2;
+const ENCODING_LZF: u8 = 3;
+
+/// Strings shorter than this are never worth compressing.
+const MIN_COMPRESS_LEN: usize = 21;
+
+/// A quicklist node holding a single element rather than a listpack.
+const QUICKLIST_NODE_PLAIN: u64 = 1;
+
+const STREAM_ITEM_DELETED: i64 = 1;
+const STREAM_ITEM_SAME_FIELDS: i64 = 2;
+
+/// Returns whether `data` starts with an RDB file.
+pub fn is_rdb(data: &[u8]) -> bool {
+    data.starts_with(MAGIC)
+}
+
+pub fn write<W: Write>(out: &mut W, databases: &impl Dataset) -> std::io::Result<()> {
+    let mut encoder = Encoder { out, crc: 0 };
+    encoder.raw(format!("REDIS{:04}", VERSION).as_bytes())?;
+    encoder.aux(
+        b"redis-bits",
+        &(usize::BITS as u64).to_string().into_bytes(),
+    )?;
+    encoder.aux(b"ctime", &(now_ms() / 1000).to_string().into_bytes())?;
+    let now = now_ms();
+    for index in 0..databases.count() {
+        let (len, expires) = databases
+            .entries(index)
+            .fold((0, 0), |(len, expires), (_, _, deadline)| {
+                (len + 1, expires + deadline.is_some() as u64)
+            });
+        if len == 0 {
+            continue;
+        }
+        encoder.raw(&[OPCODE_SELECT_DB])?;
+        encoder.len(index as u64)?;
+        encoder.raw(&[OPCODE_RESIZE_DB])?;
+        encoder.len(len)?;
+        encoder.len(expires)?;
+        for (key, entry, deadline) in databases.entries(index) {
+            if let Some(deadline) = deadline {
+                // Expired keys that have not been evicted yet are left out.
+                if deadline <= now {
+                    continue;
+                }
+                encoder.raw(&[OPCODE_EXPIRE_TIME_MS])?;
+                encoder.raw(&deadline.to_le_bytes())?;
+            }
+            encoder.entry(Some(key), entry)?;
+        }
+    }
+    encoder.raw(&[OPCODE_EOF])?;
+    let crc = encoder.crc;
+    encoder.raw(&crc.to_le_bytes())
+}
+
+/// Serialises a value the way `DUMP` does: the value as stored in an RDB file, followed by the
+/// RDB version and a CRC-64 of everything before it.
+pub fn dump(entry: &RedisEntry) -> std::io::Result<Vec<u8>> {
+    let mut out = Vec::new();
+    let mut encoder = Encoder {
+        out: &mut out,
+        crc: 0,
+    };
+    encoder.entry(None, entry)?;
+    encoder.raw(&(VERSION as u16).to_le_bytes())?;
+    let crc = encoder.crc;
+    encoder.raw(&crc.to_le_bytes())?;
+    Ok(out)
+}
+
+/// Reads a value serialised by `DUMP`, checking its version and checksum.
+pub fn load_dump(payload: &[u8]) -> Result<RedisEntry, SnapshotError> {
+    let Some(body_len) = payload.len().checked_sub(10) else {
+        return Err(SnapshotError::UnexpectedEnd);
+    };
+    let version = u16::from_le_bytes([payload[body_len], payload[body_len + 1]]) as u32;
+    if version > MAX_VERSION {
+        return Err(SnapshotError::UnsupportedVersion(version));
+    }
+    let expected = u64::from_le_bytes(payload[body_len + 2..].try_into().unwrap());
+    let actual = crc64::update(0, &payload[..body_len + 2]);
+    if expected != actual {
+        return Err(SnapshotError::ChecksumMismatch(expected, actual));
+    }
+    let mut decoder = Decoder {
+        data: &payload[..body_len],
+        pos: 0,
+    };
+    let type_ = decoder.u8()?;
+    let entry = decoder.entry(type_, b"")?;
+    if decoder.pos != body_len {
+        return Err(SnapshotError::InvalidData(
+            "trailing bytes after the value".to_string(),
+        ));
+    }
+    Ok(entry)
+}
+
+/// Reads the RDB file at the start of `data` into `databases`. Returns the length of the file,
+/// which may be followed by other data such as the commands of an append-only file.
+pub fn read(data: &[u8], databases: &mut Databases) -> Result<usize, SnapshotError> {
+    let mut decoder = Decoder { data, pos: 0 };
+    if decoder.take(MAGIC.len())? != MAGIC {
+        return Err(SnapshotError::InvalidHeader);
+    }
+    let version = std::str::from_utf8(decoder.take(4)?)
+        .ok()
+        .and_then(|v| v.parse::<u32>().ok())
+        .ok_or(SnapshotError::InvalidHeader)?;
+    if !(MIN_VERSION..=MAX_VERSION).contains(&version) {
+        return Err(SnapshotError::UnsupportedVersion(version));
+    }
+    let mut db = 0;
+    let mut deadline = None;
+    loop {
+        match decoder.u8()? {
+            OPCODE_EOF => {
+                let end = decoder.pos;
+                let expected = u64::from_le_bytes(decoder.take(8)?.try_into().unwrap());
+                // A checksum of 0 means the file was saved with checksums disabled.
+                let actual = crc64::update(0, &data[..end]);
+                if expected != 0 && expected != actual {
+                    return Err(SnapshotError::ChecksumMismatch(expected, actual));
+                }
+                return Ok(decoder.pos);
+            }
+            OPCODE_SELECT_DB => {
+                let index = decoder.len()?;
+                if index >= databases.count() as u64 {
+                    return Err(SnapshotError::DbIndexOutOfRange(index));
+                }
+                db = index as usize;
+            }
+            OPCODE_RESIZE_DB => {
+                decoder.len()?;
+                decoder.len()?;
+            }
+            OPCODE_EXPIRE_TIME_MS => {
+                deadline = Some(u64::from_le_bytes(decoder.take(8)?.try_into().unwrap()))
+            }
+            OPCODE_EXPIRE_TIME => {
+                let secs = u32::from_le_bytes(decoder.take(4)?.try_into().unwrap());
+                deadline = Some(secs as u64 * 1000);
+            }
+            OPCODE_AUX => {
+                decoder.string()?;
+                decoder.string()?;
+            }
+            // Eviction hints and the slot of the keys that follow are of no use here.
+            OPCODE_IDLE => {
+                decoder.len()?;
+            }
+            OPCODE_FREQ => {
+                decoder.u8()?;
+            }
+            OPCODE_SLOT_INFO => {
+                for _ in 0..3 {
+                    decoder.len()?;
+                }
+            }
+            OPCODE_FUNCTION2 => {
+                log::warn!("Skipping a function library, which is not supported");
+                decoder.string()?;
+            }
+            OPCODE_FUNCTION | OPCODE_MODULE_AUX => {
+                return Err(SnapshotError::InvalidData(
+                    "modules and pre-release functions are not supported".to_string(),
+                ))
+            }
+            type_ => {
+                let key = decoder.string()?;
+                let entry = decoder.entry(type_, &key)?;
+                databases.db_mut(db).restore(&key, entry, deadline.take());
+            }
+        }
+    }
+}
+
+/// Returns whether `value` is stored as an integer by Redis, which is the case when it is the
+/// canonical decimal form of a 32-bit integer.
+fn as_encoded_int(value: &[u8]) -> Option<i32> {
+    if value.len() > 11 {
+        return None;
+    }
+    let v: i32 = std::str::from_utf8(value).ok()?.parse().ok()?;
+    (v.to_string().as_bytes() == value).then_some(v)
+}
+
+struct Encoder<'a, W: Write> {
+    out: &'a mut W,
+    /// The checksum of everything written so far.
+    crc: u64,
+}
+
+impl<W: Write> Encoder<'_, W> {
+    fn raw(&mut self, value: &[u8]) -> std::io::Result<()> {
+        self.crc = crc64::update(self.crc, value);
+        self.out.write_all(value)
+    }
+
+    fn len(&mut self, len: u64) -> std::io::Result<()> {
+        match len {
+            0..=0x3f => self.raw(&[len as u8]),
+            0x40..=0x3fff => self.raw(&[0x40 | (len >> 8) as u8, len as u8]),
+            _ if len <= u32::MAX as u64 => {
+                self.raw(&[0x80])?;
+                self.raw(&(len as u32).to_be_bytes())
+            }
+            _ => {
+                self.raw(&[0x81])?;
+                self.raw(&len.to_be_bytes())
+            }
+        }
+    }
+
+    fn string(&mut self, value: &[u8]) -> std::io::Result<()> {
+        if let Some(v) = as_encoded_int(value) {
+            return match v {
+                _ if i8::try_from(v).is_ok() => self.raw(&[ENCODED_LEN | ENCODING_INT8, v as u8]),
+                _ if i16::try_from(v).is_ok() => {
+                    self.raw(&[ENCODED_LEN | ENCODING_INT16])?;
+                    self.raw(&(v as i16).to_le_bytes())
+                }
+                _ => {
+                    self.raw(&[ENCODED_LEN | ENCODING_INT32])?;
+                    self.raw(&v.to_le_bytes())
+                }
+            };
+        }
+        if value.len() >= MIN_COMPRESS_LEN {
+            let compressed = lzf::compress(value);
+            // Like Redis, only keep the compressed form if it saves a few bytes.
+            if compressed.len() + 4 <= value.len() {
+                self.raw(&[ENCODED_LEN | ENCODING_LZF])?;
+                self.len(compressed.len() as u64)?;
+                self.len(value.len() as u64)?;
+                return self.raw(&compressed);
+            }
+        }
+        self.len(value.len() as u64)?;
+        self.raw(value)
+    }
+
+    fn aux(&mut self, name: &[u8], value: &[u8]) -> std::io::Result<()> {
+        self.raw(&[OPCODE_AUX])?;
+        self.string(name)?;
+        self.string(value)
+    }
+
+    /// Writes the key of an entry of the file. `DUMP` payloads have none.
+    fn key(&mut self, key: Option<&[u8]>) -> std::io::Result<()> {
+        match key {
+            Some(key) => self.string(key),
+            None => Ok(()),
+        }
+    }
+
+    fn entry(&mut self, key: Option<&[u8]>, entry: &RedisEntry) -> std::io::Result<()> {
+        match entry {
+            RedisEntry {
+                type_: RedisEntryType::String,
+                string: Some(v),
+                ..
+            } => {
+                self.raw(&[TYPE_STRING])?;
+                self.key(key)?;
+                self.string(v)
+            }
+            RedisEntry {
+                type_: RedisEntryType::List,
+                list: Some(v),
+                ..
+            } => {
+                self.raw(&[TYPE_LIST])?;
+                self.key(key)?;
+                self.len(v.len() as u64)?;
+                v.iter().try_for_each(|item| self.string(item))
+            }
+            RedisEntry {
+                type_: RedisEntryType::Set,
+                set: Some(v),
+                ..
+            } => {
+                self.raw(&[TYPE_SET])?;
+                self.key(key)?;
+                self.len(v.len() as u64)?;
+                v.iter().try_for_each(|member| self.string(member))
+            }
+            RedisEntry {
+                type_: RedisEntryType::Hash,
+                hash: Some(v),
+                ..
+            } => {
+                self.raw(&[TYPE_HASH])?;
+                self.key(key)?;
+                self.len(v.len() as u64)?;
+                v.iter().try_for_each(|(field, value)| {
+                    self.string(field)?;
+                    self.string(value)
+                })
+            }
+            RedisEntry {
+                type_: RedisEntryType::SortedSet,
+                sorted_set: Some(v),
+                ..
+            } => {
+                self.raw(&[TYPE_ZSET_2])?;
+                self.key(key)?;
+                self.len(v.len() as u64)?;
+                v.iter().try_for_each(|(member, score)| {
+                    self.string(member)?;
+                    self.raw(&score.to_le_bytes())
+                })
+            }
+            RedisEntry {
+                type_: RedisEntryType::Stream,
+                stream: Some(v),
+                ..
+            } => {
+                self.raw(&[TYPE_STREAM_LISTPACKS])?;
+                self.key(key)?;
+                self.stream(v)
+            }
+            _ => Err(std::io::Error::new(
+                std::io::ErrorKind::InvalidData,
+                format!(
+                    "key '{}' has no value of its type",
+                    String::from_utf8_lossy(key.unwrap_or_default())
+                ),
+            )),
+        }
+    }
+
+    /// Writes a stream as a single listpack node whose master entry is the first entry. Entries
+    /// with the same fields as the master entry only store their values.
+    fn stream(&mut self, stream: &Stream) -> std::io::Result<()> {
+        let entries = stream.entries();
+        let Some(&(master_id, master_fields)) = entries.first() else {
+            self.len(0)?;
+            // The length, the last ID and the number of consumer groups.
+            return [0, 0, 0, 0].into_iter().try_for_each(|v| self.len(v));
+        };
+        let mut node = ListpackWriter::new();
+        node.push_int(entries.len() as i64);
+        node.push_int(0);
+        node.push_int(master_fields.len() as i64);
+        for [field, _] in master_fields {
+            node.push_str(field);
+        }
+        node.push_int(0);
+        for &(id, fields) in &entries {
+            let same_fields = fields.len() == master_fields.len()
+                && fields
+                    .iter()
+                    .zip(master_fields)
+                    .all(|([a, _], [b, _])| a == b);
+            node.push_int(match same_fields {
+                true => STREAM_ITEM_SAME_FIELDS,
+                false => 0,
+            });
+            node.push_int(id[0].wrapping_sub(master_id[0]) as i64);
+            node.push_int(id[1].wrapping_sub(master_id[1]) as i64);
+            if same_fields {
+                for [_, value] in fields {
+                    node.push_str(value);
+                }
+                node.push_int(fields.len() as i64 + 3);
+            } else {
+                node.push_int(fields.len() as i64);
+                for [field, value] in fields {
+                    node.push_str(field);
+                    node.push_str(value);
+                }
+                node.push_int(fields.len() as i64 * 2 + 4);
+            }
+        }
+        self.len(1)?;
+        self.string(&[master_id[0].to_be_bytes(), master_id[1].to_be_bytes()].concat())?;
+        self.string(&node.finish())?;
+        let last_id = entries.last().unwrap().0;
+        self.len(entries.len() as u64)?;
+        self.len(last_id[0])?;
+        self.len(last_id[1])?;
+        self.len(0)
+    }
+}
+
+struct Decoder<'a> {
+    data: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> Decoder<'a> {
+    fn take(&mut self, len: usize) -> Result<&'a [u8], SnapshotError> {
+        if self.data.len() - self.pos < len {
+            return Err(SnapshotError::UnexpectedEnd);
+        }
+        let v = &self.data[self.pos..self.pos + len];
+        self.pos += len;
+        Ok(v)
+    }
+
+    fn u8(&mut self) -> Result<u8, SnapshotError> {
+        Ok(self.take(1)?[0])
+    }
+
+    /// Reads a length, or the special encoding of the string that follows, in which case the
+    /// second value is true.
+    fn len_or_encoding(&mut self) -> Result<(u64, bool), SnapshotError> {
+        let b = self.u8()?;
+        match b >> 6 {
+            0 => Ok((b as u64 & 0x3f, false)),
+            1 => Ok((((b as u64 & 0x3f) << 8) | self.u8()? as u64, false)),
+            2 => match b {
+                0x80 => Ok((
+                    u32::from_be_bytes(self.take(4)?.try_into().unwrap()) as u64,
+                    false,
+                )),
+                0x81 => Ok((u64::from_be_bytes(self.take(8)?.try_into().unwrap()), false)),
+                _ => Err(SnapshotError::InvalidData(format!(
+                    "invalid length encoding {:#04x}",
+                    b
+                ))),
+            },
+            _ => Ok((b as u64 & 0x3f, true)),
+        }
+    }
+
+    fn len(&mut self) -> Result<u64, SnapshotError> {
+        match self.len_or_encoding()? {
+            (len, false) => Ok(len),
+            _ => Err(SnapshotError::InvalidData(
+                "expected a length, found an encoded string".to_string(),
+            )),
+        }
+    }
+
+    /// Reads a length of bytes, which cannot be trusted to allocate up front as the data may be
+    /// corrupt.
+    fn byte_len(&mut self) -> Result<usize, SnapshotError> {
+        let len = self.len()?;
+        match usize::try_from(len) {
+            Ok(v) if v <= self.data.len() - self.pos => Ok(v),
+            _ => Err(SnapshotError::UnexpectedEnd),
+        }
+    }
+
+    fn string(&mut self) -> Result<Vec<u8>, SnapshotError> {
+        let (len, encoded) = self.len_or_encoding()?;
+        if !encoded {
+            let len = usize::try_from(len).map_err(|_| SnapshotError::UnexpectedEnd)?;
+            return Ok(self.take(len)?.to_vec());
+        }
+        let int = match len as u8 {
+            ENCODING_INT8 => self.u8()? as i8 as i64,
+            ENCODING_INT16 => i16::from_le_bytes(self.take(2)?.try_into().unwrap()) as i64,
+            ENCODING_INT32 => i32::from_le_bytes(self.take(4)?.try_into().unwrap()) as i64,
+            ENCODING_LZF => {
+                let compressed_len = self.byte_len()?;
+                let len = self.len()?;
+                let compressed = self.take(compressed_len)?;
+                // A run of up to 264 bytes takes 3 bytes, which bounds how much the data expands.
+                if len > compressed_len as u64 * 88 + 32 {
+                    return Err(SnapshotError::InvalidData("invalid LZF length".to_string()));
+                }
+                return lzf::decompress(compressed, len as usize)
+                    .ok_or_else(|| SnapshotError::InvalidData("invalid LZF data".to_string()));
+            }
+            v => {
+                return Err(SnapshotError::InvalidData(format!(
+                    "invalid string encoding {}",
+                    v
+                )))
+            }
+        };
+        Ok(int.to_string().into_bytes())
+    }
+
+    /// Reads a score stored as a string, with special lengths for NaN and infinities.
+    fn string_double(&mut self) -> Result<f64, SnapshotError> {
+        match self.u8()? {
+            253 => Ok(f64::NAN),
+            254 => Ok(f64::INFINITY),
+            255 => Ok(f64::NEG_INFINITY),
+            len => parse_score(self.take(len as usize)?),
+        }
+    }
+
+    /// Reads a listpack, ziplist or integer set stored as a string, with `parse`.
+    fn compact(&mut self, parse: compact::Parser) -> Result<Vec<Vec<u8>>, SnapshotError> {
+        parse(&self.string()?).map_err(SnapshotError::InvalidData)
+    }
+
+    fn entry(&mut self, type_: u8, key: &[u8]) -> Result<RedisEntry, SnapshotError> {
+        let entry = match type_ {
+            TYPE_STRING => RedisEntry::create_string(&self.string()?),
+            TYPE_LIST => {
+                let mut items = Vec::new();
+                for _ in 0..self.len()? {
+                    items.push(self.string()?);
+                }
+                list_entry(items)
+            }
+            TYPE_LIST_ZIPLIST => list_entry(self.compact(compact::ziplist)?),
+            TYPE_LIST_QUICKLIST | TYPE_LIST_QUICKLIST_2 => {
+                let mut items = Vec::new();
+                for _ in 0..self.len()? {
+                    if type_ == TYPE_LIST_QUICKLIST {
+                        items.extend(self.compact(compact::ziplist)?);
+                    } else if self.len()? == QUICKLIST_NODE_PLAIN {
+                        items.push(self.string()?);
+                    } else {
+                        items.extend(self.compact(compact::listpack)?);
+                    }
+                }
+                list_entry(items)
+            }
+            TYPE_SET => {
+                let mut members = Vec::new();
+                for _ in 0..self.len()? {
+                    members.push(self.string()?);
+                }
+                set_entry(members)
+            }
+            TYPE_SET_INTSET => set_entry(self.compact(compact::intset)?),
+            TYPE_SET_LISTPACK => set_entry(self.compact(compact::listpack)?),
+            TYPE_HASH => {
+                let mut pairs = Vec::new();
+                for _ in 0..self.len()? {
+                    pairs.push(self.string()?);
+                    pairs.push(self.string()?);
+                }
+                hash_entry(pairs)?
+            }
+            TYPE_HASH_ZIPLIST => hash_entry(self.compact(compact::ziplist)?)?,
+            TYPE_HASH_LISTPACK => hash_entry(self.compact(compact::listpack)?)?,
+            TYPE_ZSET | TYPE_ZSET_2 => {
+                let mut sorted_set = SortedSet::new();
+                for _ in 0..self.len()? {
+                    let member = self.string()?;
+                    let score = match type_ {
+                        TYPE_ZSET => self.string_double()?,
+                        _ => f64::from_le_bytes(self.take(8)?.try_into().unwrap()),
+                    };
+                    sorted_set.insert(score, member);
+                }
+                sorted_set_entry(sorted_set)
+            }
+            TYPE_ZSET_ZIPLIST => compact_sorted_set(self.compact(compact::ziplist)?)?,
+            TYPE_ZSET_LISTPACK => compact_sorted_set(self.compact(compact::listpack)?)?,
+            TYPE_STREAM_LISTPACKS | TYPE_STREAM_LISTPACKS_2 | TYPE_STREAM_LISTPACKS_3 => {
+                self.stream(type_, key)?
+            }
+            v => return Err(SnapshotError::UnsupportedType(v)),
+        };
+        Ok(entry)
+    }
+
+    fn stream(&mut self, type_: u8, key: &[u8]) -> Result<RedisEntry, SnapshotError> {
+        let mut stream = Stream::new();
+        for _ in 0..self.len()? {
+            let node_key = self.string()?;
+            if node_key.len() != 16 {
+                return Err(SnapshotError::InvalidData(
+                    "invalid stream node key".to_string(),
+                ));
+            }
+            let master_id = [
+                u64::from_be_bytes(node_key[..8].try_into().unwrap()),
+                u64::from_be_bytes(node_key[8..].try_into().unwrap()),
+            ];
+            let elements = self.compact(compact::listpack)?;
+            let mut node = StreamNode {
+                elements: elements.into_iter(),
+            };
+            let count = node.int()? + node.int()?;
+            let master_fields = (0..node.int()?)
+                .map(|_| node.element())
+                .collect::<Result<Vec<_>, _>>()?;
+            node.int()?;
+            for _ in 0..count {
+                let flags = node.int()?;
+                let id = [
+                    master_id[0].wrapping_add(node.int()? as u64),
+                    master_id[1].wrapping_add(node.int()? as u64),
+                ];
+                let fields = match flags & STREAM_ITEM_SAME_FIELDS {
+                    0 => (0..node.int()?)
+                        .map(|_| Ok([node.element()?, node.element()?]))
+                        .collect::<Result<Vec<_>, SnapshotError>>()?,
+                    _ => master_fields
+                        .iter()
+                        .map(|field| Ok([field.clone(), node.element()?]))
+                        .collect::<Result<Vec<_>, SnapshotError>>()?,
+                };
+                node.int()?;
+                if flags & STREAM_ITEM_DELETED != 0 {
+                    continue;
+                }
+                if let Err(e) = stream.insert(Some(id), fields) {
+                    return Err(SnapshotError::InvalidData(e.to_string()));
+                }
+            }
+        }
+        // The length and last ID, then from version 2 on the first ID, the last deleted ID and
+        // the number of entries ever added.
+        let metadata = match type_ {
+            TYPE_STREAM_LISTPACKS => 3,
+            _ => 8,
+        };
+        for _ in 0..metadata {
+            self.len()?;
+        }
+        let groups = self.len()?;
+        if groups > 0 {
+            log::warn!(
+                "Dropping the consumer groups of stream '{}', which are not supported",
+                String::from_utf8_lossy(key)
+            );
+        }
+        for _ in 0..groups {
+            self.string()?;
+            self.len()?;
+            self.len()?;
+            if type_ != TYPE_STREAM_LISTPACKS {
+                self.len()?;
+            }
+            // Each pending entry has an ID, a delivery time and a delivery count.
+            for _ in 0..self.len()? {
+                self.take(16 + 8)?;
+                self.len()?;
+            }
+            for _ in 0..self.len()? {
+                self.string()?;
+                self.take(match type_ {
+                    TYPE_STREAM_LISTPACKS_3 => 16,
+                    _ => 8,
+                })?;
+                for _ in 0..self.len()? {
+                    self.take(16)?;
+                }
+            }
+        }
+        Ok(RedisEntry {
+            type_: RedisEntryType::Stream,
+            stream: Some(stream),
+            ..Default::default()
+        })
+    }
+}
+
+/// The elements of a stream listpack node.
+struct StreamNode {
+    elements: std::vec::IntoIter<Vec<u8>>,
+}
+
+impl StreamNode {
+    fn element(&mut self) -> Result<Vec<u8>, SnapshotError> {
+        self.elements
+            .next()
+            .ok_or_else(|| SnapshotError::InvalidData("truncated stream node".to_string()))
+    }
+
+    fn int(&mut self) -> Result<i64, SnapshotError> {
+        let element = self.element()?;
+        std::str::from_utf8(&element)
+            .ok()
+            .and_then(|v| v.parse().ok())
+            .ok_or_else(|| SnapshotError::InvalidData("invalid stream node".to_string()))
+    }
+}
+
+fn parse_score(value: &[u8]) -> Result<f64, SnapshotError> {
+    std::str::from_utf8(value)
+        .ok()
+        .and_then(|v| v.parse().ok())
+        .ok_or_else(|| {
+            SnapshotError::InvalidData(format!(
+                "invalid score '{}'",
+                String::from_utf8_lossy(value)
+            ))
+        })
+}
+
+fn list_entry(items: Vec<Vec<u8>>) -> RedisEntry {
+    RedisEntry {
+        type_: RedisEntryType::List,
+        list: Some(items.into_iter().collect::<LinkedList<_>>()),
+        ..Default::default()
+    }
+}
+
+fn set_entry(members: Vec<Vec<u8>>) -> RedisEntry {
+    let mut set = RedisSet::default();
+    for member in members {
+        set.insert(member);
+    }
+    RedisEntry {
+        type_: RedisEntryType::Set,
+        set: Some(set),
+        ..Default::default()
+    }
+}
+
+/// Builds a hash from alternating fields and values.
+fn hash_entry(pairs: Vec<Vec<u8>>) -> Result<RedisEntry, SnapshotError> {
+    if !pairs.len().is_multiple_of(2) {
+        return Err(SnapshotError::InvalidData(
+            "hash with a field but no value".to_string(),
+        ));
+    }
+    let mut hash = RedisHash::default();
+    let mut pairs = pairs.into_iter();
+    while let (Some(field), Some(value)) = (pairs.next(), pairs.next()) {
+        hash.insert(field, value);
+    }
+    Ok(RedisEntry {
+        type_: RedisEntryType::Hash,
+        hash: Some(hash),
+        ..Default::default()
+    })
+}
+
+fn sorted_set_entry(sorted_set: SortedSet) -> RedisEntry {
+    RedisEntry {
+        type_: RedisEntryType::SortedSet,
+        sorted_set: Some(sorted_set),
+        ..Default::default()
+    }
+}
+
+/// Builds a sorted set from alternating members and scores.
+fn compact_sorted_set(pairs: Vec<Vec<u8>>) -> Result<RedisEntry, SnapshotError> {
+    if !pairs.len().is_multiple_of(2) {
+        return Err(SnapshotError::InvalidData(
+            "sorted set member with no score".to_string(),
+        ));
+    }
+    let mut sorted_set = SortedSet::new();
+    for pair in pairs.chunks(2) {
+        sorted_set.insert(parse_score(&pair[1])?, pair[0].clone());
+    }
+    Ok(sorted_set_entry(sorted_set))
+}
+
+#[cfg(test)]
+mod test {
+    use super::compact::ListpackWriter;
+    use super::{dump, load_dump, read, write, Encoder};
+    use crate::data_store::{now_ms, Databases};
+
+    fn populate(dbs: &mut Databases) {
+        let db = dbs.db_mut(0);
+        db.set_string_overwrite(b"string", b"value");
+        db.set_expire_at(b"string", now_ms() + 100000);
+        db.set_string_overwrite(b"int", b"-300");
+        db.set_string_overwrite(b"big", b"12345678901");
+        db.set_string_overwrite(b"long", &b"compressible ".repeat(10));
+        let _ = db.insert_list(b"list");
+        let list = db.get_list_mut(b"list").unwrap().unwrap();
+        list.push_back(b"a".to_vec());
+        list.push_back(b"7".to_vec());
+        let _ = db.insert_set(b"set");
+        db.get_set_mut(b"set")
+            .unwrap()
+            .unwrap()
+            .insert(b"m".to_vec());
+        let _ = db.insert_hash(b"hash");
+        let hash = db.get_hash_mut(b"hash").unwrap().unwrap();
+        hash.insert(b"f".to_vec(), b"v".to_vec());
+
+        let db = dbs.db_mut(3);
+        let _ = db.insert_sorted_set(b"zset");
+        let zset = db.get_sorted_set_mut(b"zset").unwrap().unwrap();
+        zset.insert(1.5, b"x".to_vec());
+        zset.insert(f64::NEG_INFINITY, b"y".to_vec());
+        let _ = db.insert_stream(b"stream");
+        let stream = db.get_stream_mut(b"stream").unwrap().unwrap();
+        stream
+            .insert(Some([5, 1]), vec![[b"k".to_vec(), b"1".to_vec()]])
+            .unwrap();
+        stream
+            .insert(Some([5, 2]), vec![[b"k".to_vec(), b"2".to_vec()]])
+            .unwrap();
+        stream
+            .insert(
+                Some([9, 0]),
+                vec![
+                    [b"a".to_vec(), b"x".to_vec()],
+                    [b"b".to_vec(), b"y".to_vec()],
+                ],
+            )
+            .unwrap();
+    }
+
+    #[test]
+    fn should_round_trip_every_type() {
+        let mut dbs = Databases::new(4);
+        populate(&mut dbs);
+        let mut data = Vec::new();
+        write(&mut data, &dbs).unwrap();
+        assert!(data.starts_with(b"REDIS0009"));
+        // The long string is stored compressed.
+        assert!(!data
+            .windows(26)
+            .any(|w| w == b"compressible compressible".as_slice()));
+
+        let mut restored = Databases::new(4);
+        assert_eq!(read(&data, &mut restored).unwrap(), data.len());
+        let db = restored.db_mut(0);
+        assert_eq!(db.len(), 7);
+        assert_eq!(db.get_string(b"string").unwrap().unwrap(), b"value");
+        assert_eq!(
+            db.get_expire_at(b"string"),
+            dbs.db_mut(0).get_expire_at(b"string")
+        );
+        assert_eq!(db.get_string(b"int").unwrap().unwrap(), b"-300");
+        assert_eq!(db.get_string(b"big").unwrap().unwrap(), b"12345678901");
+        assert_eq!(
+            db.get_string(b"long").unwrap().unwrap(),
+            &b"compressible ".repeat(10)
+        );
+        let list = db.get_list_mut(b"list").unwrap().unwrap();
+        assert_eq!(list.iter().collect::<Vec<_>>(), vec![b"a", b"7"]);
+        assert!(db
+            .get_set_mut(b"set")
+            .unwrap()
+            .unwrap()
+            .contains(b"m".as_slice()));
+        let hash = db.get_hash_mut(b"hash").unwrap().unwrap();
+        assert_eq!(hash.get(b"f".as_slice()).unwrap(), b"v");
+
+        let db = restored.db_mut(3);
+        let zset = db.get_sorted_set_mut(b"zset").unwrap().unwrap();
+        assert_eq!(zset.get(b"x"), Some(1.5));
+        assert_eq!(zset.get(b"y"), Some(f64::NEG_INFINITY));
+        let stream = db.get_stream_mut(b"stream").unwrap().unwrap();
+        let entries = stream.entries();
+        assert_eq!(
+            entries.iter().map(|(id, _)| *id).collect::<Vec<_>>(),
+            vec![[5, 1], [5, 2], [9, 0]]
+        );
+        assert_eq!(entries[1].1, &[[b"k".to_vec(), b"2".to_vec()]]);
+        assert_eq!(entries[2].1[1], [b"b".to_vec(), b"y".to_vec()]);
+    }
+
+    #[test]
+    fn should_read_compact_encodings() {
+        let mut data = Vec::new();
+        let mut encoder = Encoder {
+            out: &mut data,
+            crc: 0,
+        };
+        encoder.raw(b"REDIS0011").unwrap();
+        encoder.aux(b"redis-ver", b"7.2.4").unwrap();
+        encoder.raw(&[0xfe, 1]).unwrap();
+
+        let listpack = |values: &[&[u8]]| {
+            let mut writer = ListpackWriter::new();
+            values.iter().for_each(|v| writer.push_str(v));
+            writer.finish()
+        };
+        encoder.raw(&[16]).unwrap();
+        encoder.string(b"hash").unwrap();
+        encoder
+            .string(&listpack(&[b"f1", b"v1", b"f2", b"v2"]))
+            .unwrap();
+        encoder.raw(&[11]).unwrap();
+        encoder.string(b"intset").unwrap();
+        encoder
+            .string(&[2, 0, 0, 0, 2, 0, 0, 0, 0xfd, 0xff, 9, 0])
+            .unwrap();
+        // Expires at second 4102444800, i.e. in 2100.
+        encoder.raw(&[0xfd, 0x00, 0x57, 0x86, 0xf4]).unwrap();
+        encoder.raw(&[18]).unwrap();
+        encoder.string(b"list").unwrap();
+        encoder.len(2).unwrap();
+        encoder.len(2).unwrap();
+        encoder.string(&listpack(&[b"a", b"b"])).unwrap();
+        encoder.len(1).unwrap();
+        encoder.string(b"plain").unwrap();
+        encoder.raw(&[17]).unwrap();
+        encoder.string(b"zset").unwrap();
+        encoder
+            .string(&listpack(&[b"m", b"2.5", b"n", b"-1"]))
+            .unwrap();
+
+        // A stream with a deleted entry and a consumer group.
+        encoder.raw(&[21]).unwrap();
+        encoder.string(b"stream").unwrap();
+        encoder.len(1).unwrap();
+        encoder
+            .string(&[10u64.to_be_bytes(), 0u64.to_be_bytes()].concat())
+            .unwrap();
+        let mut node = ListpackWriter::new();
+        for v in [1, 1, 1] {
+            node.push_int(v);
+        }
+        node.push_str(b"f");
+        node.push_int(0);
+        for v in [3, 0, 0] {
+            node.push_int(v);
+        }
+        node.push_str(b"gone");
+        node.push_int(4);
+        for v in [2, 5, 1] {
+            node.push_int(v);
+        }
+        node.push_str(b"kept");
+        node.push_int(4);
+        encoder.string(&node.finish()).unwrap();
+        for v in [1, 15, 1, 10, 0, 10, 0, 2, 1] {
+            encoder.len(v).unwrap();
+        }
+        encoder.string(b"group").unwrap();
+        for v in [15, 1, 1, 0] {
+            encoder.len(v).unwrap();
+        }
+        encoder.len(1).unwrap();
+        encoder.string(b"consumer").unwrap();
+        encoder.raw(&[0; 16]).unwrap();
+        encoder.len(0).unwrap();
+        // Checksums disabled.
+        encoder.raw(&[0xff]).unwrap();
+        encoder.raw(&[0; 8]).unwrap();
+
+        let mut dbs = Databases::new(2);
+        read(&data, &mut dbs).unwrap();
+        let db = dbs.db_mut(1);
+        let hash = db.get_hash_mut(b"hash").unwrap().unwrap();
+        assert_eq!(hash.get(b"f2".as_slice()).unwrap(), b"v2");
+        let set = db.get_set_mut(b"intset").unwrap().unwrap();
+        assert!(set.contains(b"-3".as_slice()) && set.contains(b"9".as_slice()));
+        assert_eq!(db.get_expire_at(b"list"), Some(4102444800000));
+        let list = db.get_list_mut(b"list").unwrap().unwrap();
+        assert_eq!(
+            list.iter().collect::<Vec<_>>(),
+            vec![&b"a"[..], b"b", b"plain"]
+        );
+        let zset = db.get_sorted_set_mut(b"zset").unwrap().unwrap();
+        assert_eq!(
+            zset.get_values_by_rank(0, 1),
+            vec![b"n".to_vec(), b"m".to_vec()]
+        );
+        let stream = db.get_stream_mut(b"stream").unwrap().unwrap();
+        assert_eq!(
+            stream.entries(),
+            vec![([15, 1], &[[b"f".to_vec(), b"kept".to_vec()]][..])]
+        );
+    }
+
+    #[test]
+    fn should_reject_invalid_files() {
+        let mut dbs = Databases::new(4);
+        populate(&mut dbs);
+        let mut data = Vec::new();
+        write(&mut data, &dbs).unwrap();
+
+        let mut corrupt = data.clone();
+        corrupt[20] ^= 1;
+        let err = read(&corrupt, &mut Databases::new(4)).err().unwrap();
+        assert!(err.to_string().starts_with("snapshot checksum mismatch"));
+        let err = read(&data[..data.len() - 1], &mut Databases::new(4))
+            .err()
+            .unwrap();
+        assert_eq!(err.to_string(), "unexpected end of snapshot".to_string());
+        let err = read(b"REDIS0006", &mut Databases::new(4)).err().unwrap();
+        assert_eq!(
+            err.to_string(),
+            "unsupported snapshot version 6".to_string()
+        );
+        let err = read(b"REDIS0011\x07\x01k", &mut Databases::new(4))
+            .err()
+            .unwrap();
+        assert_eq!(
+            err.to_string(),
+            "unsupported snapshot object type 7".to_string()
+        );
+    }
+
+    #[test]
+    fn should_dump_and_load_values() {
+        let mut dbs = Databases::new(4);
+        populate(&mut dbs);
+        for key in [
+            b"string".as_slice(),
+            b"int",
+            b"long",
+            b"list",
+            b"set",
+            b"hash",
+        ] {
+            let (entry, _) = dbs.db_mut(0).dump(key).unwrap();
+            let payload = dump(&entry).unwrap();
+            assert_eq!(dump(&load_dump(&payload).unwrap()).unwrap(), payload);
+        }
+        let (entry, _) = dbs.db_mut(3).dump(b"stream").unwrap();
+        let payload = dump(&entry).unwrap();
+        let loaded = load_dump(&payload).unwrap();
+        assert_eq!(loaded.stream.unwrap().entries().len(), 3);
+
+        let (entry, _) = dbs.db_mut(0).dump(b"string").unwrap();
+        let mut payload = dump(&entry).unwrap();
+        assert_eq!(&payload[..payload.len() - 8], b"\x00\x05value\x09\x00");
+        payload[2] = b'V';
+        let err = load_dump(&payload).err().unwrap();
+        assert!(err.to_string().starts_with("snapshot checksum mismatch"));
+        let err = load_dump(b"\x00").err().unwrap();
+        assert_eq!(err.to_string(), "unexpected end of snapshot".to_string());
+    }
+}
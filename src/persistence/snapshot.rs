@@ -0,0 +1,467 @@
+//! The snapshot file format. A snapshot starts with a header and the format version, followed by
+//! the keys of every non-empty database, and ends with an end-of-file marker:
+//!
+//! ```text
+//! "RRDB" version
+//! SELECT_DB index
+//!   [EXPIRE_AT deadline] type key value
+//!   ...
+//! EOF
+//! ```
+//!
+//! Integers and lengths are 8-byte little-endian, and strings are prefixed with their length.
+
+use super::rdb;
+use crate::config::SnapshotFormat;
+use crate::data_store::{
+    now_ms, Databases, Dataset, RedisEntry, RedisEntryType, RedisHash, RedisSet, SortedSet, Stream,
+};
+use crate::error::SnapshotError;
+use std::collections::LinkedList;
+use std::io::{BufWriter, Write};
+use std::path::Path;
+
+const MAGIC: &[u8] = b"RRDB";
+const VERSION: u8 = 1;
+
+const OPCODE_SELECT_DB: u8 = 0xfe;
+const OPCODE_EXPIRE_AT: u8 = 0xfc;
+const OPCODE_EOF: u8 = 0xff;
+
+const TYPE_STRING: u8 = 0;
+const TYPE_LIST: u8 = 1;
+const TYPE_SET: u8 = 2;
+const TYPE_HASH: u8 = 3;
+const TYPE_SORTED_SET: u8 = 4;
+const TYPE_STREAM: u8 = 5;
+
+/// Writes a snapshot of `databases` to `path` in `format`. The snapshot is written to a temporary
+/// file first and then renamed, so `path` always holds a complete snapshot even if the write fails
+/// midway.
+pub fn save(path: &Path, databases: &impl Dataset, format: SnapshotFormat) -> std::io::Result<()> {
+    let temp_path = path.with_file_name(format!("temp-{}.rdb", std::process::id()));
+    let result = std::fs::File::create(&temp_path).and_then(|file| {
+        let mut out = BufWriter::new(file);
+        match format {
+            SnapshotFormat::Native => write(&mut out, databases)?,
+            SnapshotFormat::Redis => rdb::write(&mut out, databases)?,
+        }
+        out.into_inner()?.sync_all()
+    });
+    match result {
+        Ok(()) => std::fs::rename(&temp_path, path),
+        Err(e) => {
+            let _ = std::fs::remove_file(&temp_path);
+            Err(e)
+        }
+    }
+}
+
+/// Loads the snapshot at `path` into `databases`, in either format. Returns false if there is no
+/// snapshot yet.
+pub fn load(path: &Path, databases: &mut Databases) -> Result<bool, SnapshotError> {
+    let data = match std::fs::read(path) {
+        Ok(v) => v,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(false),
+        Err(e) => {
+            return Err(SnapshotError::ReadFailed(
+                path.display().to_string(),
+                e.to_string(),
+            ))
+        }
+    };
+    read(&data, databases)?;
+    Ok(true)
+}
+
+/// Returns whether `data` starts with a snapshot, in either format.
+pub fn is_snapshot(data: &[u8]) -> bool {
+    data.starts_with(MAGIC) || rdb::is_rdb(data)
+}
+
+pub fn write<W: Write>(out: &mut W, databases: &impl Dataset) -> std::io::Result<()> {
+    let mut encoder = Encoder { out };
+    encoder.out.write_all(MAGIC)?;
+    encoder.u8(VERSION)?;
+    let now = now_ms();
+    for index in 0..databases.count() {
+        let mut entries = databases.entries(index).peekable();
+        if entries.peek().is_none() {
+            continue;
+        }
+        encoder.u8(OPCODE_SELECT_DB)?;
+        encoder.u64(index as u64)?;
+        for (key, entry, deadline) in entries {
+            if let Some(deadline) = deadline {
+                // Expired keys that have not been evicted yet are left out.
+                if deadline <= now {
+                    continue;
+                }
+                encoder.u8(OPCODE_EXPIRE_AT)?;
+                encoder.u64(deadline)?;
+            }
+            encoder.entry(key, entry)?;
+        }
+    }
+    encoder.u8(OPCODE_EOF)
+}
+
+/// Reads the snapshot at the start of `data` into `databases`, in either format. Returns the length
+/// of the snapshot, which may be followed by other data such as the commands of an append-only
+/// file.
+pub fn read(data: &[u8], databases: &mut Databases) -> Result<usize, SnapshotError> {
+    if rdb::is_rdb(data) {
+        return rdb::read(data, databases);
+    }
+    let mut decoder = Decoder { data, pos: 0 };
+    if decoder.take(MAGIC.len())? != MAGIC {
+        return Err(SnapshotError::InvalidHeader);
+    }
+    let version = decoder.u8()?;
+    if version != VERSION {
+        return Err(SnapshotError::UnsupportedVersion(version.into()));
+    }
+    let mut db = 0;
+    let mut deadline = None;
+    loop {
+        match decoder.u8()? {
+            OPCODE_EOF => return Ok(decoder.pos),
+            OPCODE_SELECT_DB => {
+                let index = decoder.u64()?;
+                if index >= databases.count() as u64 {
+                    return Err(SnapshotError::DbIndexOutOfRange(index));
+                }
+                db = index as usize;
+            }
+            OPCODE_EXPIRE_AT => deadline = Some(decoder.u64()?),
+            type_ => {
+                let key = decoder.bytes()?;
+                let entry = decoder.entry(type_)?;
+                databases.db_mut(db).restore(&key, entry, deadline.take());
+            }
+        }
+    }
+}
+
+struct Encoder<'a, W: Write> {
+    out: &'a mut W,
+}
+
+impl<W: Write> Encoder<'_, W> {
+    fn u8(&mut self, value: u8) -> std::io::Result<()> {
+        self.out.write_all(&[value])
+    }
+
+    fn u64(&mut self, value: u64) -> std::io::Result<()> {
+        self.out.write_all(&value.to_le_bytes())
+    }
+
+    fn bytes(&mut self, value: &[u8]) -> std::io::Result<()> {
+        self.u64(value.len() as u64)?;
+        self.out.write_all(value)
+    }
+
+    fn entry(&mut self, key: &[u8], entry: &RedisEntry) -> std::io::Result<()> {
+        match entry {
+            RedisEntry {
+                type_: RedisEntryType::String,
+                string: Some(v),
+                ..
+            } => {
+                self.u8(TYPE_STRING)?;
+                self.bytes(key)?;
+                self.bytes(v)
+            }
+            RedisEntry {
+                type_: RedisEntryType::List,
+                list: Some(v),
+                ..
+            } => {
+                self.u8(TYPE_LIST)?;
+                self.bytes(key)?;
+                self.u64(v.len() as u64)?;
+                v.iter().try_for_each(|item| self.bytes(item))
+            }
+            RedisEntry {
+                type_: RedisEntryType::Set,
+                set: Some(v),
+                ..
+            } => {
+                self.u8(TYPE_SET)?;
+                self.bytes(key)?;
+                self.u64(v.len() as u64)?;
+                v.iter().try_for_each(|member| self.bytes(member))
+            }
+            RedisEntry {
+                type_: RedisEntryType::Hash,
+                hash: Some(v),
+                ..
+            } => {
+                self.u8(TYPE_HASH)?;
+                self.bytes(key)?;
+                self.u64(v.len() as u64)?;
+                v.iter().try_for_each(|(field, value)| {
+                    self.bytes(field)?;
+                    self.bytes(value)
+                })
+            }
+            RedisEntry {
+                type_: RedisEntryType::SortedSet,
+                sorted_set: Some(v),
+                ..
+            } => {
+                self.u8(TYPE_SORTED_SET)?;
+                self.bytes(key)?;
+                self.u64(v.len() as u64)?;
+                v.iter().try_for_each(|(member, score)| {
+                    self.bytes(member)?;
+                    self.u64(score.to_bits())
+                })
+            }
+            RedisEntry {
+                type_: RedisEntryType::Stream,
+                stream: Some(v),
+                ..
+            } => {
+                self.u8(TYPE_STREAM)?;
+                self.bytes(key)?;
+                let entries = v.entries();
+                self.u64(entries.len() as u64)?;
+                entries.into_iter().try_for_each(|(id, fields)| {
+                    self.u64(id[0])?;
+                    self.u64(id[1])?;
+                    self.u64(fields.len() as u64)?;
+                    fields.iter().try_for_each(|[field, value]| {
+                        self.bytes(field)?;
+                        self.bytes(value)
+                    })
+                })
+            }
+            _ => Err(std::io::Error::new(
+                std::io::ErrorKind::InvalidData,
+                format!(
+                    "key '{}' has no value of its type",
+                    String::from_utf8_lossy(key)
+                ),
+            )),
+        }
+    }
+}
+
+struct Decoder<'a> {
+    data: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> Decoder<'a> {
+    fn take(&mut self, len: usize) -> Result<&'a [u8], SnapshotError> {
+        if self.data.len() - self.pos < len {
+            return Err(SnapshotError::UnexpectedEnd);
+        }
+        let v = &self.data[self.pos..self.pos + len];
+        self.pos += len;
+        Ok(v)
+    }
+
+    fn u8(&mut self) -> Result<u8, SnapshotError> {
+        Ok(self.take(1)?[0])
+    }
+
+    fn u64(&mut self) -> Result<u64, SnapshotError> {
+        Ok(u64::from_le_bytes(self.take(8)?.try_into().unwrap()))
+    }
+
+    /// Reads a length, which cannot be trusted to allocate up front as the data may be corrupt.
+    fn len(&mut self) -> Result<usize, SnapshotError> {
+        let len = self.u64()?;
+        match usize::try_from(len) {
+            Ok(v) if v <= self.data.len() - self.pos => Ok(v),
+            _ => Err(SnapshotError::UnexpectedEnd),
+        }
+    }
+
+    fn bytes(&mut self) -> Result<Vec<u8>, SnapshotError> {
+        let len = self.len()?;
+        Ok(self.take(len)?.to_vec())
+    }
+
+    fn entry(&mut self, type_: u8) -> Result<RedisEntry, SnapshotError> {
+        let entry = match type_ {
+            TYPE_STRING => RedisEntry::create_string(&self.bytes()?),
+            TYPE_LIST => {
+                let mut list = LinkedList::new();
+                for _ in 0..self.len()? {
+                    list.push_back(self.bytes()?);
+                }
+                RedisEntry {
+                    type_: RedisEntryType::List,
+                    list: Some(list),
+                    ..Default::default()
+                }
+            }
+            TYPE_SET => {
+                let mut set = RedisSet::default();
+                for _ in 0..self.len()? {
+                    set.insert(self.bytes()?);
+                }
+                RedisEntry {
+                    type_: RedisEntryType::Set,
+                    set: Some(set),
+                    ..Default::default()
+                }
+            }
+            TYPE_HASH => {
+                let mut hash = RedisHash::default();
+                for _ in 0..self.len()? {
+                    let field = self.bytes()?;
+                    hash.insert(field, self.bytes()?);
+                }
+                RedisEntry {
+                    type_: RedisEntryType::Hash,
+                    hash: Some(hash),
+                    ..Default::default()
+                }
+            }
+            TYPE_SORTED_SET => {
+                let mut sorted_set = SortedSet::new();
+                for _ in 0..self.len()? {
+                    let member = self.bytes()?;
+                    sorted_set.insert(f64::from_bits(self.u64()?), member);
+                }
+                RedisEntry {
+                    type_: RedisEntryType::SortedSet,
+                    sorted_set: Some(sorted_set),
+                    ..Default::default()
+                }
+            }
+            TYPE_STREAM => {
+                let mut stream = Stream::new();
+                for _ in 0..self.len()? {
+                    let id = [self.u64()?, self.u64()?];
+                    let mut fields = Vec::new();
+                    for _ in 0..self.len()? {
+                        fields.push([self.bytes()?, self.bytes()?]);
+                    }
+                    if let Err(e) = stream.insert(Some(id), fields) {
+                        return Err(SnapshotError::InvalidData(e.to_string()));
+                    }
+                }
+                RedisEntry {
+                    type_: RedisEntryType::Stream,
+                    stream: Some(stream),
+                    ..Default::default()
+                }
+            }
+            v => return Err(SnapshotError::InvalidData(format!("unknown type {}", v))),
+        };
+        Ok(entry)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{read, write};
+    use crate::data_store::{now_ms, Databases};
+
+    fn populate(dbs: &mut Databases) {
+        let db = dbs.db_mut(0);
+        db.set_string_overwrite(b"string", b"value");
+        db.set_expire_at(b"string", now_ms() + 100000);
+        db.set_string_overwrite(b"expired", b"value");
+        db.set_expire_at(b"expired", now_ms() - 1);
+        let _ = db.insert_list(b"list");
+        let list = db.get_list_mut(b"list").unwrap().unwrap();
+        list.push_back(b"a".to_vec());
+        list.push_back(b"b".to_vec());
+        let _ = db.insert_set(b"set");
+        db.get_set_mut(b"set")
+            .unwrap()
+            .unwrap()
+            .insert(b"m".to_vec());
+        let _ = db.insert_hash(b"hash");
+        let hash = db.get_hash_mut(b"hash").unwrap().unwrap();
+        hash.insert(b"f".to_vec(), b"v".to_vec());
+
+        let db = dbs.db_mut(3);
+        let _ = db.insert_sorted_set(b"zset");
+        let zset = db.get_sorted_set_mut(b"zset").unwrap().unwrap();
+        zset.insert(1.5, b"x".to_vec());
+        zset.insert(-2.0, b"y".to_vec());
+        let _ = db.insert_stream(b"stream");
+        let stream = db.get_stream_mut(b"stream").unwrap().unwrap();
+        for id in [[1, 0], [1, 1], [2, 300], [0x1_0000_0000, 5]] {
+            stream
+                .insert(
+                    Some(id),
+                    vec![[b"k".to_vec(), id[1].to_string().into_bytes()]],
+                )
+                .unwrap();
+        }
+    }
+
+    #[test]
+    fn should_restore_every_type() {
+        let mut dbs = Databases::new(4);
+        populate(&mut dbs);
+        let mut data = Vec::new();
+        write(&mut data, &dbs).unwrap();
+
+        let mut restored = Databases::new(4);
+        read(&data, &mut restored).unwrap();
+        let db = restored.db_mut(0);
+        assert_eq!(db.len(), 4);
+        assert_eq!(db.get_string(b"string").unwrap().unwrap(), b"value");
+        assert_eq!(
+            db.get_expire_at(b"string"),
+            dbs.db_mut(0).get_expire_at(b"string")
+        );
+        assert!(!db.contains_key(b"expired"));
+        let list = db.get_list_mut(b"list").unwrap().unwrap();
+        assert_eq!(list.iter().collect::<Vec<_>>(), vec![b"a", b"b"]);
+        assert!(db
+            .get_set_mut(b"set")
+            .unwrap()
+            .unwrap()
+            .contains(b"m".as_slice()));
+        let hash = db.get_hash_mut(b"hash").unwrap().unwrap();
+        assert_eq!(hash.get(b"f".as_slice()).unwrap(), b"v");
+        assert_eq!(hash.scan(0, 10).0.len(), 1);
+
+        let db = restored.db_mut(3);
+        let zset = db.get_sorted_set_mut(b"zset").unwrap().unwrap();
+        assert_eq!(
+            zset.get_values_by_rank(0, 1),
+            vec![b"y".to_vec(), b"x".to_vec()]
+        );
+        assert_eq!(zset.get(b"x"), Some(1.5));
+        let stream = db.get_stream_mut(b"stream").unwrap().unwrap();
+        let ids: Vec<[u64; 2]> = stream.entries().into_iter().map(|(id, _)| id).collect();
+        assert_eq!(ids, vec![[1, 0], [1, 1], [2, 300], [0x1_0000_0000, 5]]);
+        assert_eq!(stream.entries()[2].1, &[[b"k".to_vec(), b"300".to_vec()]]);
+        // New IDs keep increasing from the restored ones.
+        assert_eq!(
+            stream.insert(None, vec![]).unwrap(),
+            "4294967296-6".to_string()
+        );
+    }
+
+    #[test]
+    fn should_reject_invalid_snapshots() {
+        let mut dbs = Databases::new(4);
+        populate(&mut dbs);
+        let mut data = Vec::new();
+        write(&mut data, &dbs).unwrap();
+
+        let err = read(&data[..data.len() - 1], &mut Databases::new(4))
+            .err()
+            .unwrap();
+        assert_eq!(err.to_string(), "unexpected end of snapshot".to_string());
+        let err = read(b"JUNK", &mut Databases::new(4)).err().unwrap();
+        assert_eq!(err.to_string(), "not a snapshot file".to_string());
+        let err = read(&data, &mut Databases::new(2)).err().unwrap();
+        assert_eq!(
+            err.to_string(),
+            "snapshot database index 3 is out of range".to_string()
+        );
+    }
+}
@@ -0,0 +1,351 @@
+//! Publish/subscribe messaging. Clients subscribe to channels, and every message published to a
+//! channel afterwards is pushed to the connections of its subscribers as soon as it is published.
+//! Messages are not stored, so only the clients subscribed at the time get them. Clients can also
+//! subscribe to glob-style patterns, and get the messages of every channel that matches them.
+
+mod patterns;
+use patterns::Patterns;
+
+use crate::config::ServerConfig;
+use crate::execution_result::{BulkStringReply, PushReply, RespReply, RespVersion};
+use crate::utils::glob_match;
+use std::collections::{HashMap, HashSet};
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::Arc;
+use tokio::sync::mpsc::{unbounded_channel, UnboundedReceiver, UnboundedSender};
+use tokio::sync::Notify;
+
+/// Where to send the messages of each subscriber of a channel or pattern, by session ID.
+type Subscribers = HashMap<u64, Mailbox>;
+
+/// The messages sent to a subscriber that its connection has not taken yet.
+#[derive(Debug, Default)]
+struct Queue {
+    /// The size of the messages, as counted by `Message::size`.
+    bytes: AtomicUsize,
+    /// Whether the messages would have gone over the output buffer limit, after which the
+    /// subscriber is sent no more messages and its connection is closed.
+    overflowed: AtomicBool,
+    /// Wakes the connection up once the subscriber overflowed, even while it is stuck writing to a
+    /// client that does not read.
+    overflow: Notify,
+}
+
+/// The sending end of the messages of a subscriber.
+#[derive(Debug, Clone)]
+struct Mailbox {
+    sender: UnboundedSender<Message>,
+    queue: Arc<Queue>,
+}
+
+impl Mailbox {
+    /// Sends a message unless it would take the queue of the subscriber over `limit` bytes, and
+    /// returns whether it was sent. Once a message is not sent, none are anymore.
+    fn send(&self, message: &Message, limit: usize) -> bool {
+        let queue = &self.queue;
+        if queue.overflowed.load(Ordering::Relaxed) {
+            return false;
+        }
+        let size = message.size();
+        let queued = queue.bytes.fetch_add(size, Ordering::Relaxed) + size;
+        if limit > 0 && queued > limit {
+            queue.bytes.fetch_sub(size, Ordering::Relaxed);
+            queue.overflowed.store(true, Ordering::Relaxed);
+            queue.overflow.notify_one();
+            return false;
+        }
+        self.sender.send(message.clone()).is_ok()
+    }
+}
+
+/// A message published to a channel, as it is pushed to a subscriber.
+#[derive(Debug, Clone)]
+pub struct Message {
+    /// The pattern the subscriber matched the channel with, if it subscribed to a pattern.
+    pub pattern: Option<Vec<u8>>,
+    pub channel: Vec<u8>,
+    pub payload: Vec<u8>,
+}
+
+impl Message {
+    /// Returns the number of bytes the message takes up in a subscriber's queue.
+    fn size(&self) -> usize {
+        self.pattern.as_ref().map_or(0, |p| p.len()) + self.channel.len() + self.payload.len()
+    }
+
+    pub fn serialise(&self, protocol: RespVersion) -> Vec<u8> {
+        let bulk = |value: &[u8]| {
+            Box::new(BulkStringReply {
+                value: value.to_vec(),
+            }) as Box<dyn RespReply>
+        };
+        let values = match &self.pattern {
+            Some(pattern) => vec![
+                bulk(b"pmessage"),
+                bulk(pattern),
+                bulk(&self.channel),
+                bulk(&self.payload),
+            ],
+            None => vec![bulk(b"message"), bulk(&self.channel), bulk(&self.payload)],
+        };
+        PushReply { values }.serialise(protocol)
+    }
+}
+
+/// The subscriptions of a client, which put its connection in subscriber mode for as long as there
+/// are any.
+#[derive(Debug)]
+pub struct Subscriber {
+    pub channels: HashSet<Vec<u8>>,
+    pub patterns: HashSet<Vec<u8>>,
+    mailbox: Mailbox,
+    /// The messages to push to the client.
+    receiver: UnboundedReceiver<Message>,
+}
+
+impl Default for Subscriber {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Subscriber {
+    pub fn new() -> Self {
+        let (sender, receiver) = unbounded_channel();
+        Subscriber {
+            channels: HashSet::new(),
+            patterns: HashSet::new(),
+            mailbox: Mailbox {
+                sender,
+                queue: Arc::default(),
+            },
+            receiver,
+        }
+    }
+
+    /// Waits for the next message to push to the client, or returns `None` once the client went
+    /// over the output buffer limit. There always is one or the other eventually, as the
+    /// subscriber holds a sender itself.
+    pub async fn recv(&mut self) -> Option<Message> {
+        let message = tokio::select! {
+            biased;
+            _ = self.mailbox.queue.overflow.notified() => return None,
+            message = self.receiver.recv() => message?,
+        };
+        self.taken(&message);
+        Some(message)
+    }
+
+    /// Returns the next message to push to the client, if there is one already.
+    pub fn try_recv(&mut self) -> Option<Message> {
+        let message = self.receiver.try_recv().ok()?;
+        self.taken(&message);
+        Some(message)
+    }
+
+    fn taken(&self, message: &Message) {
+        let queue = &self.mailbox.queue;
+        queue.bytes.fetch_sub(message.size(), Ordering::Relaxed);
+    }
+
+    /// Returns whether the messages queued for the client went over the output buffer limit, in
+    /// which case it has to be disconnected.
+    pub fn overflowed(&self) -> bool {
+        self.mailbox.queue.overflowed.load(Ordering::Relaxed)
+    }
+
+    /// Waits until the client goes over the output buffer limit.
+    pub async fn overflow(&self) {
+        self.mailbox.queue.overflow.notified().await
+    }
+
+    /// Returns the number of channels and patterns the client subscribed to.
+    pub fn count(&self) -> usize {
+        self.channels.len() + self.patterns.len()
+    }
+}
+
+/// The channels and patterns that have subscribers.
+#[derive(Debug)]
+pub struct PubSub {
+    channels: HashMap<Vec<u8>, Subscribers>,
+    patterns: Patterns,
+    /// How many bytes of messages may be queued for a subscriber, or 0 for no limit.
+    output_buffer_limit: usize,
+}
+
+impl PubSub {
+    pub fn new(config: &ServerConfig) -> Self {
+        PubSub {
+            channels: HashMap::new(),
+            patterns: Patterns::default(),
+            output_buffer_limit: config.pubsub_output_buffer_limit,
+        }
+    }
+
+    /// Subscribes the client of session `id` to `channel`.
+    pub fn subscribe(&mut self, channel: &[u8], id: u64, subscriber: &Subscriber) {
+        self.channels
+            .entry(channel.to_vec())
+            .or_default()
+            .insert(id, subscriber.mailbox.clone());
+    }
+
+    pub fn unsubscribe(&mut self, channel: &[u8], id: u64) {
+        if let Some(subscribers) = self.channels.get_mut(channel) {
+            subscribers.remove(&id);
+            if subscribers.is_empty() {
+                self.channels.remove(channel);
+            }
+        }
+    }
+
+    /// Subscribes the client of session `id` to every channel that matches `pattern`.
+    pub fn psubscribe(&mut self, pattern: &[u8], id: u64, subscriber: &Subscriber) {
+        self.patterns
+            .entry(pattern)
+            .insert(id, subscriber.mailbox.clone());
+    }
+
+    pub fn punsubscribe(&mut self, pattern: &[u8], id: u64) {
+        self.patterns.remove(pattern, id);
+    }
+
+    /// Drops every subscription of a client, e.g. once it disconnected.
+    pub fn remove(&mut self, id: u64, subscriber: &Subscriber) {
+        for channel in &subscriber.channels {
+            self.unsubscribe(channel, id);
+        }
+        for pattern in &subscriber.patterns {
+            self.punsubscribe(pattern, id);
+        }
+    }
+
+    /// Sends a message to the subscribers of `channel` and of the patterns that match it, and
+    /// returns how many messages were sent. A client that subscribed to the channel and to
+    /// matching patterns gets the message once for each of them. Clients that went over the output
+    /// buffer limit are left out.
+    pub fn publish(&self, channel: &[u8], payload: &[u8]) -> usize {
+        let mut sent = 0;
+        if let Some(subscribers) = self.channels.get(channel) {
+            let message = Message {
+                pattern: None,
+                channel: channel.to_vec(),
+                payload: payload.to_vec(),
+            };
+            sent += self.send(subscribers, &message);
+        }
+        for (pattern, subscribers) in self.patterns.matching(channel) {
+            let message = Message {
+                pattern: Some(pattern.to_vec()),
+                channel: channel.to_vec(),
+                payload: payload.to_vec(),
+            };
+            sent += self.send(subscribers, &message);
+        }
+        sent
+    }
+
+    /// Returns the channels that have subscribers, only keeping those that match `pattern` if
+    /// there is one.
+    pub fn channels(&self, pattern: Option<&[u8]>) -> Vec<Vec<u8>> {
+        self.channels
+            .keys()
+            .filter(|channel| pattern.is_none_or(|p| glob_match(p, channel)))
+            .cloned()
+            .collect()
+    }
+
+    /// Returns the number of clients subscribed to `channel` itself, leaving out patterns.
+    pub fn subscribers(&self, channel: &[u8]) -> usize {
+        self.channels.get(channel).map_or(0, |s| s.len())
+    }
+
+    /// Returns the number of patterns that have subscribers.
+    pub fn numpat(&self) -> usize {
+        self.patterns.len()
+    }
+
+    /// Sends a message to subscribers, and returns how many are still connected to get it.
+    fn send(&self, subscribers: &Subscribers, message: &Message) -> usize {
+        subscribers
+            .values()
+            .filter(|mailbox| mailbox.send(message, self.output_buffer_limit))
+            .count()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{PubSub, Subscriber};
+    use crate::config::ServerConfig;
+    use crate::execution_result::RespVersion;
+
+    #[test]
+    fn should_publish_to_subscribers() {
+        let mut pubsub = PubSub::new(&ServerConfig::default());
+        let mut first = Subscriber::new();
+        let mut second = Subscriber::new();
+        pubsub.subscribe(b"news", 1, &first);
+        pubsub.subscribe(b"news", 2, &second);
+        pubsub.subscribe(b"weather", 2, &second);
+        second
+            .channels
+            .extend([b"news".to_vec(), b"weather".to_vec()]);
+
+        assert_eq!(pubsub.publish(b"news", b"hello"), 2);
+        assert_eq!(pubsub.publish(b"sports", b"goal"), 0);
+        let message = first.try_recv().unwrap();
+        assert_eq!(
+            message.serialise(RespVersion::Resp2),
+            b"*3\r\n$7\r\nmessage\r\n$4\r\nnews\r\n$5\r\nhello\r\n".to_vec()
+        );
+        assert_eq!(
+            message.serialise(RespVersion::Resp3),
+            b">3\r\n$7\r\nmessage\r\n$4\r\nnews\r\n$5\r\nhello\r\n".to_vec()
+        );
+        assert!(first.try_recv().is_none());
+        assert_eq!(second.try_recv().unwrap().payload, b"hello");
+
+        let mut channels = pubsub.channels(None);
+        channels.sort();
+        assert_eq!(channels, vec![b"news".to_vec(), b"weather".to_vec()]);
+        assert_eq!(pubsub.channels(Some(b"w*")), vec![b"weather".to_vec()]);
+        assert_eq!(pubsub.subscribers(b"news"), 2);
+
+        pubsub.remove(2, &second);
+        assert_eq!(pubsub.subscribers(b"news"), 1);
+        assert_eq!(pubsub.channels(None), vec![b"news".to_vec()]);
+        pubsub.unsubscribe(b"news", 1);
+        assert!(pubsub.channels(None).is_empty());
+    }
+
+    #[tokio::test]
+    async fn should_stop_sending_to_subscribers_over_the_limit() {
+        let mut pubsub = PubSub::new(&ServerConfig {
+            pubsub_output_buffer_limit: 100,
+            ..ServerConfig::default()
+        });
+        let mut subscriber = Subscriber::new();
+        pubsub.subscribe(b"news", 1, &subscriber);
+
+        // Messages taken by the connection no longer count towards the limit.
+        for _ in 0..10 {
+            assert_eq!(pubsub.publish(b"news", &[b'x'; 40]), 1);
+            assert!(subscriber.try_recv().is_some());
+        }
+        assert!(!subscriber.overflowed());
+
+        assert_eq!(pubsub.publish(b"news", &[b'x'; 40]), 1);
+        assert_eq!(pubsub.publish(b"news", &[b'x'; 40]), 1);
+        assert!(!subscriber.overflowed());
+        assert_eq!(pubsub.publish(b"news", &[b'x'; 40]), 0);
+        assert!(subscriber.overflowed());
+        // Not even a smaller message is sent once the subscriber overflowed.
+        assert!(subscriber.try_recv().is_some());
+        assert_eq!(pubsub.publish(b"news", b"x"), 0);
+        assert!(subscriber.try_recv().is_some());
+        assert!(subscriber.try_recv().is_none());
+        subscriber.overflow().await;
+    }
+}
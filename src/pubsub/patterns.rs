@@ -0,0 +1,183 @@
+use super::Subscribers;
+use crate::utils::glob_match;
+use std::collections::HashMap;
+
+/// The pattern subscriptions, in a trie keyed by the literal prefix of each pattern, i.e. what
+/// comes before its first wildcard. Only the patterns whose prefix is also a prefix of a channel
+/// can match it, so finding the patterns of a channel walks down the trie along the channel and
+/// only matches the patterns found on the way, rather than every pattern.
+#[derive(Debug, Default)]
+pub struct Patterns {
+    root: Node,
+    len: usize,
+}
+
+#[derive(Debug, Default)]
+struct Node {
+    children: HashMap<u8, Node>,
+    /// The patterns whose literal prefix ends here, with their subscribers.
+    patterns: HashMap<Vec<u8>, Subscribers>,
+}
+
+impl Node {
+    fn is_empty(&self) -> bool {
+        self.children.is_empty() && self.patterns.is_empty()
+    }
+
+    /// Removes a subscriber of `pattern`, dropping the nodes left empty on the way back up.
+    /// Returns whether the pattern has no subscribers left.
+    fn remove(&mut self, prefix: &[u8], pattern: &[u8], id: u64) -> bool {
+        let Some((byte, rest)) = prefix.split_first() else {
+            let Some(subscribers) = self.patterns.get_mut(pattern) else {
+                return false;
+            };
+            subscribers.remove(&id);
+            if !subscribers.is_empty() {
+                return false;
+            }
+            self.patterns.remove(pattern);
+            return true;
+        };
+        let Some(child) = self.children.get_mut(byte) else {
+            return false;
+        };
+        let dropped = child.remove(rest, pattern, id);
+        if child.is_empty() {
+            self.children.remove(byte);
+        }
+        dropped
+    }
+}
+
+/// Returns the part of `pattern` before its first wildcard or escape.
+fn literal_prefix(pattern: &[u8]) -> &[u8] {
+    let end = pattern
+        .iter()
+        .position(|c| matches!(c, b'*' | b'?' | b'[' | b'\\'))
+        .unwrap_or(pattern.len());
+    &pattern[..end]
+}
+
+impl Patterns {
+    /// Returns the subscribers of `pattern`, adding the pattern if it has none yet.
+    pub fn entry(&mut self, pattern: &[u8]) -> &mut Subscribers {
+        let mut node = &mut self.root;
+        for byte in literal_prefix(pattern) {
+            node = node.children.entry(*byte).or_default();
+        }
+        if !node.patterns.contains_key(pattern) {
+            self.len += 1;
+        }
+        node.patterns.entry(pattern.to_vec()).or_default()
+    }
+
+    pub fn remove(&mut self, pattern: &[u8], id: u64) {
+        if self.root.remove(literal_prefix(pattern), pattern, id) {
+            self.len -= 1;
+        }
+    }
+
+    /// Returns the patterns that match `channel`, with their subscribers.
+    pub fn matching(&self, channel: &[u8]) -> Vec<(&[u8], &Subscribers)> {
+        let mut found = Vec::new();
+        let mut node = &self.root;
+        let mut rest = channel;
+        loop {
+            found.extend(
+                node.patterns
+                    .iter()
+                    .filter(|(pattern, _)| glob_match(pattern, channel))
+                    .map(|(pattern, subscribers)| (pattern.as_slice(), subscribers)),
+            );
+            let Some((byte, tail)) = rest.split_first() else {
+                break;
+            };
+            match node.children.get(byte) {
+                Some(child) => node = child,
+                None => break,
+            }
+            rest = tail;
+        }
+        found
+    }
+
+    /// Returns the number of patterns that have subscribers.
+    pub fn len(&self) -> usize {
+        self.len
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{literal_prefix, Patterns};
+    use crate::pubsub::Subscriber;
+
+    fn matching(patterns: &Patterns, channel: &[u8]) -> Vec<Vec<u8>> {
+        let mut found: Vec<_> = patterns
+            .matching(channel)
+            .into_iter()
+            .map(|(pattern, _)| pattern.to_vec())
+            .collect();
+        found.sort();
+        found
+    }
+
+    #[test]
+    fn should_only_match_patterns_along_the_channel() {
+        assert_eq!(literal_prefix(b"orders.*"), b"orders.");
+        assert_eq!(literal_prefix(b"user:?:events"), b"user:");
+        assert_eq!(literal_prefix(b"news"), b"news");
+        assert_eq!(literal_prefix(b"*"), b"");
+
+        let subscriber = Subscriber::new();
+        let mut patterns = Patterns::default();
+        for pattern in [
+            "orders.*",
+            "orders.eu.*",
+            "user:*:events",
+            "*",
+            "news",
+            "o[rx]ders.*",
+        ] {
+            patterns
+                .entry(pattern.as_bytes())
+                .insert(1, subscriber.mailbox.clone());
+        }
+        patterns
+            .entry(b"orders.*")
+            .insert(2, subscriber.mailbox.clone());
+        assert_eq!(patterns.len(), 6);
+
+        assert_eq!(
+            matching(&patterns, b"orders.eu.42"),
+            vec![
+                b"*".to_vec(),
+                b"o[rx]ders.*".to_vec(),
+                b"orders.*".to_vec(),
+                b"orders.eu.*".to_vec()
+            ]
+        );
+        assert_eq!(
+            matching(&patterns, b"user:7:events"),
+            vec![b"*".to_vec(), b"user:*:events".to_vec()]
+        );
+        assert_eq!(
+            matching(&patterns, b"news"),
+            vec![b"*".to_vec(), b"news".to_vec()]
+        );
+        assert_eq!(matching(&patterns, b"newsletter"), vec![b"*".to_vec()]);
+
+        patterns.remove(b"orders.*", 1);
+        assert_eq!(patterns.len(), 6);
+        patterns.remove(b"orders.*", 2);
+        patterns.remove(b"orders.eu.*", 1);
+        patterns.remove(b"missing.*", 1);
+        assert_eq!(patterns.len(), 4);
+        assert_eq!(
+            matching(&patterns, b"orders.eu.42"),
+            vec![b"*".to_vec(), b"o[rx]ders.*".to_vec()]
+        );
+        // Nodes left without patterns are dropped.
+        assert!(!patterns.root.children[&b'o'].children.contains_key(&b'r'));
+    }
+}
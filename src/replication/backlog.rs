@@ -0,0 +1,77 @@
+use std::collections::VecDeque;
+
+/// The most recent part of the replication stream, which a replica that briefly lost its link can
+/// resume from instead of doing a full sync. Offsets count bytes of the stream from 1.
+pub struct Backlog {
+    data: VecDeque<u8>,
+    capacity: usize,
+    /// The offset of the first byte kept.
+    first_offset: u64,
+}
+
+impl Backlog {
+    pub fn new(capacity: usize) -> Self {
+        Backlog {
+            data: VecDeque::new(),
+            capacity,
+            first_offset: 1,
+        }
+    }
+
+    pub fn first_offset(&self) -> u64 {
+        self.first_offset
+    }
+
+    pub fn len(&self) -> usize {
+        self.data.len()
+    }
+
+    pub fn capacity(&self) -> usize {
+        self.capacity
+    }
+
+    /// Appends the next bytes of the stream, dropping the oldest ones beyond the capacity.
+    pub fn append(&mut self, bytes: &[u8]) {
+        self.data.extend(bytes);
+        let excess = self.data.len().saturating_sub(self.capacity);
+        self.data.drain(..excess);
+        self.first_offset += excess as u64;
+    }
+
+    /// Drops everything, so that the stream starts over at `next_offset`.
+    pub fn reset(&mut self, next_offset: u64) {
+        self.data.clear();
+        self.first_offset = next_offset;
+    }
+
+    /// Returns the bytes of the stream from `offset` on, or `None` if they are no longer kept.
+    pub fn since(&self, offset: u64) -> Option<Vec<u8>> {
+        if offset < self.first_offset || offset > self.first_offset + self.data.len() as u64 {
+            return None;
+        }
+        let start = (offset - self.first_offset) as usize;
+        Some(self.data.range(start..).copied().collect())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::Backlog;
+
+    #[test]
+    fn should_keep_most_recent_bytes() {
+        let mut backlog = Backlog::new(4);
+        assert_eq!(backlog.since(1), Some(vec![]));
+        backlog.append(b"abc");
+        assert_eq!(backlog.since(2), Some(b"bc".to_vec()));
+        backlog.append(b"def");
+        assert_eq!(backlog.first_offset(), 3);
+        assert_eq!(backlog.since(3), Some(b"cdef".to_vec()));
+        assert_eq!(backlog.since(7), Some(vec![]));
+        assert_eq!(backlog.since(2), None);
+        assert_eq!(backlog.since(8), None);
+        backlog.reset(11);
+        assert_eq!(backlog.len(), 0);
+        assert_eq!(backlog.since(11), Some(vec![]));
+    }
+}
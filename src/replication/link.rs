@@ -0,0 +1,228 @@
+//! The link of a replica to its master: the handshake, the initial sync and the stream of writes
+//! that follows.
+
+use super::{LinkState, MasterLink};
+use crate::persistence::aof::encode_command;
+use crate::persistence::snapshot;
+use crate::scripting::ScriptMonitor;
+use crate::server::Server;
+use crate::session::Session;
+use crate::utils::{lock_server, RequestDecoder};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::tcp::{OwnedReadHalf, OwnedWriteHalf};
+use tokio::net::TcpStream;
+
+/// How often the replica reports its offset to the master.
+const ACK_INTERVAL: Duration = Duration::from_secs(1);
+
+type LinkResult<T> = Result<T, Box<dyn std::error::Error + Send + Sync>>;
+
+/// Replicates from the master of `link` until the link fails or is replaced.
+pub async fn run(server: Arc<Mutex<Server>>, monitor: Arc<ScriptMonitor>, link: MasterLink) {
+    let result = replicate(&server, &monitor, &link).await;
+    let mut server = lock_server(&server, &monitor).await;
+    if !server.replication.is_current(link.generation) {
+        return;
+    }
+    if let Err(e) = result {
+        log::warn!("Lost the link to master {}:{}: {}", link.host, link.port, e);
+    }
+    server.replication.link_down();
+}
+
+/// Reads the replies of the master during the handshake.
+struct LinkReader {
+    rx: OwnedReadHalf,
+    buffer: Vec<u8>,
+}
+
+impl LinkReader {
+    async fn fill(&mut self) -> LinkResult<()> {
+        if self.rx.read_buf(&mut self.buffer).await? == 0 {
+            return Err("connection closed by master".into());
+        }
+        Ok(())
+    }
+
+    /// Reads a line without its terminator, skipping the empty lines the master sends to keep the
+    /// link alive while it prepares a snapshot.
+    async fn line(&mut self) -> LinkResult<String> {
+        loop {
+            if let Some(end) = self.buffer.iter().position(|&b| b == b'\n') {
+                let line: Vec<u8> = self.buffer.drain(..=end).collect();
+                let line = String::from_utf8_lossy(&line).trim_end().to_string();
+                if line.is_empty() {
+                    continue;
+                }
+                return Ok(line);
+            }
+            self.fill().await?;
+        }
+    }
+
+    async fn bytes(&mut self, len: usize) -> LinkResult<Vec<u8>> {
+        while self.buffer.len() < len {
+            self.fill().await?;
+        }
+        Ok(self.buffer.drain(..len).collect())
+    }
+}
+
+async fn send(tx: &mut OwnedWriteHalf, args: &[&str]) -> LinkResult<()> {
+    let tokens: Vec<Vec<u8>> = args.iter().map(|a| a.as_bytes().to_vec()).collect();
+    tx.write_all(&encode_command(&tokens)).await?;
+    Ok(())
+}
+
+/// Sends a command of the handshake and checks that the master accepted it.
+async fn handshake(
+    reader: &mut LinkReader,
+    tx: &mut OwnedWriteHalf,
+    args: &[&str],
+) -> LinkResult<()> {
+    send(tx, args).await?;
+    let reply = reader.line().await?;
+    if let Some(error) = reply.strip_prefix('-') {
+        return Err(format!("{} failed: {}", args[0], error).into());
+    }
+    Ok(())
+}
+
+async fn replicate(
+    server: &Mutex<Server>,
+    monitor: &ScriptMonitor,
+    link: &MasterLink,
+) -> LinkResult<()> {
+    let stream = TcpStream::connect((link.host.as_str(), link.port)).await?;
+    log::info!("Connected to master {}:{}", link.host, link.port);
+    let (rx, mut tx) = stream.into_split();
+    let mut reader = LinkReader {
+        rx,
+        buffer: Vec::new(),
+    };
+    let (port, replid, offset) = {
+        let server = lock_server(server, monitor).await;
+        let replication = &server.replication;
+        (
+            replication.port.to_string(),
+            replication.replid.clone(),
+            replication.offset + 1,
+        )
+    };
+    handshake(&mut reader, &mut tx, &["PING"]).await?;
+    handshake(&mut reader, &mut tx, &["REPLCONF", "listening-port", &port]).await?;
+    handshake(&mut reader, &mut tx, &["REPLCONF", "capa", "psync2"]).await?;
+    send(&mut tx, &["PSYNC", &replid, &offset.to_string()]).await?;
+
+    let reply = reader.line().await?;
+    let mut words = reply.split(' ');
+    match words.next() {
+        Some("+FULLRESYNC") => {
+            let (Some(replid), Some(Ok(offset))) = (words.next(), words.next().map(str::parse))
+            else {
+                return Err(format!("invalid PSYNC reply: {}", reply).into());
+            };
+            lock_server(server, monitor).await.replication.link_state = LinkState::Sync;
+            let header = reader.line().await?;
+            let len = header
+                .strip_prefix('$')
+                .and_then(|v| v.parse().ok())
+                .ok_or_else(|| format!("invalid snapshot header: {}", header))?;
+            let data = reader.bytes(len).await?;
+            let mut server = lock_server(server, monitor).await;
+            if !server.replication.is_current(link.generation) {
+                return Ok(());
+            }
+            server.databases.flush_all();
+            snapshot::read(&data, &mut server.databases)?;
+            server.replication.full_sync_done(replid, offset);
+            // The data was replaced wholesale, so the append-only file has to start over.
+            if server.aof.enabled {
+                let server = &mut *server;
+                let _ = server.aof.start_rewrite(&server.databases);
+            }
+            log::info!("Full sync with master done, {} bytes loaded", len);
+        }
+        Some("+CONTINUE") => {
+            let mut server = lock_server(server, monitor).await;
+            if !server.replication.is_current(link.generation) {
+                return Ok(());
+            }
+            server.replication.partial_sync_done(words.next());
+            log::info!("Partial resync with master accepted");
+        }
+        _ => return Err(format!("PSYNC failed: {}", reply).into()),
+    }
+    stream_writes(server, monitor, link, reader, tx).await
+}
+
+/// Applies the stream of writes from the master, and passes it on to our own replicas.
+async fn stream_writes(
+    server: &Mutex<Server>,
+    monitor: &ScriptMonitor,
+    link: &MasterLink,
+    reader: LinkReader,
+    mut tx: OwnedWriteHalf,
+) -> LinkResult<()> {
+    let LinkReader { mut rx, buffer } = reader;
+    let mut decoder = RequestDecoder::new();
+    decoder.buffer_mut().extend(buffer);
+    let mut session = Session::new();
+    session.is_master = true;
+    session.db = lock_server(server, monitor).await.replication.master_db;
+    let mut ack = tokio::time::interval(ACK_INTERVAL);
+    loop {
+        let mut send_ack = false;
+        {
+            let mut server = lock_server(server, monitor).await;
+            if !server.replication.is_current(link.generation) {
+                return Ok(());
+            }
+            loop {
+                let tokens = match decoder.decode() {
+                    Ok(Some(tokens)) => tokens,
+                    Ok(None) => break,
+                    Err(e) => return Err(e.to_string().into()),
+                };
+                if tokens.is_empty() {
+                    continue;
+                }
+                let is_getack = tokens.len() >= 2
+                    && tokens[0].eq_ignore_ascii_case(b"replconf")
+                    && tokens[1].eq_ignore_ascii_case(b"getack");
+                if is_getack {
+                    send_ack = true;
+                } else if let Err(e) = server.execute(&tokens, &mut session) {
+                    log::warn!("Failed to apply a write from master: {}", e);
+                }
+                // The master sends commands in the same form they are encoded in here, so the
+                // stream is passed on unchanged.
+                server.replication.feed_raw(&encode_command(&tokens));
+            }
+            server.replication.master_db = session.db;
+            server.replication.touch_link();
+        }
+        if send_ack {
+            send_offset(server, monitor, &mut tx).await?;
+        }
+        tokio::select! {
+            read = rx.read_buf(decoder.buffer_mut()) => {
+                if read? == 0 {
+                    return Err("connection closed by master".into());
+                }
+            }
+            _ = ack.tick() => send_offset(server, monitor, &mut tx).await?,
+        }
+    }
+}
+
+async fn send_offset(
+    server: &Mutex<Server>,
+    monitor: &ScriptMonitor,
+    tx: &mut OwnedWriteHalf,
+) -> LinkResult<()> {
+    let offset = lock_server(server, monitor).await.replication.offset;
+    send(tx, &["REPLCONF", "ACK", &offset.to_string()]).await
+}
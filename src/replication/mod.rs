@@ -0,0 +1,570 @@
+//! Master-replica replication. A master streams every write to its replicas after sending them
+//! a snapshot of its data, and keeps the recent part of the stream in a backlog so that replicas
+//! that briefly lost their link can resume from their offset. A replica keeps a link to its master
+//! in a background task and passes the stream on to its own replicas.
+
+mod backlog;
+pub mod link;
+
+use crate::config::ServerConfig;
+use crate::data_store::{Databases, Snapshot};
+use crate::error::ExecutionError;
+use crate::persistence::aof::encode_command;
+use crate::persistence::rdb;
+use crate::session::Session;
+use crate::utils::random_id;
+use backlog::Backlog;
+use std::fmt::Write;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tokio::sync::mpsc::{unbounded_channel, UnboundedReceiver, UnboundedSender};
+use tokio::sync::Notify;
+
+/// How long to wait between attempts to connect to the master.
+const RECONNECT_INTERVAL: Duration = Duration::from_secs(1);
+
+/// The state of a replica's link to its master.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum LinkState {
+    /// Waiting to connect.
+    Connect,
+    /// Connecting and handshaking.
+    Connecting,
+    /// Receiving the snapshot of a full sync.
+    Sync,
+    /// Receiving the stream of writes.
+    Connected,
+}
+
+/// A request for the link task to connect to the master.
+pub struct MasterLink {
+    pub host: String,
+    pub port: u16,
+    /// Identifies the `REPLICAOF` the link is for. A link whose generation is no longer the
+    /// current one has been replaced and must stop.
+    pub generation: u64,
+}
+
+/// What a replica is sent once its `PSYNC` is answered: the data to load for a full
+/// resynchronization, and then the stream of writes.
+#[derive(Debug)]
+pub struct ReplicaFeed {
+    /// The keys to send for a full resynchronization. They are serialised once the server is
+    /// released, while the writes made meanwhile wait in `stream`.
+    pub snapshot: Option<Snapshot>,
+    pub stream: ReplicaStream,
+}
+
+/// The part of the replication stream sent to a replica that its connection has not taken yet.
+#[derive(Debug, Default)]
+struct Queue {
+    bytes: AtomicUsize,
+    /// Whether the replica fell too far behind and was dropped.
+    dropped: AtomicBool,
+    /// Wakes the connection up once the replica is dropped, even while it is stuck writing to a
+    /// replica that does not read.
+    wake: Notify,
+}
+
+impl Queue {
+    async fn until_dropped(&self) {
+        while !self.dropped.load(Ordering::Relaxed) {
+            self.wake.notified().await
+        }
+    }
+}
+
+/// The writes to send to a replica.
+#[derive(Debug)]
+pub struct ReplicaStream {
+    receiver: UnboundedReceiver<Vec<u8>>,
+    queue: Arc<Queue>,
+}
+
+impl ReplicaStream {
+    /// Waits for the next part of the stream, or returns `None` once the replica was dropped, in
+    /// which case its connection has to be closed.
+    pub async fn recv(&mut self) -> Option<Vec<u8>> {
+        let data = tokio::select! {
+            biased;
+            _ = self.queue.until_dropped() => return None,
+            data = self.receiver.recv() => data?,
+        };
+        self.queue.bytes.fetch_sub(data.len(), Ordering::Relaxed);
+        Some(data)
+    }
+
+    /// Returns the next part of the stream, if there is one already.
+    pub fn try_recv(&mut self) -> Option<Vec<u8>> {
+        let data = self.receiver.try_recv().ok()?;
+        self.queue.bytes.fetch_sub(data.len(), Ordering::Relaxed);
+        Some(data)
+    }
+
+    /// Waits until the replica is dropped for falling behind.
+    pub async fn dropped(&self) {
+        self.queue.until_dropped().await
+    }
+}
+
+/// Serialises the keys of a full resynchronization, as the bulk string that follows
+/// `+FULLRESYNC`.
+pub fn encode_snapshot(snapshot: &Snapshot) -> std::io::Result<Vec<u8>> {
+    let mut data = Vec::new();
+    rdb::write(&mut data, snapshot)?;
+    Ok([format!("${}\r\n", data.len()).into_bytes(), data].concat())
+}
+
+/// A replica connected to this server.
+struct Replica {
+    session_id: u64,
+    addr: String,
+    port: u16,
+    /// The offset the replica last reported to have processed.
+    ack_offset: u64,
+    last_ack: Instant,
+    feed: UnboundedSender<Vec<u8>>,
+    queue: Arc<Queue>,
+}
+
+pub struct Replication {
+    /// Identifies the history of the data set. Together with `offset`, it tells where in that
+    /// history the data is.
+    pub replid: String,
+    /// The previous replication ID, which replicas of our former master can still resume with.
+    replid2: String,
+    /// The offset up to which `replid2` is valid, or -1 for none.
+    second_replid_offset: i64,
+    /// The number of bytes of the replication stream so far.
+    pub offset: u64,
+    backlog: Backlog,
+    /// The database the last command of the stream runs against.
+    selected_db: Option<usize>,
+    replicas: Vec<Replica>,
+    /// The master we replicate from, if this server is a replica.
+    master: Option<(String, u16)>,
+    pub link_state: LinkState,
+    generation: u64,
+    last_attempt: Option<Instant>,
+    last_io: Instant,
+    /// The database the master's stream was last applied to, which a resumed stream continues
+    /// with.
+    pub master_db: usize,
+    read_only: bool,
+    /// The port this server listens on, which is announced to the master.
+    pub port: u16,
+}
+
+impl Replication {
+    pub fn new(config: &ServerConfig) -> Self {
+        Replication {
+            replid: random_id(),
+            replid2: "0".repeat(40),
+            second_replid_offset: -1,
+            offset: 0,
+            backlog: Backlog::new(config.repl_backlog_size),
+            selected_db: None,
+            replicas: Vec::new(),
+            master: config.replicaof.clone(),
+            link_state: LinkState::Connect,
+            generation: 0,
+            last_attempt: None,
+            last_io: Instant::now(),
+            master_db: 0,
+            read_only: config.replica_read_only,
+            port: config.port,
+        }
+    }
+
+    pub fn is_replica(&self) -> bool {
+        self.master.is_some()
+    }
+
+    /// Returns whether writes from clients are rejected.
+    pub fn is_read_only(&self) -> bool {
+        self.is_replica() && self.read_only
+    }
+
+    /// Streams a write made to database `db` to the replicas. Replicas pass on the stream of
+    /// their master instead, with `feed_raw`.
+    pub fn feed(&mut self, db: usize, tokens: &[Vec<u8>]) {
+        if self.is_replica() {
+            return;
+        }
+        let mut bytes = Vec::new();
+        if self.selected_db != Some(db) {
+            bytes.extend(encode_command(&[
+                b"SELECT".to_vec(),
+                db.to_string().into_bytes(),
+            ]));
+            self.selected_db = Some(db);
+        }
+        bytes.extend(encode_command(tokens));
+        self.feed_raw(&bytes);
+    }
+
+    /// Appends `bytes` to the replication stream. A replica that has more of the stream waiting
+    /// to be sent than the backlog holds is dropped, as it could not resume from the backlog
+    /// either, and has to resync once it reconnects.
+    pub fn feed_raw(&mut self, bytes: &[u8]) {
+        self.offset += bytes.len() as u64;
+        self.backlog.append(bytes);
+        let limit = self.backlog.capacity();
+        self.replicas.retain(|replica| {
+            let queued = replica
+                .queue
+                .bytes
+                .fetch_add(bytes.len(), Ordering::Relaxed);
+            if queued + bytes.len() > limit {
+                log::warn!(
+                    "Replica {} dropped for falling behind by more than the backlog",
+                    replica.addr
+                );
+                replica.queue.dropped.store(true, Ordering::Relaxed);
+                replica.queue.wake.notify_one();
+                return false;
+            }
+            // A replica whose connection is gone is dropped.
+            replica.feed.send(bytes.to_vec()).is_ok()
+        });
+    }
+
+    /// Starts replicating from `host:port`. Returns false if that is already our master.
+    pub fn set_master(&mut self, host: &str, port: u16) -> bool {
+        if self.master.as_ref() == Some(&(host.to_string(), port)) {
+            return false;
+        }
+        log::info!("Replicating from {}:{}", host, port);
+        self.master = Some((host.to_string(), port));
+        self.restart_link();
+        // Our replicas have to sync again with the data of the new master.
+        self.replicas.clear();
+        true
+    }
+
+    /// Stops replicating and turns into a master. The replication ID changes, but replicas that
+    /// were in sync with the previous one can resume with it.
+    pub fn promote(&mut self) {
+        if self.master.take().is_none() {
+            return;
+        }
+        log::info!("Promoted to master");
+        self.restart_link();
+        self.replid2 = std::mem::replace(&mut self.replid, random_id());
+        self.second_replid_offset = self.offset as i64 + 1;
+        self.selected_db = None;
+    }
+
+    fn restart_link(&mut self) {
+        self.generation += 1;
+        self.link_state = LinkState::Connect;
+        self.last_attempt = None;
+    }
+
+    /// Returns whether `generation` is the link to the current master.
+    pub fn is_current(&self, generation: u64) -> bool {
+        self.is_replica() && self.generation == generation
+    }
+
+    /// Returns the link to start if the replica is not connected to its master, at most once per
+    /// second.
+    pub fn take_pending_link(&mut self) -> Option<MasterLink> {
+        let (host, port) = self.master.clone()?;
+        if self.link_state != LinkState::Connect
+            || self
+                .last_attempt
+                .is_some_and(|t| t.elapsed() < RECONNECT_INTERVAL)
+        {
+            return None;
+        }
+        self.link_state = LinkState::Connecting;
+        self.last_attempt = Some(Instant::now());
+        Some(MasterLink {
+            host,
+            port,
+            generation: self.generation,
+        })
+    }
+
+    /// Records that the link to the master was lost, so that it gets connected again.
+    pub fn link_down(&mut self) {
+        self.link_state = LinkState::Connect;
+    }
+
+    pub fn touch_link(&mut self) {
+        self.last_io = Instant::now();
+    }
+
+    /// Adopts the history of the master after loading its snapshot.
+    pub fn full_sync_done(&mut self, replid: &str, offset: u64) {
+        self.replid = replid.to_string();
+        self.replid2 = "0".repeat(40);
+        self.second_replid_offset = -1;
+        self.offset = offset;
+        self.backlog.reset(offset + 1);
+        self.master_db = 0;
+        self.link_state = LinkState::Connected;
+        self.touch_link();
+    }
+
+    /// Resumes the stream of the master, whose replication ID may have changed since, e.g. if it
+    /// was promoted.
+    pub fn partial_sync_done(&mut self, replid: Option<&str>) {
+        if let Some(replid) = replid.filter(|id| *id != self.replid) {
+            self.replid2 = std::mem::replace(&mut self.replid, replid.to_string());
+            self.second_replid_offset = self.offset as i64 + 1;
+        }
+        self.link_state = LinkState::Connected;
+        self.touch_link();
+    }
+
+    /// Serves `PSYNC replid offset` from a replica. Returns the reply, which for a partial
+    /// resynchronization carries the part of the stream the replica missed, along with what the
+    /// replica is sent afterwards. Like a background save, a full resynchronization only copies
+    /// the keys here, and they are serialised without holding the server.
+    pub fn psync(
+        &mut self,
+        replid: &str,
+        offset: i64,
+        databases: &Databases,
+        session: &Session,
+    ) -> Result<(Vec<u8>, ReplicaFeed), ExecutionError> {
+        if self.is_replica() && self.link_state != LinkState::Connected {
+            return Err(ExecutionError::NoMasterLink);
+        }
+        let addr = session.addr.clone().unwrap_or_default();
+        let (reply, snapshot) = match self.missed_since(replid, offset) {
+            Some(missed) => {
+                log::info!("Partial resynchronization of replica {} accepted", addr);
+                let reply = [
+                    format!("+CONTINUE {}\r\n", self.replid).into_bytes(),
+                    missed,
+                ]
+                .concat();
+                (reply, None)
+            }
+            None => {
+                log::info!("Full resynchronization of replica {}", addr);
+                // The replica applies the stream from database 0 on.
+                self.selected_db = None;
+                let reply = format!("+FULLRESYNC {} {}\r\n", self.replid, self.offset);
+                (reply.into_bytes(), Some(databases.snapshot()))
+            }
+        };
+        let (feed, receiver) = unbounded_channel();
+        let queue = Arc::new(Queue::default());
+        let stream = ReplicaStream {
+            receiver,
+            queue: queue.clone(),
+        };
+        self.replicas.push(Replica {
+            session_id: session.id,
+            addr,
+            port: session.replica_port.unwrap_or(0),
+            ack_offset: 0,
+            last_ack: Instant::now(),
+            feed,
+            queue,
+        });
+        Ok((reply, ReplicaFeed { snapshot, stream }))
+    }
+
+    /// Returns the stream from `offset` on if the replica can resume from there.
+    fn missed_since(&self, replid: &str, offset: i64) -> Option<Vec<u8>> {
+        let known = replid == self.replid
+            || (replid == self.replid2 && offset <= self.second_replid_offset);
+        if !known || offset < 1 {
+            return None;
+        }
+        self.backlog.since(offset as u64)
+    }
+
+    /// Records the offset a replica reported to have processed.
+    pub fn ack(&mut self, session_id: u64, offset: u64) {
+        if let Some(replica) = self
+            .replicas
+            .iter_mut()
+            .find(|r| r.session_id == session_id)
+        {
+            replica.ack_offset = offset;
+            replica.last_ack = Instant::now();
+        }
+    }
+
+    pub fn remove_replica(&mut self, session_id: u64) {
+        self.replicas.retain(|r| r.session_id != session_id);
+    }
+
+    /// Returns the replication section of `INFO`.
+    pub fn info(&self) -> String {
+        let mut info = String::from("# Replication\r\n");
+        match &self.master {
+            Some((host, port)) => {
+                let up = self.link_state == LinkState::Connected;
+                let _ = write!(
+                    info,
+                    "role:slave\r\nmaster_host:{}\r\nmaster_port:{}\r\nmaster_link_status:{}\r\n\
+                     master_last_io_seconds_ago:{}\r\nmaster_sync_in_progress:{}\r\n\
+                     slave_repl_offset:{}\r\nslave_read_only:{}\r\n",
+                    host,
+                    port,
+                    if up { "up" } else { "down" },
+                    if up {
+                        self.last_io.elapsed().as_secs() as i64
+                    } else {
+                        -1
+                    },
+                    (self.link_state == LinkState::Sync) as u8,
+                    self.offset,
+                    self.read_only as u8,
+                );
+            }
+            None => info.push_str("role:master\r\n"),
+        }
+        let _ = write!(info, "connected_slaves:{}\r\n", self.replicas.len());
+        for (i, replica) in self.replicas.iter().enumerate() {
+            let _ = write!(
+                info,
+                "slave{}:ip={},port={},state=online,offset={},lag={}\r\n",
+                i,
+                replica.addr,
+                replica.port,
+                replica.ack_offset,
+                replica.last_ack.elapsed().as_secs()
+            );
+        }
+        let _ = write!(
+            info,
+            "master_replid:{}\r\nmaster_replid2:{}\r\nmaster_repl_offset:{}\r\n\
+             second_repl_offset:{}\r\nrepl_backlog_active:1\r\nrepl_backlog_size:{}\r\n\
+             repl_backlog_first_byte_offset:{}\r\nrepl_backlog_histlen:{}\r\n",
+            self.replid,
+            self.replid2,
+            self.offset,
+            self.second_replid_offset,
+            self.backlog.capacity(),
+            self.backlog.first_offset(),
+            self.backlog.len()
+        );
+        info
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{encode_snapshot, LinkState, Replication};
+    use crate::config::ServerConfig;
+    use crate::data_store::Databases;
+    use crate::persistence::rdb;
+    use crate::session::Session;
+    use crate::utils::tokens;
+
+    #[test]
+    fn should_resume_from_backlog_or_fall_back_to_full_sync() {
+        let mut replication = Replication::new(&ServerConfig::default());
+        let dbs = Databases::new(16);
+        replication.feed(0, &tokens(&["SET", "a", "1"]));
+        let offset = replication.offset;
+        let replid = replication.replid.clone();
+
+        let session = Session::new();
+        let (reply, feed) = replication.psync("?", -1, &dbs, &session).unwrap();
+        let header = format!("+FULLRESYNC {} {}\r\n", replid, offset);
+        assert_eq!(reply, header.into_bytes());
+        // The keys are serialised once the server is released, before the writes made meanwhile.
+        let snapshot = encode_snapshot(&feed.snapshot.unwrap()).unwrap();
+        let start = snapshot.iter().position(|c| *c == b'\n').unwrap() + 1;
+        let header = format!("${}\r\n", snapshot.len() - start);
+        assert!(snapshot.starts_with(header.as_bytes()));
+        assert!(rdb::is_rdb(&snapshot[start..]));
+        let mut stream = feed.stream;
+        replication.feed(0, &tokens(&["DEL", "a"]));
+        assert_eq!(
+            stream.try_recv().unwrap(),
+            b"*2\r\n$6\r\nSELECT\r\n$1\r\n0\r\n*2\r\n$3\r\nDEL\r\n$1\r\na\r\n".to_vec()
+        );
+
+        let (reply, _) = replication
+            .psync(&replid, offset as i64 + 1, &dbs, &session)
+            .unwrap();
+        let mut expected = format!("+CONTINUE {}\r\n", replid).into_bytes();
+        expected.extend(b"*2\r\n$6\r\nSELECT\r\n$1\r\n0\r\n*2\r\n$3\r\nDEL\r\n$1\r\na\r\n");
+        assert_eq!(reply, expected);
+        assert_eq!(replication.replicas.len(), 2);
+        replication.remove_replica(session.id);
+        assert!(replication.replicas.is_empty());
+
+        // An unknown history or an offset past the stream needs a full sync.
+        let (reply, _) = replication.psync("other", 1, &dbs, &session).unwrap();
+        assert!(reply.starts_with(b"+FULLRESYNC"));
+        let (reply, _) = replication
+            .psync(&replid, replication.offset as i64 + 2, &dbs, &session)
+            .unwrap();
+        assert!(reply.starts_with(b"+FULLRESYNC"));
+    }
+
+    #[test]
+    fn should_keep_previous_history_when_promoted() {
+        let mut replication = Replication::new(&ServerConfig::default());
+        assert!(replication.set_master("127.0.0.1", 6380));
+        assert!(!replication.set_master("127.0.0.1", 6380));
+        assert!(replication.is_read_only());
+        let link = replication.take_pending_link().unwrap();
+        assert!(replication.is_current(link.generation));
+        assert!(replication.take_pending_link().is_none());
+        assert_eq!(replication.link_state, LinkState::Connecting);
+
+        let dbs = Databases::new(16);
+        let err = replication
+            .psync("?", -1, &dbs, &Session::new())
+            .err()
+            .unwrap();
+        assert_eq!(
+            err.to_string(),
+            "NOMASTERLINK Can't SYNC while not connected with my master".to_string()
+        );
+        replication.full_sync_done(&"a".repeat(40), 100);
+        replication.feed_raw(b"*1\r\n$4\r\nPING\r\n");
+        let offset = replication.offset;
+
+        replication.promote();
+        assert!(!replication.is_current(link.generation));
+        assert_ne!(replication.replid, "a".repeat(40));
+        // Replicas of the former master can resume with its history.
+        let (reply, _) = replication
+            .psync(&"a".repeat(40), offset as i64 + 1, &dbs, &Session::new())
+            .unwrap();
+        assert_eq!(
+            reply,
+            format!("+CONTINUE {}\r\n", replication.replid).into_bytes()
+        );
+        assert!(replication.info().contains("role:master\r\n"));
+    }
+
+    #[tokio::test]
+    async fn should_drop_replicas_that_fall_behind_the_backlog() {
+        let mut replication = Replication::new(&ServerConfig {
+            repl_backlog_size: 100,
+            ..ServerConfig::default()
+        });
+        let dbs = Databases::new(16);
+        let session = Session::new();
+        let (_, feed) = replication.psync("?", -1, &dbs, &session).unwrap();
+        let mut stream = feed.stream;
+
+        // What the connection took no longer counts towards the limit.
+        for _ in 0..10 {
+            replication.feed_raw(&[b'x'; 40]);
+            assert_eq!(stream.try_recv().unwrap().len(), 40);
+        }
+        replication.feed_raw(&[b'x'; 40]);
+        replication.feed_raw(&[b'x'; 40]);
+        assert_eq!(replication.replicas.len(), 1);
+        replication.feed_raw(&[b'x'; 40]);
+        assert!(replication.replicas.is_empty());
+        // The connection is closed without sending the rest of the stream.
+        stream.dropped().await;
+        assert_eq!(stream.recv().await, None);
+    }
+}
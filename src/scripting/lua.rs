@@ -0,0 +1,383 @@
+use super::ScriptMonitor;
+use crate::command::is_write_command;
+use crate::error::ScriptError;
+use crate::execution_result::server::ScriptResult;
+use crate::execution_result::{
+    ArrayReply, BulkStringReply, ExecutionResult, IntegerReply, NullReply, RespReply, RespVersion,
+    SimpleErrorReply, SimpleStringReply,
+};
+use crate::server::Server;
+use crate::session::Session;
+use crate::utils::sha1_hex;
+use mlua::{
+    ChunkMode, Error as LuaError, Function, HookTriggers, Lua, LuaOptions, StdLib, Table, Value,
+    Variadic,
+};
+use std::cell::RefCell;
+use std::error::Error;
+use std::sync::atomic::Ordering;
+use std::sync::Arc;
+
+/// How many Lua instructions run between two checks of whether the script was killed.
+const KILL_CHECK_INTERVAL: u32 = 100_000;
+
+/// The commands scripts cannot call, as they run scripts themselves or change the state of the
+/// connection rather than the data.
+const FORBIDDEN_COMMANDS: &[&str] = &[
+    "eval",
+    "evalsha",
+    "script",
+    "multi",
+    "exec",
+    "discard",
+    "watch",
+    "unwatch",
+    "hello",
+    "psync",
+    "replconf",
+    "replicaof",
+    "slaveof",
+    "subscribe",
+    "unsubscribe",
+    "psubscribe",
+    "punsubscribe",
+    "quit",
+];
+
+/// The part of the `redis` library that is written in Lua, on top of `redis.pcall`. Scripts
+/// cannot use global variables, which would otherwise leak from one script to the next.
+const PRELUDE: &str = r#"
+function redis.call(...)
+    local reply = redis.pcall(...)
+    if type(reply) == "table" and reply.err then
+        error(reply, 2)
+    end
+    return reply
+end
+function redis.error_reply(message)
+    return {err = message}
+end
+function redis.status_reply(message)
+    return {ok = message}
+end
+setmetatable(_G, {
+    __newindex = function(_, name)
+        error("Script attempted to create global variable '" .. tostring(name) .. "'", 2)
+    end,
+    __index = function(_, name)
+        error("Script attempted to access nonexistent global variable '" .. tostring(name) .. "'", 2)
+    end,
+})
+"#;
+
+/// Checks that the body of a script compiles.
+pub(super) fn compile(body: &[u8]) -> Result<(), ScriptError> {
+    let lua = Lua::new_with(StdLib::NONE, LuaOptions::new())
+        .map_err(|e| ScriptError::Compile(e.to_string()))?;
+    load(&lua, body).map(|_| ())
+}
+
+fn load<'lua>(lua: &'lua Lua, body: &[u8]) -> Result<Function<'lua>, ScriptError> {
+    // Precompiled chunks could crash the interpreter, so only source code is accepted.
+    let chunk = lua
+        .load(body)
+        .set_name("@user_script")
+        .set_mode(ChunkMode::Text);
+    match chunk.into_function() {
+        Ok(v) => Ok(v),
+        Err(LuaError::SyntaxError { message, .. }) => Err(ScriptError::Compile(message)),
+        Err(e) => Err(ScriptError::Compile(e.to_string())),
+    }
+}
+
+/// Creates the interpreter a script runs in, with the `KEYS` and `ARGV` of the script and the
+/// `redis` library, except for `redis.pcall` which needs the server.
+fn new_lua(monitor: &Arc<ScriptMonitor>, keys: &[Vec<u8>], args: &[Vec<u8>]) -> mlua::Result<Lua> {
+    let lua = Lua::new_with(
+        StdLib::TABLE | StdLib::STRING | StdLib::MATH,
+        LuaOptions::new(),
+    )?;
+    let globals = lua.globals();
+    // Scripts can only reach the data through the server, and not the file system.
+    for name in ["dofile", "loadfile", "print"] {
+        globals.raw_remove(name)?;
+    }
+    let strings = |values: &[Vec<u8>]| -> mlua::Result<Table> {
+        lua.create_sequence_from(
+            values
+                .iter()
+                .map(|v| lua.create_string(v))
+                .collect::<mlua::Result<Vec<_>>>()?,
+        )
+    };
+    globals.raw_set("KEYS", strings(keys)?)?;
+    globals.raw_set("ARGV", strings(args)?)?;
+
+    let redis = lua.create_table()?;
+    redis.raw_set(
+        "sha1hex",
+        lua.create_function(|_, body: mlua::String| Ok(sha1_hex(body.as_bytes())))?,
+    )?;
+    redis.raw_set(
+        "log",
+        lua.create_function(|_, (level, parts): (i64, Variadic<mlua::String>)| {
+            let message = parts
+                .iter()
+                .map(|part| part.to_string_lossy())
+                .collect::<Vec<_>>()
+                .join(" ");
+            match level {
+                0 | 1 => log::debug!("{}", message),
+                2 => log::info!("{}", message),
+                _ => log::warn!("{}", message),
+            }
+            Ok(())
+        })?,
+    )?;
+    for (i, name) in ["LOG_DEBUG", "LOG_VERBOSE", "LOG_NOTICE", "LOG_WARNING"]
+        .iter()
+        .enumerate()
+    {
+        redis.raw_set(*name, i)?;
+    }
+    globals.raw_set("redis", redis)?;
+
+    let monitor = monitor.clone();
+    lua.set_hook(
+        HookTriggers::new().every_nth_instruction(KILL_CHECK_INTERVAL),
+        move |_, _| match monitor.is_killed() {
+            true => Err(LuaError::RuntimeError(ScriptError::Killed.to_string())),
+            false => Ok(()),
+        },
+    );
+    lua.load(PRELUDE).set_name("@prelude").exec()?;
+    drop(globals);
+    Ok(lua)
+}
+
+/// Runs a script on behalf of `session`, given the digest and body of the script, and the keys
+/// and arguments it is called with. The writes of the script are logged as one transaction, and
+/// a database it selects is only selected for the rest of the script.
+pub fn eval(
+    server: &mut Server,
+    session: &mut Session,
+    sha: &str,
+    body: &[u8],
+    keys: &[Vec<u8>],
+    args: &[Vec<u8>],
+) -> Result<Box<dyn ExecutionResult>, Box<dyn Error>> {
+    let monitor = server.scripts.monitor.clone();
+    let lua = match new_lua(&monitor, keys, args) {
+        Ok(v) => v,
+        Err(e) => {
+            return Err(Box::new(ScriptError::Runtime(
+                sha.to_string(),
+                e.to_string(),
+            )))
+        }
+    };
+    let function = load(&lua, body)?;
+    let db = session.db;
+    monitor.start();
+    let result = server.atomically(|server| run(&lua, function, server, session, &monitor));
+    monitor.stop();
+    session.db = db;
+    if monitor.is_killed() {
+        return Err(Box::new(ScriptError::Killed));
+    }
+    let value = match result {
+        Ok(v) => v,
+        Err(e) => {
+            return Err(Box::new(ScriptError::Runtime(
+                sha.to_string(),
+                e.to_string(),
+            )))
+        }
+    };
+    match value {
+        (true, value) => match error_field(&value) {
+            Some(message) => Err(Box::new(ScriptError::Reply(message))),
+            None => Ok(Box::new(ScriptResult {
+                reply: to_reply(&value),
+            })),
+        },
+        // `redis.call` raises the error replies of commands as they are.
+        (false, value) => match error_field(&value) {
+            Some(message) => Err(Box::new(ScriptError::Reply(message))),
+            None => Err(Box::new(ScriptError::Runtime(
+                sha.to_string(),
+                error_message(&value),
+            ))),
+        },
+    }
+}
+
+/// Calls the function of a script in protected mode, which returns whether it succeeded along
+/// with its return value or the error it raised.
+fn run<'lua>(
+    lua: &'lua Lua,
+    function: Function<'lua>,
+    server: &mut Server,
+    session: &mut Session,
+    monitor: &ScriptMonitor,
+) -> mlua::Result<(bool, Value<'lua>)> {
+    let context = RefCell::new((server, session));
+    lua.scope(|scope| {
+        let pcall = scope
+            .create_function(|lua, args: Variadic<Value>| call(lua, &context, monitor, args))?;
+        lua.globals()
+            .raw_get::<_, Table>("redis")?
+            .raw_set("pcall", pcall)?;
+        lua.globals()
+            .raw_get::<_, Function>("pcall")?
+            .call(function)
+    })
+}
+
+/// `redis.pcall`, which executes a command and returns its reply, or its error reply as a table
+/// with an `err` field.
+fn call<'lua>(
+    lua: &'lua Lua,
+    context: &RefCell<(&mut Server, &mut Session)>,
+    monitor: &ScriptMonitor,
+    args: Variadic<Value<'lua>>,
+) -> mlua::Result<Value<'lua>> {
+    if monitor.is_killed() {
+        return Err(LuaError::RuntimeError(ScriptError::Killed.to_string()));
+    }
+    let tokens = match command_tokens(&args) {
+        Ok(v) => v,
+        Err(e) => return error_table(lua, &e.to_string()),
+    };
+    if is_write_command(&tokens[0]) {
+        monitor.wrote.store(true, Ordering::SeqCst);
+    }
+    let (server, session) = &mut *context.borrow_mut();
+    match server.execute(&tokens, session) {
+        Ok(result) => from_reply(lua, &result.serialise(RespVersion::Resp2), &mut 0),
+        Err(e) => error_table(lua, &e.to_string()),
+    }
+}
+
+/// Turns the arguments given to `redis.call` into the tokens of a request.
+fn command_tokens(args: &[Value]) -> Result<Vec<Vec<u8>>, ScriptError> {
+    if args.is_empty() {
+        return Err(ScriptError::NoArguments);
+    }
+    let tokens = args
+        .iter()
+        .map(|arg| match arg {
+            Value::String(v) => Ok(v.as_bytes().to_vec()),
+            Value::Integer(v) => Ok(v.to_string().into_bytes()),
+            Value::Number(v) => Ok(v.to_string().into_bytes()),
+            _ => Err(ScriptError::InvalidArgument),
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+    let name = String::from_utf8_lossy(&tokens[0]).to_lowercase();
+    if FORBIDDEN_COMMANDS.contains(&name.as_str()) {
+        return Err(ScriptError::NotAllowed);
+    }
+    Ok(tokens)
+}
+
+fn error_table<'lua>(lua: &'lua Lua, message: &str) -> mlua::Result<Value<'lua>> {
+    let table = lua.create_table()?;
+    table.raw_set("err", message)?;
+    Ok(Value::Table(table))
+}
+
+/// Converts the RESP2 reply of a command, starting at `pos`, to a Lua value: integers become
+/// numbers, bulk strings become strings, arrays become tables, and nulls become `false`. Status
+/// and error replies become tables with an `ok` or `err` field.
+fn from_reply<'lua>(lua: &'lua Lua, reply: &[u8], pos: &mut usize) -> mlua::Result<Value<'lua>> {
+    let Some(len) = reply[*pos..].windows(2).position(|w| w == b"\r\n") else {
+        return Err(LuaError::RuntimeError("incomplete reply".to_string()));
+    };
+    let kind = reply[*pos];
+    let line = String::from_utf8_lossy(&reply[*pos + 1..*pos + len]).to_string();
+    *pos += len + 2;
+    let size = line.parse::<usize>().ok();
+    match (kind, size) {
+        (b'+', _) => {
+            let table = lua.create_table()?;
+            table.raw_set("ok", line)?;
+            Ok(Value::Table(table))
+        }
+        (b'-', _) => error_table(lua, &line),
+        (b':', _) => match line.parse::<mlua::Integer>() {
+            Ok(v) => Ok(Value::Integer(v)),
+            Err(_) => Err(LuaError::RuntimeError(format!("invalid integer {}", line))),
+        },
+        (b'$', Some(size)) => {
+            let value = lua.create_string(&reply[*pos..*pos + size])?;
+            *pos += size + 2;
+            Ok(Value::String(value))
+        }
+        (b'*', Some(size)) => {
+            let table = lua.create_table_with_capacity(size, 0)?;
+            for i in 1..=size {
+                table.raw_set(i, from_reply(lua, reply, pos)?)?;
+            }
+            Ok(Value::Table(table))
+        }
+        // The null bulk string and the null array.
+        (b'$' | b'*', None) => Ok(Value::Boolean(false)),
+        _ => Err(LuaError::RuntimeError(format!(
+            "unexpected reply type `{}`",
+            kind as char
+        ))),
+    }
+}
+
+/// Returns the message of a table with an `err` field, which is how scripts return errors.
+fn error_field(value: &Value) -> Option<String> {
+    let Value::Table(table) = value else {
+        return None;
+    };
+    match table.raw_get::<_, Value>("err") {
+        Ok(Value::String(message)) => Some(message.to_string_lossy().to_string()),
+        _ => None,
+    }
+}
+
+fn error_message(value: &Value) -> String {
+    match value {
+        Value::String(v) => v.to_string_lossy().to_string(),
+        Value::Error(e) => e.to_string(),
+        v => format!("{:?}", v),
+    }
+}
+
+/// Converts a value returned by a script to a reply: numbers become integers, truncating them,
+/// strings become bulk strings, `true` becomes 1, and `false` and `nil` become null. Tables become
+/// arrays of the values up to their first `nil`, unless they have an `err` or `ok` field, which
+/// makes them error and status replies, where line breaks become spaces like in any other.
+fn to_reply(value: &Value) -> Box<dyn RespReply> {
+    match value {
+        Value::String(v) => Box::new(BulkStringReply {
+            value: v.as_bytes().to_vec(),
+        }),
+        Value::Integer(v) => Box::new(IntegerReply { value: *v }),
+        Value::Number(v) => Box::new(IntegerReply { value: *v as i64 }),
+        Value::Boolean(true) => Box::new(IntegerReply { value: 1 }),
+        Value::Table(table) => {
+            if let Some(message) = error_field(value) {
+                return Box::new(SimpleErrorReply { message });
+            }
+            if let Ok(Value::String(status)) = table.raw_get::<_, Value>("ok") {
+                return Box::new(SimpleStringReply {
+                    value: status.to_string_lossy().to_string(),
+                });
+            }
+            let mut values = Vec::new();
+            for i in 1.. {
+                match table.raw_get::<_, Value>(i) {
+                    Ok(Value::Nil) | Err(_) => break,
+                    Ok(v) => values.push(to_reply(&v)),
+                }
+            }
+            Box::new(ArrayReply { values })
+        }
+        _ => Box::new(NullReply),
+    }
+}
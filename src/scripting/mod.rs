@@ -0,0 +1,179 @@
+//! Lua scripting. Scripts sent with `EVAL`, or loaded once and run by their SHA-1 digest with
+//! `EVALSHA`, are run by Lua 5.1 and call commands with `redis.call` and `redis.pcall`.
+//!
+//! A script runs as a whole, with no other client running commands in between. One that runs for
+//! longer than the script time limit makes other clients get `BUSY` errors instead of waiting,
+//! and can be stopped with `SCRIPT KILL` as long as it did not write anything yet.
+
+mod lua;
+pub use lua::eval;
+
+use crate::config::ServerConfig;
+use crate::data_store::now_ms;
+use crate::error::ScriptError;
+use crate::execution_result::server::OkResult;
+use crate::execution_result::ExecutionResult;
+use crate::utils::sha1_hex;
+use std::collections::HashMap;
+use std::error::Error;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::Arc;
+
+/// The scripts known to the server, by the SHA-1 digest of their body.
+pub struct Scripts {
+    cache: HashMap<String, Vec<u8>>,
+    /// The state of the running script, which connections read without locking the server.
+    pub monitor: Arc<ScriptMonitor>,
+}
+
+impl Scripts {
+    pub fn new(config: &ServerConfig) -> Self {
+        Scripts {
+            cache: HashMap::new(),
+            monitor: Arc::new(ScriptMonitor::new(config.lua_time_limit)),
+        }
+    }
+
+    /// Adds a script to the cache once it compiled, and returns its digest.
+    pub fn load(&mut self, body: &[u8]) -> Result<String, ScriptError> {
+        lua::compile(body)?;
+        let sha = sha1_hex(body);
+        self.cache.insert(sha.clone(), body.to_vec());
+        Ok(sha)
+    }
+
+    /// Returns the body of the script with the digest `sha`, in either case.
+    pub fn get(&self, sha: &[u8]) -> Option<&[u8]> {
+        let sha = String::from_utf8_lossy(sha).to_lowercase();
+        self.cache.get(&sha).map(|body| body.as_slice())
+    }
+
+    pub fn flush(&mut self) {
+        self.cache.clear();
+    }
+}
+
+/// What connections need to know about the running script while it holds the server: when it
+/// started, whether it wrote anything, and whether a client asked to stop it.
+#[derive(Debug)]
+pub struct ScriptMonitor {
+    /// How long a script runs, in milliseconds, before the server is busy. 0 means never.
+    time_limit: u64,
+    /// When the running script started, in milliseconds since the epoch, or 0 if none is running.
+    started: AtomicU64,
+    wrote: AtomicBool,
+    killed: AtomicBool,
+}
+
+impl ScriptMonitor {
+    fn new(time_limit: u64) -> Self {
+        ScriptMonitor {
+            time_limit,
+            started: AtomicU64::new(0),
+            wrote: AtomicBool::new(false),
+            killed: AtomicBool::new(false),
+        }
+    }
+
+    fn start(&self) {
+        self.wrote.store(false, Ordering::SeqCst);
+        self.killed.store(false, Ordering::SeqCst);
+        self.started.store(now_ms(), Ordering::SeqCst);
+    }
+
+    fn stop(&self) {
+        self.started.store(0, Ordering::SeqCst);
+    }
+
+    pub fn is_running(&self) -> bool {
+        self.started.load(Ordering::SeqCst) != 0
+    }
+
+    /// Returns whether a script has been running for longer than the time limit.
+    pub fn is_busy(&self) -> bool {
+        let started = self.started.load(Ordering::SeqCst);
+        started != 0 && self.time_limit > 0 && now_ms().saturating_sub(started) >= self.time_limit
+    }
+
+    fn is_killed(&self) -> bool {
+        self.killed.load(Ordering::SeqCst)
+    }
+
+    /// Answers a request received while the server is busy running a script: `SCRIPT KILL` stops
+    /// the script, unless it already wrote to the data, and every other request is refused.
+    pub fn interrupt(
+        &self,
+        tokens: &[Vec<u8>],
+    ) -> Result<Box<dyn ExecutionResult>, Box<dyn Error>> {
+        let kill = tokens.len() == 2
+            && tokens[0].eq_ignore_ascii_case(b"script")
+            && tokens[1].eq_ignore_ascii_case(b"kill");
+        if !kill {
+            return Err(Box::new(ScriptError::Busy));
+        }
+        if self.wrote.load(Ordering::SeqCst) {
+            return Err(Box::new(ScriptError::Unkillable));
+        }
+        self.killed.store(true, Ordering::SeqCst);
+        Ok(Box::new(OkResult))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::Scripts;
+    use crate::config::ServerConfig;
+    use crate::utils::tokens;
+
+    #[test]
+    fn should_cache_scripts_by_digest() {
+        let mut scripts = Scripts::new(&ServerConfig::default());
+        let sha = scripts.load(b"return 1").unwrap();
+        assert_eq!(sha, "e0e1f9fabfc9d4800c877a703b823ac0578ff8db".to_string());
+        assert_eq!(scripts.get(sha.as_bytes()), Some(b"return 1".as_slice()));
+        assert!(scripts.get(sha.to_uppercase().as_bytes()).is_some());
+        let err = scripts.load(b"return (").err().unwrap();
+        assert_eq!(
+            err.to_string(),
+            "ERR Error compiling script (new function): user_script:1: unexpected symbol near '<eof>'"
+                .to_string()
+        );
+        scripts.flush();
+        assert!(scripts.get(sha.as_bytes()).is_none());
+    }
+
+    #[test]
+    fn should_only_kill_scripts_that_did_not_write() {
+        let config = ServerConfig {
+            lua_time_limit: 1,
+            ..ServerConfig::default()
+        };
+        let monitor = Scripts::new(&config).monitor;
+        assert!(!monitor.is_busy());
+        monitor.start();
+        std::thread::sleep(std::time::Duration::from_millis(5));
+        assert!(monitor.is_busy());
+        let err = monitor.interrupt(&tokens(&["GET", "a"])).err().unwrap();
+        assert_eq!(
+            err.to_string(),
+            "BUSY Redis is busy running a script. You can only call SCRIPT KILL or SHUTDOWN NOSAVE."
+                .to_string()
+        );
+        monitor
+            .wrote
+            .store(true, std::sync::atomic::Ordering::SeqCst);
+        let err = monitor
+            .interrupt(&tokens(&["SCRIPT", "KILL"]))
+            .err()
+            .unwrap();
+        assert!(err.to_string().starts_with("UNKILLABLE"));
+        assert!(!monitor.is_killed());
+
+        monitor.start();
+        let result = monitor.interrupt(&tokens(&["script", "kill"])).unwrap();
+        assert_eq!(result.to_string(), "OK".to_string());
+        assert!(monitor.is_killed());
+        monitor.stop();
+        assert!(!monitor.is_busy());
+    }
+}
@@ -0,0 +1,639 @@
+use crate::cluster::Cluster;
+use crate::command::{
+    command_keys, is_subscriber_command, is_transaction_command, is_write_command, CommandFactory,
+    ParsedCommand,
+};
+use crate::config::ServerConfig;
+use crate::data_store::Databases;
+use crate::error::{AofError, ExecutionError, PubSubError};
+use crate::execution_result::server::QueuedResult;
+use crate::execution_result::{ErrorResult, ExecutionResult, RespVersion};
+use crate::persistence::aof::Aof;
+use crate::persistence::{snapshot, Persistence};
+use crate::pubsub::PubSub;
+use crate::replication::Replication;
+use crate::scripting::Scripts;
+use crate::session::Session;
+use crate::utils::RequestDecoder;
+use std::error::Error;
+
+/// How much of the append-only file is handed to the decoder at once while replaying it.
+const REPLAY_CHUNK_SIZE: usize = 64 * 1024;
+
+/// The state shared by every connection of the server.
+pub struct Server {
+    pub databases: Databases,
+    pub persistence: Persistence,
+    pub aof: Aof,
+    pub replication: Replication,
+    pub cluster: Cluster,
+    pub scripts: Scripts,
+    pub pubsub: PubSub,
+    /// While the commands of a transaction or a script run, the database of the last write they
+    /// logged, if any. Their writes are logged between `MULTI` and `EXEC`.
+    atomic: Option<Option<usize>>,
+    /// Whether the append-only file is being replayed, whose writes are already logged and are
+    /// part of the data replicas sync from.
+    loading: bool,
+}
+
+impl Server {
+    pub fn new(config: &ServerConfig) -> Self {
+        Server {
+            databases: Databases::new(config.databases),
+            persistence: Persistence::new(config),
+            aof: Aof::new(config),
+            replication: Replication::new(config),
+            cluster: Cluster::new(config),
+            scripts: Scripts::new(config),
+            pubsub: PubSub::new(config),
+            atomic: None,
+            loading: false,
+        }
+    }
+
+    /// Loads the data saved by a previous run: the append-only file when it is enabled, or the
+    /// snapshot file otherwise. Returns false if there was nothing to load. In cluster mode, the
+    /// view of the cluster is loaded first.
+    pub fn load(&mut self) -> Result<bool, Box<dyn Error>> {
+        self.cluster.load()?;
+        if let Some((host, port)) = self.cluster.master_addr() {
+            self.replication.set_master(&host, port);
+        }
+        if !self.aof.enabled {
+            return Ok(self.persistence.load(&mut self.databases)?);
+        }
+        let loaded = match self.aof.read()? {
+            Some(data) => {
+                self.loading = true;
+                let replayed = self.replay(&data);
+                self.loading = false;
+                replayed?;
+                self.aof.open()?;
+                true
+            }
+            None => {
+                // Start the log from the snapshot, so that its data is kept once the log is
+                // replayed instead.
+                let loaded = self.persistence.load(&mut self.databases)?;
+                self.aof.create(&self.databases)?;
+                loaded
+            }
+        };
+        self.persistence.dirty = 0;
+        Ok(loaded)
+    }
+
+    /// Executes every command of the append-only file, after loading the snapshot it may start
+    /// with. A final record cut short by a crash is dropped from the file, and so is a final
+    /// transaction that was never closed by its `EXEC`.
+    fn replay(&mut self, data: &[u8]) -> Result<(), Box<dyn Error>> {
+        let mut fed = 0;
+        if snapshot::is_snapshot(data) {
+            fed = snapshot::read(data, &mut self.databases)?;
+        }
+        let mut decoder = RequestDecoder::new();
+        let mut session = Session::new();
+        session.is_master = true;
+        let mut multi = 0;
+        for chunk in data[fed..].chunks(REPLAY_CHUNK_SIZE) {
+            decoder.buffer_mut().extend_from_slice(chunk);
+            fed += chunk.len();
+            loop {
+                let offset = fed - decoder.buffer_mut().len();
+                let tokens = match decoder.decode() {
+                    Ok(Some(tokens)) => tokens,
+                    Ok(None) => break,
+                    Err(e) => return Err(Box::new(AofError::InvalidRecord(offset, e.to_string()))),
+                };
+                if tokens.is_empty() {
+                    continue;
+                }
+                if tokens[0].eq_ignore_ascii_case(b"multi") {
+                    multi = offset;
+                }
+                if let Err(e) = self.execute(&tokens, &mut session) {
+                    return Err(Box::new(AofError::InvalidRecord(offset, e.to_string())));
+                }
+            }
+        }
+        if session.transaction.is_some() {
+            log::warn!(
+                "The append-only file ends with an unfinished transaction, truncating it from {} to {} bytes",
+                data.len(),
+                multi
+            );
+            self.aof.truncate(multi)?;
+        } else if !decoder.is_empty() {
+            let len = data.len() - decoder.buffer_mut().len();
+            log::warn!(
+                "The append-only file ends with an incomplete record, truncating it from {} to {} bytes",
+                data.len(),
+                len
+            );
+            self.aof.truncate(len)?;
+        }
+        Ok(())
+    }
+
+    /// Executes a single request on behalf of `session`. In cluster mode, requests for keys of
+    /// other nodes are redirected. Writes that modified the data count towards the next automatic
+    /// save, and are logged to the append-only file and streamed to replicas. Within a transaction, the
+    /// request is only checked and queued, and any error makes the transaction fail.
+    pub fn execute(
+        &mut self,
+        tokens: &[Vec<u8>],
+        session: &mut Session,
+    ) -> Result<Box<dyn ExecutionResult>, Box<dyn Error>> {
+        let command = self.check(tokens, session);
+        if let Some(transaction) = &mut session.transaction {
+            if !is_transaction_command(&tokens[0]) {
+                if command.is_err() {
+                    transaction.aborted = true;
+                }
+                command?;
+                transaction.queue.push(tokens.to_vec());
+                return Ok(Box::new(QueuedResult));
+            }
+        }
+        let changes = self.databases.changes();
+        let result = match command? {
+            ParsedCommand::Data(cmd) => cmd.execute(self.databases.db_mut(session.db)),
+            ParsedCommand::Connection(cmd) => cmd.execute(session),
+            ParsedCommand::Server(cmd) => cmd.execute(self, session),
+        }?;
+        // Writes that changed nothing, e.g. a `SET NX` of a key that exists, are not recorded.
+        // MIGRATE records the keys it moved away itself, once the target accepted them.
+        if is_write_command(&tokens[0])
+            && !tokens[0].eq_ignore_ascii_case(b"migrate")
+            && self.databases.changes() != changes
+        {
+            self.mark_dirty();
+            self.propagate(session.db, tokens);
+        }
+        Ok(result)
+    }
+
+    /// Builds the command of a request, and checks that this server can run it.
+    fn check(
+        &mut self,
+        tokens: &[Vec<u8>],
+        session: &mut Session,
+    ) -> Result<ParsedCommand, Box<dyn Error>> {
+        // RESP2 has no way to tell replies from pushed messages, so a subscribed connection is
+        // only used to manage its subscriptions.
+        if session.subscriber.is_some()
+            && session.protocol == RespVersion::Resp2
+            && !is_subscriber_command(&tokens[0])
+        {
+            let name = String::from_utf8_lossy(&tokens[0]).to_lowercase();
+            return Err(Box::new(PubSubError::SubscriberContext(name)));
+        }
+        let command = CommandFactory::new(tokens)?;
+        // RESTORE-ASKING is how MIGRATE moves keys into a slot that is still being imported.
+        let asking = std::mem::take(&mut session.asking)
+            || tokens[0].eq_ignore_ascii_case(b"restore-asking");
+        if self.cluster.enabled && !session.is_master {
+            let data_store = self.databases.db_mut(session.db);
+            self.cluster
+                .check_keys(&command_keys(tokens), asking, |key| {
+                    data_store.contains_key(key)
+                })?;
+        }
+        if self.replication.is_read_only() && !session.is_master && is_write_command(&tokens[0]) {
+            return Err(Box::new(ExecutionError::ReadOnlyReplica));
+        }
+        // Writes that could not be logged would be lost on restart.
+        if let Some(error) = self.aof.write_error() {
+            if !session.is_master && is_write_command(&tokens[0]) {
+                return Err(Box::new(ExecutionError::AofWriteFailed(error.to_string())));
+            }
+        }
+        Ok(command)
+    }
+
+    /// Forgets the keys `session` watches. Returns whether any of them changed since it started
+    /// watching them.
+    pub fn unwatch(&mut self, session: &mut Session) -> bool {
+        let mut changed = false;
+        for (db, key, version) in std::mem::take(&mut session.watched) {
+            let data_store = self.databases.db_mut(db);
+            changed |= data_store.version(&key) != version;
+            data_store.unwatch(&key);
+        }
+        changed
+    }
+
+    /// Runs the commands queued by a transaction one after the other, with no other client
+    /// running commands in between.
+    pub fn exec(
+        &mut self,
+        queue: &[Vec<Vec<u8>>],
+        session: &mut Session,
+    ) -> Vec<Box<dyn ExecutionResult>> {
+        self.atomically(|server| {
+            queue
+                .iter()
+                .map(|tokens| match server.execute(tokens, session) {
+                    Ok(result) => result,
+                    Err(e) => Box::new(ErrorResult {
+                        message: e.to_string(),
+                    }) as Box<dyn ExecutionResult>,
+                })
+                .collect()
+        })
+    }
+
+    /// Runs `f`, logging the writes it makes between `MULTI` and `EXEC`, so that replicas and the
+    /// append-only file apply all of them or none. Nothing is logged if it makes no writes, and
+    /// the writes of nested calls are part of the outer transaction.
+    pub fn atomically<T>(&mut self, f: impl FnOnce(&mut Self) -> T) -> T {
+        if self.atomic.is_some() {
+            return f(self);
+        }
+        self.atomic = Some(None);
+        let result = f(self);
+        if let Some(Some(db)) = self.atomic.take() {
+            self.feed(db, &[b"EXEC".to_vec()]);
+        }
+        result
+    }
+
+    /// Logs a write made to database `db` to the append-only file and the replication stream.
+    /// Relative expiry times are turned into the deadline they set, so that replaying the log
+    /// later or applying it on a replica gives the same one.
+    fn propagate(&mut self, db: usize, tokens: &[Vec<u8>]) {
+        let name = String::from_utf8_lossy(&tokens[0]).to_lowercase();
+        let key = tokens.get(1).map(|k| k.as_slice()).unwrap_or_default();
+        let data_store = self.databases.db_mut(db);
+        let tokens = match name.as_str() {
+            "expire" | "pexpire" => match data_store.get_expire_at(key) {
+                Some(deadline) => vec![
+                    b"PEXPIREAT".to_vec(),
+                    key.to_vec(),
+                    deadline.to_string().into_bytes(),
+                ],
+                None if !data_store.contains_key(key) => vec![b"DEL".to_vec(), key.to_vec()],
+                None => tokens.to_vec(),
+            },
+            "set" => match data_store.get_expire_at(key) {
+                Some(deadline) => absolute_set_expiry(tokens, deadline),
+                None => tokens.to_vec(),
+            },
+            "restore" | "restore-asking" => match data_store.dump(key) {
+                Some((_, deadline)) => vec![
+                    b"RESTORE".to_vec(),
+                    key.to_vec(),
+                    deadline.unwrap_or_default().to_string().into_bytes(),
+                    tokens[3].clone(),
+                    b"REPLACE".to_vec(),
+                    b"ABSTTL".to_vec(),
+                ],
+                None => vec![b"DEL".to_vec(), key.to_vec()],
+            },
+            _ => tokens.to_vec(),
+        };
+        if let Some(logged) = &mut self.atomic {
+            if logged.is_none() {
+                self.feed(db, &[b"MULTI".to_vec()]);
+            }
+            self.atomic = Some(Some(db));
+        }
+        self.feed(db, &tokens);
+    }
+
+    /// Returns whether the commands of a transaction or a script are running.
+    pub fn is_atomic(&self) -> bool {
+        self.atomic.is_some()
+    }
+
+    /// Records that a command deleted `keys` from database `db` after it ran, e.g. the keys
+    /// `MIGRATE` moved away, which are logged as their deletion.
+    pub fn record_deletions(&mut self, db: usize, keys: Vec<Vec<u8>>) {
+        if keys.is_empty() {
+            return;
+        }
+        self.mark_dirty();
+        self.propagate(db, &[vec![b"DEL".to_vec()], keys].concat());
+    }
+
+    fn feed(&mut self, db: usize, tokens: &[Vec<u8>]) {
+        if self.loading {
+            return;
+        }
+        self.aof.feed(db, tokens);
+        self.replication.feed(db, tokens);
+    }
+
+    /// Records that a command changed the data, which counts towards the next automatic save.
+    pub fn mark_dirty(&mut self) {
+        self.persistence.dirty += 1;
+    }
+
+    /// Runs the periodic background work: evicting expired keys, saving snapshots, flushing
+    /// the append-only file and talking to the other nodes of the cluster.
+    pub fn cron(&mut self) {
+        let evicted = self.databases.expire_cycle();
+        if evicted > 0 {
+            log::debug!("Evicted {} expired keys", evicted);
+        }
+        self.persistence.cron(&self.databases);
+        self.aof.cron();
+        self.cluster.cron(&mut self.replication);
+    }
+}
+
+/// Replaces the `EX` or `PX` option of a `SET` with `PXAT deadline`.
+fn absolute_set_expiry(tokens: &[Vec<u8>], deadline: u64) -> Vec<Vec<u8>> {
+    let mut out = tokens[..3.min(tokens.len())].to_vec();
+    let mut options = tokens.iter().skip(3);
+    while let Some(option) = options.next() {
+        if option.eq_ignore_ascii_case(b"ex") || option.eq_ignore_ascii_case(b"px") {
+            options.next();
+            out.push(b"PXAT".to_vec());
+            out.push(deadline.to_string().into_bytes());
+        } else {
+            out.push(option.clone());
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+mod test {
+    use super::Server;
+    use crate::config::ServerConfig;
+    use crate::execution_result::RespVersion;
+    use crate::session::Session;
+    use crate::utils::tokens;
+
+    fn config(name: &str) -> ServerConfig {
+        ServerConfig {
+            dir: std::env::temp_dir().to_str().unwrap().to_string(),
+            dbfilename: format!("redis-rust-server-{}-{}.rdb", name, std::process::id()),
+            appendfilename: format!("redis-rust-server-{}-{}.aof", name, std::process::id()),
+            appendonly: true,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn should_replay_append_only_file() {
+        let config = config("replay");
+        let path = std::path::Path::new(&config.dir).join(&config.appendfilename);
+        let mut server = Server::new(&config);
+        assert!(!server.load().unwrap());
+        let mut session = Session::new();
+        for command in [
+            &["SET", "a", "1", "EX", "100"][..],
+            &["SELECT", "3"],
+            &["SADD", "s", "x", "y"],
+            &["EXPIRE", "s", "50"],
+            &["SET", "b", "2"],
+            &["EXPIRE", "b", "-1"],
+            &["GET", "b"],
+        ] {
+            server.execute(&tokens(command), &mut session).unwrap();
+        }
+        let deadline = server.databases.db_mut(0).get_expire_at(b"a").unwrap();
+        drop(server);
+
+        // A record cut short by a crash is dropped.
+        let mut data = std::fs::read(&path).unwrap();
+        let len = data.len();
+        data.extend_from_slice(b"*3\r\n$3\r\nSET\r\n$1\r\nc");
+        std::fs::write(&path, &data).unwrap();
+
+        let mut server = Server::new(&config);
+        assert!(server.load().unwrap());
+        assert_eq!(std::fs::metadata(&path).unwrap().len(), len as u64);
+        assert_eq!(server.persistence.dirty, 0);
+        // The writes replayed are not streamed to replicas again.
+        assert_eq!(server.replication.offset, 0);
+        assert_eq!(
+            server.databases.db_mut(0).get_expire_at(b"a"),
+            Some(deadline)
+        );
+        assert!(server.databases.db_mut(3).get_expire_at(b"s").is_some());
+        assert!(!server.databases.db_mut(3).contains_key(b"b"));
+        std::fs::remove_file(&path).unwrap();
+
+        // Anything else that cannot be replayed stops the startup.
+        std::fs::write(&path, b"*1\r\n$7\r\nUNKNOWN\r\n").unwrap();
+        let err = Server::new(&config).load().err().unwrap();
+        assert_eq!(
+            err.to_string(),
+            "invalid append-only file at offset 0. Details: unsupported command `unknown`"
+                .to_string()
+        );
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn should_redirect_requests_in_cluster_mode() {
+        let config = ServerConfig {
+            cluster_enabled: true,
+            ..config("cluster")
+        };
+        let path = std::path::Path::new(&config.dir).join(&config.cluster_config_file);
+        let mut server = Server::new(&config);
+        let mut session = Session::new();
+        server
+            .execute(&tokens(&["CLUSTER", "ADDSLOTS", "5061"]), &mut session)
+            .unwrap();
+        server
+            .execute(&tokens(&["MSET", "bar", "1", "{bar}2", "2"]), &mut session)
+            .unwrap();
+        for (command, message) in [
+            (&["GET", "foo"][..], "CLUSTERDOWN Hash slot not served"),
+            (
+                &["MGET", "bar", "foo"],
+                "CROSSSLOT Keys in request don't hash to the same slot",
+            ),
+            (
+                &["SELECT", "1"],
+                "ERR SELECT is not allowed in cluster mode",
+            ),
+        ] {
+            let err = server
+                .execute(&tokens(command), &mut session)
+                .err()
+                .unwrap();
+            assert_eq!(err.to_string(), message.to_string());
+        }
+        server.cluster.myself = "0".repeat(40);
+        let err = server
+            .execute(&tokens(&["GET", "bar"]), &mut session)
+            .err()
+            .unwrap();
+        assert!(err.to_string().starts_with("MOVED 5061 "));
+        assert!(server.execute(&tokens(&["PING"]), &mut session).is_ok());
+        server.execute(&tokens(&["ASKING"]), &mut session).unwrap();
+        assert!(session.asking);
+        server.execute(&tokens(&["PING"]), &mut session).unwrap();
+        assert!(!session.asking);
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn should_run_transactions() {
+        let config = config("multi");
+        let path = std::path::Path::new(&config.dir).join(&config.appendfilename);
+        let mut server = Server::new(&config);
+        server.load().unwrap();
+        let mut session = Session::new();
+        let mut run = |command: &[&str]| match server.execute(&tokens(command), &mut session) {
+            Ok(result) => result.serialise(RespVersion::Resp2),
+            Err(e) => format!("-{}\r\n", e).into_bytes(),
+        };
+        assert_eq!(run(&["EXEC"]), b"-ERR EXEC without MULTI\r\n".to_vec());
+        assert_eq!(run(&["MULTI"]), b"+OK\r\n".to_vec());
+        assert_eq!(
+            run(&["MULTI"]),
+            b"-ERR MULTI calls can not be nested\r\n".to_vec()
+        );
+        assert_eq!(run(&["SET", "a", "1"]), b"+QUEUED\r\n".to_vec());
+        run(&["INCR", "a"]);
+        run(&["LPUSH", "a", "x"]);
+        run(&["GET", "a"]);
+        // Errors found when the command runs do not stop the other commands.
+        assert_eq!(
+            run(&["EXEC"]),
+            b"*4\r\n+OK\r\n:2\r\n-WRONGTYPE Operation against a key holding the wrong kind of value\r\n$1\r\n2\r\n"
+                .to_vec()
+        );
+
+        // Errors found while queueing make the whole transaction fail.
+        run(&["MULTI"]);
+        run(&["SET", "b", "1"]);
+        assert_eq!(
+            run(&["GET"]),
+            b"-ERR wrong number of arguments for command\r\n".to_vec()
+        );
+        assert_eq!(
+            run(&["EXEC"]),
+            b"-EXECABORT Transaction discarded because of previous errors.\r\n".to_vec()
+        );
+        run(&["MULTI"]);
+        run(&["SET", "b", "1"]);
+        assert_eq!(run(&["DISCARD"]), b"+OK\r\n".to_vec());
+        assert_eq!(run(&["GET", "b"]), b"$-1\r\n".to_vec());
+        assert_eq!(
+            run(&["DISCARD"]),
+            b"-ERR DISCARD without MULTI\r\n".to_vec()
+        );
+        drop(server);
+
+        // Writes are logged within the transaction, and an unfinished one is dropped.
+        let mut data = std::fs::read(&path).unwrap();
+        let len = data.len();
+        let log = String::from_utf8_lossy(&data).to_string();
+        assert!(log.contains("MULTI") && log.ends_with("*1\r\n$4\r\nEXEC\r\n"));
+        data.extend_from_slice(b"*1\r\n$5\r\nMULTI\r\n*3\r\n$3\r\nSET\r\n$1\r\nb\r\n$1\r\n1\r\n");
+        std::fs::write(&path, &data).unwrap();
+        let mut server = Server::new(&config);
+        server.load().unwrap();
+        assert_eq!(std::fs::metadata(&path).unwrap().len(), len as u64);
+        assert!(server.databases.db_mut(0).contains_key(b"a"));
+        assert!(!server.databases.db_mut(0).contains_key(b"b"));
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn should_fail_transactions_when_watched_keys_change() {
+        let mut server = Server::new(&ServerConfig::default());
+        let (mut client, mut other) = (Session::new(), Session::new());
+        let mut run = |command: &[&str], session: &mut Session| match server
+            .execute(&tokens(command), session)
+        {
+            Ok(result) => result.serialise(RespVersion::Resp2),
+            Err(e) => format!("-{}\r\n", e).into_bytes(),
+        };
+        run(&["SET", "counter", "1"], &mut other);
+        run(&["WATCH", "counter", "missing"], &mut client);
+        run(&["GET", "counter"], &mut other);
+        run(&["MULTI"], &mut client);
+        assert_eq!(
+            run(&["WATCH", "counter"], &mut client),
+            b"-ERR WATCH inside MULTI is not allowed\r\n".to_vec()
+        );
+        run(&["INCR", "counter"], &mut client);
+        assert_eq!(run(&["EXEC"], &mut client), b"*1\r\n:2\r\n".to_vec());
+
+        // A change made in place by another client fails the transaction.
+        run(&["WATCH", "counter"], &mut client);
+        run(&["INCR", "counter"], &mut other);
+        run(&["MULTI"], &mut client);
+        run(&["INCR", "counter"], &mut client);
+        assert_eq!(run(&["EXEC"], &mut client), b"*-1\r\n".to_vec());
+        assert_eq!(
+            run(&["GET", "counter"], &mut client),
+            b"$1\r\n3\r\n".to_vec()
+        );
+
+        // So do keys that expire, and keys created after they were watched.
+        run(&["PEXPIRE", "counter", "20"], &mut other);
+        run(&["WATCH", "counter", "missing"], &mut client);
+        std::thread::sleep(std::time::Duration::from_millis(30));
+        run(&["MULTI"], &mut client);
+        assert_eq!(run(&["EXEC"], &mut client), b"*-1\r\n".to_vec());
+        run(&["WATCH", "missing"], &mut client);
+        run(&["SET", "missing", "1"], &mut other);
+        run(&["UNWATCH"], &mut client);
+        run(&["MULTI"], &mut client);
+        assert_eq!(run(&["EXEC"], &mut client), b"*0\r\n".to_vec());
+
+        // A watched key moved away by SWAPDB has changed.
+        run(&["WATCH", "missing"], &mut client);
+        run(&["SWAPDB", "0", "1"], &mut other);
+        run(&["MULTI"], &mut client);
+        assert_eq!(run(&["EXEC"], &mut client), b"*-1\r\n".to_vec());
+    }
+
+    #[test]
+    fn should_only_fail_transactions_when_watched_keys_are_modified() {
+        let mut server = Server::new(&ServerConfig::default());
+        let (mut client, mut other) = (Session::new(), Session::new());
+        let mut run = |command: &[&str], session: &mut Session| match server
+            .execute(&tokens(command), session)
+        {
+            Ok(result) => result.serialise(RespVersion::Resp2),
+            Err(e) => format!("-{}\r\n", e).into_bytes(),
+        };
+        run(&["SET", "k", "v"], &mut other);
+        run(&["SADD", "s", "a"], &mut other);
+        run(&["WATCH", "k", "s", "missing"], &mut client);
+        run(&["SET", "k", "w", "NX"], &mut other);
+        run(&["DEL", "missing"], &mut other);
+        run(&["SREM", "s", "absent"], &mut other);
+        run(&["SADD", "s", "a"], &mut other);
+        run(&["MULTI"], &mut client);
+        run(&["GET", "k"], &mut client);
+        assert_eq!(run(&["EXEC"], &mut client), b"*1\r\n$1\r\nv\r\n".to_vec());
+
+        // Values modified in place are seen as changed, but only when they do change.
+        for (write, changed) in [
+            (&["SADD", "s", "b"][..], true),
+            (&["SREM", "s", "a"], true),
+            (&["LPUSH", "l", "x"], true),
+            (&["RPUSH", "l", "y"], true),
+            (&["LPOP", "l"], true),
+            (&["HSET", "h", "f", "v"], true),
+            (&["HINCRBY", "h", "n", "1"], true),
+            (&["ZADD", "z", "1", "m"], true),
+            (&["ZADD", "z", "2", "m"], true),
+            (&["ZADD", "z", "2", "m"], false),
+            (&["ZREM", "z", "absent"], false),
+            (&["RPOP", "missing"], false),
+        ] {
+            run(&["WATCH", "s", "l", "h", "z", "missing"], &mut client);
+            run(write, &mut other);
+            run(&["MULTI"], &mut client);
+            let expected = match changed {
+                true => b"*-1\r\n".to_vec(),
+                false => b"*0\r\n".to_vec(),
+            };
+            assert_eq!(run(&["EXEC"], &mut client), expected, "{:?}", write);
+        }
+    }
+}
@@ -0,0 +1,78 @@
+use crate::command::Migration;
+use crate::execution_result::RespVersion;
+use crate::pubsub::Subscriber;
+use crate::replication::ReplicaFeed;
+use std::sync::atomic::{AtomicU64, Ordering};
+
+static NEXT_SESSION_ID: AtomicU64 = AtomicU64::new(1);
+
+/// The commands a client queued after `MULTI`, to be run together by `EXEC`.
+#[derive(Debug, Default)]
+pub struct Transaction {
+    pub queue: Vec<Vec<Vec<u8>>>,
+    /// Whether a command was rejected while queueing, in which case `EXEC` runs nothing.
+    pub aborted: bool,
+}
+
+/// The state of a single client connection, which lives as long as the connection does.
+#[derive(Debug)]
+pub struct Session {
+    pub id: u64,
+    pub name: Option<Vec<u8>>,
+    pub protocol: RespVersion,
+    /// The index of the database selected with `SELECT`.
+    pub db: usize,
+    /// The address of the client, if it is connected over TCP.
+    pub addr: Option<String>,
+    /// Whether the session applies writes that were already accepted elsewhere, i.e. the
+    /// replication stream of our master or the append-only file, which bypasses the restrictions
+    /// on replicas and the cluster redirections.
+    pub is_master: bool,
+    /// The port a replica announced with `REPLCONF listening-port`.
+    pub replica_port: Option<u16>,
+    /// The replication stream for a replica, set once it sent `PSYNC`. The connection then
+    /// forwards the stream instead of serving requests.
+    pub replica_feed: Option<ReplicaFeed>,
+    /// Whether the client sent `ASKING`, which lets the next command use a slot that is being
+    /// imported to this node.
+    pub asking: bool,
+    /// The transaction started with `MULTI`, if any.
+    pub transaction: Option<Transaction>,
+    /// The keys watched with `WATCH`, as their database, name and version when the watch
+    /// started.
+    pub watched: Vec<(usize, Vec<u8>, u64)>,
+    /// The channels the client subscribed to, if any, in which case the connection is in
+    /// subscriber mode.
+    pub subscriber: Option<Subscriber>,
+    /// Whether the client sent `QUIT`, after which the connection is closed once it replied.
+    pub closing: bool,
+    /// The keys of a `MIGRATE`, which the connection sends once it released the server.
+    pub migration: Option<Migration>,
+}
+
+impl Default for Session {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Session {
+    pub fn new() -> Self {
+        Session {
+            id: NEXT_SESSION_ID.fetch_add(1, Ordering::Relaxed),
+            name: None,
+            protocol: RespVersion::default(),
+            db: 0,
+            addr: None,
+            is_master: false,
+            replica_port: None,
+            replica_feed: None,
+            asking: false,
+            transaction: None,
+            watched: Vec::new(),
+            subscriber: None,
+            closing: false,
+            migration: None,
+        }
+    }
+}
@@ -0,0 +1,347 @@
+use crate::error::RequestError;
+use regex::bytes::Regex;
+
+/// The largest argument a client may send, as Redis' default `proto-max-bulk-len`.
+const MAX_BULK_LEN: usize = 512 * 1024 * 1024;
+/// The most arguments a single request may have.
+const MAX_MULTIBULK_LEN: usize = 1024 * 1024;
+/// The longest inline request, which has no length telling how much is left to receive.
+const MAX_INLINE_LEN: usize = 64 * 1024;
+
+/// Incrementally decodes RESP requests from the bytes received on a single connection.
+///
+/// Bytes are appended to the internal buffer as they arrive and `decode` hands out one complete
+/// request at a time. Any bytes that belong to a request that has not been fully received yet stay
+/// in the buffer, so clients are free to pipeline as many commands as they like in one write.
+///
+/// Requests that do not start with `*` are read as inline commands, i.e. a single line of
+/// space-separated arguments as typed into `telnet` or `nc`.
+///
+/// Lengths and argument counts are capped, so that a client cannot make the server buffer an
+/// unbounded amount of data.
+pub struct RequestDecoder {
+    buffer: Vec<u8>,
+    array_regex: Regex,
+    bulk_string_regex: Regex,
+}
+
+impl Default for RequestDecoder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl RequestDecoder {
+    pub fn new() -> Self {
+        RequestDecoder {
+            buffer: Vec::new(),
+            array_regex: Regex::new(r"^\*(\d+)\r\n$").unwrap(),
+            bulk_string_regex: Regex::new(r"^\$(\d+)\r\n$").unwrap(),
+        }
+    }
+
+    pub fn buffer_mut(&mut self) -> &mut Vec<u8> {
+        &mut self.buffer
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.buffer.is_empty()
+    }
+
+    /// Returns the next complete request in the buffer, or `None` if more bytes are needed.
+    pub fn decode(&mut self) -> Result<Option<Vec<Vec<u8>>>, RequestError> {
+        let mut pos = 0;
+        if self.buffer.first().is_some_and(|b| *b != b'*') {
+            return self.decode_inline();
+        }
+        let Some(length_line) = self.read_line(&mut pos) else {
+            if self.buffer.len() > MAX_INLINE_LEN {
+                return Err(RequestError::InvalidMultibulkLength);
+            }
+            return Ok(None);
+        };
+        let token_count = match self.array_regex.captures(length_line) {
+            Some(cap) => match String::from_utf8_lossy(&cap[1]).parse::<usize>() {
+                Ok(v) if v <= MAX_MULTIBULK_LEN => v,
+                _ => return Err(RequestError::InvalidMultibulkLength),
+            },
+            None => {
+                return Err(RequestError::ParseRequestFailed(
+                    "parse token count".to_string(),
+                    "none".to_string(),
+                ));
+            }
+        };
+
+        let mut tokens: Vec<Vec<u8>> = vec![];
+        for i in 0..token_count {
+            let Some(length_line) = self.read_line(&mut pos) else {
+                if self.buffer.len() - pos > MAX_INLINE_LEN {
+                    return Err(RequestError::InvalidBulkLength);
+                }
+                return Ok(None);
+            };
+            let token_length = match self.bulk_string_regex.captures(length_line) {
+                Some(cap) => match String::from_utf8_lossy(&cap[1]).parse::<usize>() {
+                    Ok(v) if v <= MAX_BULK_LEN => v,
+                    _ => return Err(RequestError::InvalidBulkLength),
+                },
+                None => {
+                    return Err(RequestError::ParseRequestFailed(
+                        format!("parse size of token {}", i).to_string(),
+                        "none".to_string(),
+                    ))
+                }
+            };
+
+            // The body is read by its declared length rather than up to the next line break so
+            // that it may contain arbitrary bytes, including CR and LF.
+            let Some(end) = pos.checked_add(token_length + 2) else {
+                return Err(RequestError::InvalidBulkLength);
+            };
+            if self.buffer.len() < end {
+                return Ok(None);
+            }
+            if &self.buffer[end - 2..end] != b"\r\n" {
+                return Err(RequestError::ParseRequestFailed(
+                    format!("extract body of token {}", i).to_string(),
+                    "none".to_string(),
+                ));
+            }
+            tokens.push(self.buffer[pos..end - 2].to_vec());
+            pos = end;
+        }
+        self.buffer.drain(..pos);
+        Ok(Some(tokens))
+    }
+
+    fn decode_inline(&mut self) -> Result<Option<Vec<Vec<u8>>>, RequestError> {
+        let mut pos = 0;
+        let Some(line) = self.read_line(&mut pos) else {
+            if self.buffer.len() > MAX_INLINE_LEN {
+                return Err(RequestError::InlineRequestTooBig);
+            }
+            return Ok(None);
+        };
+        let line = line.strip_suffix(b"\n").unwrap_or(line);
+        let line = line.strip_suffix(b"\r").unwrap_or(line);
+        let tokens = split_args(line)?;
+        self.buffer.drain(..pos);
+        Ok(Some(tokens))
+    }
+
+    fn read_line(&self, pos: &mut usize) -> Option<&[u8]> {
+        let start = *pos;
+        let offset = self.buffer[start..].iter().position(|b| *b == b'\n')?;
+        *pos = start + offset + 1;
+        Some(&self.buffer[start..*pos])
+    }
+}
+
+/// Splits a line such as an inline command into its arguments. Arguments are separated by
+/// whitespace and may be wrapped in double quotes, which support the usual backslash escapes
+/// including `\xHH`, or in single quotes, where only `\'` is unescaped.
+pub fn split_args(line: &[u8]) -> Result<Vec<Vec<u8>>, RequestError> {
+    let unbalanced = || {
+        RequestError::ParseRequestFailed(
+            "split arguments".to_string(),
+            "unbalanced quotes in request".to_string(),
+        )
+    };
+    let mut tokens = Vec::new();
+    let mut i = 0;
+    loop {
+        while i < line.len() && line[i].is_ascii_whitespace() {
+            i += 1;
+        }
+        if i == line.len() {
+            return Ok(tokens);
+        }
+        let mut token = Vec::new();
+        let quote = match line[i] {
+            b'"' | b'\'' => {
+                i += 1;
+                Some(line[i - 1])
+            }
+            _ => None,
+        };
+        loop {
+            match (quote, line.get(i)) {
+                (None, None) => break,
+                (None, Some(c)) if c.is_ascii_whitespace() => break,
+                (None, Some(c)) => token.push(*c),
+                (Some(_), None) => return Err(unbalanced()),
+                (Some(q), Some(c)) if *c == q => {
+                    // A closing quote must be followed by a separator.
+                    if line.get(i + 1).is_some_and(|c| !c.is_ascii_whitespace()) {
+                        return Err(unbalanced());
+                    }
+                    i += 1;
+                    break;
+                }
+                (Some(b'"'), Some(b'\\')) if i + 1 < line.len() => {
+                    i += 1;
+                    let hex = line
+                        .get(i + 1..i + 3)
+                        .and_then(|h| u8::from_str_radix(std::str::from_utf8(h).ok()?, 16).ok());
+                    match (line[i], hex) {
+                        (b'x', Some(v)) => {
+                            token.push(v);
+                            i += 2;
+                        }
+                        (b'n', _) => token.push(b'\n'),
+                        (b'r', _) => token.push(b'\r'),
+                        (b't', _) => token.push(b'\t'),
+                        (b'b', _) => token.push(0x08),
+                        (b'a', _) => token.push(0x07),
+                        (c, _) => token.push(c),
+                    }
+                }
+                (Some(b'\''), Some(b'\\')) if line.get(i + 1) == Some(&b'\'') => {
+                    i += 1;
+                    token.push(b'\'');
+                }
+                (Some(_), Some(c)) => token.push(*c),
+            }
+            i += 1;
+        }
+        tokens.push(token);
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::RequestDecoder;
+
+    fn decoder_with(bytes: &[u8]) -> RequestDecoder {
+        let mut decoder = RequestDecoder::new();
+        decoder.buffer_mut().extend_from_slice(bytes);
+        decoder
+    }
+
+    #[test]
+    fn should_decode_single_request() {
+        let mut decoder = decoder_with(b"*2\r\n$3\r\nGET\r\n$3\r\nfoo\r\n");
+        let tokens = decoder.decode().unwrap().unwrap();
+        assert_eq!(tokens, vec![b"GET".to_vec(), b"foo".to_vec()]);
+        assert!(decoder.is_empty());
+        assert!(decoder.decode().unwrap().is_none());
+    }
+
+    #[test]
+    fn should_decode_pipelined_requests_in_order() {
+        let mut decoder = decoder_with(
+            b"*1\r\n$4\r\nPING\r\n*2\r\n$3\r\nGET\r\n$1\r\na\r\n*2\r\n$3\r\nGET\r\n$1\r\nb\r\n",
+        );
+        assert_eq!(decoder.decode().unwrap().unwrap(), vec![b"PING".to_vec()]);
+        assert_eq!(
+            decoder.decode().unwrap().unwrap(),
+            vec![b"GET".to_vec(), b"a".to_vec()]
+        );
+        assert_eq!(
+            decoder.decode().unwrap().unwrap(),
+            vec![b"GET".to_vec(), b"b".to_vec()]
+        );
+        assert!(decoder.decode().unwrap().is_none());
+    }
+
+    #[test]
+    fn should_keep_partial_request_buffered() {
+        let mut decoder = decoder_with(b"*1\r\n$4\r\nPING\r\n*2\r\n$3\r\nGET\r\n$3\r\nfo");
+        assert_eq!(decoder.decode().unwrap().unwrap(), vec![b"PING".to_vec()]);
+        assert!(decoder.decode().unwrap().is_none());
+        assert!(!decoder.is_empty());
+        decoder.buffer_mut().extend_from_slice(b"o\r\n");
+        assert_eq!(
+            decoder.decode().unwrap().unwrap(),
+            vec![b"GET".to_vec(), b"foo".to_vec()]
+        );
+        assert!(decoder.is_empty());
+    }
+
+    #[test]
+    fn should_read_body_by_length() {
+        let mut decoder = decoder_with(b"*1\r\n$4\r\na\r\nb\r\n");
+        assert_eq!(decoder.decode().unwrap().unwrap(), vec![b"a\r\nb".to_vec()]);
+    }
+
+    #[test]
+    fn should_reject_malformed_request() {
+        let mut decoder = decoder_with(b"*1\r\n$1\r\nab\r\n");
+        assert!(decoder.decode().is_err());
+        let mut decoder = decoder_with(b"*x\r\n");
+        assert!(decoder.decode().is_err());
+    }
+
+    #[test]
+    fn should_reject_oversized_lengths() {
+        let mut decoder = decoder_with(b"*1\r\n$18446744073709551615\r\n");
+        assert_eq!(
+            decoder.decode().err().unwrap().to_string(),
+            "ERR Protocol error: invalid bulk length".to_string()
+        );
+        let mut decoder = decoder_with(b"*1\r\n$4000000000\r\n");
+        assert!(decoder.decode().is_err());
+        let mut decoder = decoder_with(b"*1\r\n$536870912\r\n");
+        assert!(decoder.decode().unwrap().is_none());
+
+        let mut decoder = decoder_with(b"*18446744073709551616\r\n");
+        assert_eq!(
+            decoder.decode().err().unwrap().to_string(),
+            "ERR Protocol error: invalid multibulk length".to_string()
+        );
+        let mut decoder = decoder_with(b"*1048577\r\n");
+        assert!(decoder.decode().is_err());
+        // A length line that never ends is not buffered forever either.
+        let mut decoder =
+            decoder_with(&[b"*1\r\n$".as_slice(), &vec![b'1'; 64 * 1024 + 1]].concat());
+        assert!(decoder.decode().is_err());
+
+        let mut decoder = decoder_with(&vec![b'a'; 64 * 1024 + 1]);
+        assert_eq!(
+            decoder.decode().err().unwrap().to_string(),
+            "ERR Protocol error: too big inline request".to_string()
+        );
+    }
+
+    #[test]
+    fn should_decode_inline_requests() {
+        let mut decoder = decoder_with(b"PING\r\nset  foo \"a b\"\nGET");
+        assert_eq!(decoder.decode().unwrap().unwrap(), vec![b"PING".to_vec()]);
+        assert_eq!(
+            decoder.decode().unwrap().unwrap(),
+            vec![b"set".to_vec(), b"foo".to_vec(), b"a b".to_vec()]
+        );
+        assert!(decoder.decode().unwrap().is_none());
+        decoder.buffer_mut().extend_from_slice(b" foo\r\n");
+        assert_eq!(
+            decoder.decode().unwrap().unwrap(),
+            vec![b"GET".to_vec(), b"foo".to_vec()]
+        );
+        assert!(decoder.is_empty());
+    }
+
+    #[test]
+    fn should_unescape_quoted_inline_arguments() {
+        let mut decoder = decoder_with(b"SET \"a\\r\\n\\x00\\\"\" 'it\\'s' \"\"\r\n");
+        assert_eq!(
+            decoder.decode().unwrap().unwrap(),
+            vec![
+                b"SET".to_vec(),
+                b"a\r\n\x00\"".to_vec(),
+                b"it's".to_vec(),
+                b"".to_vec()
+            ]
+        );
+        let mut decoder = decoder_with(b"\r\n");
+        assert!(decoder.decode().unwrap().unwrap().is_empty());
+    }
+
+    #[test]
+    fn should_reject_unbalanced_quotes() {
+        let mut decoder = decoder_with(b"SET \"foo bar\r\n");
+        assert!(decoder.decode().is_err());
+        let mut decoder = decoder_with(b"SET 'foo'bar\r\n");
+        assert!(decoder.decode().is_err());
+    }
+}
@@ -0,0 +1,124 @@
+/// Matches `string` against a glob-style `pattern` the way Redis does:
+///
+/// - `*` matches any number of bytes, including none.
+/// - `?` matches a single byte.
+/// - `[abc]`, `[a-z]` and `[^a]` match a single byte in, or with `^` not in, the class.
+/// - `\` escapes the next byte, both inside and outside of a class.
+///
+/// Matching is done on bytes, so keys do not need to be valid UTF-8.
+pub fn glob_match(pattern: &[u8], string: &[u8]) -> bool {
+    let (mut p, mut s) = (0, 0);
+    // Where to resume from when the bytes after the last `*` fail to match: the pattern position
+    // after the `*` and the string position that `*` last stopped at.
+    let mut backtrack: Option<(usize, usize)> = None;
+    while s < string.len() {
+        if p < pattern.len() {
+            if pattern[p] == b'*' {
+                while p < pattern.len() && pattern[p] == b'*' {
+                    p += 1;
+                }
+                if p == pattern.len() {
+                    return true;
+                }
+                backtrack = Some((p, s));
+                continue;
+            }
+            if let Some(next) = match_single(pattern, p, string[s]) {
+                p = next;
+                s += 1;
+                continue;
+            }
+        }
+        match backtrack {
+            Some((bp, bs)) => {
+                // Let the `*` consume one more byte and try again.
+                backtrack = Some((bp, bs + 1));
+                p = bp;
+                s = bs + 1;
+            }
+            None => return false,
+        }
+    }
+    pattern[p..].iter().all(|c| *c == b'*')
+}
+
+/// Matches `c` against the pattern token at `p`, which is anything but `*`. Returns the position
+/// of the next token if it matches.
+fn match_single(pattern: &[u8], p: usize, c: u8) -> Option<usize> {
+    match pattern[p] {
+        b'?' => Some(p + 1),
+        b'\\' if p + 1 < pattern.len() => (pattern[p + 1] == c).then_some(p + 2),
+        b'[' => match_class(pattern, p + 1, c),
+        v => (v == c).then_some(p + 1),
+    }
+}
+
+/// Matches `c` against the class starting at `p`, just after the `[`. An unterminated class ends
+/// with the pattern.
+fn match_class(pattern: &[u8], mut p: usize, c: u8) -> Option<usize> {
+    let negate = pattern.get(p) == Some(&b'^');
+    if negate {
+        p += 1;
+    }
+    let mut matched = false;
+    while p < pattern.len() && pattern[p] != b']' {
+        if pattern[p] == b'\\' && p + 1 < pattern.len() {
+            p += 1;
+            matched |= pattern[p] == c;
+        } else if p + 2 < pattern.len() && pattern[p + 1] == b'-' {
+            let (start, end) = match pattern[p] <= pattern[p + 2] {
+                true => (pattern[p], pattern[p + 2]),
+                false => (pattern[p + 2], pattern[p]),
+            };
+            matched |= start <= c && c <= end;
+            p += 2;
+        } else {
+            matched |= pattern[p] == c;
+        }
+        p += 1;
+    }
+    (matched != negate).then_some((p + 1).min(pattern.len()))
+}
+
+#[cfg(test)]
+mod test {
+    use super::glob_match;
+
+    #[test]
+    fn should_match_wildcards() {
+        assert!(glob_match(b"*", b""));
+        assert!(glob_match(b"*", b"anything"));
+        assert!(glob_match(b"h?llo", b"hello"));
+        assert!(!glob_match(b"h?llo", b"hllo"));
+        assert!(glob_match(b"h*llo", b"hllo"));
+        assert!(glob_match(b"h*llo", b"heeeello"));
+        assert!(glob_match(b"*a*b*", b"xxaxxbxx"));
+        assert!(!glob_match(b"*a*b", b"xxaxxbxx"));
+        assert!(glob_match(b"user:*:name", b"user:1:2:name"));
+        assert!(!glob_match(b"foo", b"foobar"));
+        assert!(!glob_match(b"foobar", b"foo"));
+    }
+
+    #[test]
+    fn should_match_classes() {
+        assert!(glob_match(b"h[ae]llo", b"hello"));
+        assert!(glob_match(b"h[ae]llo", b"hallo"));
+        assert!(!glob_match(b"h[ae]llo", b"hillo"));
+        assert!(glob_match(b"h[^e]llo", b"hallo"));
+        assert!(!glob_match(b"h[^e]llo", b"hello"));
+        assert!(glob_match(b"h[a-b]llo", b"hbllo"));
+        assert!(glob_match(b"h[b-a]llo", b"hallo"));
+        assert!(!glob_match(b"h[a-b]llo", b"hcllo"));
+        assert!(glob_match(b"[\\]]", b"]"));
+        assert!(glob_match(b"a[bc", b"ac"));
+    }
+
+    #[test]
+    fn should_match_escaped_bytes() {
+        assert!(glob_match(b"\\*", b"*"));
+        assert!(!glob_match(b"\\*", b"a"));
+        assert!(glob_match(b"a\\?", b"a?"));
+        assert!(!glob_match(b"a\\?", b"ab"));
+        assert!(glob_match(b"\xff*", b"\xff\x00"));
+    }
+}
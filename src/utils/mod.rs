@@ -0,0 +1,505 @@
+mod decoder;
+pub use decoder::{split_args, RequestDecoder};
+mod glob;
+pub use glob::glob_match;
+mod sha1;
+pub use sha1::sha1_hex;
+
+use crate::command::is_script_command;
+use crate::execution_result::{ExecutionResult, RespVersion};
+
+use super::error::RequestError;
+use super::execution_result::ErrorResult;
+use crate::replication::{encode_snapshot, ReplicaFeed, ReplicaStream};
+use crate::scripting::ScriptMonitor;
+use crate::server::Server;
+use crate::session::Session;
+use log;
+use rand::Rng;
+use std::error::Error;
+use std::sync::{Arc, Mutex, MutexGuard, TryLockError};
+use std::time::Duration;
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt};
+use tokio::runtime::{Handle, RuntimeFlavor};
+
+/// How often a task waiting for a script to finish checks whether it did.
+const SCRIPT_POLL_INTERVAL: Duration = Duration::from_millis(1);
+
+/// Builds the tokens of a request from its arguments, for tests.
+#[cfg(test)]
+pub fn tokens(values: &[&str]) -> Vec<Vec<u8>> {
+    values.iter().map(|v| v.as_bytes().to_vec()).collect()
+}
+
+/// Returns a random ID of 40 hex digits, such as a replication ID or a cluster node ID.
+pub fn random_id() -> String {
+    let mut rng = rand::thread_rng();
+    (0..40)
+        .map(|_| char::from_digit(rng.gen_range(0..16), 16).unwrap())
+        .collect()
+}
+
+pub async fn handle_error<W: AsyncWrite + Unpin>(tx: &mut W, error_message: String) {
+    log::error!("Error: {}", &error_message);
+    let err = ErrorResult {
+        message: error_message,
+    };
+    // If the error message cannot be written to the stream somehow, just let it go. Error replies
+    // are encoded the same way in every protocol version.
+    let _ = tx.write_all(&err.serialise(RespVersion::default())).await;
+}
+
+/// Serves the requests of a single client until it disconnects. The connection can be of any
+/// kind, e.g. TCP or a Unix domain socket, in which case there is no `addr`. The keys the client
+/// watched and the channels it subscribed to are released once it disconnects.
+pub async fn handle_connection<R: AsyncRead + Unpin, W: AsyncWrite + Unpin>(
+    rx: R,
+    tx: &mut W,
+    server: Arc<Mutex<Server>>,
+    monitor: Arc<ScriptMonitor>,
+    addr: Option<String>,
+) -> Result<(), String> {
+    let mut session = Session::new();
+    session.addr = addr;
+    let result = serve_requests(rx, tx, &server, &monitor, &mut session).await;
+    let mut server = lock_server(&server, &monitor).await;
+    server.unwatch(&mut session);
+    if let Some(subscriber) = session.subscriber.take() {
+        server.pubsub.remove(session.id, &subscriber);
+    }
+    result
+}
+
+async fn serve_requests<R: AsyncRead + Unpin, W: AsyncWrite + Unpin>(
+    mut rx: R,
+    tx: &mut W,
+    server: &Mutex<Server>,
+    monitor: &ScriptMonitor,
+    session: &mut Session,
+) -> Result<(), String> {
+    let mut decoder = RequestDecoder::new();
+    loop {
+        // Execute every complete request that is already buffered and send the replies back in
+        // one batch, in the order the requests were received.
+        let mut replies = Vec::new();
+        loop {
+            let tokens = match decoder.decode() {
+                Ok(Some(tokens)) => tokens,
+                Ok(None) => break,
+                // The stream can no longer be trusted to be aligned on request boundaries, so
+                // reply to what has been executed so far and drop the connection.
+                Err(e) => {
+                    let _ = tx.write_all(&replies).await;
+                    return Err(e.to_string());
+                }
+            };
+            if tokens.is_empty() {
+                continue;
+            }
+            log::info!(
+                "tokens: {:?}",
+                tokens
+                    .iter()
+                    .map(|t| String::from_utf8_lossy(t))
+                    .collect::<Vec<_>>()
+            );
+            let msg = serve_request(&tokens, server, monitor, session).await;
+            log::info!("response: {}", String::from_utf8_lossy(&msg));
+            replies.extend(msg);
+            // Requests sent after `QUIT` are dropped.
+            if session.closing {
+                break;
+            }
+        }
+        if !replies.is_empty() {
+            if let Err(e) = tx.write_all(&replies).await {
+                return Err(e.to_string());
+            }
+        }
+        if session.closing {
+            break;
+        }
+        // After `PSYNC`, the connection carries the replication stream to the replica.
+        if let Some(feed) = session.replica_feed.take() {
+            let result = serve_replica(rx, tx, decoder, feed, server, monitor, session).await;
+            lock_server(server, monitor)
+                .await
+                .replication
+                .remove_replica(session.id);
+            log::info!("Replica disconnected");
+            return result;
+        }
+
+        let protocol = session.protocol;
+        let read = match &mut session.subscriber {
+            // A subscriber is pushed the messages published to its channels while it waits for
+            // requests. The subscriber holds a sender itself, so its messages never run out. A
+            // subscriber that fell too far behind on its messages is disconnected, like Redis does.
+            Some(subscriber) => tokio::select! {
+                message = subscriber.recv() => {
+                    let Some(message) = message else {
+                        log::warn!("Subscriber disconnected for exceeding the output buffer limit");
+                        return Ok(());
+                    };
+                    let mut data = message.serialise(protocol);
+                    while let Some(message) = subscriber.try_recv() {
+                        data.extend(message.serialise(protocol));
+                    }
+                    tokio::select! {
+                        written = tx.write_all(&data) => written.map_err(|e| e.to_string())?,
+                        _ = subscriber.overflow() => {
+                            log::warn!(
+                                "Subscriber disconnected for exceeding the output buffer limit"
+                            );
+                            return Ok(());
+                        }
+                    }
+                    continue;
+                }
+                read = rx.read_buf(decoder.buffer_mut()) => read,
+            },
+            None => rx.read_buf(decoder.buffer_mut()).await,
+        };
+        match read {
+            // No bytes read from the stream; EOF is received: this connection is closed, which
+            // means no more command from this client so we can gracefully return.
+            Ok(0) => break,
+            Ok(_) => (),
+            Err(e) => {
+                return Err(RequestError::ParseRequestFailed(
+                    "read request".to_string(),
+                    e.to_string(),
+                )
+                .to_string())
+            }
+        };
+    }
+    log::info!("Connection dropped");
+    Ok(())
+}
+
+/// Forwards the replication stream to a replica, while applying the acknowledgements it sends
+/// back, until either side closes the connection.
+async fn serve_replica<R: AsyncRead + Unpin, W: AsyncWrite + Unpin>(
+    mut rx: R,
+    tx: &mut W,
+    mut decoder: RequestDecoder,
+    feed: ReplicaFeed,
+    server: &Mutex<Server>,
+    monitor: &ScriptMonitor,
+    session: &mut Session,
+) -> Result<(), String> {
+    // The keys of a full resynchronization are serialised on a thread of their own, and the
+    // writes made meanwhile are sent once they are.
+    if let Some(snapshot) = feed.snapshot {
+        let data = tokio::task::spawn_blocking(move || encode_snapshot(&snapshot))
+            .await
+            .unwrap()
+            .map_err(|e| e.to_string())?;
+        write_stream(tx, &data, &feed.stream).await?;
+    }
+    let mut feed = feed.stream;
+    loop {
+        while let Some(tokens) = decoder.decode().map_err(|e| e.to_string())? {
+            if !tokens.is_empty() {
+                // Replicas only send acknowledgements, which are not answered.
+                let _ = lock_server(server, monitor).await.execute(&tokens, session);
+            }
+        }
+        tokio::select! {
+            data = feed.recv() => match data {
+                Some(data) => write_stream(tx, &data, &feed).await?,
+                // The replica was dropped, e.g. because it fell behind or this server became a
+                // replica itself.
+                None => return Ok(()),
+            },
+            read = rx.read_buf(decoder.buffer_mut()) => match read {
+                Ok(0) => return Ok(()),
+                Ok(_) => (),
+                Err(e) => return Err(e.to_string()),
+            },
+        }
+    }
+}
+
+/// Writes `data` to a replica, unless it is dropped meanwhile, after which the stream has nothing
+/// more to send.
+async fn write_stream<W: AsyncWrite + Unpin>(
+    tx: &mut W,
+    data: &[u8],
+    stream: &ReplicaStream,
+) -> Result<(), String> {
+    tokio::select! {
+        written = tx.write_all(data) => written.map_err(|e| e.to_string()),
+        _ = stream.dropped() => Ok(()),
+    }
+}
+
+/// Locks the server from an asynchronous task. While a script runs, the task waits for it without
+/// holding up its thread, which the other clients need to be told that the server is busy or to
+/// kill the script.
+pub async fn lock_server<'a>(
+    server: &'a Mutex<Server>,
+    monitor: &ScriptMonitor,
+) -> MutexGuard<'a, Server> {
+    loop {
+        // The result of `try_lock` must be dropped before waiting, as the task may move to another
+        // thread meanwhile.
+        let waiting = match server.try_lock() {
+            Ok(server) => return server,
+            Err(TryLockError::WouldBlock) => monitor.is_running(),
+            Err(TryLockError::Poisoned(_)) => false,
+        };
+        // The server may be taken by a script right after, so the thread is handed over to the
+        // other tasks while waiting.
+        if !waiting {
+            return run_blocking(|| server.lock().unwrap());
+        }
+        tokio::time::sleep(SCRIPT_POLL_INTERVAL).await;
+    }
+}
+
+/// Executes a request of a client connection. A request received while a script of another
+/// client runs waits for it, unless the script runs for longer than the time limit, in which case
+/// the request is answered right away, which is how `SCRIPT KILL` stops the script.
+async fn serve_request(
+    tokens: &[Vec<u8>],
+    server: &Mutex<Server>,
+    monitor: &ScriptMonitor,
+    session: &mut Session,
+) -> Vec<u8> {
+    let result = loop {
+        let waiting = match server.try_lock() {
+            Ok(mut server) if is_script_command(&tokens[0]) => {
+                break run_blocking(|| server.execute(tokens, session))
+            }
+            Ok(mut server) => break server.execute(tokens, session),
+            Err(TryLockError::WouldBlock) if monitor.is_busy() => break monitor.interrupt(tokens),
+            Err(TryLockError::WouldBlock) => monitor.is_running(),
+            Err(TryLockError::Poisoned(_)) => false,
+        };
+        if !waiting {
+            break run_blocking(|| server.lock().unwrap()).execute(tokens, session);
+        }
+        tokio::time::sleep(SCRIPT_POLL_INTERVAL).await;
+    };
+    // A `MIGRATE` sends its keys on a thread of its own while the server is released, and then
+    // deletes them once it holds the server again. Its reply is the one it finishes with.
+    let Some(migration) = session.migration.take() else {
+        return serialise_result(result, session);
+    };
+    drop(result);
+    let (migration, transfer) = tokio::task::spawn_blocking(move || {
+        let transfer = migration.transfer();
+        (migration, transfer)
+    })
+    .await
+    .unwrap();
+    let result = migration.finish(&mut *lock_server(server, monitor).await, transfer);
+    serialise_result(result, session)
+}
+
+/// Runs `f` on the current thread, letting the other tasks of the runtime move to another thread
+/// meanwhile, as `f` may run a script for as long as it takes.
+fn run_blocking<T>(f: impl FnOnce() -> T) -> T {
+    match Handle::current().runtime_flavor() {
+        RuntimeFlavor::MultiThread => tokio::task::block_in_place(f),
+        _ => f(),
+    }
+}
+
+/// Serialises the result of a request. Errors raised while building or executing the command are
+/// turned into error replies so that the connection can keep serving requests.
+fn serialise_result(
+    result: Result<Box<dyn ExecutionResult>, Box<dyn Error>>,
+    session: &Session,
+) -> Vec<u8> {
+    // The reply is encoded after executing the command so that `HELLO` already answers in the
+    // protocol version it switched to.
+    match result {
+        Ok(res) => res.serialise(session.protocol),
+        Err(e) => {
+            log::error!("Error: {}", e);
+            ErrorResult {
+                message: e.to_string(),
+            }
+            .serialise(session.protocol)
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{handle_connection, serialise_result, tokens};
+    use crate::config::ServerConfig;
+    use crate::persistence::aof::encode_command;
+    use crate::scripting::ScriptMonitor;
+    use crate::server::Server;
+    use crate::session::Session;
+    use std::sync::{Arc, Mutex};
+    use std::time::Duration;
+    use tokio::io::{AsyncReadExt, AsyncWriteExt, DuplexStream};
+
+    /// Executes a single request and serialises its reply.
+    fn execute_request(
+        tokens: &[Vec<u8>],
+        server: &Mutex<Server>,
+        session: &mut Session,
+    ) -> Vec<u8> {
+        let result = server.lock().unwrap().execute(tokens, session);
+        serialise_result(result, session)
+    }
+
+    /// Opens a client connection to `server`, returning the end of the client.
+    fn connect(server: &Arc<Mutex<Server>>, monitor: &Arc<ScriptMonitor>) -> DuplexStream {
+        let (client, connection) = tokio::io::duplex(1024);
+        let (server, monitor) = (server.clone(), monitor.clone());
+        tokio::spawn(async move {
+            let (rx, mut tx) = tokio::io::split(connection);
+            let _ = handle_connection(rx, &mut tx, server, monitor, None).await;
+        });
+        client
+    }
+
+    async fn request(client: &mut DuplexStream, args: &[&str]) -> Vec<u8> {
+        client
+            .write_all(&encode_command(&tokens(args)))
+            .await
+            .unwrap();
+        let mut reply = Vec::new();
+        while !reply.ends_with(b"\r\n") {
+            client.read_buf(&mut reply).await.unwrap();
+        }
+        reply
+    }
+
+    #[test]
+    fn should_reply_with_error_for_unknown_command() {
+        let ds = Mutex::new(Server::new(&ServerConfig::default()));
+        let mut session = Session::new();
+        let reply = execute_request(&tokens(&["GETT", "foo"]), &ds, &mut session);
+        assert_eq!(reply, b"-ERR unsupported command `gett`\r\n".to_vec());
+        // A name that spans lines must not be read as several replies.
+        let reply = execute_request(&tokens(&["GET\r\n:1", "foo"]), &ds, &mut session);
+        assert_eq!(reply, b"-ERR unsupported command `get  :1`\r\n".to_vec());
+    }
+
+    #[test]
+    fn should_reply_with_error_for_invalid_arguments() {
+        let ds = Mutex::new(Server::new(&ServerConfig::default()));
+        let mut session = Session::new();
+        let reply = execute_request(&tokens(&["GET"]), &ds, &mut session);
+        assert_eq!(
+            reply,
+            b"-ERR wrong number of arguments for command\r\n".to_vec()
+        );
+        let reply = execute_request(&tokens(&["LPOP", "foo", "bar"]), &ds, &mut session);
+        assert_eq!(
+            reply,
+            b"-ERR value is out of range, must be positive\r\n".to_vec()
+        );
+    }
+
+    #[test]
+    fn should_reply_with_wrongtype_error() {
+        let ds = Mutex::new(Server::new(&ServerConfig::default()));
+        let mut session = Session::new();
+        execute_request(&tokens(&["SET", "foo", "bar"]), &ds, &mut session);
+        let reply = execute_request(&tokens(&["LPUSH", "foo", "bar"]), &ds, &mut session);
+        assert_eq!(
+            reply,
+            b"-WRONGTYPE Operation against a key holding the wrong kind of value\r\n".to_vec()
+        );
+        let reply = execute_request(&tokens(&["GET", "foo"]), &ds, &mut session);
+        assert_eq!(reply, b"$3\r\nbar\r\n".to_vec());
+    }
+
+    #[test]
+    fn should_reply_in_negotiated_protocol() {
+        let ds = Mutex::new(Server::new(&ServerConfig::default()));
+        let mut session = Session::new();
+        execute_request(&tokens(&["HSET", "h", "f", "v"]), &ds, &mut session);
+        let reply = execute_request(&tokens(&["HGETALL", "h"]), &ds, &mut session);
+        assert_eq!(reply, b"*2\r\n$1\r\nf\r\n$1\r\nv\r\n".to_vec());
+        let reply = execute_request(&tokens(&["GET", "missing"]), &ds, &mut session);
+        assert_eq!(reply, b"$-1\r\n".to_vec());
+
+        let reply = execute_request(&tokens(&["HELLO", "3"]), &ds, &mut session);
+        assert!(reply.starts_with(b"%7\r\n$6\r\nserver\r\n$5\r\nredis\r\n"));
+        let reply = execute_request(&tokens(&["HGETALL", "h"]), &ds, &mut session);
+        assert_eq!(reply, b"%1\r\n$1\r\nf\r\n$1\r\nv\r\n".to_vec());
+        let reply = execute_request(&tokens(&["GET", "missing"]), &ds, &mut session);
+        assert_eq!(reply, b"_\r\n".to_vec());
+    }
+
+    #[test]
+    fn should_run_commands_against_selected_database() {
+        let ds = Mutex::new(Server::new(&ServerConfig::default()));
+        let mut session = Session::new();
+        execute_request(&tokens(&["SET", "foo", "0"]), &ds, &mut session);
+        let reply = execute_request(&tokens(&["SELECT", "1"]), &ds, &mut session);
+        assert_eq!(reply, b"+OK\r\n".to_vec());
+        let reply = execute_request(&tokens(&["GET", "foo"]), &ds, &mut session);
+        assert_eq!(reply, b"$-1\r\n".to_vec());
+        execute_request(&tokens(&["SET", "foo", "1"]), &ds, &mut session);
+        let reply = execute_request(&tokens(&["DBSIZE"]), &ds, &mut session);
+        assert_eq!(reply, b":1\r\n".to_vec());
+
+        // Other connections keep using the default database.
+        let mut other = Session::new();
+        let reply = execute_request(&tokens(&["GET", "foo"]), &ds, &mut other);
+        assert_eq!(reply, b"$1\r\n0\r\n".to_vec());
+        let reply = execute_request(&tokens(&["SELECT", "16"]), &ds, &mut other);
+        assert_eq!(reply, b"-ERR DB index is out of range\r\n".to_vec());
+    }
+
+    #[test]
+    fn should_count_changes_for_automatic_saves() {
+        let ds = Mutex::new(Server::new(&ServerConfig::default()));
+        let mut session = Session::new();
+        execute_request(&tokens(&["SET", "foo", "bar"]), &ds, &mut session);
+        execute_request(&tokens(&["GET", "foo"]), &ds, &mut session);
+        execute_request(&tokens(&["LPUSH", "foo", "bar"]), &ds, &mut session);
+        // Writes that change nothing are neither counted nor logged.
+        let offset = ds.lock().unwrap().replication.offset;
+        execute_request(&tokens(&["SET", "foo", "baz", "NX"]), &ds, &mut session);
+        execute_request(&tokens(&["SREM", "set", "a"]), &ds, &mut session);
+        execute_request(&tokens(&["EXPIRE", "missing", "10"]), &ds, &mut session);
+        assert_eq!(ds.lock().unwrap().replication.offset, offset);
+        execute_request(&tokens(&["del", "foo"]), &ds, &mut session);
+        execute_request(&tokens(&["del", "foo"]), &ds, &mut session);
+        assert_eq!(ds.lock().unwrap().persistence.dirty, 2);
+        assert!(ds.lock().unwrap().replication.offset > offset);
+    }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+    async fn should_kill_a_script_while_clients_connect_and_disconnect() {
+        let config = ServerConfig {
+            lua_time_limit: 10,
+            ..ServerConfig::default()
+        };
+        let server = Arc::new(Mutex::new(Server::new(&config)));
+        let monitor = server.lock().unwrap().scripts.monitor.clone();
+        let mut script = connect(&server, &monitor);
+        let running =
+            tokio::spawn(
+                async move { request(&mut script, &["EVAL", "while true do end", "0"]).await },
+            );
+        while !monitor.is_busy() {
+            tokio::time::sleep(Duration::from_millis(1)).await;
+        }
+        // More clients come and go than there are worker threads.
+        let idle: Vec<_> = (0..4).map(|_| connect(&server, &monitor)).collect();
+        for _ in 0..4 {
+            drop(connect(&server, &monitor));
+        }
+        let mut client = connect(&server, &monitor);
+        assert_eq!(request(&mut client, &["SCRIPT", "KILL"]).await, b"+OK\r\n");
+        assert_eq!(
+            running.await.unwrap(),
+            b"-ERR Script killed by user with SCRIPT KILL...\r\n"
+        );
+        assert_eq!(request(&mut client, &["DBSIZE"]).await, b":0\r\n");
+        drop(idle);
+    }
+}